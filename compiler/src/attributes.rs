@@ -0,0 +1,406 @@
+use std::{collections::HashMap, fmt};
+
+use bitflags::bitflags;
+use miette::SourceSpan;
+use tangic_ast as ast;
+use tangic_middle::{
+    span::FileId,
+    symbol::{sym, Symbol},
+    util::edit_distance::find_best_match,
+};
+
+/// What an attribute is applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    File,
+    Fn,
+    Struct,
+    Enum,
+    Variant,
+    Field,
+    Trait,
+    Impl,
+    TypeAlias,
+    Const,
+    Static,
+    AssocType,
+    Mod,
+    Use,
+}
+
+impl Target {
+    pub fn flag(self) -> Targets {
+        match self {
+            Self::File => Targets::FILE,
+            Self::Fn => Targets::FN,
+            Self::Struct => Targets::STRUCT,
+            Self::Enum => Targets::ENUM,
+            Self::Variant => Targets::VARIANT,
+            Self::Field => Targets::FIELD,
+            Self::Trait => Targets::TRAIT,
+            Self::Impl => Targets::IMPL,
+            Self::TypeAlias => Targets::TYPE_ALIAS,
+            Self::Const => Targets::CONST,
+            Self::Static => Targets::STATIC,
+            Self::AssocType => Targets::ASSOC_TYPE,
+            Self::Mod => Targets::MOD,
+            Self::Use => Targets::USE,
+        }
+    }
+
+    const ALL: [Self; 14] = [
+        Self::File,
+        Self::Fn,
+        Self::Struct,
+        Self::Enum,
+        Self::Variant,
+        Self::Field,
+        Self::Trait,
+        Self::Impl,
+        Self::TypeAlias,
+        Self::Const,
+        Self::Static,
+        Self::AssocType,
+        Self::Mod,
+        Self::Use,
+    ];
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::File => "a file",
+            Self::Fn => "a function",
+            Self::Struct => "a struct",
+            Self::Enum => "an enum",
+            Self::Variant => "an enum variant",
+            Self::Field => "a field",
+            Self::Trait => "a trait",
+            Self::Impl => "an impl",
+            Self::TypeAlias => "a type alias",
+            Self::Const => "a constant",
+            Self::Static => "a static",
+            Self::AssocType => "an associated type",
+            Self::Mod => "a module",
+            Self::Use => "an import",
+        })
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Targets: u16 {
+        const FILE = 1 << 0;
+        const FN = 1 << 1;
+        const STRUCT = 1 << 2;
+        const ENUM = 1 << 3;
+        const VARIANT = 1 << 4;
+        const FIELD = 1 << 5;
+        const TRAIT = 1 << 6;
+        const IMPL = 1 << 7;
+        const TYPE_ALIAS = 1 << 8;
+        const CONST = 1 << 9;
+        const STATIC = 1 << 10;
+        const ASSOC_TYPE = 1 << 11;
+        const MOD = 1 << 12;
+        const USE = 1 << 13;
+
+        const ITEM = Self::FN.bits()
+            | Self::STRUCT.bits()
+            | Self::ENUM.bits()
+            | Self::TRAIT.bits()
+            | Self::IMPL.bits()
+            | Self::TYPE_ALIAS.bits()
+            | Self::CONST.bits()
+            | Self::STATIC.bits()
+            | Self::MOD.bits();
+    }
+}
+
+impl fmt::Display for Targets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let targets: Vec<String> = Target::ALL
+            .into_iter()
+            .filter(|target| self.contains(target.flag()))
+            .map(|target| target.to_string())
+            .collect();
+
+        match targets.split_last() {
+            Some((last, [])) => f.write_str(last),
+            Some((last, rest)) => write!(f, "{} or {last}", rest.join(", ")),
+            None => f.write_str("nothing"),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum AttributeError {
+    #[error("unknown attribute `{name}`")]
+    #[diagnostic(code(tangic::attributes::unknown))]
+    Unknown {
+        name: Symbol,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not a known attribute")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`@{name}` cannot be used on {target}")]
+    #[diagnostic(
+        code(tangic::attributes::misplaced),
+        help("`@{name}` can only be used on {allowed}")
+    )]
+    Misplaced {
+        name: Symbol,
+        target: Target,
+        allowed: Targets,
+        #[label("not allowed here")]
+        at: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl AttributeError {
+    /// The file the attribute is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::Unknown { file, .. } | Self::Misplaced { file, .. } => *file,
+        }
+    }
+}
+
+/// The attributes the compiler knows about, and what each of them can be applied to.
+pub struct AttributeRegistry {
+    attributes: HashMap<Symbol, Targets>,
+}
+
+impl AttributeRegistry {
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            attributes: HashMap::new(),
+        };
+
+        let everything = Targets::all();
+
+        registry.register(sym::doc, everything);
+        registry.register(sym::allow, everything);
+        registry.register(sym::warn, everything);
+        registry.register(sym::deny, everything);
+        registry.register(
+            sym::deprecated,
+            Targets::ITEM.difference(Targets::IMPL) | Targets::VARIANT | Targets::FIELD | Targets::ASSOC_TYPE,
+        );
+        registry.register(sym::feature, Targets::FILE);
+        registry.register(sym::inline, Targets::FN);
+        registry.register(sym::cold, Targets::FN);
+        registry.register(sym::test, Targets::FN);
+        registry.register(
+            sym::must_use,
+            Targets::FN | Targets::STRUCT | Targets::ENUM | Targets::TRAIT,
+        );
+        registry.register(sym::repr, Targets::STRUCT | Targets::ENUM);
+        registry.register(sym::lang, Targets::ITEM | Targets::ASSOC_TYPE);
+
+        registry
+    }
+
+    pub fn register(&mut self, name: Symbol, targets: Targets) {
+        self.attributes.insert(name, targets);
+    }
+
+    /// Checks every attribute in `file`, and in the modules loaded into it, for being known and
+    /// applied to something it can be.
+    pub fn check(&self, file: &ast::File) -> Vec<AttributeError> {
+        let mut errors = vec![];
+
+        self.check_attributes(&file.attributes, Target::File, &mut errors);
+
+        for item in &file.items {
+            self.check_item(item, &mut errors);
+        }
+
+        errors
+    }
+
+    fn check_item(&self, item: &ast::Item, errors: &mut Vec<AttributeError>) {
+        match item {
+            ast::Item::Fn(function) => {
+                self.check_attributes(&function.attributes, Target::Fn, errors)
+            }
+            ast::Item::Struct(structure) => {
+                self.check_attributes(&structure.attributes, Target::Struct, errors);
+                self.check_fields(&structure.fields, errors);
+            }
+            ast::Item::Enum(enumeration) => {
+                self.check_attributes(&enumeration.attributes, Target::Enum, errors);
+
+                for variant in &enumeration.variants {
+                    self.check_attributes(&variant.attributes, Target::Variant, errors);
+
+                    if let ast::EnumFields::Struct(fields) = &variant.fields {
+                        self.check_fields(fields, errors);
+                    }
+                }
+            }
+            ast::Item::Type(alias) => {
+                self.check_attributes(&alias.attributes, Target::TypeAlias, errors)
+            }
+            ast::Item::Const(constant) => {
+                self.check_attributes(&constant.attributes, Target::Const, errors)
+            }
+            ast::Item::Static(constant) => {
+                self.check_attributes(&constant.attributes, Target::Static, errors)
+            }
+            ast::Item::Trait(trait_) => {
+                self.check_attributes(&trait_.attributes, Target::Trait, errors);
+                self.check_assoc_items(&trait_.items, errors);
+            }
+            ast::Item::Impl(impl_) => {
+                self.check_attributes(&impl_.attributes, Target::Impl, errors);
+                self.check_assoc_items(&impl_.items, errors);
+            }
+            ast::Item::Mod(module) => {
+                self.check_attributes(&module.attributes, Target::Mod, errors);
+
+                for item in module.items.iter().flatten() {
+                    self.check_item(item, errors);
+                }
+            }
+            ast::Item::Use(use_) => self.check_attributes(&use_.attributes, Target::Use, errors),
+            ast::Item::Error(_) => {}
+        }
+    }
+
+    fn check_fields(&self, fields: &[ast::StructField], errors: &mut Vec<AttributeError>) {
+        for field in fields {
+            self.check_attributes(&field.attributes, Target::Field, errors);
+        }
+    }
+
+    fn check_assoc_items(&self, items: &[ast::AssocItem], errors: &mut Vec<AttributeError>) {
+        for item in items {
+            match item {
+                ast::AssocItem::Fn(function) => {
+                    self.check_attributes(&function.attributes, Target::Fn, errors)
+                }
+                ast::AssocItem::Type(ty) => {
+                    self.check_attributes(&ty.attributes, Target::AssocType, errors)
+                }
+            }
+        }
+    }
+
+    /// The known attribute closest to `name`. The candidates are sorted, so that ties always go
+    /// the same way instead of depending on the order of the map.
+    fn suggest(&self, name: Symbol) -> Option<Symbol> {
+        let mut known: Vec<Symbol> = self.attributes.keys().copied().collect();
+        known.sort_by_key(|known| known.as_str());
+
+        find_best_match(known, name)
+    }
+
+    fn check_attributes(
+        &self,
+        attributes: &[ast::Attribute],
+        target: Target,
+        errors: &mut Vec<AttributeError>,
+    ) {
+        for attribute in attributes {
+            let name = attribute.name.name;
+            let at = attribute.span.range().into();
+
+            match self.attributes.get(&name) {
+                None => errors.push(AttributeError::Unknown {
+                    name,
+                    suggestion: self
+                        .suggest(name)
+                        .map(|known| format!("did you mean `@{known}`?")),
+                    at,
+                    file: attribute.span.file,
+                }),
+                Some(&allowed) if !allowed.contains(target.flag()) => {
+                    errors.push(AttributeError::Misplaced {
+                        name,
+                        target,
+                        allowed,
+                        at,
+                        file: attribute.span.file,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(name: &str) -> ast::Attribute {
+        let span = ast::Span::new(FileId::from_raw(0), 0..name.len() + 1);
+
+        ast::Attribute {
+            name: ast::Ident::new(Symbol::intern(name), span),
+            arguments: vec![],
+            inner: false,
+            span,
+        }
+    }
+
+    fn check(registry: &AttributeRegistry, name: &str, target: Target) -> Vec<AttributeError> {
+        let mut errors = vec![];
+        registry.check_attributes(&[attribute(name)], target, &mut errors);
+
+        errors
+    }
+
+    #[test]
+    fn known_attributes_in_place() {
+        let registry = AttributeRegistry::builtin();
+
+        assert!(check(&registry, "inline", Target::Fn).is_empty());
+        assert!(check(&registry, "doc", Target::Field).is_empty());
+        assert!(check(&registry, "feature", Target::File).is_empty());
+    }
+
+    #[test]
+    fn misplaced_attributes() {
+        let errors = check(&AttributeRegistry::builtin(), "inline", Target::Struct);
+
+        assert!(matches!(
+            &errors[..],
+            [AttributeError::Misplaced { target: Target::Struct, allowed, .. }] if *allowed == Targets::FN
+        ));
+        assert_eq!(
+            Targets::FN.union(Targets::STRUCT).to_string(),
+            "a function or a struct"
+        );
+    }
+
+    #[test]
+    fn unknown_attributes_get_a_suggestion() {
+        let errors = check(&AttributeRegistry::builtin(), "inlnie", Target::Fn);
+
+        assert!(matches!(
+            &errors[..],
+            [AttributeError::Unknown { suggestion: Some(suggestion), .. }]
+                if suggestion == "did you mean `@inline`?"
+        ));
+    }
+
+    #[test]
+    fn suggestions_break_ties_by_name() {
+        // every map iterates in a different order
+        for _ in 0..10 {
+            let mut registry = AttributeRegistry::builtin();
+            registry.register(Symbol::intern("abd"), Targets::FN);
+            registry.register(Symbol::intern("abc"), Targets::FN);
+
+            assert_eq!(
+                registry.suggest(Symbol::intern("abx")),
+                Some(Symbol::intern("abc"))
+            );
+        }
+    }
+}
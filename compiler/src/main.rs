@@ -1,7 +1,14 @@
-use miette::{GraphicalTheme, IntoDiagnostic, NamedSource, Result, ThemeCharacters, ThemeStyles, RgbColors};
+use std::path::Path;
 
+use miette::{GraphicalTheme, Result, ThemeCharacters, ThemeStyles, RgbColors};
+use tangic_middle::{span::SourceMap, Cx, DataLayout};
 use tracing::*;
 
+mod attributes;
+mod modules;
+
+use attributes::AttributeRegistry;
+
 fn main() -> Result<()> {
     tracing_subscriber::fmt().with_env_filter(tracing_subscriber::EnvFilter::from_env("TANGIC_LOG")).without_time().with_file(true).init();
     trace!("yeetus");
@@ -19,17 +26,84 @@ fn main() -> Result<()> {
         )
     }))?;
 
-    let input = std::fs::read_to_string("small.tn").into_diagnostic()?;
+    let root = std::env::args().nth(1).unwrap_or_else(|| "small.tn".to_owned());
+    let mut files = SourceMap::default();
+
+    info!(?root, "Parsing\n");
+
+    let krate = match modules::load_crate(Path::new(&root), &mut files) {
+        Ok(krate) => krate,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error:?}");
+            }
+
+            std::process::exit(1);
+        }
+    };
+
+    let attribute_errors = AttributeRegistry::builtin().check(&krate.root);
+    let (resolutions, resolve_errors) = tangic_resolve::resolve(&krate.root);
+
+    debug!(?resolutions, "Resolved");
+
+    let (hir, lowering_errors) = tangic_ast_lowering::lower(&krate.root, &resolutions);
+
+    debug!(?hir, "Lowered");
+
+    let cx = Cx::new(DataLayout::host());
+    let (types, typeck_errors) = tangic_typeck::check_crate(&cx, &hir);
 
-    info!(?input, "Parsing\n");
+    debug!(bodies = ?types.bodies, "Type checked");
 
-    let (ast, errors) =
-        tangic_parser::parse(input.clone(), NamedSource::new("small.tn", input))?;
+    let mut mir_errors = vec![];
+    let mut mono_errors = vec![];
+
+    if krate.errors.is_empty()
+        && attribute_errors.is_empty()
+        && resolve_errors.is_empty()
+        && lowering_errors.is_empty()
+        && typeck_errors.is_empty()
+    {
+        let (bodies, errors) = tangic_mir_build::build_mir(&types);
+        mir_errors = errors;
+
+        debug!(?bodies, "Built MIR");
+
+        match tangic_monomorphize::entry_fn(&hir) {
+            Some(entry) if mir_errors.is_empty() => {
+                let (instances, errors) = tangic_monomorphize::collect_mono_items(&types, &bodies, entry);
+                mono_errors = errors;
+
+                debug!(?instances, "Collected");
+            }
+            Some(_) => {}
+            None => debug!("No `main`, nothing to collect"),
+        }
+    }
+
+    for warning in krate.warnings {
+        eprintln!("{warning:?}");
+    }
+
+    let errors = krate
+        .errors
+        .into_iter()
+        .chain(attribute_errors.into_iter().map(|error| modules::report(&files, error.file(), error)))
+        .chain(resolve_errors.into_iter().map(|error| modules::report(&files, error.file(), error)))
+        .chain(lowering_errors.into_iter().map(|error| modules::report(&files, error.file(), error)))
+        .chain(typeck_errors.into_iter().map(|error| modules::report(&files, error.file(), error)))
+        .chain(mir_errors.into_iter().map(|error| modules::report(&files, error.file(), error)))
+        .chain(mono_errors.into_iter().map(|error| modules::report(&files, error.file(), error)));
+    let mut failed = false;
+
+    for error in errors {
+        eprintln!("{error:?}");
+        failed = true;
+    }
 
-    if errors.errors.is_empty() {
-        println!("{ast:#?}");
-    } else {
-        eprintln!("{errors:?}");
+    if failed {
+        std::process::exit(1);
     }
 
     Ok(())
@@ -0,0 +1,274 @@
+use std::{io, path::Path};
+
+use miette::{Diagnostic, IntoDiagnostic, NamedSource, Severity, SourceSpan, WrapErr};
+use tangic_ast as ast;
+use tangic_middle::{
+    span::{FileId, SourceMap},
+    symbol::Symbol,
+};
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum ModuleError {
+    #[error("file not found for module `{name}`")]
+    #[diagnostic(
+        code(tangic::modules::not_found),
+        help("create `{path}` or `{alternative}`, or write the module inline with `mod {name} {{ ... }}`")
+    )]
+    NotFound {
+        name: Symbol,
+        path: String,
+        alternative: String,
+        #[label("declared here")]
+        at: SourceSpan,
+    },
+    #[error("module `{name}` is in both `{path}` and `{alternative}`")]
+    #[diagnostic(code(tangic::modules::ambiguous), help("delete or rename one of them"))]
+    Ambiguous {
+        name: Symbol,
+        path: String,
+        alternative: String,
+        #[label("declared here")]
+        at: SourceSpan,
+    },
+    #[error("couldn't read `{path}`: {error}")]
+    #[diagnostic(code(tangic::modules::io))]
+    Io {
+        path: String,
+        error: io::Error,
+        #[label("for this module")]
+        at: SourceSpan,
+    },
+}
+
+/// A crate, parsed with all of its modules.
+pub struct Crate {
+    /// The root file, where every `mod name` has its items loaded.
+    pub root: ast::File,
+    /// Errors from parsing and loading the files, with their sources attached.
+    pub errors: Vec<miette::Report>,
+    /// Warnings from parsing, which don't stop compilation.
+    pub warnings: Vec<miette::Report>,
+}
+
+/// Parses the file at `root` and every file it declares a module for, recursively. The files of
+/// modules declared in `dir/name.tn` or `dir/name/mod.tn` are looked for in `dir/name/`, and
+/// those of the crate root next to it. If the root itself can't be read or parsed, there is no
+/// crate to return, only the errors.
+pub fn load_crate(root: &Path, files: &mut SourceMap) -> Result<Crate, Vec<miette::Report>> {
+    let mut loader = Loader {
+        files,
+        errors: vec![],
+        warnings: vec![],
+    };
+
+    let parsed = loader
+        .parse_file(root)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("couldn't read `{}`", root.display()));
+
+    let mut file = match parsed {
+        Ok(Some(file)) => file,
+        Ok(None) => return Err(loader.errors.into_iter().chain(loader.warnings).collect()),
+        Err(error) => return Err(vec![error]),
+    };
+
+    let dir = root.parent().unwrap_or(Path::new("."));
+    loader.load_modules(&mut file.items, dir);
+
+    Ok(Crate {
+        root: file,
+        errors: loader.errors,
+        warnings: loader.warnings,
+    })
+}
+
+struct Loader<'a> {
+    files: &'a mut SourceMap,
+    errors: Vec<miette::Report>,
+    warnings: Vec<miette::Report>,
+}
+
+impl Loader<'_> {
+    /// Reads and parses the file at `path`. `None` if it has errors that left nothing to use.
+    fn parse_file(&mut self, path: &Path) -> io::Result<Option<ast::File>> {
+        let source = std::fs::read_to_string(path)?;
+        let name = path.display().to_string();
+        let file = self.files.add(name.clone(), source.clone());
+
+        match tangic_parser::parse(source.clone(), file, NamedSource::new(name, source)) {
+            Ok((ast, mut errors)) => {
+                let (warnings, rest): (Vec<_>, Vec<_>) = errors
+                    .errors
+                    .into_iter()
+                    .partition(|error| error.severity() == Some(Severity::Warning));
+                errors.errors = rest;
+
+                for warning in warnings {
+                    self.warnings.push(report(self.files, file, warning));
+                }
+
+                if !errors.errors.is_empty() {
+                    self.errors.push(miette::Report::new(errors));
+                }
+
+                Ok(Some(ast))
+            }
+            Err(errors) => {
+                self.errors.push(miette::Report::new(errors));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Loads the modules declared among `items`, for which files are looked for in `dir`.
+    fn load_modules(&mut self, items: &mut [ast::Item], dir: &Path) {
+        for item in items {
+            let ast::Item::Mod(module) = item else {
+                continue;
+            };
+
+            let dir = dir.join(module.name.as_str());
+
+            match &mut module.items {
+                Some(items) => self.load_modules(items, &dir),
+                None => self.load_module(module, &dir),
+            }
+        }
+    }
+
+    fn load_module(&mut self, module: &mut ast::Module, dir: &Path) {
+        let name = module.name.name;
+        let at = module.name.span.range().into();
+        let declared_in = module.span.file;
+
+        let path = dir.with_extension("tn");
+        let alternative = dir.join("mod.tn");
+
+        let path = match (path.is_file(), alternative.is_file()) {
+            (true, false) => path,
+            (false, true) => alternative,
+            (found, _) => {
+                let (path, alternative) = (display(&path), display(&alternative));
+                let error = if found {
+                    ModuleError::Ambiguous {
+                        name,
+                        path,
+                        alternative,
+                        at,
+                    }
+                } else {
+                    ModuleError::NotFound {
+                        name,
+                        path,
+                        alternative,
+                        at,
+                    }
+                };
+
+                self.errors.push(report(self.files, declared_in, error));
+                return;
+            }
+        };
+
+        match self.parse_file(&path) {
+            Ok(Some(mut file)) => {
+                self.load_modules(&mut file.items, dir);
+
+                module.attributes.extend(file.attributes);
+                module.items = Some(file.items);
+                module.file = Some(file.file);
+            }
+            // the parse errors are reported already
+            Ok(None) => {}
+            Err(error) => {
+                let error = ModuleError::Io {
+                    path: display(&path),
+                    error,
+                    at,
+                };
+
+                self.errors.push(report(self.files, declared_in, error));
+            }
+        }
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Attaches the source of `file` to an error whose spans are in it.
+pub fn report(
+    files: &SourceMap,
+    file: FileId,
+    error: impl Diagnostic + Send + Sync + 'static,
+) -> miette::Report {
+    let file = files.get(file);
+    miette::Report::new(error).with_source_code(NamedSource::new(&file.name, file.source.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `files` into a fresh directory and loads the crate rooted at its `main.tn`.
+    fn load(name: &str, files: &[(&str, &str)]) -> Result<Crate, Vec<miette::Report>> {
+        let dir = std::env::temp_dir().join(format!("tangic-modules-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        for (path, source) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+
+        let krate = load_crate(&dir.join("main.tn"), &mut SourceMap::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        krate
+    }
+
+    #[test]
+    fn nested_module_files() {
+        let Ok(krate) = load(
+            "nested",
+            &[
+                ("main.tn", "mod a\n"),
+                ("a.tn", "mod c\n()i32 f = 1\n"),
+                ("a/c.tn", "()i32 g = 2\n"),
+            ],
+        ) else {
+            panic!("the crate root failed to load");
+        };
+
+        assert!(krate.errors.is_empty(), "{:?}", krate.errors);
+
+        let [ast::Item::Mod(a)] = &krate.root.items[..] else {
+            panic!("expected one module, found {:?}", krate.root.items);
+        };
+        assert!(a.file.is_some());
+
+        let [ast::Item::Mod(c), ast::Item::Fn(_)] = &a.items.as_ref().unwrap()[..] else {
+            panic!("expected a module and a function, found {:?}", a.items);
+        };
+        assert!(matches!(&c.items.as_ref().unwrap()[..], [ast::Item::Fn(_)]));
+    }
+
+    #[test]
+    fn missing_and_ambiguous_module_files() {
+        let Ok(krate) = load(
+            "missing",
+            &[
+                ("main.tn", "mod missing\nmod both\n"),
+                ("both.tn", ""),
+                ("both/mod.tn", ""),
+            ],
+        ) else {
+            panic!("the crate root failed to load");
+        };
+
+        assert_eq!(krate.errors.len(), 2, "{:?}", krate.errors);
+        assert_eq!(krate.errors[0].to_string(), "file not found for module `missing`");
+        assert!(krate.errors[1].to_string().starts_with("module `both` is in both"));
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use logos::Lexer;
+
+use crate::{LexingError, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// `// ...`
+    Line,
+    /// `/* ... */`, which may nest.
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub kind: CommentKind,
+    /// The text between the delimiters.
+    pub text: String,
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            CommentKind::Line => write!(f, "//{}", self.text),
+            CommentKind::Block => write!(f, "/*{}*/", self.text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocComment {
+    /// `//!` documents the enclosing item, `///` documents the next one.
+    pub inner: bool,
+    /// The text after the `///` or `//!`.
+    pub text: String,
+}
+
+impl fmt::Display for DocComment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.inner { "//!" } else { "///" }, self.text)
+    }
+}
+
+pub(crate) fn lex_line_comment(lex: &mut Lexer<'_, Token>) -> Comment {
+    Comment {
+        kind: CommentKind::Line,
+        text: lex.slice()[2..].to_owned(),
+    }
+}
+
+pub(crate) fn lex_doc_comment(lex: &mut Lexer<'_, Token>) -> DocComment {
+    let slice = lex.slice();
+
+    DocComment {
+        inner: slice.starts_with("//!"),
+        text: slice[3..].to_owned(),
+    }
+}
+
+/// Scans a block comment after its opening `/*`, keeping track of nested `/* */` pairs.
+pub(crate) fn lex_block_comment(lex: &mut Lexer<'_, Token>) -> Result<Comment, LexingError> {
+    let remainder = lex.remainder();
+    let mut depth = 1usize;
+    let mut offset = 0;
+
+    while depth > 0 {
+        let rest = &remainder[offset..];
+
+        if rest.starts_with("/*") {
+            depth += 1;
+            offset += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            offset += 2;
+        } else if let Some(ch) = rest.chars().next() {
+            offset += ch.len_utf8();
+        } else {
+            lex.bump(remainder.len());
+
+            return Err(LexingError::UnterminatedBlockComment(depth));
+        }
+    }
+
+    let text = remainder[..offset - 2].to_owned();
+    lex.bump(offset);
+
+    Ok(Comment {
+        kind: CommentKind::Block,
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use logos::Logos;
+    use tangic_middle::symbol::Symbol;
+
+    use super::*;
+
+    fn lex(source: &str) -> Vec<Result<Token, LexingError>> {
+        Token::lexer(source).collect()
+    }
+
+    fn comment(kind: CommentKind, text: &str) -> Result<Token, LexingError> {
+        Ok(Token::Comment(Comment {
+            kind,
+            text: text.to_owned(),
+        }))
+    }
+
+    fn doc(inner: bool, text: &str) -> Result<Token, LexingError> {
+        Ok(Token::DocComment(DocComment {
+            inner,
+            text: text.to_owned(),
+        }))
+    }
+
+    #[test]
+    fn line_comments() {
+        assert_eq!(lex("// hi"), [comment(CommentKind::Line, " hi")]);
+        assert_eq!(lex("//"), [comment(CommentKind::Line, "")]);
+        // four slashes are a regular comment, not a doc comment
+        assert_eq!(lex("//// hi"), [comment(CommentKind::Line, "// hi")]);
+        assert_eq!(
+            lex("// a\n// b"),
+            [
+                comment(CommentKind::Line, " a"),
+                comment(CommentKind::Line, " b")
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        assert_eq!(lex("/// outer"), [doc(false, " outer")]);
+        assert_eq!(lex("///"), [doc(false, "")]);
+        assert_eq!(lex("//! inner"), [doc(true, " inner")]);
+        assert_eq!(lex("//!"), [doc(true, "")]);
+    }
+
+    #[test]
+    fn block_comments() {
+        assert_eq!(lex("/* hi */"), [comment(CommentKind::Block, " hi ")]);
+        assert_eq!(lex("/**/"), [comment(CommentKind::Block, "")]);
+        assert_eq!(lex("/* a\nb */"), [comment(CommentKind::Block, " a\nb ")]);
+        assert_eq!(
+            lex("/* a /* b */ c */ x"),
+            [
+                comment(CommentKind::Block, " a /* b */ c "),
+                Ok(Token::Identifier(Symbol::intern("x"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_block_comments() {
+        assert_eq!(lex("/* a"), [Err(LexingError::UnterminatedBlockComment(1))]);
+        assert_eq!(
+            lex("/* a /* b */"),
+            [Err(LexingError::UnterminatedBlockComment(1))]
+        );
+        assert_eq!(
+            lex("/* a /* b"),
+            [Err(LexingError::UnterminatedBlockComment(2))]
+        );
+    }
+}
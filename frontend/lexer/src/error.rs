@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+use miette::SourceSpan;
+
+use crate::{NumberError, StringError};
+
+/// The error produced by the [`Logos`](logos::Logos) callbacks, before it gets a span attached
+/// in [`Help`](crate::Help).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LexingError {
+    #[default]
+    InvalidCharacter,
+    Number(NumberError),
+    String(Vec<StringError>),
+    /// Holds the delimiter that would have closed the string.
+    UnterminatedString(String),
+    /// Holds how many `/*` were left open.
+    UnterminatedBlockComment(usize),
+}
+
+impl From<NumberError> for LexingError {
+    fn from(error: NumberError) -> Self {
+        Self::Number(error)
+    }
+}
+
+impl LexingError {
+    /// Whether a [`Token::Error`](crate::Token::Error) should take the place of the input that
+    /// failed to lex. Comments never reach the parser, so there is nothing to stand in for.
+    pub fn recover(&self) -> bool {
+        !matches!(self, Self::UnterminatedBlockComment(_))
+    }
+
+    pub fn into_errors(self, input: &str, span: Range<usize>) -> Vec<LexerError> {
+        let opening = (span.start..span.start + 1).into();
+
+        match self {
+            Self::InvalidCharacter => {
+                let ch = input[span.clone()].chars().next().unwrap_or_default();
+
+                vec![LexerError::InvalidCharacter {
+                    ch,
+                    suggestion: suggest(ch),
+                    at: span.into(),
+                }]
+            }
+            Self::Number(error) => vec![LexerError::InvalidNumber(error)],
+            Self::String(errors) => errors.into_iter().map(LexerError::InvalidLiteral).collect(),
+            Self::UnterminatedString(closing) => {
+                vec![LexerError::UnterminatedString { closing, at: opening }]
+            }
+            Self::UnterminatedBlockComment(depth) => vec![LexerError::UnterminatedBlockComment {
+                closing: "*/".repeat(depth),
+                at: (span.start..span.start + 2).into(),
+            }],
+        }
+    }
+}
+
+/// Suggests what might have been meant instead of a character that is not valid anywhere.
+fn suggest(ch: char) -> Option<String> {
+    Some(match ch {
+        ';' => "tangi does not use semicolons, remove it".to_owned(),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{00AB}' | '\u{00BB}' => {
+            format!("`{ch}` looks like a double quote, but it is not; use `\"`")
+        }
+        '\u{2018}' | '\u{2019}' | '\u{201A}' => {
+            format!("`{ch}` looks like a single quote, but it is not; use `'`")
+        }
+        '\u{00A0}' | '\u{2000}'..='\u{200B}' | '\u{3000}' | '\u{FEFF}' => {
+            format!("`{}` is an invisible whitespace character, replace it with a space", ch.escape_unicode())
+        }
+        '\u{2212}' | '\u{2013}' | '\u{2014}' => {
+            format!("`{ch}` looks like a minus sign, but it is not; use `-`")
+        }
+        _ => return None,
+    })
+}
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum LexerError {
+    #[error("invalid character `{}`", ch.escape_debug())]
+    #[diagnostic(code(tangi::lexer::invalid_character))]
+    InvalidCharacter {
+        ch: char,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not valid here")]
+        at: SourceSpan,
+    },
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidNumber(#[from] NumberError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidLiteral(#[from] StringError),
+    #[error("unterminated string")]
+    #[diagnostic(
+        code(tangi::lexer::unterminated_string),
+        help("close the string with `{closing}`")
+    )]
+    UnterminatedString {
+        closing: String,
+        #[label("string starts here")]
+        at: SourceSpan,
+    },
+    #[error("unterminated block comment")]
+    #[diagnostic(
+        code(tangi::lexer::unterminated_block_comment),
+        help("block comments nest, so every `/*` needs its own `*/`; this one needs `{closing}`")
+    )]
+    UnterminatedBlockComment {
+        closing: String,
+        #[label("comment starts here")]
+        at: SourceSpan,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_middle::symbol::Symbol;
+
+    use super::*;
+    use crate::{Help, Token};
+
+    /// Lexes `source` the way the parser sees it, returning the tokens and the errors.
+    fn lex(source: &str) -> (Vec<Token>, Vec<LexerError>) {
+        let help = Help::new(source);
+        let errors = help.errors();
+        let tokens = help.map(|(token, _)| token).collect();
+
+        (tokens, errors.take())
+    }
+
+    fn ident(name: &str) -> Token {
+        Token::Identifier(Symbol::intern(name))
+    }
+
+    #[test]
+    fn invalid_characters_become_error_tokens() {
+        let (tokens, errors) = lex("a $ b");
+
+        assert_eq!(tokens, [ident("a"), Token::Error, ident("b")]);
+        assert!(matches!(
+            &errors[..],
+            [LexerError::InvalidCharacter { ch: '$', suggestion: None, at }] if *at == (2..3).into()
+        ));
+    }
+
+    #[test]
+    fn lookalike_characters_get_a_suggestion() {
+        for source in ["a;", "a\u{201C}", "a\u{00A0}", "a\u{2212}"] {
+            let (tokens, errors) = lex(source);
+
+            assert_eq!(tokens, [ident("a"), Token::Error]);
+            assert!(
+                matches!(&errors[..], [LexerError::InvalidCharacter { suggestion: Some(_), .. }]),
+                "{source:?}: {errors:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn literal_errors_are_classified() {
+        let (tokens, errors) = lex("0xg \"\\q\" \"open");
+
+        assert_eq!(tokens, [Token::Error, Token::Error, Token::Error]);
+        assert!(matches!(
+            &errors[..],
+            [
+                LexerError::InvalidNumber(_),
+                LexerError::InvalidLiteral(_),
+                LexerError::UnterminatedString { closing, .. },
+            ] if closing == "\""
+        ));
+    }
+
+    #[test]
+    fn unterminated_block_comments_leave_no_token() {
+        let (tokens, errors) = lex("a /* /* b */");
+
+        assert_eq!(tokens, [ident("a")]);
+        assert!(matches!(
+            &errors[..],
+            [LexerError::UnterminatedBlockComment { closing, at }]
+                if closing == "*/" && *at == (2..4).into()
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+use std::ops::Range;
+
+use logos::Logos;
+
+use crate::{LexerError, LexingError, Token};
+
+type Lexed = (Result<Token, LexingError>, Range<usize>);
+
+/// A replacement of `range` (in bytes of the text before the edit) with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// A lexed file that can be kept up to date as it gets edited, re-lexing only around the edit
+/// instead of the whole file.
+pub struct LexedFile {
+    source: String,
+    tokens: Vec<Lexed>,
+}
+
+impl LexedFile {
+    pub fn new(source: String) -> Self {
+        let tokens = Token::lexer(&source).spanned().collect();
+
+        Self { source, tokens }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The tokens of the file, with [`Token::Error`] standing in for anything that failed to
+    /// lex, the same as [`Help`](crate::Help) produces.
+    pub fn tokens(&self) -> impl Iterator<Item = (Token, Range<usize>)> + '_ {
+        self.tokens.iter().filter_map(|(token, span)| match token {
+            Ok(token) => Some((token.clone(), span.clone())),
+            Err(error) => error.recover().then(|| (Token::Error, span.clone())),
+        })
+    }
+
+    pub fn errors(&self) -> Vec<LexerError> {
+        self.tokens
+            .iter()
+            .filter_map(|(token, span)| Some((token.as_ref().err()?.clone(), span.clone())))
+            .flat_map(|(error, span)| error.into_errors(&self.source, span))
+            .collect()
+    }
+
+    /// Applies `edit` to the source and re-lexes the tokens it could have changed, returning
+    /// the range of token indices that were replaced.
+    ///
+    /// Lexing restarts one token before the edit, since the new text may join up with it (like
+    /// typing a second `/` after one), and stops as soon as it produces a token identical to one
+    /// that was already there past the edit, as from there on the input is the same.
+    pub fn edit(&mut self, TextEdit { range, text }: TextEdit) -> Range<usize> {
+        self.source.replace_range(range.clone(), &text);
+
+        let new_end = range.start + text.len();
+        let shift = |span: &Range<usize>| span.start + new_end - range.end..span.end + new_end - range.end;
+
+        let first = self
+            .tokens
+            .partition_point(|(_, span)| span.end < range.start)
+            .saturating_sub(1);
+        let restart = self
+            .tokens
+            .get(first)
+            .map_or(range.start, |(_, span)| span.start.min(range.start));
+
+        let mut lexer = Token::lexer(&self.source);
+        lexer.bump(restart);
+        let mut lexer = lexer.spanned();
+
+        let mut old = first;
+        let mut relexed = vec![];
+        let resync = loop {
+            let Some((token, span)) = lexer.next() else {
+                break self.tokens.len();
+            };
+
+            if span.start >= new_end {
+                while self
+                    .tokens
+                    .get(old)
+                    .is_some_and(|(_, old_span)| old_span.start < range.end || shift(old_span).start < span.start)
+                {
+                    old += 1;
+                }
+
+                if let Some((old_token, old_span)) = self.tokens.get(old) {
+                    if shift(old_span) == span && *old_token == token {
+                        break old;
+                    }
+                }
+            }
+
+            relexed.push((token, span));
+        };
+
+        let moved = new_end != range.end;
+        for (token, span) in &mut self.tokens[resync..] {
+            *span = shift(span);
+
+            // diagnostics carry absolute spans, so errors past the edit are lexed again to
+            // point at the right place
+            if token.is_err() && moved {
+                let mut lexer = Token::lexer(&self.source);
+                lexer.bump(span.start);
+
+                *token = lexer.next().expect("the same text lexes the same");
+            }
+        }
+
+        let replaced = first..first + relexed.len();
+        self.tokens.splice(first..resync, relexed);
+
+        replaced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies `edit` to `source`, checking that re-lexing around it gives the same tokens as
+    /// lexing the edited source from scratch, and returns the replaced token range.
+    fn check_edit(source: &str, range: Range<usize>, text: &str) -> Range<usize> {
+        let mut file = LexedFile::new(source.to_owned());
+        let replaced = file.edit(TextEdit {
+            range,
+            text: text.to_owned(),
+        });
+
+        let fresh = LexedFile::new(file.source().to_owned());
+        assert_eq!(file.tokens, fresh.tokens, "after editing {source:?} into {:?}", file.source());
+
+        replaced
+    }
+
+    #[test]
+    fn edits_relex_only_around_the_edit() {
+        // `a b c d` -> `a xyz c d`, `a` is lexed again in case the edit joins up with it
+        assert_eq!(check_edit("a b c d", 2..3, "xyz"), 0..2);
+        // deleting `b ` entirely
+        assert_eq!(check_edit("a b c d", 2..4, ""), 0..1);
+    }
+
+    #[test]
+    fn edits_join_with_the_token_before() {
+        // a second `/` turns the division into a comment that eats the rest of the line
+        check_edit("a / b\nc", 3..3, "/");
+        check_edit("x = 1", 5..5, "23");
+        check_edit("let x", 3..3, "ter");
+    }
+
+    #[test]
+    fn edits_that_change_everything_after() {
+        // opening a string or block comment changes how the rest of the file lexes
+        check_edit("a b \"c\" d", 2..2, "\"");
+        check_edit("a b c */ d", 2..2, "/*");
+    }
+
+    #[test]
+    fn errors_past_an_edit_move_with_it() {
+        let mut file = LexedFile::new("a $".to_owned());
+        file.edit(TextEdit {
+            range: 0..1,
+            text: "abc".to_owned(),
+        });
+
+        assert!(matches!(
+            &file.errors()[..],
+            [LexerError::InvalidCharacter { at, .. }] if *at == (4..5).into()
+        ));
+        check_edit("a $", 0..1, "abc");
+    }
+
+    #[test]
+    fn edits_at_the_ends() {
+        check_edit("", 0..0, "a b");
+        check_edit("a b", 0..3, "");
+        check_edit("a b", 3..3, " c");
+    }
+}
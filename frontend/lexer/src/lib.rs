@@ -1,19 +1,58 @@
-#![feature(iterator_try_collect)]
-
-use std::ops::Range;
+use std::{cell::RefCell, ops::Range, rc::Rc};
 
 pub use logos::{Lexer, Logos, SpannedIter};
-use miette::SourceSpan;
+use tangic_middle::symbol::Symbol;
+
+mod comment;
+mod error;
+mod incremental;
+mod number;
+mod string;
+pub use comment::*;
+pub use error::*;
+pub use incremental::*;
+pub use number::*;
+pub use string::StringError;
 
 #[derive(Logos, Debug, Clone, PartialEq, derive_more::Display)]
-#[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"[ \t\r\n\f]+")]
+#[logos(error = LexingError)]
 pub enum Token {
     #[display(fmt = "an identifier \"{_0}\"")]
-    #[regex("[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_owned())]
-    Identifier(String),
+    #[regex("[a-zA-Z_][a-zA-Z0-9_]*", |lex| Symbol::intern(lex.slice()))]
+    Identifier(Symbol),
     #[display(fmt = "a string literal \"{_0}\"")]
-    #[regex(r#""[^"]*""#, |lex| { let slice = lex.slice(); slice[1..slice.len()-1].to_owned() })]
+    #[regex(r#""([^"\\]|\\(.|\n))*""#, string::lex_string)]
+    #[regex(r#""([^"\\]|\\(.|\n))*"#, string::unterminated)]
+    #[regex(r#"r#*""#, string::lex_raw_string)]
     StringLiteral(String),
+    #[display(fmt = "a byte string literal b\"{}\"", "_0.escape_ascii()")]
+    #[regex(r#"b"([^"\\]|\\(.|\n))*""#, string::lex_byte_string)]
+    #[regex(r#"b"([^"\\]|\\(.|\n))*"#, string::unterminated)]
+    #[regex(r#"br#*""#, string::lex_raw_byte_string)]
+    ByteStringLiteral(Vec<u8>),
+    #[display(fmt = "a character literal {_0:?}")]
+    #[regex(r"'([^'\\\n]|\\[^\n][^'\n]*)'", string::lex_char)]
+    CharLiteral(char),
+    #[display(fmt = "a byte literal b'{}'", "_0.escape_ascii()")]
+    #[regex(r"b'([^'\\\n]|\\[^\n][^'\n]*)'", string::lex_byte)]
+    ByteLiteral(u8),
+    #[display(fmt = "an integer literal `{_0}`")]
+    #[regex("[0-9][0-9a-zA-Z_]*", number::lex_int)]
+    IntLiteral(IntLiteral),
+    #[display(fmt = "a float literal `{_0}`")]
+    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*([eE][+-]?[0-9_]+)?[a-zA-Z0-9_]*", number::lex_float, priority = 10)]
+    #[regex("[0-9][0-9_]*[eE][+-]?[0-9_]+[a-zA-Z0-9_]*", number::lex_float, priority = 10)]
+    #[regex("[0-9][0-9_]*f(32|64)", number::lex_float, priority = 10)]
+    FloatLiteral(FloatLiteral),
+    #[display(fmt = "a comment `{_0}`")]
+    #[regex("//[^\n]*", comment::lex_line_comment)]
+    #[token("/*", comment::lex_block_comment)]
+    Comment(Comment),
+    #[display(fmt = "a doc comment `{_0}`")]
+    #[regex("///([^/\n][^\n]*)?", comment::lex_doc_comment, priority = 10)]
+    #[regex("//![^\n]*", comment::lex_doc_comment, priority = 10)]
+    DocComment(DocComment),
     #[display(fmt = "an opening curly brace {{")]
     #[token("{")]
     OpenCurly,
@@ -26,6 +65,12 @@ pub enum Token {
     #[display(fmt = "closing parentheses `)`")]
     #[token(")")]
     CloseParen,
+    #[display(fmt = "an opening square bracket `[`")]
+    #[token("[")]
+    OpenBracket,
+    #[display(fmt = "a closing square bracket `]`")]
+    #[token("]")]
+    CloseBracket,
     #[display(fmt = "a dot .")]
     #[token(".")]
     Dot,
@@ -50,6 +95,12 @@ pub enum Token {
     #[display(fmt = "an exclamation mark")]
     #[token("!")]
     Excl,
+    #[display(fmt = "a plus sign +")]
+    #[token("+")]
+    Plus,
+    #[display(fmt = "a question mark ?")]
+    #[token("?")]
+    Question,
     #[display(fmt = "the `const` keyword")]
     #[token("const")]
     KwConst,
@@ -82,7 +133,109 @@ pub enum Token {
     KwTrait,
     #[display(fmt = "the `impl` keyword")]
     #[token("impl")]
-    KwImpl
+    KwImpl,
+    #[display(fmt = "the `type` keyword")]
+    #[token("type")]
+    KwType,
+    #[display(fmt = "the `static` keyword")]
+    #[token("static")]
+    KwStatic,
+    #[display(fmt = "the `where` keyword")]
+    #[token("where")]
+    KwWhere,
+    #[display(fmt = "the `for` keyword")]
+    #[token("for")]
+    KwFor,
+    #[display(fmt = "a minus sign -")]
+    #[token("-")]
+    Minus,
+    #[display(fmt = "an asterisk *")]
+    #[token("*")]
+    Star,
+    #[display(fmt = "a slash /")]
+    #[token("/")]
+    Slash,
+    #[display(fmt = "a percent sign %")]
+    #[token("%")]
+    Percent,
+    #[display(fmt = "a double equals sign ==")]
+    #[token("==")]
+    EqEq,
+    #[display(fmt = "a not-equals sign !=")]
+    #[token("!=")]
+    NotEq,
+    #[display(fmt = "a less-than sign <")]
+    #[token("<")]
+    Lt,
+    #[display(fmt = "a less-than-or-equal sign <=")]
+    #[token("<=")]
+    Le,
+    #[display(fmt = "a greater-than sign >")]
+    #[token(">")]
+    Gt,
+    #[display(fmt = "a greater-than-or-equal sign >=")]
+    #[token(">=")]
+    Ge,
+    #[display(fmt = "a double ampersand &&")]
+    #[token("&&")]
+    AndAnd,
+    #[display(fmt = "a double pipe ||")]
+    #[token("||")]
+    OrOr,
+    #[display(fmt = "a pipe |")]
+    #[token("|")]
+    Pipe,
+    #[display(fmt = "a caret ^")]
+    #[token("^")]
+    Caret,
+    #[display(fmt = "a left shift <<")]
+    #[token("<<")]
+    Shl,
+    #[display(fmt = "a right shift >>")]
+    #[token(">>")]
+    Shr,
+    #[display(fmt = "a fat arrow =>")]
+    #[token("=>")]
+    FatArrow,
+    #[display(fmt = "the `if` keyword")]
+    #[token("if")]
+    KwIf,
+    #[display(fmt = "the `else` keyword")]
+    #[token("else")]
+    KwElse,
+    #[display(fmt = "the `unsafe` keyword")]
+    #[token("unsafe")]
+    KwUnsafe,
+    #[display(fmt = "the `return` keyword")]
+    #[token("return")]
+    KwReturn,
+    #[display(fmt = "the `loop` keyword")]
+    #[token("loop")]
+    KwLoop,
+    #[display(fmt = "the `while` keyword")]
+    #[token("while")]
+    KwWhile,
+    #[display(fmt = "the `break` keyword")]
+    #[token("break")]
+    KwBreak,
+    #[display(fmt = "the `continue` keyword")]
+    #[token("continue")]
+    KwContinue,
+    #[display(fmt = "the `dyn` keyword")]
+    #[token("dyn")]
+    KwDyn,
+    #[display(fmt = "the `as` keyword")]
+    #[token("as")]
+    KwAs,
+    #[display(fmt = "the `mod` keyword")]
+    #[token("mod")]
+    KwMod,
+    #[display(fmt = "the `use` keyword")]
+    #[token("use")]
+    KwUse,
+    /// Stands in for input that failed to lex, so that parsing can carry on past it.
+    #[display(fmt = "an invalid token")]
+    Error,
 }
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
@@ -91,6 +244,20 @@ pub enum TokenKind {
     Identifier,
     #[error("a string literal")]
     StringLiteral,
+    #[error("a byte string literal")]
+    ByteStringLiteral,
+    #[error("a character literal")]
+    CharLiteral,
+    #[error("a byte literal")]
+    ByteLiteral,
+    #[error("an integer literal")]
+    IntLiteral,
+    #[error("a float literal")]
+    FloatLiteral,
+    #[error("a comment")]
+    Comment,
+    #[error("a doc comment")]
+    DocComment,
     #[error("an opening curly brace {{")]
     OpenCurly,
     #[error("a closing curly brace }}")]
@@ -99,6 +266,10 @@ pub enum TokenKind {
     OpenParen,
     #[error("closing parentheses `)`")]
     CloseParen,
+    #[error("an opening square bracket `[`")]
+    OpenBracket,
+    #[error("a closing square bracket `]`")]
+    CloseBracket,
     #[error("a dot .")]
     Dot,
     #[error("a comma ,")]
@@ -123,6 +294,10 @@ pub enum TokenKind {
     KwLet,
     #[error("an exclamation mark")]
     Excl,
+    #[error("a plus sign +")]
+    Plus,
+    #[error("a question mark ?")]
+    Question,
     #[error("the `mut` keyword")]
     KwMut,
     #[error("the `true` keyword")]
@@ -136,7 +311,75 @@ pub enum TokenKind {
     #[error("the `trait` keyword")]
     KwTrait,
     #[error("the `impl` keyword")]
-    KwImpl
+    KwImpl,
+    #[error("the `type` keyword")]
+    KwType,
+    #[error("the `static` keyword")]
+    KwStatic,
+    #[error("the `where` keyword")]
+    KwWhere,
+    #[error("the `for` keyword")]
+    KwFor,
+    #[error("a minus sign -")]
+    Minus,
+    #[error("an asterisk *")]
+    Star,
+    #[error("a slash /")]
+    Slash,
+    #[error("a percent sign %")]
+    Percent,
+    #[error("a double equals sign ==")]
+    EqEq,
+    #[error("a not-equals sign !=")]
+    NotEq,
+    #[error("a less-than sign <")]
+    Lt,
+    #[error("a less-than-or-equal sign <=")]
+    Le,
+    #[error("a greater-than sign >")]
+    Gt,
+    #[error("a greater-than-or-equal sign >=")]
+    Ge,
+    #[error("a double ampersand &&")]
+    AndAnd,
+    #[error("a double pipe ||")]
+    OrOr,
+    #[error("a pipe |")]
+    Pipe,
+    #[error("a caret ^")]
+    Caret,
+    #[error("a left shift <<")]
+    Shl,
+    #[error("a right shift >>")]
+    Shr,
+    #[error("a fat arrow =>")]
+    FatArrow,
+    #[error("the `if` keyword")]
+    KwIf,
+    #[error("the `else` keyword")]
+    KwElse,
+    #[error("the `unsafe` keyword")]
+    KwUnsafe,
+    #[error("the `return` keyword")]
+    KwReturn,
+    #[error("the `loop` keyword")]
+    KwLoop,
+    #[error("the `while` keyword")]
+    KwWhile,
+    #[error("the `break` keyword")]
+    KwBreak,
+    #[error("the `continue` keyword")]
+    KwContinue,
+    #[error("the `dyn` keyword")]
+    KwDyn,
+    #[error("the `as` keyword")]
+    KwAs,
+    #[error("the `mod` keyword")]
+    KwMod,
+    #[error("the `use` keyword")]
+    KwUse,
+    #[error("an invalid token")]
+    Error,
 }
 
 impl Token {
@@ -144,17 +387,28 @@ impl Token {
         match self {
             Self::At => TokenKind::At,
             Self::Comma => TokenKind::Comma,
+            Self::Comment(_) => TokenKind::Comment,
+            Self::DocComment(_) => TokenKind::DocComment,
             Self::OpenCurly => TokenKind::OpenCurly,
             Self::CloseCurly => TokenKind::CloseCurly,
             Self::Dot => TokenKind::Dot,
             Self::Eq => TokenKind::Eq,
             Self::Identifier(_) => TokenKind::Identifier,
             Self::StringLiteral(_) => TokenKind::StringLiteral,
+            Self::ByteStringLiteral(_) => TokenKind::ByteStringLiteral,
+            Self::CharLiteral(_) => TokenKind::CharLiteral,
+            Self::ByteLiteral(_) => TokenKind::ByteLiteral,
+            Self::IntLiteral(_) => TokenKind::IntLiteral,
+            Self::FloatLiteral(_) => TokenKind::FloatLiteral,
             Self::KwConst => TokenKind::KwConst,
             Self::KwPub => TokenKind::KwPub,
             Self::OpenParen => TokenKind::OpenParen,
             Self::CloseParen => TokenKind::CloseParen,
+            Self::OpenBracket => TokenKind::OpenBracket,
+            Self::CloseBracket => TokenKind::CloseBracket,
             Self::Excl => TokenKind::Excl,
+            Self::Plus => TokenKind::Plus,
+            Self::Question => TokenKind::Question,
             Self::KwLet => TokenKind::KwLet,
             Self::Colon => TokenKind::Colon,
             Self::Amp => TokenKind::Amp,
@@ -167,48 +421,162 @@ impl Token {
             Self::KwEnum => TokenKind::KwEnum,
             Self::KwTrait => TokenKind::KwTrait,
             Self::KwImpl => TokenKind::KwImpl,
+            Self::KwType => TokenKind::KwType,
+            Self::KwStatic => TokenKind::KwStatic,
+            Self::KwWhere => TokenKind::KwWhere,
+            Self::KwFor => TokenKind::KwFor,
+            Self::Minus => TokenKind::Minus,
+            Self::Star => TokenKind::Star,
+            Self::Slash => TokenKind::Slash,
+            Self::Percent => TokenKind::Percent,
+            Self::EqEq => TokenKind::EqEq,
+            Self::NotEq => TokenKind::NotEq,
+            Self::Lt => TokenKind::Lt,
+            Self::Le => TokenKind::Le,
+            Self::Gt => TokenKind::Gt,
+            Self::Ge => TokenKind::Ge,
+            Self::AndAnd => TokenKind::AndAnd,
+            Self::OrOr => TokenKind::OrOr,
+            Self::Pipe => TokenKind::Pipe,
+            Self::Caret => TokenKind::Caret,
+            Self::Shl => TokenKind::Shl,
+            Self::Shr => TokenKind::Shr,
+            Self::FatArrow => TokenKind::FatArrow,
+            Self::KwIf => TokenKind::KwIf,
+            Self::KwElse => TokenKind::KwElse,
+            Self::KwUnsafe => TokenKind::KwUnsafe,
+            Self::KwReturn => TokenKind::KwReturn,
+            Self::KwLoop => TokenKind::KwLoop,
+            Self::KwWhile => TokenKind::KwWhile,
+            Self::KwBreak => TokenKind::KwBreak,
+            Self::KwContinue => TokenKind::KwContinue,
+            Self::KwDyn => TokenKind::KwDyn,
+            Self::KwAs => TokenKind::KwAs,
+            Self::KwMod => TokenKind::KwMod,
+            Self::KwUse => TokenKind::KwUse,
+            Self::Error => TokenKind::Error,
         }
     }
-}
 
-#[derive(thiserror::Error, Debug, miette::Diagnostic)]
-#[error("lexer error")]
-#[diagnostic(code = "tangi::lexer")]
-pub struct LexerError(#[label = "here"] pub SourceSpan);
+    /// Whether this token carries no meaning for the parser. Doc comments are not trivia,
+    /// since they end up as `doc` attributes.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Comment(_))
+    }
+
+    /// How this token is written if it is a keyword, which can't be used as a name.
+    pub fn keyword(&self) -> Option<&'static str> {
+        let keyword = match self {
+            Self::KwConst => "const",
+            Self::KwRef => "ref",
+            Self::KwPub => "pub",
+            Self::KwLet => "let",
+            Self::KwMut => "mut",
+            Self::KwTrue => "true",
+            Self::KwFalse => "false",
+            Self::KwStruct => "struct",
+            Self::KwEnum => "enum",
+            Self::KwTrait => "trait",
+            Self::KwImpl => "impl",
+            Self::KwType => "type",
+            Self::KwStatic => "static",
+            Self::KwWhere => "where",
+            Self::KwFor => "for",
+            Self::KwIf => "if",
+            Self::KwElse => "else",
+            Self::KwUnsafe => "unsafe",
+            Self::KwReturn => "return",
+            Self::KwLoop => "loop",
+            Self::KwWhile => "while",
+            Self::KwBreak => "break",
+            Self::KwContinue => "continue",
+            Self::KwDyn => "dyn",
+            Self::KwAs => "as",
+            Self::KwMod => "mod",
+            Self::KwUse => "use",
+            _ => return None,
+        };
 
-pub struct Help {
-    j: <Vec<(Token, Range<usize>)> as IntoIterator>::IntoIter,
+        Some(keyword)
+    }
 }
 
-impl Help {
-    pub fn new(input: &str) -> Result<Self, Vec<LexerError>> {
-        let mut lexer = Token::lexer(input).spanned();
+/// Lexer errors, shared between a [`Help`] and whoever consumes its tokens.
+pub type LexerErrors = Rc<RefCell<Vec<LexerError>>>;
 
-        let mut help = vec![];
-        let mut guh = vec![];
-        while let Some(s) = lexer.next() {
-            match s {
-                (Ok(j), span) => guh.push((j, span)),
-                (Err(()), span) => help.push(LexerError(span.into()))
-            }
-        };
+/// For every token yielded by a [`Help`] so far, whether there is a line break between it and
+/// the token before it. tangi has no semicolons, so that is what ends a statement.
+pub type LineStarts = Rc<RefCell<Vec<bool>>>;
 
-        if help.is_empty() {
-            Ok(Self { j: guh.into_iter() })
-        } else {
-            Err(help)
+/// Lazily lexes tokens as they are pulled, straight off of a [`SpannedIter`].
+///
+/// Anything that fails to lex becomes a [`Token::Error`], so the tokens can still be parsed,
+/// with the reasons pushed to [`Help::errors`].
+pub struct Help<'src> {
+    lexer: SpannedIter<'src, Token>,
+    source: &'src str,
+    errors: LexerErrors,
+    line_starts: LineStarts,
+    last_end: usize,
+    skip_trivia: bool,
+}
+
+impl<'src> Help<'src> {
+    pub fn new(source: &'src str) -> Self {
+        Self {
+            lexer: Token::lexer(source).spanned(),
+            source,
+            errors: LexerErrors::default(),
+            line_starts: LineStarts::default(),
+            last_end: 0,
+            skip_trivia: false,
         }
     }
+
+    /// Drops comments, keeping only the tokens the parser cares about.
+    pub fn without_trivia(mut self) -> Self {
+        self.skip_trivia = true;
+        self
+    }
+
+    /// The errors for the part of the input that has been lexed so far.
+    pub fn errors(&self) -> LexerErrors {
+        self.errors.clone()
+    }
+
+    /// Which of the tokens lexed so far start a new line.
+    pub fn line_starts(&self) -> LineStarts {
+        self.line_starts.clone()
+    }
+
+    fn yield_token(&mut self, token: Token, span: Range<usize>) -> Option<(Token, Range<usize>)> {
+        let gap = &self.source[self.last_end.min(span.start)..span.start];
+        self.line_starts.borrow_mut().push(gap.contains('\n'));
+        self.last_end = span.end;
+
+        Some((token, span))
+    }
 }
 
-impl Iterator for Help {
+impl Iterator for Help<'_> {
     type Item = (Token, Range<usize>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.j.next()
-    }
+        loop {
+            match self.lexer.next()? {
+                (Ok(token), _) if self.skip_trivia && token.is_trivia() => continue,
+                (Ok(token), span) => return self.yield_token(token, span),
+                (Err(error), span) => {
+                    let recover = error.recover();
+                    self.errors
+                        .borrow_mut()
+                        .extend(error.into_errors(self.source, span.clone()));
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.j.size_hint()
+                    if recover {
+                        return self.yield_token(Token::Error, span);
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,534 @@
+use std::fmt;
+
+use logos::Lexer;
+use miette::SourceSpan;
+
+use crate::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary = 2,
+    Octal = 8,
+    Decimal = 10,
+    Hexadecimal = 16,
+}
+
+impl fmt::Display for Radix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Binary => "binary",
+            Self::Octal => "octal",
+            Self::Decimal => "decimal",
+            Self::Hexadecimal => "hexadecimal",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+}
+
+impl IntSuffix {
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Some(match suffix {
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "i128" => Self::I128,
+            "isize" => Self::Isize,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "u128" => Self::U128,
+            "usize" => Self::Usize,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::Isize => "isize",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::Usize => "usize",
+        }
+    }
+
+    pub fn signed(&self) -> bool {
+        matches!(
+            self,
+            Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128 | Self::Isize
+        )
+    }
+
+    /// The width of the type, or `None` for the pointer-sized `isize`/`usize`.
+    pub fn bits(&self) -> Option<u8> {
+        match self {
+            Self::I8 | Self::U8 => Some(8),
+            Self::I16 | Self::U16 => Some(16),
+            Self::I32 | Self::U32 => Some(32),
+            Self::I64 | Self::U64 => Some(64),
+            Self::I128 | Self::U128 => Some(128),
+            Self::Isize | Self::Usize => None,
+        }
+    }
+
+    /// The largest magnitude a literal with this suffix may have.
+    ///
+    /// Literals are always lexed unsigned (`-` is a separate operator), so signed
+    /// types accept one past their maximum to allow writing e.g. `-128i8`.
+    /// Pointer-sized types are checked as if they were 64 bits wide, since the
+    /// lexer knows nothing about the target.
+    pub fn max_magnitude(&self) -> u128 {
+        let bits = self.bits().unwrap_or(64) as u32;
+
+        if self.signed() {
+            1 << (bits - 1)
+        } else if bits == 128 {
+            u128::MAX
+        } else {
+            (1 << bits) - 1
+        }
+    }
+}
+
+impl fmt::Display for IntSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatSuffix {
+    F32,
+    F64,
+}
+
+impl FloatSuffix {
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FloatSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntLiteral {
+    pub value: u128,
+    pub radix: Radix,
+    pub suffix: Option<IntSuffix>,
+}
+
+impl fmt::Display for IntLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.radix {
+            Radix::Binary => write!(f, "{:#b}", self.value)?,
+            Radix::Octal => write!(f, "{:#o}", self.value)?,
+            Radix::Decimal => write!(f, "{}", self.value)?,
+            Radix::Hexadecimal => write!(f, "{:#x}", self.value)?,
+        }
+
+        if let Some(suffix) = self.suffix {
+            write!(f, "{suffix}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatLiteral {
+    pub value: f64,
+    pub suffix: Option<FloatSuffix>,
+}
+
+impl fmt::Display for FloatLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.value)?;
+
+        if let Some(suffix) = self.suffix {
+            write!(f, "{suffix}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, miette::Diagnostic)]
+pub enum NumberError {
+    #[error("integer literal is too large for `{ty}`")]
+    #[diagnostic(
+        code(tangi::lexer::int_overflow),
+        help("the maximum value of `{ty}` is {max}")
+    )]
+    Overflow {
+        ty: &'static str,
+        max: u128,
+        #[label("this literal does not fit")]
+        at: SourceSpan,
+    },
+    #[error("float literal is too large for `{ty}`")]
+    #[diagnostic(
+        code(tangi::lexer::float_overflow),
+        help("the maximum value of `{ty}` is {max:e}")
+    )]
+    FloatOverflow {
+        ty: &'static str,
+        max: f64,
+        #[label("this literal does not fit")]
+        at: SourceSpan,
+    },
+    #[error("invalid digit `{digit}` in {radix} literal")]
+    #[diagnostic(code(tangi::lexer::invalid_digit))]
+    InvalidDigit {
+        digit: char,
+        radix: Radix,
+        #[label("not a {radix} digit")]
+        at: SourceSpan,
+    },
+    #[error("no valid digits found for number")]
+    #[diagnostic(code(tangi::lexer::empty_number))]
+    NoDigits {
+        radix: Radix,
+        #[label("expected at least one {radix} digit")]
+        at: SourceSpan,
+    },
+    #[error("expected at least one digit in exponent")]
+    #[diagnostic(code(tangi::lexer::empty_exponent))]
+    EmptyExponent {
+        #[label("exponent has no digits")]
+        at: SourceSpan,
+    },
+    #[error("invalid suffix `{suffix}` for {kind} literal")]
+    #[diagnostic(
+        code(tangi::lexer::invalid_suffix),
+        help("the suffix must be one of the numeric types (`u32`, `isize`, `f32`, etc.)")
+    )]
+    InvalidSuffix {
+        suffix: String,
+        kind: &'static str,
+        #[label("invalid suffix")]
+        at: SourceSpan,
+    },
+}
+
+fn span(lex: &Lexer<'_, Token>, within: std::ops::Range<usize>) -> SourceSpan {
+    let start = lex.span().start;
+
+    (start + within.start..start + within.end).into()
+}
+
+/// Splits `slice` at the first character that cannot continue the number, returning the
+/// digits and the (possibly empty) suffix.
+fn split_suffix(slice: &str, is_digit: impl Fn(char) -> bool) -> (&str, &str) {
+    let end = slice
+        .find(|ch: char| !(is_digit(ch) || ch == '_'))
+        .unwrap_or(slice.len());
+
+    slice.split_at(end)
+}
+
+pub(crate) fn lex_int(lex: &mut Lexer<'_, Token>) -> Result<IntLiteral, NumberError> {
+    let slice = lex.slice();
+
+    let (radix, prefix) = match slice.get(..2) {
+        Some("0b") => (Radix::Binary, 2),
+        Some("0o") => (Radix::Octal, 2),
+        Some("0x") => (Radix::Hexadecimal, 2),
+        _ => (Radix::Decimal, 0),
+    };
+
+    let (digits, suffix) = split_suffix(&slice[prefix..], |ch| match radix {
+        Radix::Hexadecimal => ch.is_ascii_hexdigit(),
+        _ => ch.is_ascii_digit(),
+    });
+    let suffix_start = prefix + digits.len();
+
+    let mut value: u128 = 0;
+    let mut seen_digit = false;
+    let mut overflowed = false;
+
+    for (offset, ch) in digits.char_indices() {
+        if ch == '_' {
+            continue;
+        }
+
+        let Some(digit) = ch.to_digit(radix as u32) else {
+            let at = prefix + offset;
+
+            return Err(NumberError::InvalidDigit {
+                digit: ch,
+                radix,
+                at: span(lex, at..at + 1),
+            });
+        };
+
+        seen_digit = true;
+        match value
+            .checked_mul(radix as u128)
+            .and_then(|value| value.checked_add(digit as u128))
+        {
+            Some(next) => value = next,
+            None => overflowed = true,
+        }
+    }
+
+    if !seen_digit {
+        return Err(NumberError::NoDigits {
+            radix,
+            at: lex.span().into(),
+        });
+    }
+
+    let suffix = match suffix {
+        "" => None,
+        exponent if radix == Radix::Decimal && exponent.starts_with(['e', 'E']) => {
+            return Err(NumberError::EmptyExponent {
+                at: span(lex, suffix_start..slice.len()),
+            })
+        }
+        other => Some(IntSuffix::from_suffix(other).ok_or_else(|| NumberError::InvalidSuffix {
+            suffix: other.to_owned(),
+            kind: "integer",
+            at: span(lex, suffix_start..slice.len()),
+        })?),
+    };
+
+    let max = suffix.map_or(u128::MAX, |suffix| suffix.max_magnitude());
+
+    if overflowed || value > max {
+        return Err(NumberError::Overflow {
+            ty: suffix.map_or("u128", |suffix| suffix.name()),
+            max,
+            at: lex.span().into(),
+        });
+    }
+
+    Ok(IntLiteral {
+        value,
+        radix,
+        suffix,
+    })
+}
+
+pub(crate) fn lex_float(lex: &mut Lexer<'_, Token>) -> Result<FloatLiteral, NumberError> {
+    let slice = lex.slice();
+
+    let (mantissa, rest) = split_suffix(slice, |ch| ch.is_ascii_digit() || ch == '.');
+
+    let (exponent, suffix) = match rest.strip_prefix(['e', 'E']) {
+        Some(exponent) => {
+            let sign = usize::from(exponent.starts_with(['+', '-']));
+            let (digits, suffix) = split_suffix(&exponent[sign..], |ch| ch.is_ascii_digit());
+
+            if !digits.contains(|ch: char| ch.is_ascii_digit()) {
+                let start = mantissa.len();
+
+                return Err(NumberError::EmptyExponent {
+                    at: span(lex, start..start + 1 + sign + digits.len()),
+                });
+            }
+
+            (&rest[..1 + sign + digits.len()], suffix)
+        }
+        None => ("", rest),
+    };
+
+    let suffix = match suffix {
+        "" => None,
+        other => Some(FloatSuffix::from_suffix(other).ok_or_else(|| {
+            NumberError::InvalidSuffix {
+                suffix: other.to_owned(),
+                kind: "float",
+                at: span(lex, slice.len() - other.len()..slice.len()),
+            }
+        })?),
+    };
+
+    let value: f64 = format!("{mantissa}{exponent}")
+        .replace('_', "")
+        .parse()
+        .expect("float literal regex only matches valid floats");
+
+    let (ty, max, finite) = match suffix {
+        Some(FloatSuffix::F32) => ("f32", f32::MAX as f64, (value as f32).is_finite()),
+        _ => ("f64", f64::MAX, value.is_finite()),
+    };
+
+    if !finite {
+        return Err(NumberError::FloatOverflow {
+            ty,
+            max,
+            at: lex.span().into(),
+        });
+    }
+
+    Ok(FloatLiteral { value, suffix })
+}
+
+#[cfg(test)]
+mod tests {
+    use logos::Logos;
+
+    use super::*;
+    use crate::LexingError;
+
+    fn lex(source: &str) -> Vec<Result<Token, LexingError>> {
+        Token::lexer(source).collect()
+    }
+
+    fn int(value: u128, radix: Radix, suffix: Option<IntSuffix>) -> Result<Token, LexingError> {
+        Ok(Token::IntLiteral(IntLiteral { value, radix, suffix }))
+    }
+
+    fn float(value: f64, suffix: Option<FloatSuffix>) -> Result<Token, LexingError> {
+        Ok(Token::FloatLiteral(FloatLiteral { value, suffix }))
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        assert_eq!(
+            lex("0b1010 0o17 0xfF 1_000"),
+            [
+                int(10, Radix::Binary, None),
+                int(15, Radix::Octal, None),
+                int(255, Radix::Hexadecimal, None),
+                int(1000, Radix::Decimal, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn int_suffixes() {
+        assert_eq!(
+            lex("1u8 0x10i64 7usize"),
+            [
+                int(1, Radix::Decimal, Some(IntSuffix::U8)),
+                int(16, Radix::Hexadecimal, Some(IntSuffix::I64)),
+                int(7, Radix::Decimal, Some(IntSuffix::Usize)),
+            ]
+        );
+    }
+
+    #[test]
+    fn int_overflow() {
+        assert_eq!(lex("255u8"), [int(255, Radix::Decimal, Some(IntSuffix::U8))]);
+        assert_eq!(lex("128i8"), [int(128, Radix::Decimal, Some(IntSuffix::I8))]);
+
+        assert_eq!(
+            lex("256u8"),
+            [Err(LexingError::Number(NumberError::Overflow {
+                ty: "u8",
+                max: 255,
+                at: (0..5).into(),
+            }))]
+        );
+        assert!(matches!(
+            lex("340282366920938463463374607431768211456")[..],
+            [Err(LexingError::Number(NumberError::Overflow { ty: "u128", .. }))]
+        ));
+    }
+
+    #[test]
+    fn invalid_digits_and_suffixes() {
+        assert_eq!(
+            lex("0b102"),
+            [Err(LexingError::Number(NumberError::InvalidDigit {
+                digit: '2',
+                radix: Radix::Binary,
+                at: (4..5).into(),
+            }))]
+        );
+        assert!(matches!(
+            lex("0x")[..],
+            [Err(LexingError::Number(NumberError::NoDigits {
+                radix: Radix::Hexadecimal,
+                ..
+            }))]
+        ));
+        assert_eq!(
+            lex("12u7"),
+            [Err(LexingError::Number(NumberError::InvalidSuffix {
+                suffix: "u7".to_owned(),
+                kind: "integer",
+                at: (2..4).into(),
+            }))]
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(
+            lex("1.5 2e3 1_0.2_5E-1 3f32 0.5f64"),
+            [
+                float(1.5, None),
+                float(2000.0, None),
+                float(1.025, None),
+                float(3.0, Some(FloatSuffix::F32)),
+                float(0.5, Some(FloatSuffix::F64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn float_errors() {
+        assert_eq!(
+            lex("1e_"),
+            [Err(LexingError::Number(NumberError::EmptyExponent { at: (1..3).into() }))]
+        );
+        assert!(matches!(
+            lex("1.0f16")[..],
+            [Err(LexingError::Number(NumberError::InvalidSuffix { kind: "float", .. }))]
+        ));
+    }
+
+    #[test]
+    fn float_overflow() {
+        assert_eq!(
+            lex("1e400"),
+            [Err(LexingError::Number(NumberError::FloatOverflow {
+                ty: "f64",
+                max: f64::MAX,
+                at: (0..5).into(),
+            }))]
+        );
+        assert!(matches!(
+            lex("1e39f32")[..],
+            [Err(LexingError::Number(NumberError::FloatOverflow { ty: "f32", .. }))]
+        ));
+        assert_eq!(lex("1e38f32"), [float(1e38, Some(FloatSuffix::F32))]);
+    }
+}
@@ -0,0 +1,406 @@
+use std::ops::Range;
+
+use logos::Lexer;
+use miette::SourceSpan;
+
+use crate::{LexingError, Token};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, miette::Diagnostic)]
+pub enum StringError {
+    #[error("unknown character escape `\\{escape}`")]
+    #[diagnostic(
+        code(tangi::lexer::unknown_escape),
+        help("if you meant to write a literal backslash, escape it: `\\\\`")
+    )]
+    UnknownEscape {
+        escape: char,
+        #[label("unknown escape")]
+        at: SourceSpan,
+    },
+    #[error("invalid `\\x` escape")]
+    #[diagnostic(
+        code(tangi::lexer::invalid_hex_escape),
+        help("a `\\x` escape must be followed by exactly two hex digits, like `\\x7F`")
+    )]
+    InvalidHexEscape {
+        #[label("expected two hex digits")]
+        at: SourceSpan,
+    },
+    #[error("out of range hex escape")]
+    #[diagnostic(
+        code(tangi::lexer::hex_escape_out_of_range),
+        help("outside of byte literals, a `\\x` escape must be at most `\\x7F`; use `\\u{{...}}` instead")
+    )]
+    HexEscapeOutOfRange {
+        #[label("must be at most `\\x7F`")]
+        at: SourceSpan,
+    },
+    #[error("invalid unicode escape: {reason}")]
+    #[diagnostic(
+        code(tangi::lexer::invalid_unicode_escape),
+        help("a unicode escape looks like `\\u{{1F600}}`, with 1 to 6 hex digits naming a valid character")
+    )]
+    InvalidUnicodeEscape {
+        reason: &'static str,
+        #[label("{reason}")]
+        at: SourceSpan,
+    },
+    #[error("unicode escape in byte literal")]
+    #[diagnostic(
+        code(tangi::lexer::unicode_escape_in_byte),
+        help("byte literals can only contain ASCII, use a `\\x` escape for other bytes")
+    )]
+    UnicodeEscapeInByte {
+        #[label("not allowed in a byte literal")]
+        at: SourceSpan,
+    },
+    #[error("non-ASCII character `{ch}` in byte literal")]
+    #[diagnostic(
+        code(tangi::lexer::non_ascii_in_byte),
+        help("use a `\\x` escape for bytes above `\\x7F`")
+    )]
+    NonAsciiInByte {
+        ch: char,
+        #[label("must be ASCII")]
+        at: SourceSpan,
+    },
+    #[error("character literal may only contain one codepoint")]
+    #[diagnostic(
+        code(tangi::lexer::char_too_long),
+        help("if you meant to write a string literal, use double quotes")
+    )]
+    CharTooLong {
+        #[label("more than one character")]
+        at: SourceSpan,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Str,
+    Char,
+    ByteStr,
+    Byte,
+}
+
+impl Mode {
+    fn is_byte(self) -> bool {
+        matches!(self, Self::ByteStr | Self::Byte)
+    }
+}
+
+/// Decodes the escapes in `content`, which starts at byte `offset` of the source.
+///
+/// Byte modes only ever produce characters in `0..=0xFF`, so callers can narrow them to `u8`.
+/// Every invalid escape is collected instead of stopping at the first one.
+fn unescape(content: &str, offset: usize, mode: Mode) -> Result<Vec<char>, Vec<StringError>> {
+    let span = |range: Range<usize>| -> SourceSpan { (offset + range.start..offset + range.end).into() };
+
+    let mut out = vec![];
+    let mut errors = vec![];
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if ch != '\\' {
+            if mode.is_byte() && !ch.is_ascii() {
+                errors.push(StringError::NonAsciiInByte {
+                    ch,
+                    at: span(start..start + ch.len_utf8()),
+                });
+            }
+
+            out.push(ch);
+            continue;
+        }
+
+        let Some((_, escape)) = chars.next() else {
+            // the literal regexes never end on an unpaired backslash
+            break;
+        };
+
+        match escape {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' | '\'' | '"' => out.push(escape),
+            '\n' if matches!(mode, Mode::Str | Mode::ByteStr) => {
+                while chars.next_if(|(_, ch)| ch.is_whitespace()).is_some() {}
+            }
+            'x' => {
+                let digits: String = (0..2)
+                    .map_while(|_| chars.next_if(|(_, ch)| ch.is_ascii_hexdigit()))
+                    .map(|(_, ch)| ch)
+                    .collect();
+                let end = start + 2 + digits.len();
+
+                if digits.len() != 2 {
+                    errors.push(StringError::InvalidHexEscape {
+                        at: span(start..end),
+                    });
+                    continue;
+                }
+
+                let value = u8::from_str_radix(&digits, 16).unwrap();
+
+                if value > 0x7F && !mode.is_byte() {
+                    errors.push(StringError::HexEscapeOutOfRange {
+                        at: span(start..end),
+                    });
+                }
+
+                out.push(char::from(value));
+            }
+            'u' => match unicode_escape(&mut chars, start) {
+                Ok((_, end)) if mode.is_byte() => {
+                    errors.push(StringError::UnicodeEscapeInByte {
+                        at: span(start..end),
+                    })
+                }
+                Ok((ch, _)) => out.push(ch),
+                Err((reason, end)) => errors.push(StringError::InvalidUnicodeEscape {
+                    reason,
+                    at: span(start..end),
+                }),
+            },
+            other => errors.push(StringError::UnknownEscape {
+                escape: other,
+                at: span(start..start + 1 + other.len_utf8()),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(out)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses the `{...}` part of a `\u{...}` escape starting at `start`, returning the character
+/// (or why it is invalid) together with the end of the escape.
+fn unicode_escape(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    start: usize,
+) -> Result<(char, usize), (&'static str, usize)> {
+    let mut end = start + 2;
+
+    if chars.next_if(|(_, ch)| *ch == '{').is_none() {
+        return Err(("expected `{`", end));
+    }
+    end += 1;
+
+    let mut digits = String::new();
+    while let Some((_, ch)) = chars.next_if(|(_, ch)| ch.is_ascii_hexdigit() || *ch == '_') {
+        end += 1;
+        if ch != '_' {
+            digits.push(ch);
+        }
+    }
+
+    if chars.next_if(|(_, ch)| *ch == '}').is_none() {
+        return Err(("expected `}`", end));
+    }
+    end += 1;
+
+    match digits.len() {
+        0 => Err(("empty escape", end)),
+        7.. => Err(("more than 6 hex digits", end)),
+        _ => char::from_u32(u32::from_str_radix(&digits, 16).unwrap())
+            .map(|ch| (ch, end))
+            .ok_or(("not a valid character", end)),
+    }
+}
+
+/// Decodes a literal whose `prefix_len` leading and one trailing delimiter bytes get stripped.
+fn lex_quoted(
+    lex: &mut Lexer<'_, Token>,
+    prefix_len: usize,
+    mode: Mode,
+) -> Result<Vec<char>, LexingError> {
+    let slice = lex.slice();
+    let content = &slice[prefix_len..slice.len() - 1];
+
+    unescape(content, lex.span().start + prefix_len, mode).map_err(LexingError::String)
+}
+
+pub(crate) fn lex_string(lex: &mut Lexer<'_, Token>) -> Result<String, LexingError> {
+    lex_quoted(lex, 1, Mode::Str).map(String::from_iter)
+}
+
+/// Called for a string that runs into the end of the input without its closing `"`.
+pub(crate) fn unterminated<T>(_: &mut Lexer<'_, Token>) -> Result<T, LexingError> {
+    Err(LexingError::UnterminatedString("\"".to_owned()))
+}
+
+pub(crate) fn lex_byte_string(lex: &mut Lexer<'_, Token>) -> Result<Vec<u8>, LexingError> {
+    lex_quoted(lex, 2, Mode::ByteStr).map(|chars| chars.into_iter().map(|ch| ch as u8).collect())
+}
+
+fn single(lex: &Lexer<'_, Token>, chars: Vec<char>) -> Result<char, LexingError> {
+    match chars[..] {
+        [ch] => Ok(ch),
+        _ => Err(LexingError::String(vec![StringError::CharTooLong {
+            at: lex.span().into(),
+        }])),
+    }
+}
+
+pub(crate) fn lex_char(lex: &mut Lexer<'_, Token>) -> Result<char, LexingError> {
+    let chars = lex_quoted(lex, 1, Mode::Char)?;
+
+    single(lex, chars)
+}
+
+pub(crate) fn lex_byte(lex: &mut Lexer<'_, Token>) -> Result<u8, LexingError> {
+    let chars = lex_quoted(lex, 2, Mode::Byte)?;
+
+    single(lex, chars).map(|ch| ch as u8)
+}
+
+/// Scans a raw string after its opening `r#*"`, which may use any number of `#`s, returning
+/// its contents and where they start in the source.
+fn lex_raw(lex: &mut Lexer<'_, Token>, prefix_len: usize) -> Result<(String, usize), LexingError> {
+    let hashes = lex.slice().len() - prefix_len - 1;
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let content_start = lex.span().end;
+
+    match lex.remainder().find(&terminator) {
+        Some(end) => {
+            let content = lex.remainder()[..end].to_owned();
+            lex.bump(end + terminator.len());
+
+            Ok((content, content_start))
+        }
+        None => {
+            lex.bump(lex.remainder().len());
+
+            Err(LexingError::UnterminatedString(terminator))
+        }
+    }
+}
+
+pub(crate) fn lex_raw_string(lex: &mut Lexer<'_, Token>) -> Result<String, LexingError> {
+    lex_raw(lex, 1).map(|(content, _)| content)
+}
+
+pub(crate) fn lex_raw_byte_string(lex: &mut Lexer<'_, Token>) -> Result<Vec<u8>, LexingError> {
+    let (content, start) = lex_raw(lex, 2)?;
+
+    let errors: Vec<_> = content
+        .char_indices()
+        .filter(|(_, ch)| !ch.is_ascii())
+        .map(|(at, ch)| StringError::NonAsciiInByte {
+            ch,
+            at: (start + at..start + at + ch.len_utf8()).into(),
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(content.into_bytes())
+    } else {
+        Err(LexingError::String(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use logos::Logos;
+
+    use super::*;
+
+    fn lex(source: &str) -> Vec<Result<Token, LexingError>> {
+        Token::lexer(source).collect()
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            lex(r#""a\n\t\\\"\x41\u{1F600}""#),
+            [Ok(Token::StringLiteral("a\n\t\\\"A\u{1F600}".to_owned()))]
+        );
+        assert_eq!(
+            lex("\"one \\\n    two\""),
+            [Ok(Token::StringLiteral("one two".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn invalid_escapes_are_all_reported() {
+        assert_eq!(
+            lex(r#""\q\x4\x80""#),
+            [Err(LexingError::String(vec![
+                StringError::UnknownEscape {
+                    escape: 'q',
+                    at: (1..3).into(),
+                },
+                StringError::InvalidHexEscape { at: (3..6).into() },
+                StringError::HexEscapeOutOfRange { at: (6..10).into() },
+            ]))]
+        );
+        assert!(matches!(
+            &lex(r#""\u{110000}""#)[..],
+            [Err(LexingError::String(errors))]
+                if matches!(errors[..], [StringError::InvalidUnicodeEscape { reason: "not a valid character", .. }])
+        ));
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert_eq!(
+            lex(r###"r#"no \n "escapes""# r"x""###),
+            [
+                Ok(Token::StringLiteral(r#"no \n "escapes""#.to_owned())),
+                Ok(Token::StringLiteral("x".to_owned())),
+            ]
+        );
+        assert_eq!(
+            lex(r###"r##"open"#"###),
+            [Err(LexingError::UnterminatedString("\"##".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn chars_and_bytes() {
+        assert_eq!(
+            lex(r"'a' '\'' b'\xFF' b'z'"),
+            [
+                Ok(Token::CharLiteral('a')),
+                Ok(Token::CharLiteral('\'')),
+                Ok(Token::ByteLiteral(0xFF)),
+                Ok(Token::ByteLiteral(b'z')),
+            ]
+        );
+        assert_eq!(
+            lex(r"'\nx'"),
+            [Err(LexingError::String(vec![StringError::CharTooLong { at: (0..5).into() }]))]
+        );
+        assert_eq!(
+            lex("b\"é\""),
+            [Err(LexingError::String(vec![StringError::NonAsciiInByte {
+                ch: 'é',
+                at: (2..4).into(),
+            }]))]
+        );
+    }
+
+    #[test]
+    fn byte_strings() {
+        assert_eq!(
+            lex(r#"b"\x00\xFFa" br"\n""#),
+            [
+                Ok(Token::ByteStringLiteral(vec![0, 0xFF, b'a'])),
+                Ok(Token::ByteStringLiteral(b"\\n".to_vec())),
+            ]
+        );
+        assert!(matches!(
+            &lex(r#"b"\u{41}""#)[..],
+            [Err(LexingError::String(errors))] if matches!(errors[..], [StringError::UnicodeEscapeInByte { .. }])
+        ));
+    }
+
+    #[test]
+    fn unterminated_strings() {
+        assert_eq!(lex("\"abc"), [Err(LexingError::UnterminatedString("\"".to_owned()))]);
+    }
+}
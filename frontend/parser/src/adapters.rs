@@ -1,38 +1,90 @@
 use super::*;
-use std::{ops::Range, fmt::Debug};
+use std::{cell::RefCell, fmt::Debug, ops::Range};
 use tangic_lexer::*;
 use aott::input::SpannedInput;
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Extra;
 
-impl ParserExtras<TokenStream> for Extra {
+impl<'src> ParserExtras<TokenStream<'src>> for Extra {
     type Error = ParserError;
     type Context = ();
 }
 
-pub struct TokenStream {
-    pub input: String,
-    what: SpannedInput<Token, Range<usize>, Stream<Help>>,
+/// The parser's input, lexing the source lazily as the parser pulls tokens.
+pub struct TokenStream<'src> {
+    pub input: &'src str,
+    /// The file the spans in the AST will point into.
+    pub file: ast::FileId,
+    errors: LexerErrors,
+    line_starts: LineStarts,
+    recovered: RefCell<Vec<ParserError>>,
+    what: SpannedInput<Token, Range<usize>, Stream<Help<'src>>>,
 }
 
-impl Debug for TokenStream {
+impl Debug for TokenStream<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "TokenStream {{ input: <redacted> }}")
     }
 }
 
-impl TokenStream {
-    pub fn new(input: String) -> Result<Self, Vec<LexerError>> {
-        let what = Stream::from_iter(Help::new(&input)?).spanned(input.len()-1..input.len());
+impl<'src> TokenStream<'src> {
+    pub fn new(input: &'src str, file: ast::FileId) -> Self {
+        let tokens = Help::new(input).without_trivia();
+        let errors = tokens.errors();
+        let line_starts = tokens.line_starts();
+        let what = Stream::from_iter(tokens).spanned(input.len().saturating_sub(1)..input.len());
 
-        Ok(Self { input, what })
+        Self {
+            input,
+            file,
+            errors,
+            line_starts,
+            recovered: RefCell::default(),
+            what,
+        }
+    }
+
+    /// Takes the errors for the tokens lexed so far. Lexer errors do not stop parsing, they
+    /// come out as [`Token::Error`]s instead.
+    pub fn take_lexer_errors(&self) -> Vec<LexerError> {
+        self.errors.take()
+    }
+
+    /// Records an error that parsing skipped past and carried on from.
+    pub fn report(&self, error: ParserError) {
+        self.recovered.borrow_mut().push(error);
+    }
+
+    pub fn take_recovered(&self) -> Vec<ParserError> {
+        self.recovered.take()
+    }
+
+    /// How many errors have been recovered from so far. Rewinding the input doesn't roll these
+    /// back, so a parser that backtracks takes this first and truncates to it when it rewinds,
+    /// or the errors get reported again by the next attempt.
+    pub fn recovered_len(&self) -> usize {
+        self.recovered.borrow().len()
+    }
+
+    pub fn truncate_recovered(&self, len: usize) {
+        self.recovered.borrow_mut().truncate(len);
+    }
+
+    /// Whether the token at `offset` is the first on its line. Only tokens that have already
+    /// been pulled are known, so peek before asking.
+    pub fn starts_line(&self, offset: usize) -> bool {
+        self.line_starts
+            .borrow()
+            .get(offset)
+            .copied()
+            .unwrap_or(false)
     }
 }
 
-impl InputType for TokenStream {
+impl<'src> InputType for TokenStream<'src> {
     type Token = Token;
-    type OwnedMut = Help;
+    type OwnedMut = Help<'src>;
     type Offset = usize;
     type Span = Range<usize>;
 
@@ -1,7 +1,8 @@
-use std::{ops::Range, num::ParseIntError};
+use std::ops::Range;
 
 use aott::error::LabelError;
 use miette::SourceCode;
+use tangic_ast::Ident;
 use tangic_lexer::{LexerError, Token, TokenKind};
 
 use crate::adapters::TokenStream;
@@ -26,12 +27,66 @@ pub enum ParserError {
         #[label = "here"]
         at: Range<usize>,
     },
-    #[error("number parsing error: {error}")]
-    NumberError {
-        error: ParseIntError,
-        #[label = "here"]
+    #[error("`{keyword}` is a keyword, not a name")]
+    #[diagnostic(
+        code(tangic::parser::keyword_as_ident),
+        help("keywords can't be used as names, rename it, like `{keyword}_`")
+    )]
+    KeywordAsIdent {
+        keyword: &'static str,
+        #[label = "expected a name here"]
         at: Range<usize>,
-    }
+    },
+    #[error("function `{name}` has no body")]
+    #[diagnostic(
+        code(tangic::parser::missing_body),
+        help("only functions in traits can leave out the body, add one with `= expr` or `{{ ... }}`")
+    )]
+    MissingBody {
+        name: Ident,
+        #[label = "this function"]
+        at: Range<usize>,
+    },
+    #[error("generic parameter after a value parameter")]
+    #[diagnostic(
+        code(tangic::parser::generic_after_value),
+        help("move the generic parameters to the start of the argument list")
+    )]
+    GenericAfterValue {
+        #[label = "this generic parameter"]
+        at: Range<usize>,
+        #[label = "comes after this value parameter"]
+        value: Range<usize>,
+    },
+    #[error("associated type `{name}` is not given a type")]
+    #[diagnostic(
+        code(tangic::parser::missing_assoc_type),
+        help("in an impl, associated types need to be defined, like `type {name} = Type`")
+    )]
+    MissingAssocType {
+        name: Ident,
+        #[label = "this associated type"]
+        at: Range<usize>,
+    },
+    #[error("unmatched closing curly brace")]
+    #[diagnostic(
+        code(tangic::parser::unmatched_close_curly),
+        help("there is no `{{` for it to close, remove it")
+    )]
+    UnmatchedCloseCurly {
+        #[label = "this brace"]
+        at: Range<usize>,
+    },
+    #[error("doc comment doesn't document anything")]
+    #[diagnostic(
+        code(tangic::parser::stray_doc_comment),
+        severity(Warning),
+        help("doc comments go right before an item, field or variant, use `//` for a regular comment")
+    )]
+    StrayDocComment {
+        #[label = "this doc comment"]
+        at: Range<usize>,
+    },
 }
 
 #[derive(thiserror::Error, Debug, Clone, miette::Diagnostic)]
@@ -50,10 +105,12 @@ pub enum Expectation {
     #[error("a pattern")]
     Pattern,
     #[error("a type")]
-    Type
+    Type,
+    #[error("an expression")]
+    Expr,
 }
 
-impl LabelError<TokenStream, Expectation> for ParserError {
+impl LabelError<TokenStream<'_>, Expectation> for ParserError {
     fn from_label(span: Range<usize>, label: Expectation, last_token: Option<Token>) -> Self {
         Self::Expected {
             expectation: vec![label],
@@ -69,10 +126,10 @@ impl From<Vec<tangic_lexer::LexerError>> for ParserError {
     }
 }
 
-impl aott::error::Error<TokenStream> for ParserError {
+impl<'src> aott::error::Error<TokenStream<'src>> for ParserError {
     fn unexpected_eof(
-        at: <TokenStream as aott::prelude::InputType>::Span,
-        expected: Option<Vec<<TokenStream as aott::prelude::InputType>::Token>>,
+        at: <TokenStream<'src> as aott::prelude::InputType>::Span,
+        expected: Option<Vec<<TokenStream<'src> as aott::prelude::InputType>::Token>>,
     ) -> Self {
         Self::UnexpectedEof {
             at,
@@ -85,8 +142,8 @@ impl aott::error::Error<TokenStream> for ParserError {
     }
 
     fn expected_eof_found(
-        at: <TokenStream as aott::prelude::InputType>::Span,
-        found: <TokenStream as aott::prelude::InputType>::Token,
+        at: <TokenStream<'src> as aott::prelude::InputType>::Span,
+        found: <TokenStream<'src> as aott::prelude::InputType>::Token,
     ) -> Self {
         Self::Expected {
             expectation: vec![Expectation::EndOfInput],
@@ -96,9 +153,9 @@ impl aott::error::Error<TokenStream> for ParserError {
     }
 
     fn expected_token_found(
-        at: <TokenStream as aott::prelude::InputType>::Span,
-        expected: Vec<<TokenStream as aott::prelude::InputType>::Token>,
-        found: <TokenStream as aott::prelude::InputType>::Token,
+        at: <TokenStream<'src> as aott::prelude::InputType>::Span,
+        expected: Vec<<TokenStream<'src> as aott::prelude::InputType>::Token>,
+        found: <TokenStream<'src> as aott::prelude::InputType>::Token,
     ) -> Self {
         Self::Expected {
             expectation: expected
@@ -0,0 +1,776 @@
+//! Expressions, parsed by binding power.
+//!
+//! From loosest to tightest: assignment, `||`, `&&`, comparisons, `|`, `^`, `&`, shifts,
+//! `+ -`, `* / %`, then the prefix operators `! - * &`, and then application, so `-f x` is
+//! `-(f x)` and `!std.canCast T U` is `!(std.canCast T U)`.
+//!
+//! Application is juxtaposition: `f a b` calls `f` with `a` and `b`, and the arguments end at
+//! the end of the line. Parentheses right after the callee are an argument list of their own,
+//! so `it.next()` calls `next` with no arguments and `f(a, b)` is the same as `f a b`.
+//!
+//! `loop { statements }` repeats until a `break`, and `while condition => body` until the
+//! condition is false. Neither `break` nor `continue` takes a value.
+//!
+//! `@name(args)` calls an intrinsic, which can also take its arguments by juxtaposition. An
+//! intrinsic isn't a value, so an operator right after one starts its argument instead of being
+//! infix: `@compile_error & message` passes `&message`.
+
+use super::*;
+use ast::{BinaryOp, ExprKind as K, UnaryOp};
+
+type ParseResult<T> = Result<T, ParserError>;
+
+enum Infix {
+    Assign,
+    Binary(BinaryOp),
+}
+
+/// The operator `token` stands for between two expressions, with its left and right binding
+/// power. Right binding power above the left one makes the operator left-associative.
+fn infix(token: &Token) -> Option<(Infix, u8, u8)> {
+    use BinaryOp::*;
+
+    let (op, power) = match token {
+        // right-associative: `a = b = c` is `a = (b = c)`
+        Token::Eq => return Some((Infix::Assign, 2, 1)),
+        Token::OrOr => (Or, 3),
+        Token::AndAnd => (And, 5),
+        Token::EqEq => (Eq, 7),
+        Token::NotEq => (Ne, 7),
+        Token::Lt => (Lt, 7),
+        Token::Le => (Le, 7),
+        Token::Gt => (Gt, 7),
+        Token::Ge => (Ge, 7),
+        Token::Pipe => (BitOr, 9),
+        Token::Caret => (BitXor, 11),
+        Token::Amp => (BitAnd, 13),
+        Token::Shl => (Shl, 15),
+        Token::Shr => (Shr, 15),
+        Token::Plus => (Add, 17),
+        Token::Minus => (Sub, 17),
+        Token::Star => (Mul, 19),
+        Token::Slash => (Div, 19),
+        Token::Percent => (Rem, 19),
+        _ => return None,
+    };
+
+    Some((Infix::Binary(op), power, power + 1))
+}
+
+/// Whether the next token is on a line of its own, which ends whatever expression came before.
+fn at_line_start(input: &mut Input<TokenStream, Extra>) -> bool {
+    input.peek().is_ok() && input.input.starts_line(input.offset)
+}
+
+fn expr(input: &mut Input<TokenStream, Extra>, min_power: u8) -> ParseResult<ast::Expr> {
+    let before = input.offset;
+    let mut lhs = prefix(input)?;
+
+    loop {
+        if at_line_start(input) {
+            break;
+        }
+
+        let Some((op, left, right)) = input.peek().ok().as_ref().and_then(infix) else {
+            break;
+        };
+
+        if left < min_power {
+            break;
+        }
+
+        input.skip()?;
+        let rhs = Box::new(expr(input, right)?);
+
+        let kind = match op {
+            Infix::Assign => K::Assign(Box::new(lhs), rhs),
+            Infix::Binary(op) => K::Binary(op, Box::new(lhs), rhs),
+        };
+
+        lhs = ast::Expr {
+            kind,
+            span: span_since(input, before),
+        };
+    }
+
+    Ok(lhs)
+}
+
+fn prefix(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::Expr> {
+    let before = input.offset;
+
+    let kind = match input.peek()? {
+        Token::Excl => {
+            input.skip()?;
+            K::Unary(UnaryOp::Not, Box::new(prefix(input)?))
+        }
+        Token::Minus => {
+            input.skip()?;
+            K::Unary(UnaryOp::Neg, Box::new(prefix(input)?))
+        }
+        Token::Star => {
+            input.skip()?;
+            K::Unary(UnaryOp::Deref, Box::new(prefix(input)?))
+        }
+        Token::Amp => {
+            input.skip()?;
+            reference(input)?
+        }
+        // `&&x` lexes as one token, but is a reference to a reference
+        Token::AndAnd => {
+            input.skip()?;
+            let inner = reference(input)?;
+
+            K::Ref {
+                mutable: false,
+                expr: Box::new(ast::Expr {
+                    kind: inner,
+                    span: span_since(input, before),
+                }),
+            }
+        }
+        _ => return application(input),
+    };
+
+    Ok(ast::Expr {
+        kind,
+        span: span_since(input, before),
+    })
+}
+
+/// The rest of `&expr` or `&mut expr`, after the `&`.
+fn reference(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::ExprKind> {
+    let mutable = matches!(input.peek(), Ok(Token::KwMut));
+    if mutable {
+        input.skip()?;
+    }
+
+    Ok(K::Ref {
+        mutable,
+        expr: Box::new(prefix(input)?),
+    })
+}
+
+/// Whether the next token can start an argument of a juxtaposition call. Operators that could
+/// also be infix (`-`, `&`, `*`) are taken as infix, `(-x)` passes a negative argument.
+fn starts_argument(input: &mut Input<TokenStream, Extra>) -> bool {
+    if at_line_start(input) {
+        return false;
+    }
+
+    matches!(
+        input.peek(),
+        Ok(Token::Identifier(_)
+            | Token::StringLiteral(_)
+            | Token::ByteStringLiteral(_)
+            | Token::CharLiteral(_)
+            | Token::ByteLiteral(_)
+            | Token::IntLiteral(_)
+            | Token::FloatLiteral(_)
+            | Token::KwTrue
+            | Token::KwFalse
+            | Token::OpenParen
+            | Token::At)
+    )
+}
+
+/// The arguments of an intrinsic called without parentheses, up to the end of the line.
+fn intrinsic_args(input: &mut Input<TokenStream, Extra>) -> ParseResult<Vec<ast::Expr>> {
+    let mut args = vec![];
+
+    loop {
+        if at_line_start(input) {
+            break;
+        }
+
+        match input.peek() {
+            Ok(Token::Excl | Token::Minus | Token::Star | Token::Amp | Token::AndAnd) => {
+                args.push(prefix(input)?)
+            }
+            _ if starts_argument(input) => args.push(postfix(input, false)?),
+            _ => break,
+        }
+    }
+
+    Ok(args)
+}
+
+fn application(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::Expr> {
+    let before = input.offset;
+    let callee = postfix(input, true)?;
+
+    let mut args = vec![];
+    while starts_argument(input) {
+        args.push(postfix(input, false)?);
+    }
+
+    if args.is_empty() {
+        return Ok(callee);
+    }
+
+    Ok(ast::Expr {
+        kind: call(callee, args),
+        span: span_since(input, before),
+    })
+}
+
+fn call(callee: ast::Expr, args: Vec<ast::Expr>) -> ast::ExprKind {
+    match callee.kind {
+        K::Field(receiver, method) => K::MethodCall {
+            receiver,
+            method,
+            args,
+        },
+        _ => K::Call {
+            callee: Box::new(callee),
+            args,
+        },
+    }
+}
+
+/// An atom followed by any number of `.field`s and `[T]` generic arguments, and, if `calls` is
+/// set, `(args)` lists.
+fn postfix(input: &mut Input<TokenStream, Extra>, calls: bool) -> ParseResult<ast::Expr> {
+    let before = input.offset;
+    let mut expr = atom(input)?;
+
+    loop {
+        if at_line_start(input) {
+            break;
+        }
+
+        let kind = match input.peek() {
+            Ok(Token::Dot) => {
+                input.skip()?;
+                K::Field(Box::new(expr), ident(input)?)
+            }
+            Ok(Token::OpenBracket) => {
+                let args = just(Token::OpenBracket)
+                    .ignore_then(
+                        ast::GenericArg::parse
+                            .separated_by(just(Token::Comma))
+                            .allow_trailing()
+                            .until(just(Token::CloseBracket))
+                            .collect(),
+                    )
+                    .parse_with(input)?;
+
+                K::Generic(Box::new(expr), args)
+            }
+            Ok(Token::OpenParen) if calls => {
+                let (args, _) = parenthesized(input)?;
+                call(expr, args)
+            }
+            _ => break,
+        };
+
+        expr = ast::Expr {
+            kind,
+            span: span_since(input, before),
+        };
+    }
+
+    Ok(expr)
+}
+
+/// `(a, b, ...)`, and whether there was a comma after the last one.
+fn parenthesized(input: &mut Input<TokenStream, Extra>) -> ParseResult<(Vec<ast::Expr>, bool)> {
+    just(Token::OpenParen)(input)?;
+
+    let mut exprs = vec![];
+    let mut trailing_comma = false;
+
+    while !matches!(input.peek()?, Token::CloseParen) {
+        exprs.push(expr(input, 0)?);
+
+        trailing_comma = matches!(input.peek()?, Token::Comma);
+        if !trailing_comma {
+            break;
+        }
+
+        input.skip()?;
+    }
+
+    just(Token::CloseParen)(input)?;
+
+    Ok((exprs, trailing_comma))
+}
+
+fn atom(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::Expr> {
+    use ast::PrimitiveExpr as P;
+
+    let before = input.offset;
+
+    let kind = match input.peek()? {
+        Token::Identifier(_) => K::Opaque(ident(input)?),
+        Token::KwTrue => {
+            input.skip()?;
+            K::Primitive(P::Bool(true))
+        }
+        Token::KwFalse => {
+            input.skip()?;
+            K::Primitive(P::Bool(false))
+        }
+        Token::IntLiteral(int) => {
+            input.skip()?;
+            K::Primitive(P::Int {
+                value: int.value,
+                suffix: int.suffix.map(|suffix| ast::Symbol::intern(suffix.name())),
+            })
+        }
+        Token::FloatLiteral(float) => {
+            input.skip()?;
+            K::Primitive(P::Float {
+                value: float.value,
+                suffix: float
+                    .suffix
+                    .map(|suffix| ast::Symbol::intern(&suffix.to_string())),
+            })
+        }
+        Token::StringLiteral(string) => {
+            input.skip()?;
+            K::Primitive(P::Str(string))
+        }
+        Token::ByteStringLiteral(bytes) => {
+            input.skip()?;
+            K::Primitive(P::ByteStr(bytes))
+        }
+        Token::CharLiteral(ch) => {
+            input.skip()?;
+            K::Primitive(P::Char(ch))
+        }
+        Token::ByteLiteral(byte) => {
+            input.skip()?;
+            K::Primitive(P::Byte(byte))
+        }
+        Token::OpenParen => {
+            let (mut exprs, trailing_comma) = parenthesized(input)?;
+
+            // `(expr)` is just `expr`, but `(expr,)` is a tuple
+            match exprs.len() {
+                0 => K::Void,
+                1 if !trailing_comma => exprs.pop().unwrap().kind,
+                _ => K::Tuple(exprs),
+            }
+        }
+        Token::OpenCurly => K::Block(block(input)?),
+        Token::At => {
+            input.skip()?;
+            let name = ident(input)?;
+
+            let args = if matches!(input.peek(), Ok(Token::OpenParen)) && !at_line_start(input) {
+                parenthesized(input)?.0
+            } else {
+                intrinsic_args(input)?
+            };
+
+            K::Intrinsic { name, args }
+        }
+        Token::KwUnsafe => {
+            input.skip()?;
+            K::Unsafe(block(input)?)
+        }
+        Token::KwIf => {
+            input.skip()?;
+
+            let condition = Box::new(expr(input, 0)?);
+            just(Token::FatArrow)(input)?;
+            let then = Box::new(expr(input, 0)?);
+            let otherwise = match input.peek() {
+                Ok(Token::KwElse) => {
+                    input.skip()?;
+                    Some(Box::new(expr(input, 0)?))
+                }
+                _ => None,
+            };
+
+            K::If {
+                condition,
+                then,
+                otherwise,
+            }
+        }
+        Token::KwLoop => {
+            input.skip()?;
+            K::Loop(block(input)?)
+        }
+        Token::KwWhile => {
+            input.skip()?;
+
+            let condition = Box::new(expr(input, 0)?);
+            just(Token::FatArrow)(input)?;
+            let body = Box::new(expr(input, 0)?);
+
+            K::While { condition, body }
+        }
+        Token::KwBreak => {
+            input.skip()?;
+            K::Break
+        }
+        Token::KwContinue => {
+            input.skip()?;
+            K::Continue
+        }
+        Token::KwReturn => {
+            input.skip()?;
+
+            let value = if at_line_start(input)
+                || matches!(input.peek(), Err(_) | Ok(Token::CloseCurly | Token::CloseParen))
+            {
+                ast::Expr {
+                    kind: K::Void,
+                    span: span_since(input, before).shrink_to_hi(),
+                }
+            } else {
+                expr(input, 0)?
+            };
+
+            K::Return(Box::new(value))
+        }
+        Token::KwLet | Token::KwMut => K::Let(let_expr(input)?),
+        _ => {
+            let found = input.next()?;
+
+            return Err(ParserError::Expected {
+                expectation: vec![Expectation::Expr],
+                found,
+                at: input.span_since(before),
+            });
+        }
+    };
+
+    Ok(ast::Expr {
+        kind,
+        span: span_since(input, before),
+    })
+}
+
+/// `let`, `mut` or `let mut`, then an optional type, a pattern and an optional value.
+fn let_expr(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::LetExpr> {
+    let mutable = match input.next()? {
+        Token::KwLet if matches!(input.peek(), Ok(Token::KwMut)) => {
+            input.skip()?;
+            true
+        }
+        Token::KwLet => false,
+        _ => true,
+    };
+
+    // `let i32 x` and `let x` both start with an identifier, it is a type only if a pattern
+    // follows it
+    let (ty, pattern) = match typed_pattern(input) {
+        Some((ty, pattern)) => (Some(ty), pattern),
+        None => (None, ast::Pattern::parse(input)?),
+    };
+
+    let value = match input.peek() {
+        Ok(Token::Eq) => {
+            input.skip()?;
+            Some(Box::new(expr(input, 0)?))
+        }
+        _ => None,
+    };
+
+    Ok(ast::LetExpr {
+        mutable,
+        pattern,
+        ty,
+        value,
+    })
+}
+
+/// `Type pattern` on one line, or nothing with the input left as it was. A declaration doesn't
+/// continue onto the next line, so `it.next()` with `x = 5` below it are two statements.
+fn typed_pattern(input: &mut Input<TokenStream, Extra>) -> Option<(ast::Type, ast::Pattern)> {
+    let save = input.save();
+    let recovered = input.input.recovered_len();
+    let typed: ParseResult<_> = try {
+        let ty = ast::Type::parse(input)?;
+
+        if at_line_start(input) {
+            None
+        } else {
+            Some((ty, ast::Pattern::parse(input)?))
+        }
+    };
+
+    match typed {
+        Ok(Some(typed)) => Some(typed),
+        _ => {
+            input.rewind(save);
+            input.input.truncate_recovered(recovered);
+            None
+        }
+    }
+}
+
+/// A statement in a block: `Type pattern = value` declares a variable without `let`, anything
+/// else is an expression.
+pub(crate) fn statement(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::Expr> {
+    let before = input.offset;
+    let save = input.save();
+    let recovered = input.input.recovered_len();
+
+    match typed_pattern(input) {
+        Some((ty, pattern)) if !at_line_start(input) && matches!(input.peek(), Ok(Token::Eq)) => {
+            input.skip()?;
+            let value = expr(input, 0)?;
+
+            Ok(ast::Expr {
+                kind: K::Let(ast::LetExpr {
+                    mutable: false,
+                    pattern,
+                    ty: Some(ty),
+                    value: Some(Box::new(value)),
+                }),
+                span: span_since(input, before),
+            })
+        }
+        _ => {
+            // the type may have had a block in it, which gets parsed again as an expression
+            input.rewind(save);
+            input.input.truncate_recovered(recovered);
+            expr(input, 0)
+        }
+    }
+}
+
+/// `{ statements }`
+pub(crate) fn block(input: &mut Input<TokenStream, Extra>) -> ParseResult<ast::Block> {
+    braced(input, false).map(|(_, block)| block)
+}
+
+/// `{ @@attributes statements }`, the body of a function, and its inner attributes.
+pub(crate) fn body(
+    input: &mut Input<TokenStream, Extra>,
+) -> ParseResult<(Vec<ast::Attribute>, ast::Block)> {
+    braced(input, true)
+}
+
+fn braced(
+    input: &mut Input<TokenStream, Extra>,
+    inner_attributes: bool,
+) -> ParseResult<(Vec<ast::Attribute>, ast::Block)> {
+    let before = input.offset;
+    just(Token::OpenCurly)(input)?;
+
+    let inner = if inner_attributes {
+        attributes(true)(input)?
+    } else {
+        vec![]
+    };
+    let mut statements = vec![];
+
+    loop {
+        // statements can't be documented
+        stray_doc_comments(input, false)?;
+
+        if let Token::CloseCurly = input.peek()? {
+            break;
+        }
+
+        let before = input.offset;
+        let save = input.save();
+
+        match statement(input) {
+            Ok(statement) => statements.push(statement),
+            Err(error) => {
+                // a statement ends with its line, so the next one starts on the next line
+                input.rewind(save);
+                input.input.report(error);
+                synchronize(input, |_| true)?;
+
+                statements.push(ast::Expr {
+                    kind: K::Error,
+                    span: span_since(input, before),
+                });
+            }
+        }
+    }
+
+    input.skip()?;
+
+    Ok((
+        inner,
+        ast::Block {
+            statements,
+            span: span_since(input, before),
+        },
+    ))
+}
+
+impl Parse for ast::Expr {
+    #[parser(extras = Extra)]
+    #[instrument(ret, err, skip(input), name = "Expr::parse", level = "TRACE")]
+    fn parse(input: TokenStream) -> Self {
+        expr(input, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `source` as one statement.
+    fn parse(source: &str) -> ast::Expr {
+        let tokens = TokenStream::new(source, ast::FileId::from_raw(0));
+        let mut input = Input::new(&tokens);
+
+        statement(&mut input).unwrap()
+    }
+
+    /// Writes `expr` with every operator and call parenthesized, to show how it was grouped.
+    fn show(expr: &ast::Expr) -> String {
+        let list = |exprs: &[ast::Expr]| exprs.iter().map(show).collect::<Vec<_>>().join(" ");
+
+        match &expr.kind {
+            K::Opaque(name) => name.to_string(),
+            K::Primitive(ast::PrimitiveExpr::Int { value, .. }) => value.to_string(),
+            K::Primitive(ast::PrimitiveExpr::Bool(value)) => value.to_string(),
+            K::Primitive(ast::PrimitiveExpr::Str(string)) => format!("{string:?}"),
+            K::Void => "()".to_owned(),
+            K::Tuple(exprs) => format!("(tuple {})", list(exprs)),
+            K::Field(expr, name) => format!("{}.{name}", show(expr)),
+            K::Generic(expr, _) => format!("{}[..]", show(expr)),
+            K::Call { callee, args } => format!("(call {} {})", show(callee), list(args)),
+            K::MethodCall {
+                receiver,
+                method,
+                args,
+            } => format!("(method {}.{method} {})", show(receiver), list(args)),
+            K::Unary(op, expr) => {
+                let op = match op {
+                    UnaryOp::Not => "!",
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Deref => "*",
+                };
+
+                format!("({op}{})", show(expr))
+            }
+            K::Ref { mutable, expr } => {
+                format!("(&{}{})", if *mutable { "mut " } else { "" }, show(expr))
+            }
+            K::Binary(op, lhs, rhs) => format!("({} {} {})", show(lhs), op.as_str(), show(rhs)),
+            K::Assign(lhs, rhs) => format!("({} = {})", show(lhs), show(rhs)),
+            K::If {
+                condition,
+                then,
+                otherwise,
+            } => match otherwise {
+                Some(otherwise) => {
+                    format!(
+                        "(if {} {} {})",
+                        show(condition),
+                        show(then),
+                        show(otherwise)
+                    )
+                }
+                None => format!("(if {} {})", show(condition), show(then)),
+            },
+            K::Block(block) => format!("{{{}}}", list(&block.statements)),
+            K::Loop(block) => format!("(loop {{{}}})", list(&block.statements)),
+            K::While { condition, body } => format!("(while {} {})", show(condition), show(body)),
+            K::Break => "break".to_owned(),
+            K::Continue => "continue".to_owned(),
+            K::Intrinsic { name, args } => format!("(@{name} {})", list(args)),
+            K::Return(value) => format!("(return {})", show(value)),
+            K::Let(ast::LetExpr { value, .. }) => match value {
+                Some(value) => format!("(let {})", show(value)),
+                None => "(let)".to_owned(),
+            },
+            other => format!("{other:?}"),
+        }
+    }
+
+    fn check(source: &str, expected: &str) {
+        assert_eq!(show(&parse(source)), expected, "{source:?}");
+    }
+
+    #[test]
+    fn binary_precedence() {
+        check("a + b * c", "(a + (b * c))");
+        check("a * b + c", "((a * b) + c)");
+        check("a - b - c", "((a - b) - c)");
+        check("a == b && c < d || e", "(((a == b) && (c < d)) || e)");
+        check("a | b ^ c & d", "(a | (b ^ (c & d)))");
+        check("a << 1 + 2", "(a << (1 + 2))");
+        check("(a + b) * c", "((a + b) * c)");
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        check("a = b = c + 1", "(a = (b = (c + 1)))");
+    }
+
+    #[test]
+    fn prefix_operators_bind_looser_than_application() {
+        check("-f x", "(-(call f x))");
+        check("!std.canCast T U", "(!(method std.canCast T U))");
+        check("-a * b", "((-a) * b)");
+        check("&mut x", "(&mut x)");
+        check("&&x", "(&(&x))");
+        check("*a + *b", "((*a) + (*b))");
+    }
+
+    #[test]
+    fn application() {
+        check("f a b", "(call f a b)");
+        check("f(a, b)", "(call f a b)");
+        check("it.next()", "(method it.next )");
+        check(
+            "self.f1.saturating_sub 1",
+            "(method self.f1.saturating_sub 1)",
+        );
+        check("f a + g b", "((call f a) + (call g b))");
+        check("(a, b,)", "(tuple a b)");
+        check("(a,)", "(tuple a)");
+    }
+
+    #[test]
+    fn generic_arguments() {
+        check("f[i32] x", "(call f[..] x)");
+        check("a.b[i32, bool].c", "a.b[..].c");
+
+        let K::Generic(expr, args) = parse("Self.This[Self.Item]").kind else {
+            panic!("expected generic arguments");
+        };
+        assert_eq!(show(&expr), "Self.This");
+        assert!(matches!(&args[..], [ast::GenericArg::Type(_)]));
+    }
+
+    #[test]
+    fn a_line_break_ends_the_expression() {
+        check("f a\nb", "(call f a)");
+        check("a + b\n- c", "(a + b)");
+    }
+
+    #[test]
+    fn intrinsics_take_operators_as_arguments() {
+        check("@compile_error & message", "(@compile_error (&message))");
+        check("@fmt(\"{}\", x)", "(@fmt \"{}\" x)");
+    }
+
+    #[test]
+    fn if_and_declarations() {
+        check("if a => b else c", "(if a b c)");
+        check("if !a => { b }", "(if (!a) {b})");
+        check("i32 x = 1 + 2", "(let (1 + 2))");
+        check("let x = f y", "(let (call f y))");
+    }
+
+    #[test]
+    fn declarations_end_at_a_line_break() {
+        check("{\n  it.next()\n  x = 5\n}", "{(method it.next ) (x = 5)}");
+        check("{\n  bool\n  x = 5\n}", "{bool (x = 5)}");
+    }
+
+    #[test]
+    fn loops() {
+        check("loop { f x }", "(loop {(call f x)})");
+        check("while i < n => i = i + 1", "(while (i < n) (i = (i + 1)))");
+        check(
+            "loop { if a => break else continue }",
+            "(loop {(if a break continue)})",
+        );
+    }
+}
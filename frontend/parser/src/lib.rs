@@ -6,6 +6,7 @@ use tracing::*;
 
 mod adapters;
 pub mod error;
+mod expr;
 use ast::TypeReference;
 use error::*;
 use miette::SourceCode;
@@ -20,14 +21,22 @@ trait Parse {
 
 pub fn parse<S: SourceCode + std::fmt::Debug>(
     input: String,
+    file: ast::FileId,
     src: S,
 ) -> Result<(ast::File, ParserErrorsWithSource<S>), ParserErrorsWithSource<S>> {
     match try {
-        let tokens = TokenStream::new(input.clone())?;
+        let tokens = TokenStream::new(&input, file);
         let mut inp = Input::new(&tokens);
         let mut errors = vec![];
         let parsed = ast::File::parse(&mut inp); // parse, store a result
 
+        let lexer_errors = tokens.take_lexer_errors();
+        if !lexer_errors.is_empty() {
+            errors.push(ParserError::Lexer(lexer_errors));
+        }
+
+        errors.extend(tokens.take_recovered());
+
         // collect secondary errors
         errors.extend(inp.errors.secondary.drain(..).map(|located| located.err));
 
@@ -53,18 +62,137 @@ pub fn parse<S: SourceCode + std::fmt::Debug>(
     }
 }
 
+/// The span of everything parsed since `before`.
+fn span_since(input: &mut Input<TokenStream, Extra>, before: usize) -> ast::Span {
+    ast::Span::new(input.input.file, input.span_since(before))
+}
+
+/// Skips past input that failed to parse: at least one token, then everything up to the next
+/// token on the same nesting level that starts a line and satisfies `resume`, or up to the
+/// delimiter closing the level it started on, whichever comes first.
+fn synchronize(
+    input: &mut Input<TokenStream, Extra>,
+    resume: impl Fn(&Token) -> bool,
+) -> Result<(), ParserError> {
+    let mut depth = 0usize;
+    let mut first = true;
+
+    while let Ok(token) = input.peek() {
+        if !first && depth == 0 && input.input.starts_line(input.offset) && resume(&token) {
+            break;
+        }
+
+        match token {
+            Token::OpenParen | Token::OpenBracket | Token::OpenCurly => depth += 1,
+            Token::CloseParen | Token::CloseBracket | Token::CloseCurly if !first => {
+                if depth == 0 {
+                    break;
+                }
+
+                depth -= 1;
+            }
+            _ => {}
+        }
+
+        input.skip()?;
+        first = false;
+    }
+
+    Ok(())
+}
+
+/// Runs `parser` as one alternative of several. If it fails, the errors it recovered from are
+/// dropped, since the input it ran on gets parsed again by the next alternative.
+fn speculative<O>(
+    parser: impl Fn(&mut Input<TokenStream, Extra>) -> Result<O, ParserError>,
+) -> pfn_type!(TokenStream, O, Extra) {
+    move |input| {
+        let recovered = input.input.recovered_len();
+
+        parser(input).map_err(|error| {
+            input.input.truncate_recovered(recovered);
+            error
+        })
+    }
+}
+
+/// Tokens that an item can start with, which is where parsing resumes after a broken item.
+fn starts_item(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::At
+            | Token::DocComment(_)
+            | Token::OpenParen
+            | Token::KwPub
+            | Token::KwConst
+            | Token::KwStatic
+            | Token::KwStruct
+            | Token::KwEnum
+            | Token::KwTrait
+            | Token::KwImpl
+            | Token::KwType
+            | Token::KwMod
+            | Token::KwUse
+    )
+}
+
+/// Skips doc comments that have nothing to document, warning about each. With `trailing`, only
+/// those right before the end of the block or file are stray, the others belong to what follows.
+fn stray_doc_comments(
+    input: &mut Input<TokenStream, Extra>,
+    trailing: bool,
+) -> Result<(), ParserError> {
+    let save = input.save();
+    let mut spans = vec![];
+
+    while let Ok(Token::DocComment(_)) = input.peek() {
+        let before = input.offset;
+        input.skip()?;
+        spans.push(input.span_since(before));
+    }
+
+    if trailing && !matches!(input.peek(), Err(_) | Ok(Token::CloseCurly)) {
+        input.rewind(save);
+        return Ok(());
+    }
+
+    for at in spans {
+        input.input.report(ParserError::StrayDocComment { at });
+    }
+
+    Ok(())
+}
+
 impl Parse for ast::File {
     #[parser(extras = Extra)]
     fn parse(input: TokenStream) -> Self {
         try {
-            let file = Self {
-                attributes: attributes(true)(input)?,
-                items: items(input)?,
-            };
+            let attributes = attributes(true)(input)?;
+            let mut items = items(input)?;
+
+            // `items` stops at a `}`, which at the top level has nothing to close
+            while let Ok(Token::CloseCurly) = input.peek() {
+                let save = input.save();
+                let before = input.offset;
+                input.skip()?;
+                let at = input.span_since(before);
+
+                input.rewind(save);
+                input.input.report(ParserError::UnmatchedCloseCurly { at });
+                synchronize(input, starts_item)?;
+
+                items.extend(crate::items(input)?);
+            }
 
             end(input)?;
 
-            file
+            Self {
+                file: input.input.file,
+                attributes,
+                items,
+                // the whole file, trivia around the items included
+                span: ast::Span::new(input.input.file, 0..input.input.input.len()),
+            }
         }
     }
 }
@@ -74,12 +202,18 @@ fn ident(input: TokenStream) -> ast::Ident {
     let before = input.offset;
 
     match input.next_or_none() {
-        Some(Token::Identifier(ident)) => Ok(ident),
-        Some(other_token) => Err(ParserError::Expected {
-            expectation: vec![Expectation::Kind(TokenKind::Identifier)],
-            found: other_token,
-            at: input.span_since(before).into(),
-        }),
+        Some(Token::Identifier(name)) => Ok(ast::Ident::new(name, span_since(input, before))),
+        Some(other_token) => match other_token.keyword() {
+            Some(keyword) => Err(ParserError::KeywordAsIdent {
+                keyword,
+                at: input.span_since(before).into(),
+            }),
+            None => Err(ParserError::Expected {
+                expectation: vec![Expectation::Kind(TokenKind::Identifier)],
+                found: other_token,
+                at: input.span_since(before).into(),
+            }),
+        },
         None => Err(ParserError::UnexpectedEof {
             expected: vec![Expectation::Kind(TokenKind::Identifier)],
             at: input.span_since(before).into(),
@@ -91,7 +225,19 @@ fn attributes(inner: bool) -> pfn_type!(TokenStream, Vec<ast::Attribute>, Extra)
     move |input| {
         let mut attributes = vec![];
 
-        while let Ok(Token::At) = input.peek() {
+        while let Ok(Token::At | Token::DocComment(_)) = input.peek() {
+            // a body can start with an intrinsic call, only `@@` starts an inner attribute
+            if inner && matches!(input.peek(), Ok(Token::At)) {
+                let lookahead = input.save();
+                input.skip()?;
+                let at_at = matches!(input.peek(), Ok(Token::At));
+                input.rewind(lookahead);
+
+                if !at_at {
+                    break;
+                }
+            }
+
             let before = input.save();
             let attr = ast::Attribute::parse(input)?;
 
@@ -111,6 +257,27 @@ impl Parse for ast::Attribute {
     #[parser(extras = Extra)]
     fn parse(input: TokenStream) -> Self {
         try {
+            let before = input.offset;
+
+            if let Token::DocComment(doc) = input.peek()? {
+                input.skip()?;
+                let span = span_since(input, before);
+
+                // `/// text` is sugar for `@doc("text")`, `//! text` for `@@doc("text")`
+                return Ok(Self {
+                    name: ast::Ident::new(ast::sym::doc, span),
+                    arguments: vec![ast::AttributeArg {
+                        kind: ast::AttributeArgKind::Expr(ast::Expr {
+                            kind: ast::ExprKind::Primitive(ast::PrimitiveExpr::Str(doc.text)),
+                            span,
+                        }),
+                        span,
+                    }],
+                    inner: doc.inner,
+                    span,
+                });
+            }
+
             just(Token::At)(input)?;
 
             let inner = just(Token::At)
@@ -121,15 +288,80 @@ impl Parse for ast::Attribute {
 
             let name = ident(input)?;
 
+            // on the next line, the parentheses start a function instead
+            let arguments = if matches!(input.peek(), Ok(Token::OpenParen))
+                && !input.input.starts_line(input.offset)
+            {
+                attribute_arguments(input)?
+            } else {
+                vec![]
+            };
+
             Self {
                 name,
-                arguments: vec![],
+                arguments,
                 inner,
+                span: span_since(input, before),
             }
         }
     }
 }
 
+/// `(argument, ...)`
+#[parser(extras = Extra)]
+fn attribute_arguments(input: TokenStream) -> Vec<ast::AttributeArg> {
+    just(Token::OpenParen)
+        .ignore_then(
+            ast::AttributeArg::parse
+                .separated_by(just(Token::Comma))
+                .allow_trailing()
+                .until(just(Token::CloseParen))
+                .collect(),
+        )
+        .parse_with(input)
+}
+
+impl Parse for ast::AttributeArg {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        use ast::AttributeArgKind as K;
+
+        let before = input.offset;
+        let save = input.save();
+
+        let kind = match input.peek()? {
+            Token::Identifier(_) => {
+                let name = ident(input)?;
+
+                match input.peek() {
+                    Ok(Token::Eq) => {
+                        input.skip()?;
+
+                        K::KeyValue {
+                            key: name,
+                            value: ast::Expr::parse(input)?,
+                        }
+                    }
+                    Ok(Token::OpenParen) => K::List {
+                        name,
+                        arguments: attribute_arguments(input)?,
+                    },
+                    _ => {
+                        input.rewind(save);
+                        K::Expr(ast::Expr::parse(input)?)
+                    }
+                }
+            }
+            _ => K::Expr(ast::Expr::parse(input)?),
+        };
+
+        Ok(Self {
+            kind,
+            span: span_since(input, before),
+        })
+    }
+}
+
 impl Parse for ast::Visibility {
     #[parser(extras = Extra)]
     fn parse(input: TokenStream) -> Self {
@@ -161,116 +393,129 @@ impl Parse for ast::Function {
     #[instrument(ret, err, skip(input), name = "Function::parse", level = "TRACE")]
     fn parse(input: TokenStream) -> Self {
         try {
-            Self {
-                attributes: attributes(false)(input)?,
-                vis: ast::Visibility::parse(input)?,
-                modifiers: ast::FunctionModifiers::parse(input)?,
-                args: just(Token::OpenParen)
-                    .ignore_then(
-                        ast::Type::parse
-                            .separated_by(just(Token::Comma))
-                            .allow_trailing()
-                            .until(just(Token::CloseParen))
-                            .collect(),
-                    )
-                    .parse_with(input)?,
-                returns: ast::Type::parse
-                    .optional()
-                    .parse_with(input)?
-                    .unwrap_or(ast::Type {
-                        kind: ast::TypeKind::Primitive(ast::TypePrimitive::Void),
-                        arguments: vec![],
-                    }),
-                name: ident(input)?,
-                cap_args: ast::Pattern::parse.repeated().collect().parse_with(input)?,
-                statements: {
-                    let before = input.offset;
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            let modifiers = ast::FunctionModifiers::parse(input)?;
+            let (params, args) = fn_arguments(input)?;
 
-                    match input.next()? {
-                        Token::Eq => vec![ast::Expr::parse(input)?],
-                        Token::OpenCurly => {
-                            let mut stmts = vec![];
+            let returns_at = input.offset;
+            let returns = match ast::Type::parse.optional().parse_with(input)? {
+                Some(returns) => returns,
+                // no return type means `()`, which gets the empty span where it would have been
+                None => ast::Type {
+                    kind: ast::TypeKind::Primitive(ast::TypePrimitive::Void),
+                    span: span_since(input, returns_at).shrink_to_lo(),
+                },
+            };
 
-                            while !matches!(input.peek()?, Token::CloseCurly) {
-                                stmts.push(ast::Expr::parse(input)?);
-                            }
+            let name = ident(input)?;
+            let cap_args = ast::Pattern::parse.repeated().collect().parse_with(input)?;
 
-                            input.skip()?;
+            // whether a body is required depends on where the function is, see `require_body`
+            let body = match input.peek() {
+                Ok(Token::Eq) => {
+                    input.skip()?;
+                    let expr = ast::Expr::parse(input)?;
 
-                            stmts
-                        }
-                        other_token => {
-                            return Err(ParserError::Expected {
-                                expectation: vec![Expectation::AnyOf(vec![
-                                    TokenKind::Eq,
-                                    TokenKind::OpenCurly,
-                                ])],
-                                found: other_token,
-                                at: input.span_since(before).into(),
-                            });
-                        }
-                    }
-                },
+                    Some(ast::Block {
+                        span: expr.span,
+                        statements: vec![expr],
+                    })
+                }
+                Ok(Token::OpenCurly) => {
+                    let (inner, body) = expr::body(input)?;
+                    attributes.extend(inner);
+
+                    Some(body)
+                }
+                _ => None,
+            };
+
+            Self {
+                attributes,
+                vis,
+                modifiers,
+                params,
+                args,
+                returns,
+                name,
+                cap_args,
+                body,
+                span: span_since(input, before),
             }
         }
     }
 }
 
-impl Parse for ast::Expr {
-    #[parser(extras = Extra)]
-    #[instrument(ret, err, skip(input), name = "Expr::parse", level = "TRACE")]
-    fn parse(input: TokenStream) -> Self {
-        choice((
-            just([Token::OpenParen, Token::CloseParen]).to(Self::Void),
-            (
-                choice((
-                    just(Token::KwLet).to(false),
-                    just(Token::KwLet)
-                        .optional()
-                        .ignore_then(just(Token::KwMut).to(true)),
-                )).optional(),
-                ast::Type::parse.optional(),
-                ast::Pattern::parse,
-                just(Token::Eq).ignore_then(ast::Expr::parse).optional(),
-            )
-                .map(|(mutable, ty, pattern, value)| {
-                    Self::Let(ast::LetExpr {
-                        mutable: mutable.unwrap_or(false),
-                        pattern,
-                        ty,
-                        value: value.map(Box::new),
-                    })
-                }),
-            just(Token::KwTrue).to(Self::Primitive(ast::PrimitiveExpr::Bool(true))),
-            just(Token::KwFalse).to(Self::Primitive(ast::PrimitiveExpr::Bool(false))),
-            ident.map(Self::Opaque),
-        ))
-        .parse_with(input)
+/// The `(...)` of a function signature, split into the generic placeholders, which have to
+/// come first, and the types of the value arguments.
+#[parser(extras = Extra)]
+fn fn_arguments(input: TokenStream) -> (Vec<ast::TypePlaceholder>, Vec<ast::Type>) {
+    just(Token::OpenParen)(input)?;
+
+    let mut params = vec![];
+    let mut args: Vec<ast::Type> = vec![];
+
+    while !matches!(input.peek()?, Token::CloseParen) {
+        if let Token::KwType | Token::Tick | Token::KwConst = input.peek()? {
+            let param = ast::TypePlaceholder::parse(input)?;
+
+            if let Some(arg) = args.last() {
+                return Err(ParserError::GenericAfterValue {
+                    at: param.span.range(),
+                    value: arg.span.range(),
+                });
+            }
+
+            params.push(param);
+        } else {
+            args.push(ast::Type::parse(input)?);
+        }
+
+        match input.peek()? {
+            Token::Comma => input.skip()?,
+            _ => break,
+        }
     }
+
+    just(Token::CloseParen)(input)?;
+
+    Ok((params, args))
 }
 
 impl Parse for ast::Pattern {
     #[parser(extras = Extra)]
     #[instrument(ret, err, skip(input), name = "Pattern::parse", level = "TRACE")]
     fn parse(input: TokenStream) -> Self {
-        choice((
+        use ast::PatternKind as K;
+
+        let before = input.offset;
+        let kind = choice((
+            // before `ref`, which would take `ref mut x` as `ref (mut x)`
+            (just(Token::KwRef), just(Token::KwMut))
+                .ignore_then(Self::parse.map(Box::new))
+                .map(K::RefMut),
             just(Token::KwRef)
                 .ignore_then(Self::parse.map(Box::new))
-                .map(Self::Ref),
+                .map(K::Ref),
             just(Token::KwMut)
                 .ignore_then(Self::parse.map(Box::new))
-                .map(Self::Mut),
-            (just(Token::KwRef), just(Token::KwMut))
-                .ignore_then(Self::parse.map(Box::new))
-                .map(Self::RefMut),
-            just([Token::OpenParen, Token::CloseParen]).to(Self::Void),
+                .map(K::Mut),
+            just([Token::OpenParen, Token::CloseParen]).to(K::Void),
             ident
                 .then_ignore(just(Token::At))
                 .then(Self::parse)
-                .map(|(var, pat)| Self::WithVariable(var, Box::new(pat))),
-            ident.map(Self::Variable),
+                .map(|(var, pat)| K::WithVariable(var, Box::new(pat))),
+            ident.map(K::Variable),
         ))
-        .parse_with(input)
+        .parse_with(input)?;
+
+        Ok(Self {
+            kind,
+            span: span_since(input, before),
+        })
     }
 }
 
@@ -278,11 +523,14 @@ impl Parse for ast::Type {
     #[parser(extras = Extra)]
     #[instrument(ret, err, skip(input), name = "Type::parse", level = "TRACE")]
     fn parse(input: TokenStream) -> Self {
-        use ast::{TypeKind as K, TypeNumber as N, TypePrimitive as P};
+        use ast::{TypeKind as K, TypePrimitive as P};
 
+        let before = input.offset;
         let kind = choice((
             just(Token::Excl).to(K::Primitive(P::Never)),
-            just([Token::OpenParen, Token::CloseParen]).to(K::Primitive(P::Void)),
+            just(Token::Question)
+                .ignore_then(Self::parse)
+                .map(|ty| K::Optional(Box::new(ty))),
             just(Token::Amp)
                 .ignore_then((
                     just(Token::Tick).ignore_then(ident).optional(),
@@ -296,65 +544,1319 @@ impl Parse for ast::Type {
                         ty,
                     }))
                 }),
-            ident
-                .try_map(|pr, extra| {
-                    Ok(match pr.as_str() {
-                        "str" => P::Str,
-                        "bool" => P::Bool,
-                        "char" => P::Char,
-                        int if int.starts_with("i") || int.starts_with("u") => P::Number(N::Int {
-                            signed: int.starts_with("i"),
-                            bits: int[1..].parse().map_err(|error| ParserError::NumberError {
-                                error,
-                                at: extra.span(),
-                            })?,
-                        }),
-                        _ => {
-                            return Err(ParserError::Expected {
-                                expectation: vec![Expectation::Type],
-                                found: Token::Identifier(pr),
-                                at: extra.span(),
-                            })
-                        }
-                    })
-                })
-                .map(|prim| K::Primitive(prim)),
+            just(Token::KwImpl).ignore_then(bounds).map(K::Impl),
+            just(Token::KwDyn).ignore_then(bounds).map(K::Dyn),
+            just([Token::OpenBracket, Token::CloseBracket])
+                .ignore_then(Self::parse)
+                .map(|ty| K::Slice(Box::new(ty))),
+            (
+                just(Token::OpenBracket)
+                    .ignore_then(ast::Expr::parse)
+                    .then_ignore(just(Token::CloseBracket)),
+                Self::parse,
+            )
+                .map(|(len, ty)| K::Array(Box::new(ty), Box::new(len))),
+            (
+                just(Token::OpenParen).ignore_then(Self::parse),
+                just(Token::KwAs).ignore_then(ast::TraitRef::parse),
+                just([Token::CloseParen, Token::Dot]).ignore_then(ast::PathSegment::parse),
+            )
+                .map(|(ty, trait_, item)| K::Projection {
+                    ty: Box::new(ty),
+                    trait_,
+                    item,
+                }),
+            tuple_type,
+            ast::Path::parse.map(|path| {
+                let [segment] = &path.segments[..] else {
+                    return K::Opaque(path);
+                };
+
+                if !segment.args.is_empty() {
+                    return K::Opaque(path);
+                }
+
+                match ast::TypePrimitive::from_name(segment.name.as_str()) {
+                    Some(primitive) => K::Primitive(primitive),
+                    None => K::Opaque(path),
+                }
+            }),
         ))
         .parse_with(input)?;
 
         Ok(Self {
-            arguments: if let K::Primitive(_) = kind {
-                vec![]
-            } else {
-                just(Token::OpenParen)
-                    .ignore_then(
-                        ast::Type::parse
-                            .separated_by(just(Token::Comma))
-                            .allow_trailing()
-                            .until(just(Token::CloseParen))
-                            .collect(),
-                    )
+            kind,
+            span: span_since(input, before),
+        })
+    }
+}
+
+/// `()`, `(T)`, which is just `T`, or a tuple `(A, B)`.
+#[parser(extras = Extra)]
+fn tuple_type(input: TokenStream) -> ast::TypeKind {
+    just(Token::OpenParen)(input)?;
+
+    let mut types = vec![];
+    let mut trailing_comma = false;
+
+    while !matches!(input.peek()?, Token::CloseParen) {
+        types.push(ast::Type::parse(input)?);
+
+        trailing_comma = matches!(input.peek()?, Token::Comma);
+        if !trailing_comma {
+            break;
+        }
+
+        input.skip()?;
+    }
+
+    just(Token::CloseParen)(input)?;
+
+    Ok(match types.len() {
+        0 => ast::TypeKind::Primitive(ast::TypePrimitive::Void),
+        1 if !trailing_comma => types.pop().unwrap().kind,
+        _ => ast::TypeKind::Tuple(types),
+    })
+}
+
+impl Parse for ast::Path {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        let before = input.offset;
+        let mut segments = vec![ast::PathSegment::parse(input)?];
+
+        while let Ok(Token::Dot) = input.peek() {
+            input.skip()?;
+            segments.push(ast::PathSegment::parse(input)?);
+        }
+
+        Ok(Self {
+            segments,
+            span: span_since(input, before),
+        })
+    }
+}
+
+impl Parse for ast::PathSegment {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        let before = input.offset;
+        let name = ident(input)?;
+
+        // on the next line, the parentheses start something else, like a function
+        let close = match input.peek() {
+            _ if input.input.starts_line(input.offset) => None,
+            Ok(Token::OpenBracket) => Some(Token::CloseBracket),
+            Ok(Token::OpenParen) => Some(Token::CloseParen),
+            _ => None,
+        };
+
+        let args = match close {
+            Some(close) => {
+                input.skip()?;
+
+                ast::GenericArg::parse
+                    .separated_by(just(Token::Comma))
+                    .allow_trailing()
+                    .until(just(close))
+                    .collect()
                     .parse_with(input)?
-            },
+            }
+            None => vec![],
+        };
+
+        Ok(Self {
+            name,
+            args,
+            span: span_since(input, before),
+        })
+    }
+}
+
+impl Parse for ast::GenericArg {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        match input.peek()? {
+            Token::Tick => {
+                input.skip()?;
+                Ok(Self::Lifetime(ident(input)?))
+            }
+            Token::Identifier(_) => {
+                let save = input.save();
+                let name = ident(input)?;
+
+                if let Ok(Token::Eq) = input.peek() {
+                    input.skip()?;
+
+                    return Ok(Self::Binding {
+                        name,
+                        ty: ast::Type::parse(input)?,
+                    });
+                }
+
+                input.rewind(save);
+                Ok(Self::Type(ast::Type::parse(input)?))
+            }
+            _ => Ok(Self::Type(ast::Type::parse(input)?)),
+        }
+    }
+}
+
+fn require_body(function: &ast::Function) -> Result<(), ParserError> {
+    match function.body {
+        Some(_) => Ok(()),
+        None => Err(ParserError::MissingBody {
+            name: function.name,
+            at: function.span.range(),
+        }),
+    }
+}
+
+fn require_assoc_type(assoc: &ast::AssocType) -> Result<(), ParserError> {
+    match assoc.ty {
+        Some(_) => Ok(()),
+        None => Err(ParserError::MissingAssocType {
+            name: assoc.name,
+            at: assoc.span.range(),
+        }),
+    }
+}
+
+/// `[T, 'a]` after the name of a type, trait or impl, or nothing.
+#[parser(extras = Extra)]
+fn placeholders(input: TokenStream) -> Vec<ast::TypePlaceholder> {
+    if !matches!(input.peek(), Ok(Token::OpenBracket)) {
+        return Ok(vec![]);
+    }
+
+    just(Token::OpenBracket)
+        .ignore_then(
+            ast::TypePlaceholder::parse
+                .separated_by(just(Token::Comma))
+                .allow_trailing()
+                .until(just(Token::CloseBracket))
+                .collect(),
+        )
+        .parse_with(input)
+}
+
+impl Parse for ast::TypePlaceholder {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        use ast::PlaceholderKind as K;
+
+        let before = input.offset;
+
+        let kind = match input.peek()? {
+            Token::Tick => {
+                input.skip()?;
+                K::Lifetime
+            }
+            Token::KwConst => {
+                input.skip()?;
+                K::Const(ast::Type::parse(input)?)
+            }
+            // `type` is needed among function arguments, to tell `type T` from an argument of
+            // type `T`, and optional elsewhere
+            Token::KwType => {
+                input.skip()?;
+                K::Type
+            }
+            _ => K::Type,
+        };
+        let name = ident(input)?;
+        let bounds = match kind {
+            K::Type => optional_bounds(input)?,
+            _ => vec![],
+        };
+
+        Ok(Self {
             kind,
+            name,
+            bounds,
+            span: span_since(input, before),
         })
     }
 }
 
-impl Parse for ast::Item {
+impl Parse for ast::TraitRef {
     #[parser(extras = Extra)]
     fn parse(input: TokenStream) -> Self {
-        choice((ast::Function::parse.map(ast::Item::Fn),)).parse_with(input)
+        let before = input.offset;
+        let path = ast::Path::parse(input)?;
+
+        Ok(Self {
+            path,
+            span: span_since(input, before),
+        })
     }
 }
 
+/// `Trait + Trait`, at least one.
 #[parser(extras = Extra)]
-fn items(input: TokenStream) -> Vec<ast::Item> {
-    let mut items = vec![];
+fn bounds(input: TokenStream) -> Vec<ast::TraitRef> {
+    let mut bounds = vec![ast::TraitRef::parse(input)?];
 
-    while input.peek().is_ok() {
-        items.push(ast::Item::parse(input)?);
+    while let Ok(Token::Plus) = input.peek() {
+        input.skip()?;
+        bounds.push(ast::TraitRef::parse(input)?);
     }
 
-    Ok(items)
+    Ok(bounds)
+}
+
+/// `: Trait + Trait`, or nothing.
+#[parser(extras = Extra)]
+fn optional_bounds(input: TokenStream) -> Vec<ast::TraitRef> {
+    match input.peek() {
+        Ok(Token::Colon) => {
+            input.skip()?;
+            bounds(input)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// `where Type: Trait, expr`, or nothing.
+#[parser(extras = Extra)]
+fn where_clause(input: TokenStream) -> Vec<ast::WherePredicate> {
+    let mut predicates = vec![];
+
+    if !matches!(input.peek(), Ok(Token::KwWhere)) {
+        return Ok(predicates);
+    }
+
+    input.skip()?;
+
+    loop {
+        predicates.push(ast::WherePredicate::parse(input)?);
+
+        match input.peek() {
+            Ok(Token::Comma) => input.skip()?,
+            _ => break Ok(predicates),
+        }
+    }
+}
+
+impl Parse for ast::WherePredicate {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        choice((
+            speculative(|input| {
+                let ty = ast::Type::parse(input)?;
+                just(Token::Colon)(input)?;
+
+                Ok(Self::Bound {
+                    ty,
+                    bounds: bounds(input)?,
+                })
+            }),
+            ast::Expr::parse.map(Self::Expr),
+        ))
+        .parse_with(input)
+    }
+}
+
+/// The `{ ... }` of a trait or an impl, and the inner attributes at its start.
+#[parser(extras = Extra)]
+fn assoc_items(input: TokenStream) -> (Vec<ast::Attribute>, Vec<ast::AssocItem>) {
+    just(Token::OpenCurly)(input)?;
+
+    let inner = attributes(true)(input)?;
+    let mut items = vec![];
+
+    loop {
+        stray_doc_comments(input, true)?;
+
+        if let Token::CloseCurly = input.peek()? {
+            break;
+        }
+
+        items.push(ast::AssocItem::parse(input)?);
+    }
+
+    input.skip()?;
+
+    Ok((inner, items))
+}
+
+impl Parse for ast::AssocItem {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        choice((
+            speculative(ast::AssocType::parse).map(Self::Type),
+            ast::Function::parse.map(Self::Fn),
+        ))
+        .parse_with(input)
+    }
+}
+
+impl Parse for ast::AssocType {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let attributes = attributes(false)(input)?;
+            just(Token::KwType)(input)?;
+            let name = ident(input)?;
+            let params = placeholders(input)?;
+            let bounds = optional_bounds(input)?;
+            let ty = just(Token::Eq)
+                .ignore_then(ast::Type::parse)
+                .optional()
+                .parse_with(input)?;
+
+            Self {
+                attributes,
+                name,
+                params,
+                bounds,
+                ty,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Trait {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwTrait)(input)?;
+            let name = ident(input)?;
+            let params = placeholders(input)?;
+            let supertraits = optional_bounds(input)?;
+            let where_clause = where_clause(input)?;
+            let (inner, items) = assoc_items(input)?;
+            attributes.extend(inner);
+
+            Self {
+                attributes,
+                vis,
+                name,
+                params,
+                supertraits,
+                where_clause,
+                items,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Impl {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            just(Token::KwImpl)(input)?;
+            let params = placeholders(input)?;
+            let trait_ = ast::TraitRef::parse
+                .then_ignore(just(Token::KwFor))
+                .optional()
+                .parse_with(input)?;
+            let self_ty = ast::Type::parse(input)?;
+            let where_clause = where_clause(input)?;
+            let (inner, items) = assoc_items(input)?;
+            attributes.extend(inner);
+
+            for item in &items {
+                match item {
+                    ast::AssocItem::Fn(function) => require_body(function)?,
+                    ast::AssocItem::Type(ty) => require_assoc_type(ty)?,
+                }
+            }
+
+            Self {
+                attributes,
+                params,
+                trait_,
+                self_ty,
+                where_clause,
+                items,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Structure {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwStruct)(input)?;
+            let name = ident(input)?;
+            let params = placeholders(input)?;
+            let (inner, fields) = fields(input)?;
+            attributes.extend(inner);
+
+            Self {
+                attributes,
+                vis,
+                name,
+                params,
+                fields,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+/// `{ Type name, ... }`, where the commas are optional, and the inner attributes at its start.
+#[parser(extras = Extra)]
+fn fields(input: TokenStream) -> (Vec<ast::Attribute>, Vec<ast::StructField>) {
+    just(Token::OpenCurly)(input)?;
+
+    let inner = attributes(true)(input)?;
+    let mut fields = vec![];
+
+    loop {
+        stray_doc_comments(input, true)?;
+
+        if let Token::CloseCurly = input.peek()? {
+            break;
+        }
+
+        fields.push(ast::StructField::parse(input)?);
+
+        if let Token::Comma = input.peek()? {
+            input.skip()?;
+        }
+    }
+
+    input.skip()?;
+
+    Ok((inner, fields))
+}
+
+impl Parse for ast::StructField {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            Self {
+                attributes: attributes(false)(input)?,
+                vis: ast::Visibility::parse(input)?,
+                ty: ast::Type::parse(input)?,
+                name: ident(input)?,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Enumeration {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwEnum)(input)?;
+            let name = ident(input)?;
+            let params = placeholders(input)?;
+
+            just(Token::OpenCurly)(input)?;
+
+            attributes.extend(crate::attributes(true)(input)?);
+            let mut variants = vec![];
+
+            loop {
+                stray_doc_comments(input, true)?;
+
+                if let Token::CloseCurly = input.peek()? {
+                    break;
+                }
+
+                variants.push(ast::EnumVariant::parse(input)?);
+
+                if let Token::Comma = input.peek()? {
+                    input.skip()?;
+                }
+            }
+
+            input.skip()?;
+
+            Self {
+                attributes,
+                vis,
+                name,
+                params,
+                variants,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::EnumVariant {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let name = ident(input)?;
+            let fields = match input.peek() {
+                Ok(Token::OpenParen) => ast::EnumFields::Tuple(
+                    just(Token::OpenParen)
+                        .ignore_then(
+                            ast::Type::parse
+                                .separated_by(just(Token::Comma))
+                                .allow_trailing()
+                                .until(just(Token::CloseParen))
+                                .collect(),
+                        )
+                        .parse_with(input)?,
+                ),
+                Ok(Token::OpenCurly) => {
+                    let (inner, fields) = fields(input)?;
+                    attributes.extend(inner);
+
+                    ast::EnumFields::Struct(fields)
+                }
+                _ => ast::EnumFields::Unit,
+            };
+
+            Self {
+                attributes,
+                name,
+                fields,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::TypeAlias {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwType)(input)?;
+            let name = ident(input)?;
+            let params = placeholders(input)?;
+            just(Token::Eq)(input)?;
+            let actual = ast::Type::parse(input)?;
+
+            Self {
+                attributes,
+                vis,
+                name,
+                params,
+                actual,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Module {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let mut attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwMod)(input)?;
+            let name = ident(input)?;
+
+            let items = match input.peek() {
+                Ok(Token::OpenCurly) => {
+                    input.skip()?;
+                    attributes.extend(crate::attributes(true)(input)?);
+                    let items = items(input)?;
+                    just(Token::CloseCurly)(input)?;
+
+                    Some(items)
+                }
+                _ => None,
+            };
+
+            Self {
+                attributes,
+                vis,
+                name,
+                items,
+                file: None,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::Use {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        try {
+            let before = input.offset;
+
+            let attributes = attributes(false)(input)?;
+            let vis = ast::Visibility::parse(input)?;
+            just(Token::KwUse)(input)?;
+            let tree = ast::UseTree::parse(input)?;
+
+            Self {
+                attributes,
+                vis,
+                tree,
+                span: span_since(input, before),
+            }
+        }
+    }
+}
+
+impl Parse for ast::UseTree {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        let before = input.offset;
+        let mut prefix = vec![];
+
+        let kind = loop {
+            match input.peek()? {
+                Token::OpenCurly => {
+                    input.skip()?;
+
+                    break ast::UseTreeKind::Nested(
+                        Self::parse
+                            .separated_by(just(Token::Comma))
+                            .allow_trailing()
+                            .until(just(Token::CloseCurly))
+                            .collect()
+                            .parse_with(input)?,
+                    );
+                }
+                Token::Star if !prefix.is_empty() => {
+                    input.skip()?;
+                    break ast::UseTreeKind::Glob;
+                }
+                _ => prefix.push(ident(input)?),
+            }
+
+            match input.peek() {
+                Ok(Token::Dot) => input.skip()?,
+                Ok(Token::KwAs) => {
+                    input.skip()?;
+                    break ast::UseTreeKind::Simple(Some(ident(input)?));
+                }
+                _ => break ast::UseTreeKind::Simple(None),
+            }
+        };
+
+        Ok(Self {
+            prefix,
+            kind,
+            span: span_since(input, before),
+        })
+    }
+}
+
+/// `const Type name = value`, or the same with `static`.
+fn constant(keyword: Token) -> pfn_type!(TokenStream, ast::Constant, Extra) {
+    move |input| {
+        let before = input.offset;
+
+        let attributes = attributes(false)(input)?;
+        let vis = ast::Visibility::parse(input)?;
+        just(keyword.clone())(input)?;
+        let ty = ast::Type::parse(input)?;
+        let name = ident(input)?;
+        just(Token::Eq)(input)?;
+        let value = ast::Expr::parse(input)?;
+
+        Ok(ast::Constant {
+            attributes,
+            vis,
+            ty,
+            name,
+            value,
+            span: span_since(input, before),
+        })
+    }
+}
+
+/// The token after an item's attributes and visibility, which says what kind of item it is.
+/// Nothing is consumed.
+#[parser(extras = Extra)]
+fn item_keyword(input: TokenStream) -> Option<Token> {
+    let save = input.save();
+    let recovered = input.input.recovered_len();
+
+    attributes(false)(input)?;
+    ast::Visibility::parse(input)?;
+    let keyword = match input.peek() {
+        // `const (` starts the arguments of a const function, not the type of a constant
+        Ok(Token::KwConst) => {
+            input.skip()?;
+            match input.peek() {
+                Ok(Token::OpenParen) => None,
+                _ => Some(Token::KwConst),
+            }
+        }
+        Ok(token) => Some(token),
+        Err(_) => None,
+    };
+
+    input.rewind(save);
+    input.input.truncate_recovered(recovered);
+
+    Ok(keyword)
+}
+
+impl Parse for ast::Item {
+    #[parser(extras = Extra)]
+    fn parse(input: TokenStream) -> Self {
+        // dispatching on the keyword reports errors from the kind of item that was meant, trying
+        // each kind in turn would report whatever the last one choked on
+        Ok(match item_keyword(input)? {
+            Some(Token::KwConst) => ast::Item::Const(constant(Token::KwConst)(input)?),
+            Some(Token::KwStatic) => ast::Item::Static(constant(Token::KwStatic)(input)?),
+            Some(Token::KwStruct) => ast::Item::Struct(ast::Structure::parse(input)?),
+            Some(Token::KwEnum) => ast::Item::Enum(ast::Enumeration::parse(input)?),
+            Some(Token::KwType) => ast::Item::Type(ast::TypeAlias::parse(input)?),
+            Some(Token::KwTrait) => ast::Item::Trait(ast::Trait::parse(input)?),
+            Some(Token::KwImpl) => ast::Item::Impl(ast::Impl::parse(input)?),
+            Some(Token::KwMod) => ast::Item::Mod(ast::Module::parse(input)?),
+            Some(Token::KwUse) => ast::Item::Use(ast::Use::parse(input)?),
+            _ => {
+                let function = ast::Function::parse(input)?;
+                require_body(&function)?;
+
+                ast::Item::Fn(function)
+            }
+        })
+    }
+}
+
+#[parser(extras = Extra)]
+fn items(input: TokenStream) -> Vec<ast::Item> {
+    let mut items = vec![];
+
+    loop {
+        stray_doc_comments(input, true)?;
+
+        // the end of an inline module, or a `}` with nothing to close, which the caller reports
+        if matches!(input.peek(), Err(_) | Ok(Token::CloseCurly)) {
+            break;
+        }
+
+        let before = input.offset;
+        let save = input.save();
+
+        match ast::Item::parse(input) {
+            Ok(item) => items.push(item),
+            Err(error) => {
+                input.rewind(save);
+                input.input.report(error);
+                synchronize(input, starts_item)?;
+
+                items.push(ast::Item::Error(span_since(input, before)));
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `source`, returning the file if there is one, and every error and warning.
+    fn parse_str(source: &str) -> (Option<ast::File>, Vec<ParserError>) {
+        match parse(
+            source.to_owned(),
+            ast::FileId::from_raw(0),
+            source.to_owned(),
+        ) {
+            Ok((file, errors)) => (Some(file), errors.errors),
+            Err(errors) => (None, errors.errors),
+        }
+    }
+
+    /// Runs `parser` on the start of `source`.
+    fn parse_with<T>(
+        source: &str,
+        parser: impl Fn(&mut Input<TokenStream, Extra>) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        let tokens = TokenStream::new(source, ast::FileId::from_raw(0));
+        let mut input = Input::new(&tokens);
+
+        parser(&mut input)
+    }
+
+    /// Parses `source`, which has to parse without errors.
+    fn parse_ok(source: &str) -> ast::File {
+        let (file, errors) = parse_str(source);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        file.unwrap()
+    }
+
+    #[test]
+    fn empty_input() {
+        let (file, errors) = parse_str("");
+
+        assert!(file.unwrap().items.is_empty());
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn spans_point_into_the_file() {
+        let source = "struct A {\n  i32 x\n}\n";
+        let file = ast::FileId::from_raw(3);
+        let (parsed, errors) = parse(source.to_owned(), file, source.to_owned()).unwrap();
+
+        assert!(errors.errors.is_empty(), "{:?}", errors.errors);
+
+        let ast::Item::Struct(structure) = &parsed.items[0] else {
+            panic!("expected a struct, found {:?}", parsed.items[0]);
+        };
+        assert_eq!(structure.span, ast::Span::new(file, 0..19));
+        assert_eq!(structure.name.span, ast::Span::new(file, 7..8));
+        assert_eq!(structure.fields[0].ty.span, ast::Span::new(file, 13..16));
+        assert_eq!(structure.fields[0].name.span, ast::Span::new(file, 17..18));
+    }
+
+    #[test]
+    fn doc_comments_document_the_next_item() {
+        let (file, errors) = parse_str("//! the file\n\n/// one\n()i32 one = 1\n");
+        let file = file.unwrap();
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(file.attributes[0].name.name, ast::sym::doc);
+
+        let ast::Item::Fn(function) = &file.items[0] else {
+            panic!("expected a function, found {:?}", file.items[0]);
+        };
+        assert_eq!(function.attributes[0].name.name, ast::sym::doc);
+    }
+
+    #[test]
+    fn stray_doc_comments_are_skipped_with_a_warning() {
+        let (file, errors) = parse_str(
+            "()i32 f {\n  /// nothing\n  1\n}\n\nstruct S {\n  i32 x\n  /// nothing\n}\n\n/// the end\n",
+        );
+
+        assert_eq!(file.unwrap().items.len(), 2);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors
+            .iter()
+            .all(|error| matches!(error, ParserError::StrayDocComment { .. })));
+    }
+
+    #[test]
+    fn items() {
+        let file = parse_ok(
+            "const bool X = true
+static bool Y = false
+type Alias = i32
+struct S { pub i32 x, i32 y }
+enum E { A, B(i32, bool), C { i32 x } }
+trait T {
+  type Item
+  ()bool get
+}
+impl T for bool {
+  type Item = i32
+  ()bool get = true
+}
+",
+        );
+
+        assert!(matches!(
+            &file.items[..],
+            [
+                ast::Item::Const(_),
+                ast::Item::Static(_),
+                ast::Item::Type(_),
+                ast::Item::Struct(_),
+                ast::Item::Enum(_),
+                ast::Item::Trait(_),
+                ast::Item::Impl(_),
+            ]
+        ));
+
+        let ast::Item::Struct(structure) = &file.items[3] else {
+            unreachable!()
+        };
+        assert_eq!(structure.fields.len(), 2);
+        assert!(matches!(structure.fields[0].vis, ast::Visibility::Public));
+
+        let ast::Item::Enum(enumeration) = &file.items[4] else {
+            unreachable!()
+        };
+        let fields: Vec<_> = enumeration
+            .variants
+            .iter()
+            .map(|variant| &variant.fields)
+            .collect();
+        assert!(matches!(
+            &fields[..],
+            [ast::EnumFields::Unit, ast::EnumFields::Tuple(types), ast::EnumFields::Struct(fields)]
+                if types.len() == 2 && fields.len() == 1
+        ));
+
+        let ast::Item::Trait(trait_) = &file.items[5] else {
+            unreachable!()
+        };
+        assert!(matches!(
+            &trait_.items[..],
+            [ast::AssocItem::Type(_), ast::AssocItem::Fn(function)] if function.body.is_none()
+        ));
+
+        let ast::Item::Impl(impl_) = &file.items[6] else {
+            unreachable!()
+        };
+        assert!(impl_.trait_.is_some());
+    }
+
+    #[test]
+    fn const_functions_and_constants() {
+        let file = parse_ok("const ()bool f = true\n@inline pub const bool X = true\n");
+
+        assert!(matches!(
+            &file.items[..],
+            [ast::Item::Fn(function), ast::Item::Const(_)] if function.modifiers.const_
+        ));
+    }
+
+    #[test]
+    fn errors_come_from_the_kind_of_item_meant() {
+        for source in ["()i32 f\n", "impl T for bool {\n  ()i32 get\n}\n"] {
+            let (file, errors) = parse_str(source);
+
+            assert!(matches!(&errors[..], [ParserError::MissingBody { .. }]), "{errors:?}");
+            assert!(matches!(&file.unwrap().items[..], [ast::Item::Error(_)]));
+        }
+
+        let (_, errors) = parse_str("impl T for bool {\n  type Item\n}\n");
+        assert!(matches!(&errors[..], [ParserError::MissingAssocType { .. }]), "{errors:?}");
+
+        // a struct missing its fields runs out of input, instead of not being a function
+        let (_, errors) = parse_str("pub struct S\n");
+        assert!(matches!(&errors[..], [ParserError::UnexpectedEof { .. }]), "{errors:?}");
+    }
+
+    fn pattern(source: &str) -> ast::PatternKind {
+        parse_with(source, ast::Pattern::parse).unwrap().kind
+    }
+
+    #[test]
+    fn patterns() {
+        use ast::PatternKind as K;
+
+        assert!(matches!(pattern("()"), K::Void));
+        assert!(matches!(pattern("x"), K::Variable(_)));
+        assert!(matches!(pattern("ref x"), K::Ref(inner) if matches!(inner.kind, K::Variable(_))));
+        assert!(matches!(pattern("mut x"), K::Mut(inner) if matches!(inner.kind, K::Variable(_))));
+        assert!(matches!(
+            pattern("ref mut x"),
+            K::RefMut(inner) if matches!(inner.kind, K::Variable(_))
+        ));
+        assert!(matches!(
+            pattern("x @ ref y"),
+            K::WithVariable(_, inner) if matches!(inner.kind, K::Ref(_))
+        ));
+    }
+
+    #[test]
+    fn keywords_are_not_names() {
+        let error = parse_with("while", ident).unwrap_err();
+        assert!(
+            matches!(error, ParserError::KeywordAsIdent { keyword: "while", ref at } if *at == (0..5)),
+            "{error:?}"
+        );
+
+        let error = parse_with("struct loop {}", ast::Structure::parse).unwrap_err();
+        assert!(
+            matches!(error, ParserError::KeywordAsIdent { keyword: "loop", .. }),
+            "{error:?}"
+        );
+
+        // anything else is just not a name
+        let error = parse_with("1", ident).unwrap_err();
+        assert!(matches!(error, ParserError::Expected { .. }), "{error:?}");
+    }
+
+    #[test]
+    fn recovery_at_item_boundaries() {
+        let (file, errors) = parse_str("struct {\n}\n\n()i32 g = 2\n");
+
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(matches!(
+            &file.unwrap().items[..],
+            [ast::Item::Error(_), ast::Item::Fn(_)]
+        ));
+    }
+
+    #[test]
+    fn recovery_at_statement_boundaries() {
+        let (file, errors) = parse_str("()i32 f {\n  )\n  1\n}\n");
+        let file = file.unwrap();
+
+        assert!(
+            matches!(&errors[..], [ParserError::Expected { .. }]),
+            "{errors:?}"
+        );
+
+        let ast::Item::Fn(function) = &file.items[0] else {
+            panic!("expected a function, found {:?}", file.items[0]);
+        };
+        assert!(matches!(
+            &function.body.as_ref().unwrap().statements[..],
+            [
+                ast::Expr {
+                    kind: ast::ExprKind::Error,
+                    ..
+                },
+                ast::Expr {
+                    kind: ast::ExprKind::Primitive(_),
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn unmatched_close_curly_at_the_top_level() {
+        let (file, errors) = parse_str("()i32 f = 1\n}\n()i32 g = 2\n");
+
+        assert!(
+            matches!(&errors[..], [ParserError::UnmatchedCloseCurly { at }] if *at == (12..13)),
+            "{errors:?}"
+        );
+        assert_eq!(file.unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn failed_alternatives_drop_their_recovered_errors() {
+        let tokens = TokenStream::new("a", ast::FileId::from_raw(0));
+        let mut input = Input::new(&tokens);
+        tokens.report(ParserError::StrayDocComment { at: 0..1 });
+
+        let failing = speculative(|input| {
+            input
+                .input
+                .report(ParserError::StrayDocComment { at: 0..1 });
+            Err::<(), _>(ParserError::UnmatchedCloseCurly { at: 0..1 })
+        });
+
+        assert!(failing(&mut input).is_err());
+        assert_eq!(tokens.recovered_len(), 1);
+    }
+
+    fn attribute(source: &str) -> ast::Attribute {
+        parse_with(source, ast::Attribute::parse).unwrap()
+    }
+
+    #[test]
+    fn attribute_arguments() {
+        use ast::AttributeArgKind as K;
+
+        let inline = attribute("@inline(always)");
+        assert_eq!(inline.name.name.as_str(), "inline");
+        assert!(matches!(
+            &inline.arguments[..],
+            [ast::AttributeArg { kind: K::Expr(ast::Expr { kind: ast::ExprKind::Opaque(always), .. }), .. }]
+                if always.name.as_str() == "always"
+        ));
+
+        let deprecated = attribute("@deprecated(note = \"old\", since = 1)");
+        assert!(matches!(
+            &deprecated.arguments[..],
+            [
+                ast::AttributeArg { kind: K::KeyValue { key, value }, .. },
+                ast::AttributeArg { kind: K::KeyValue { .. }, .. },
+            ] if key.name.as_str() == "note"
+                && matches!(value.kind, ast::ExprKind::Primitive(ast::PrimitiveExpr::Str(_)))
+        ));
+
+        let cfg = attribute("@cfg(all(unix, not(test)), x)");
+        let [all, x] = &cfg.arguments[..] else {
+            panic!("expected two arguments, found {:?}", cfg.arguments);
+        };
+        assert!(matches!(x.kind, K::Expr(_)));
+
+        let K::List { name, arguments } = &all.kind else {
+            panic!("expected a list, found {:?}", all.kind);
+        };
+        assert_eq!(name.name.as_str(), "all");
+        assert!(matches!(
+            &arguments[..],
+            [
+                ast::AttributeArg { kind: K::Expr(_), .. },
+                ast::AttributeArg { kind: K::List { name, arguments }, .. },
+            ] if name.name.as_str() == "not" && arguments.len() == 1
+        ));
+    }
+
+    #[test]
+    fn inner_attributes_on_item_bodies() {
+        let file = parse_ok("()i32 f {\n  @@inline(never)\n  1\n}\n\ntrait T {\n  @@marker\n}\n");
+
+        let ast::Item::Fn(function) = &file.items[0] else {
+            panic!("expected a function, found {:?}", file.items[0]);
+        };
+        assert!(matches!(
+            &function.attributes[..],
+            [attribute] if attribute.inner && attribute.arguments.len() == 1
+        ));
+        assert_eq!(function.body.as_ref().unwrap().statements.len(), 1);
+
+        let ast::Item::Trait(trait_) = &file.items[1] else {
+            panic!("expected a trait, found {:?}", file.items[1]);
+        };
+        assert!(matches!(&trait_.attributes[..], [attribute] if attribute.inner));
+        assert!(trait_.items.is_empty());
+    }
+
+    #[test]
+    fn attribute_arguments_stay_on_the_line() {
+        // the parentheses on the next line are the arguments of the function
+        let file = parse_ok("@inline\n(i32)i32 f x = x\n");
+
+        let ast::Item::Fn(function) = &file.items[0] else {
+            panic!("expected a function, found {:?}", file.items[0]);
+        };
+        assert!(function.attributes[0].arguments.is_empty());
+        assert_eq!(function.args.len(), 1);
+    }
+
+    #[test]
+    fn generic_parameters() {
+        let file = parse_ok("(type T: Clone + Eq, 'a, const usize N, T, &'a T)T f x y = x\n");
+
+        let ast::Item::Fn(function) = &file.items[0] else {
+            panic!("expected a function, found {:?}", file.items[0]);
+        };
+        assert!(matches!(
+            &function.params[..],
+            [
+                ast::TypePlaceholder {
+                    kind: ast::PlaceholderKind::Type,
+                    bounds,
+                    ..
+                },
+                ast::TypePlaceholder {
+                    kind: ast::PlaceholderKind::Lifetime,
+                    ..
+                },
+                ast::TypePlaceholder {
+                    kind: ast::PlaceholderKind::Const(_),
+                    ..
+                },
+            ] if bounds.len() == 2
+        ));
+        assert_eq!(function.args.len(), 2);
+        assert_eq!(function.cap_args.len(), 2);
+    }
+
+    #[test]
+    fn generic_parameters_come_first() {
+        let error = parse_with("(T, type U)", fn_arguments).unwrap_err();
+
+        assert!(matches!(error, ParserError::GenericAfterValue { .. }), "{error:?}");
+    }
+
+    fn ty(source: &str) -> ast::TypeKind {
+        parse_with(source, ast::Type::parse).unwrap().kind
+    }
+
+    #[test]
+    fn types() {
+        use ast::{TypeKind as K, TypePrimitive as P};
+
+        assert!(matches!(ty("!"), K::Primitive(P::Never)));
+        assert!(matches!(ty("()"), K::Primitive(P::Void)));
+        assert!(matches!(ty("?T"), K::Optional(_)));
+        assert!(matches!(ty("&'a mut T"), K::Reference(r) if r.mutable && r.lifetime.is_some()));
+        assert!(matches!(ty("&T"), K::Reference(r) if !r.mutable && r.lifetime.is_none()));
+        assert!(matches!(ty("impl Clone + Eq"), K::Impl(bounds) if bounds.len() == 2));
+        assert!(matches!(ty("dyn Clone"), K::Dyn(bounds) if bounds.len() == 1));
+        assert!(matches!(ty("[]T"), K::Slice(_)));
+        assert!(matches!(ty("[3]T"), K::Array(..)));
+        assert!(matches!(ty("(A, B)"), K::Tuple(types) if types.len() == 2));
+        assert!(matches!(ty("(A,)"), K::Tuple(types) if types.len() == 1));
+        assert!(matches!(ty("(A)"), K::Opaque(_)));
+        assert!(matches!(ty("(T as Iterator).Item"), K::Projection { .. }));
+        assert!(matches!(ty("Self.Item"), K::Opaque(path) if path.segments.len() == 2));
+        assert!(matches!(
+            ty("Functor[Item = T]"),
+            K::Opaque(path) if matches!(path.segments[0].args[..], [ast::GenericArg::Binding { .. }])
+        ));
+    }
+
+    #[test]
+    fn generic_arguments_in_where_clauses() {
+        let file = parse_ok("trait Functor where TypeEq(Self, Self.This[Self.Item]) {\n  type Item\n}\n");
+
+        let ast::Item::Trait(trait_) = &file.items[0] else {
+            panic!("expected a trait, found {:?}", file.items[0]);
+        };
+        let [ast::WherePredicate::Expr(predicate)] = &trait_.where_clause[..] else {
+            panic!("expected an expression, found {:?}", trait_.where_clause);
+        };
+        let ast::ExprKind::Call { args, .. } = &predicate.kind else {
+            panic!("expected a call, found {:?}", predicate.kind);
+        };
+        assert!(matches!(
+            &args[..],
+            [_, ast::Expr { kind: ast::ExprKind::Generic(_, generics), .. }] if generics.len() == 1
+        ));
+    }
+
+    #[test]
+    fn primitive_types_by_name() {
+        use ast::{TypeKind as K, TypeNumber as N, TypePrimitive as P};
+
+        assert!(matches!(ty("i32"), K::Primitive(P::Number(N::Int { signed: true, bits: 32 }))));
+        assert!(matches!(ty("usize"), K::Primitive(P::Number(N::Size { signed: false }))));
+        assert!(matches!(ty("bool"), K::Primitive(P::Bool)));
+        // anything else is up to name resolution, as are primitive names with arguments or a path
+        assert!(matches!(ty("Foo"), K::Opaque(_)));
+        assert!(matches!(ty("i32[T]"), K::Opaque(_)));
+        assert!(matches!(ty("std.i32"), K::Opaque(_)));
+    }
+
+    #[test]
+    fn modules_and_imports() {
+        let file = parse_ok("mod a\nmod b {\n  use a.c\n}\nuse a.{b, c.d as e}\npub use a.*\n");
+
+        let [ast::Item::Mod(a), ast::Item::Mod(b), ast::Item::Use(nested), ast::Item::Use(glob)] =
+            &file.items[..]
+        else {
+            panic!("expected two modules and two imports, found {:?}", file.items);
+        };
+
+        assert!(a.items.is_none());
+        assert_eq!(b.items.as_ref().map(Vec::len), Some(1));
+
+        let ast::UseTreeKind::Nested(trees) = &nested.tree.kind else {
+            panic!("expected a nested import, found {:?}", nested.tree);
+        };
+        assert!(matches!(trees[0].kind, ast::UseTreeKind::Simple(None)));
+        assert_eq!(trees[1].prefix.len(), 2);
+        assert!(matches!(trees[1].kind, ast::UseTreeKind::Simple(Some(e)) if e.as_str() == "e"));
+
+        assert!(matches!(glob.tree.kind, ast::UseTreeKind::Glob));
+        assert!(matches!(glob.vis, ast::Visibility::Public));
+    }
 }
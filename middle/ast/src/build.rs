@@ -0,0 +1,460 @@
+//! Building ASTs by hand, for testing the passes after parsing without going through source
+//! text. Every node gets a span of its own, so that nodes can be told apart by their spans the
+//! same way they can in a parsed file.
+
+use std::cell::Cell;
+
+use crate::*;
+
+#[derive(Default)]
+pub struct Builder {
+    next: Cell<usize>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A span no other node has.
+    pub fn span(&self) -> Span {
+        let start = self.next.replace(self.next.get() + 1);
+        Span::new(FileId::from_raw(0), start..start + 1)
+    }
+
+    pub fn ident(&self, name: &str) -> Ident {
+        Ident::new(Symbol::intern(name), self.span())
+    }
+
+    pub fn file(&self, items: Vec<Item>) -> File {
+        File {
+            file: FileId::from_raw(0),
+            items,
+            attributes: vec![],
+            span: self.span(),
+        }
+    }
+
+    // items
+
+    /// `(args)returns name patterns { statements }`, where `args` are the types of the
+    /// arguments and the names of the variables they are bound to.
+    pub fn function(&self, name: &str, args: Vec<(&str, Type)>, returns: Type, statements: Vec<Expr>) -> Function {
+        let (cap_args, args) = args
+            .into_iter()
+            .map(|(name, ty)| {
+                // `self` binds itself, by its type
+                let pattern = match name {
+                    "self" => None,
+                    name => Some(self.pattern(name)),
+                };
+
+                (pattern, ty)
+            })
+            .unzip::<_, _, Vec<_>, _>();
+
+        Function {
+            attributes: vec![],
+            vis: Visibility::Public,
+            modifiers: FunctionModifiers::default(),
+            params: vec![],
+            args,
+            returns,
+            name: self.ident(name),
+            cap_args: cap_args.into_iter().flatten().collect(),
+            body: Some(self.block(statements)),
+            span: self.span(),
+        }
+    }
+
+    /// A function without a body, which only trait methods may be.
+    pub fn fn_decl(&self, name: &str, args: Vec<Type>, returns: Type) -> Function {
+        Function {
+            args,
+            body: None,
+            ..self.function(name, vec![], returns, vec![])
+        }
+    }
+
+    pub fn structure(&self, name: &str, params: &[&str], fields: Vec<(&str, Type)>) -> Item {
+        Item::Struct(Structure {
+            attributes: vec![],
+            vis: Visibility::Public,
+            name: self.ident(name),
+            params: self.params(params),
+            fields: fields.into_iter().map(|(name, ty)| self.field(name, ty)).collect(),
+            span: self.span(),
+        })
+    }
+
+    pub fn field(&self, name: &str, ty: Type) -> StructField {
+        StructField {
+            attributes: vec![],
+            vis: Visibility::Public,
+            name: self.ident(name),
+            ty,
+            span: self.span(),
+        }
+    }
+
+    pub fn enumeration(&self, name: &str, params: &[&str], variants: Vec<(&str, EnumFields)>) -> Item {
+        let variants = variants
+            .into_iter()
+            .map(|(name, fields)| EnumVariant {
+                attributes: vec![],
+                name: self.ident(name),
+                fields,
+                span: self.span(),
+            })
+            .collect();
+
+        Item::Enum(Enumeration {
+            attributes: vec![],
+            vis: Visibility::Public,
+            name: self.ident(name),
+            params: self.params(params),
+            variants,
+            span: self.span(),
+        })
+    }
+
+    pub fn trait_(&self, name: &str, supertraits: Vec<TraitRef>, items: Vec<AssocItem>) -> Trait {
+        Trait {
+            attributes: vec![],
+            vis: Visibility::Public,
+            name: self.ident(name),
+            params: vec![],
+            supertraits,
+            where_clause: vec![],
+            items,
+            span: self.span(),
+        }
+    }
+
+    pub fn impl_(&self, params: &[&str], trait_: Option<TraitRef>, self_ty: Type, items: Vec<AssocItem>) -> Impl {
+        Impl {
+            attributes: vec![],
+            params: self.params(params),
+            trait_,
+            self_ty,
+            where_clause: vec![],
+            items,
+            span: self.span(),
+        }
+    }
+
+    /// `type name: bounds = ty`
+    pub fn assoc_type(&self, name: &str, bounds: Vec<TraitRef>, ty: Option<Type>) -> AssocItem {
+        AssocItem::Type(AssocType {
+            attributes: vec![],
+            name: self.ident(name),
+            params: vec![],
+            bounds,
+            ty,
+            span: self.span(),
+        })
+    }
+
+    /// `pub mod name { items }`
+    pub fn module(&self, name: &str, items: Vec<Item>) -> Item {
+        Item::Mod(Module {
+            attributes: vec![],
+            vis: Visibility::Public,
+            name: self.ident(name),
+            items: Some(items),
+            file: None,
+            span: self.span(),
+        })
+    }
+
+    /// `use a.b.c`, or `use a.b.*` if `path` ends in `*`, and `pub use` if `public`.
+    pub fn import(&self, path: &str, public: bool) -> Item {
+        let (path, kind) = match path.strip_suffix(".*") {
+            Some(path) => (path, UseTreeKind::Glob),
+            None => (path, UseTreeKind::Simple(None)),
+        };
+
+        let tree = UseTree {
+            prefix: path.split('.').map(|segment| self.ident(segment)).collect(),
+            kind,
+            span: self.span(),
+        };
+
+        Item::Use(Use {
+            attributes: vec![],
+            vis: if public {
+                Visibility::Public
+            } else {
+                Visibility::Inherited
+            },
+            tree,
+            span: self.span(),
+        })
+    }
+
+    /// Type parameters without bounds.
+    pub fn params(&self, names: &[&str]) -> Vec<TypePlaceholder> {
+        names.iter().map(|name| self.param(name, vec![])).collect()
+    }
+
+    pub fn param(&self, name: &str, bounds: Vec<TraitRef>) -> TypePlaceholder {
+        TypePlaceholder {
+            kind: PlaceholderKind::Type,
+            name: self.ident(name),
+            bounds,
+            span: self.span(),
+        }
+    }
+
+    pub fn attribute(&self, name: &str, arguments: Vec<Expr>) -> Attribute {
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| AttributeArg {
+                span: argument.span,
+                kind: AttributeArgKind::Expr(argument),
+            })
+            .collect();
+
+        Attribute {
+            name: self.ident(name),
+            arguments,
+            inner: false,
+            span: self.span(),
+        }
+    }
+
+    // types
+
+    /// A primitive type by its name, like `i32`, or a path to a type otherwise, like `a.B`.
+    pub fn ty(&self, name: &str) -> Type {
+        match TypePrimitive::from_name(name) {
+            Some(primitive) => self.ty_kind(TypeKind::Primitive(primitive)),
+            None => self.ty_args(name, vec![]),
+        }
+    }
+
+    /// `name[args]`
+    pub fn ty_args(&self, name: &str, args: Vec<Type>) -> Type {
+        self.ty_kind(TypeKind::Opaque(
+            self.path(name, args.into_iter().map(GenericArg::Type).collect()),
+        ))
+    }
+
+    pub fn unit_ty(&self) -> Type {
+        self.ty_kind(TypeKind::Primitive(TypePrimitive::Void))
+    }
+
+    pub fn never_ty(&self) -> Type {
+        self.ty_kind(TypeKind::Primitive(TypePrimitive::Never))
+    }
+
+    pub fn ref_ty(&self, ty: Type, mutable: bool) -> Type {
+        self.ty_kind(TypeKind::Reference(Box::new(TypeReference {
+            ty,
+            mutable,
+            lifetime: None,
+        })))
+    }
+
+    pub fn tuple_ty(&self, types: Vec<Type>) -> Type {
+        self.ty_kind(TypeKind::Tuple(types))
+    }
+
+    pub fn ty_kind(&self, kind: TypeKind) -> Type {
+        Type {
+            kind,
+            span: self.span(),
+        }
+    }
+
+    /// `a.b.C` with `args` on its last segment.
+    pub fn path(&self, path: &str, args: Vec<GenericArg>) -> Path {
+        let mut segments: Vec<_> = path
+            .split('.')
+            .map(|name| PathSegment {
+                name: self.ident(name),
+                args: vec![],
+                span: self.span(),
+            })
+            .collect();
+
+        segments.last_mut().expect("paths have a segment").args = args;
+
+        Path {
+            span: segments[0].span.to(segments[segments.len() - 1].span),
+            segments,
+        }
+    }
+
+    /// `Trait[args]`
+    pub fn trait_ref(&self, path: &str, args: Vec<GenericArg>) -> TraitRef {
+        TraitRef {
+            path: self.path(path, args),
+            span: self.span(),
+        }
+    }
+
+    // patterns
+
+    pub fn pattern(&self, name: &str) -> Pattern {
+        let kind = match name {
+            "()" => PatternKind::Void,
+            name => PatternKind::Variable(self.ident(name)),
+        };
+
+        Pattern {
+            kind,
+            span: self.span(),
+        }
+    }
+
+    pub fn pattern_kind(&self, kind: PatternKind) -> Pattern {
+        Pattern {
+            kind,
+            span: self.span(),
+        }
+    }
+
+    // expressions
+
+    pub fn expr(&self, kind: ExprKind) -> Expr {
+        Expr {
+            kind,
+            span: self.span(),
+        }
+    }
+
+    /// `a.b.c`, a name or a path to one, or field accesses on it.
+    pub fn name(&self, path: &str) -> Expr {
+        let mut segments = path.split('.');
+        let mut expr = self.expr(ExprKind::Opaque(self.ident(segments.next().expect("split gives one"))));
+
+        for segment in segments {
+            expr = self.expr(ExprKind::Field(Box::new(expr), self.ident(segment)));
+        }
+
+        expr
+    }
+
+    pub fn int(&self, value: u128) -> Expr {
+        self.expr(ExprKind::Primitive(PrimitiveExpr::Int { value, suffix: None }))
+    }
+
+    /// `5i32`
+    pub fn int_suffixed(&self, value: u128, suffix: &str) -> Expr {
+        self.expr(ExprKind::Primitive(PrimitiveExpr::Int {
+            value,
+            suffix: Some(Symbol::intern(suffix)),
+        }))
+    }
+
+    pub fn float(&self, value: f64) -> Expr {
+        self.expr(ExprKind::Primitive(PrimitiveExpr::Float { value, suffix: None }))
+    }
+
+    pub fn bool(&self, value: bool) -> Expr {
+        self.expr(ExprKind::Primitive(PrimitiveExpr::Bool(value)))
+    }
+
+    pub fn str(&self, value: &str) -> Expr {
+        self.expr(ExprKind::Primitive(PrimitiveExpr::Str(value.to_owned())))
+    }
+
+    pub fn unit(&self) -> Expr {
+        self.expr(ExprKind::Void)
+    }
+
+    pub fn tuple(&self, exprs: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Tuple(exprs))
+    }
+
+    pub fn call(&self, callee: Expr, args: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Call {
+            callee: Box::new(callee),
+            args,
+        })
+    }
+
+    pub fn method_call(&self, receiver: Expr, method: &str, args: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::MethodCall {
+            receiver: Box::new(receiver),
+            method: self.ident(method),
+            args,
+        })
+    }
+
+    pub fn unary(&self, op: UnaryOp, expr: Expr) -> Expr {
+        self.expr(ExprKind::Unary(op, Box::new(expr)))
+    }
+
+    pub fn binary(&self, op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+        self.expr(ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    pub fn reference(&self, expr: Expr, mutable: bool) -> Expr {
+        self.expr(ExprKind::Ref {
+            mutable,
+            expr: Box::new(expr),
+        })
+    }
+
+    pub fn assign(&self, place: Expr, value: Expr) -> Expr {
+        self.expr(ExprKind::Assign(Box::new(place), Box::new(value)))
+    }
+
+    /// `let name: ty = value`, or `let mut` if `mutable`.
+    pub fn let_(&self, pattern: Pattern, mutable: bool, ty: Option<Type>, value: Option<Expr>) -> Expr {
+        self.expr(ExprKind::Let(LetExpr {
+            mutable,
+            pattern,
+            ty,
+            value: value.map(Box::new),
+        }))
+    }
+
+    pub fn block(&self, statements: Vec<Expr>) -> Block {
+        Block {
+            statements,
+            span: self.span(),
+        }
+    }
+
+    pub fn block_expr(&self, statements: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Block(self.block(statements)))
+    }
+
+    pub fn if_(&self, condition: Expr, then: Expr, otherwise: Option<Expr>) -> Expr {
+        self.expr(ExprKind::If {
+            condition: Box::new(condition),
+            then: Box::new(then),
+            otherwise: otherwise.map(Box::new),
+        })
+    }
+
+    pub fn loop_(&self, statements: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Loop(self.block(statements)))
+    }
+
+    pub fn while_(&self, condition: Expr, body: Expr) -> Expr {
+        self.expr(ExprKind::While {
+            condition: Box::new(condition),
+            body: Box::new(body),
+        })
+    }
+
+    pub fn return_(&self, value: Expr) -> Expr {
+        self.expr(ExprKind::Return(Box::new(value)))
+    }
+
+    pub fn unsafe_(&self, statements: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Unsafe(self.block(statements)))
+    }
+
+    /// `@name(args)`
+    pub fn intrinsic(&self, name: &str, args: Vec<Expr>) -> Expr {
+        self.expr(ExprKind::Intrinsic {
+            name: self.ident(name),
+            args,
+        })
+    }
+}
@@ -1,17 +1,43 @@
-use std::collections::HashMap;
-pub type Ident = String;
+pub use tangic_middle::{
+    span::{FileId, Span},
+    symbol::{kw, sym, Ident, Symbol},
+};
 
+#[cfg(feature = "build")]
+pub mod build;
+
+/// `@name(arguments)` applies to the item after it, `@@name(arguments)` to the item (or file)
+/// it is in.
 #[derive(Debug, Clone)]
 pub struct Attribute {
     pub name: Ident,
-    pub arguments: Vec<Expr>,
+    pub arguments: Vec<AttributeArg>,
     pub inner: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeArg {
+    pub kind: AttributeArgKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum AttributeArgKind {
+    /// `value`
+    Expr(Expr),
+    /// `key = value`
+    KeyValue { key: Ident, value: Expr },
+    /// `name(arguments)`
+    List { name: Ident, arguments: Vec<AttributeArg> },
 }
 
 #[derive(Debug, Clone)]
 pub struct File {
+    pub file: FileId,
     pub items: Vec<Item>,
     pub attributes: Vec<Attribute>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -22,37 +48,319 @@ pub enum Item {
     Type(TypeAlias),
     Const(Constant),
     Static(Constant),
+    Trait(Trait),
+    Impl(Impl),
+    Mod(Module),
+    Use(Use),
+    /// Stands in for an item that failed to parse.
+    Error(Span),
+}
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Struct(structure) => structure.span,
+            Self::Enum(enumeration) => enumeration.span,
+            Self::Fn(function) => function.span,
+            Self::Type(alias) => alias.span,
+            Self::Const(constant) | Self::Static(constant) => constant.span,
+            Self::Trait(trait_) => trait_.span,
+            Self::Impl(impl_) => impl_.span,
+            Self::Mod(module) => module.span,
+            Self::Use(use_) => use_.span,
+            Self::Error(span) => *span,
+        }
+    }
+}
+
+/// `mod name { ... }`, or `mod name`, whose items are in `name.tn` or `name/mod.tn` next to the
+/// file it is declared in.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub attributes: Vec<Attribute>,
+    pub vis: Visibility,
+    pub name: Ident,
+    /// `None` for `mod name` until the file it refers to is loaded.
+    pub items: Option<Vec<Item>>,
+    /// The file the items were loaded from, for modules that are not inline.
+    pub file: Option<FileId>,
+    pub span: Span,
+}
+
+/// `use a.b.c`, `use a.b.{c, d as e}` or `use a.b.*`. With `pub`, the imported names are
+/// re-exported.
+#[derive(Debug, Clone)]
+pub struct Use {
+    pub attributes: Vec<Attribute>,
+    pub vis: Visibility,
+    pub tree: UseTree,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct UseTree {
+    pub prefix: Vec<Ident>,
+    pub kind: UseTreeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum UseTreeKind {
+    /// Imports the last segment of the prefix, optionally renamed with `as`.
+    Simple(Option<Ident>),
+    /// `prefix.{a, b.c}`
+    Nested(Vec<UseTree>),
+    /// `prefix.*`
+    Glob,
 }
 
 #[derive(Debug, Clone)]
 pub struct TypeAlias {
+    pub attributes: Vec<Attribute>,
     pub vis: Visibility,
     pub name: Ident,
-    pub arguments: Vec<Type>,
+    pub params: Vec<TypePlaceholder>,
     pub actual: Type,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Constant {
+    pub attributes: Vec<Attribute>,
     pub vis: Visibility,
+    pub ty: Type,
     pub name: Ident,
     pub value: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trait {
+    pub attributes: Vec<Attribute>,
+    pub vis: Visibility,
+    pub name: Ident,
+    pub params: Vec<TypePlaceholder>,
+    pub supertraits: Vec<TraitRef>,
+    pub where_clause: Vec<WherePredicate>,
+    pub items: Vec<AssocItem>,
+    pub span: Span,
+}
+
+/// `impl Type { ... }` or `impl Trait for Type { ... }`.
+#[derive(Debug, Clone)]
+pub struct Impl {
+    pub attributes: Vec<Attribute>,
+    pub params: Vec<TypePlaceholder>,
+    pub trait_: Option<TraitRef>,
+    pub self_ty: Type,
+    pub where_clause: Vec<WherePredicate>,
+    pub items: Vec<AssocItem>,
+    pub span: Span,
+}
+
+/// A trait, as named in a bound, a supertrait list or an `impl`, like `Functor[Item = T]`.
+#[derive(Debug, Clone)]
+pub struct TraitRef {
+    pub path: Path,
+    pub span: Span,
+}
+
+/// `a.b.C[T]`
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub segments: Vec<PathSegment>,
+    pub span: Span,
+}
+
+/// A name in a path, with the generic arguments given to it in `[...]`, or in `(...)` like in
+/// `Fn(A, Output = B)`.
+#[derive(Debug, Clone)]
+pub struct PathSegment {
+    pub name: Ident,
+    pub args: Vec<GenericArg>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum GenericArg {
+    Type(Type),
+    Lifetime(Ident),
+    /// `Item = T`, setting an associated type of a trait.
+    Binding { name: Ident, ty: Type },
 }
 
 #[derive(Debug, Clone)]
-pub enum Expr {
+pub enum WherePredicate {
+    /// `Type: Trait + Trait`
+    Bound { ty: Type, bounds: Vec<TraitRef> },
+    /// Any other expression, which has to evaluate to `true`.
+    Expr(Expr),
+}
+
+/// An item in the body of a trait or an impl.
+#[derive(Debug, Clone)]
+pub enum AssocItem {
+    Type(AssocType),
+    Fn(Function),
+}
+
+impl AssocItem {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Type(ty) => ty.span,
+            Self::Fn(function) => function.span,
+        }
+    }
+}
+
+/// `type Name[T]: Bound = Type`. In a trait the bounds are required of implementors and the type
+/// is a default, in an impl there are no bounds and the type is required.
+#[derive(Debug, Clone)]
+pub struct AssocType {
+    pub attributes: Vec<Attribute>,
+    pub name: Ident,
+    pub params: Vec<TypePlaceholder>,
+    pub bounds: Vec<TraitRef>,
+    pub ty: Option<Type>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
     Void,
     Opaque(Ident),
     Let(LetExpr),
     Primitive(PrimitiveExpr),
-    Return(Box<Self>)
+    /// `return value`, where a bare `return` returns [`ExprKind::Void`].
+    Return(Box<Expr>),
+    /// `(a, b)`
+    Tuple(Vec<Expr>),
+    /// `{ statements }`, which evaluates to its last statement.
+    Block(Block),
+    /// `expr.name`
+    Field(Box<Expr>, Ident),
+    /// `expr[T, U]`, generic arguments for what `expr` names, like `Self.This[Self.Item]`.
+    Generic(Box<Expr>, Vec<GenericArg>),
+    /// `callee arg arg`, or `callee(arg, arg)`.
+    Call { callee: Box<Expr>, args: Vec<Expr> },
+    /// A call whose callee is a field access, like `self.f1.saturating_sub 1`.
+    MethodCall {
+        receiver: Box<Expr>,
+        method: Ident,
+        args: Vec<Expr>,
+    },
+    Unary(UnaryOp, Box<Expr>),
+    /// `&expr` or `&mut expr`
+    Ref { mutable: bool, expr: Box<Expr> },
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `place = value`
+    Assign(Box<Expr>, Box<Expr>),
+    /// `if condition => then else otherwise`
+    If {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Option<Box<Expr>>,
+    },
+    /// `loop { statements }`
+    Loop(Block),
+    /// `while condition => body`
+    While { condition: Box<Expr>, body: Box<Expr> },
+    Break,
+    Continue,
+    /// `unsafe { statements }`
+    Unsafe(Block),
+    /// `@name(arg, arg)`, or `@name arg arg`, a call of something built into the compiler.
+    Intrinsic { name: Ident, args: Vec<Expr> },
+    /// Stands in for an expression that failed to parse.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub statements: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `!`
+    Not,
+    /// `-`
+    Neg,
+    /// `*`
+    Deref,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    /// Whether this compares its operands, giving a `bool`.
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum PrimitiveExpr {
-    Int { value: i64, bits: u8 },
-    Float(f64),
+    /// The suffix is kept as written (`i32` in `5i32`), it names one of the number types.
+    Int { value: u128, suffix: Option<Symbol> },
+    Float { value: f64, suffix: Option<Symbol> },
     Str(String),
+    ByteStr(Vec<u8>),
+    Char(char),
+    Byte(u8),
     Bool(bool),
 }
 
@@ -65,12 +373,18 @@ pub struct LetExpr {
 }
 
 #[derive(Debug, Clone)]
-pub enum Pattern {
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternKind {
     Void,
     // ref mut pat
-    Ref(Box<Self>),
-    Mut(Box<Self>),
-    RefMut(Box<Self>),
+    Ref(Box<Pattern>),
+    Mut(Box<Pattern>),
+    RefMut(Box<Pattern>),
     // var
     Variable(Ident),
     // var @ pat
@@ -101,69 +415,114 @@ pub struct Function {
     pub attributes: Vec<Attribute>,
     pub vis: Visibility,
     pub modifiers: FunctionModifiers,
+    /// The `type T`, `'a` and `const usize N` among the arguments, which come before the
+    /// others, and are passed first.
+    pub params: Vec<TypePlaceholder>,
+    /// The types of the value arguments.
     pub args: Vec<Type>,
     pub returns: Type,
     pub name: Ident,
     pub cap_args: Vec<Pattern>,
-    pub statements: Vec<Expr>,
+    /// `{ statements }`, or the one statement after `=`. `None` for a function declared
+    /// without a body, which only trait methods may be.
+    pub body: Option<Block>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Enumeration {
+    pub attributes: Vec<Attribute>,
     pub vis: Visibility,
     pub name: Ident,
+    pub params: Vec<TypePlaceholder>,
     pub variants: Vec<EnumVariant>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct EnumVariant {
+    pub attributes: Vec<Attribute>,
     pub name: Ident,
     pub fields: EnumFields,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub enum EnumFields {
+    Unit,
     Tuple(Vec<Type>),
-    Struct(HashMap<Ident, Type>),
+    Struct(Vec<StructField>),
 }
 
 #[derive(Debug, Clone)]
 pub struct Structure {
+    pub attributes: Vec<Attribute>,
+    pub vis: Visibility,
     pub name: Ident,
     pub params: Vec<TypePlaceholder>,
     pub fields: Vec<StructField>,
+    pub span: Span,
 }
 
+/// A generic parameter: `T` (or `type T` among function arguments), `'a` or `const usize N`.
 #[derive(Debug, Clone)]
 pub struct TypePlaceholder {
     pub kind: PlaceholderKind,
-    pub name: Ident
+    pub name: Ident,
+    /// `T: Trait + Trait`, only ever set for [`PlaceholderKind::Type`].
+    pub bounds: Vec<TraitRef>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub enum PlaceholderKind {
     #[default]
     Type,
     Lifetime,
+    /// A value of the given type, known at compile time.
+    Const(Type),
 }
 
 #[derive(Debug, Clone)]
 pub struct StructField {
+    pub attributes: Vec<Attribute>,
+    pub vis: Visibility,
     pub name: Ident,
     pub ty: Type,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Type {
     pub kind: TypeKind,
-    pub arguments: Vec<Type>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub enum TypeKind {
     Primitive(TypePrimitive),
     Reference(Box<TypeReference>),
-    Opaque(Ident),
+    /// A user-defined type, named by a path. `Self.Item` is one too, whether that names an
+    /// associated type is up to name resolution.
+    Opaque(Path),
+    /// `(Type as Trait).Item`
+    Projection {
+        ty: Box<Type>,
+        trait_: TraitRef,
+        item: PathSegment,
+    },
+    /// `impl Trait + Trait`, some type implementing the traits.
+    Impl(Vec<TraitRef>),
+    /// `dyn Trait + Trait`
+    Dyn(Vec<TraitRef>),
+    /// `?T`
+    Optional(Box<Type>),
+    /// `(A, B)`, where `()` is [`TypePrimitive::Void`] instead.
+    Tuple(Vec<Type>),
+    /// `[N]T`
+    Array(Box<Type>, Box<Expr>),
+    /// `[]T`
+    Slice(Box<Type>),
 }
 
 #[derive(Debug, Clone)]
@@ -183,9 +542,41 @@ pub enum TypePrimitive {
     Str,
 }
 
+impl TypePrimitive {
+    /// The primitive type spelled `name`, if there is one. `()` and `!` are not names.
+    pub fn from_name(name: &str) -> Option<Self> {
+        use TypeNumber as N;
+
+        let int = |signed, bits| Self::Number(N::Int { signed, bits });
+
+        Some(match name {
+            "bool" => Self::Bool,
+            "char" => Self::Char,
+            "str" => Self::Str,
+            "i8" => int(true, 8),
+            "i16" => int(true, 16),
+            "i32" => int(true, 32),
+            "i64" => int(true, 64),
+            "i128" => int(true, 128),
+            "u8" => int(false, 8),
+            "u16" => int(false, 16),
+            "u32" => int(false, 32),
+            "u64" => int(false, 64),
+            "u128" => int(false, 128),
+            "isize" => Self::Number(N::Size { signed: true }),
+            "usize" => Self::Number(N::Size { signed: false }),
+            "f32" => Self::Number(N::Float(FloatBits::F32)),
+            "f64" => Self::Number(N::Float(FloatBits::F64)),
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum TypeNumber {
     Int { signed: bool, bits: u8 },
+    /// `isize` and `usize`, as wide as a pointer on the target.
+    Size { signed: bool },
     Float(FloatBits),
 }
 
@@ -194,3 +585,26 @@ pub enum FloatBits {
     F32,
     F64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_by_name() {
+        use {TypeNumber as N, TypePrimitive as P};
+
+        for (name, signed, bits) in [("i8", true, 8), ("i128", true, 128), ("u16", false, 16), ("u64", false, 64)] {
+            assert!(
+                matches!(P::from_name(name), Some(P::Number(N::Int { signed: s, bits: b })) if s == signed && b == bits),
+                "{name}"
+            );
+        }
+
+        assert!(matches!(P::from_name("isize"), Some(P::Number(N::Size { signed: true }))));
+        assert!(matches!(P::from_name("f32"), Some(P::Number(N::Float(FloatBits::F32)))));
+        assert!(matches!(P::from_name("str"), Some(P::Str)));
+        assert!(P::from_name("i33").is_none());
+        assert!(P::from_name("Bool").is_none());
+    }
+}
@@ -0,0 +1,1376 @@
+//! Lowers the AST of a crate, with its names resolved, to the [HIR](tangic_middle::hir).
+
+use std::collections::HashMap;
+
+use miette::SourceSpan;
+use tangic_ast::{self as ast, kw, sym, FileId, Ident, Span, Symbol};
+use tangic_middle::{
+    hir::{self, DefKind, HirId, ItemLocalId, LangItem},
+    index::IndexVec,
+    util::edit_distance::find_best_match,
+    DefId, DefIndex,
+};
+use tangic_resolve::{LocalId, Resolutions};
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum LoweringError {
+    #[error("`?T` needs the `{item}` lang item, which is not defined")]
+    #[diagnostic(
+        code(tangic::lowering::missing_lang_item),
+        help("mark an enum with one type parameter as `@lang({item})`")
+    )]
+    MissingLangItem {
+        item: Symbol,
+        #[label("used here")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("unknown lang item `{name}`")]
+    #[diagnostic(code(tangic::lowering::unknown_lang_item))]
+    UnknownLangItem {
+        name: String,
+        #[label("not a lang item")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("unknown intrinsic `@{name}`")]
+    #[diagnostic(code(tangic::lowering::unknown_intrinsic))]
+    UnknownIntrinsic {
+        name: Symbol,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not an intrinsic")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{keyword}` outside of a loop")]
+    #[diagnostic(code(tangic::lowering::outside_loop))]
+    OutsideLoop {
+        keyword: Symbol,
+        #[label("cannot `{keyword}` outside of a loop")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("generic arguments on a value")]
+    #[diagnostic(
+        code(tangic::lowering::generic_args_on_value),
+        help("only names of items take generic arguments, like `Vec[T].new`")
+    )]
+    GenericArgsOnValue {
+        #[label("not the name of an item")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("internal compiler error: name resolution made no definition for this")]
+    #[diagnostic(code(tangic::lowering::no_definition), help("this is a bug in the compiler"))]
+    NoDefinition {
+        #[label("no definition")]
+        at: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl LoweringError {
+    /// The file the error is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::MissingLangItem { file, .. }
+            | Self::UnknownLangItem { file, .. }
+            | Self::UnknownIntrinsic { file, .. }
+            | Self::OutsideLoop { file, .. }
+            | Self::GenericArgsOnValue { file, .. }
+            | Self::NoDefinition { file, .. } => *file,
+        }
+    }
+}
+
+/// Lowers `krate`, given its root with all of its modules loaded, and what its names resolve
+/// to.
+pub fn lower(krate: &ast::File, resolutions: &Resolutions) -> (hir::Crate, Vec<LoweringError>) {
+    let root = DefIndex::from_raw(0);
+
+    let mut lcx = LoweringContext {
+        resolutions,
+        owners: IndexVec::new(),
+        lang_items: HashMap::new(),
+        local_at: resolutions
+            .locals
+            .iter_enumerated()
+            .map(|(local, name)| (name.span, local))
+            .collect(),
+        locals: HashMap::new(),
+        owner: root,
+        next_local_id: 1,
+        loop_depth: 0,
+        errors: vec![],
+    };
+
+    lcx.owners.resize_with(resolutions.defs.len(), || None);
+    lcx.collect_lang_items(&krate.items);
+
+    let items = lcx.lower_items(&krate.items);
+    lcx.owners[root] = Some(hir::Owner::Item(hir::Item {
+        hir_id: HirId::make_owner(root),
+        name: None,
+        vis: hir::Visibility::Public,
+        kind: hir::ItemKind::Mod(items),
+        span: krate.span,
+    }));
+
+    let krate = hir::Crate {
+        owners: lcx.owners,
+        lang_items: lcx.lang_items,
+    };
+
+    (krate, lcx.errors)
+}
+
+struct LoweringContext<'a> {
+    resolutions: &'a Resolutions,
+    owners: IndexVec<DefIndex, Option<hir::Owner>>,
+    lang_items: HashMap<LangItem, DefId>,
+    /// The local that the identifier at each span binds.
+    local_at: HashMap<Span, LocalId>,
+    /// The pattern binding each local, once it is lowered.
+    locals: HashMap<LocalId, HirId>,
+    /// The owner of the nodes being lowered.
+    owner: DefIndex,
+    next_local_id: u32,
+    /// How many loops the expression being lowered is in.
+    loop_depth: usize,
+    errors: Vec<LoweringError>,
+}
+
+impl LoweringContext<'_> {
+    fn next_id(&mut self) -> HirId {
+        let id = HirId {
+            owner: self.owner,
+            local_id: ItemLocalId::from_raw(self.next_local_id),
+        };
+
+        self.next_local_id += 1;
+        id
+    }
+
+    /// Lowers the nodes `f` makes as nodes of `owner`.
+    fn with_owner<T>(&mut self, owner: DefIndex, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.owner, owner);
+        let outer_next = std::mem::replace(&mut self.next_local_id, 1);
+        let outer_loops = std::mem::replace(&mut self.loop_depth, 0);
+
+        let result = f(self);
+
+        self.owner = outer;
+        self.next_local_id = outer_next;
+        self.loop_depth = outer_loops;
+
+        result
+    }
+
+    /// The definition name resolution made for the node at `span`, reporting it if there is
+    /// none, in which case the node is left out.
+    fn def_at(&mut self, span: Span) -> Option<DefIndex> {
+        let def = self.resolutions.def_at(span);
+
+        if def.is_none() {
+            self.errors.push(LoweringError::NoDefinition {
+                at: span.range().into(),
+                file: span.file,
+            });
+        }
+
+        def
+    }
+
+    fn is_resolved(&self, span: Span) -> bool {
+        self.resolutions.res(span).is_some()
+    }
+
+    fn lower_res(&self, span: Span) -> hir::Res {
+        match self.resolutions.res(span) {
+            Some(hir::Res::Def(kind, def)) => hir::Res::Def(kind, def),
+            Some(hir::Res::SelfTy(def)) => hir::Res::SelfTy(def),
+            Some(hir::Res::Local(local)) => {
+                self.locals.get(&local).map_or(hir::Res::Err, |&id| hir::Res::Local(id))
+            }
+            Some(hir::Res::Err) | None => hir::Res::Err,
+        }
+    }
+
+    fn collect_lang_items(&mut self, items: &[ast::Item]) {
+        for item in items {
+            let attributes = match item {
+                ast::Item::Struct(structure) => &structure.attributes,
+                ast::Item::Enum(enumeration) => &enumeration.attributes,
+                ast::Item::Fn(function) => &function.attributes,
+                ast::Item::Type(alias) => &alias.attributes,
+                ast::Item::Const(constant) | ast::Item::Static(constant) => &constant.attributes,
+                ast::Item::Trait(trait_) => &trait_.attributes,
+                ast::Item::Mod(module) => {
+                    self.collect_lang_items(module.items.as_deref().unwrap_or_default());
+                    continue;
+                }
+                ast::Item::Impl(_) | ast::Item::Use(_) | ast::Item::Error(_) => continue,
+            };
+
+            for attribute in attributes.iter().filter(|attribute| attribute.name.name == sym::lang) {
+                let Some(name) = lang_item_name(attribute) else {
+                    continue;
+                };
+
+                match LangItem::from_name(Symbol::intern(&name)) {
+                    Some(lang_item) => {
+                        // a missing definition is reported when the item is lowered
+                        if let Some(def) = self.resolutions.def_at(item.span()) {
+                            self.lang_items.insert(lang_item, DefId::local(def));
+                        }
+                    }
+                    None => self.errors.push(LoweringError::UnknownLangItem {
+                        name,
+                        at: attribute.span.range().into(),
+                        file: attribute.span.file,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn lower_items(&mut self, items: &[ast::Item]) -> Vec<DefIndex> {
+        let mut defs = vec![];
+
+        for item in items {
+            if let ast::Item::Use(_) | ast::Item::Error(_) = item {
+                continue;
+            }
+
+            let Some(def) = self.def_at(item.span()) else {
+                continue;
+            };
+            let lowered = self.with_owner(def, |this| this.lower_item(item));
+
+            self.owners[def] = Some(hir::Owner::Item(lowered));
+            defs.push(def);
+        }
+
+        defs
+    }
+
+    fn lower_item(&mut self, item: &ast::Item) -> hir::Item {
+        let hir_id = HirId::make_owner(self.owner);
+
+        let (name, vis, kind) = match item {
+            ast::Item::Struct(structure) => {
+                let generics = self.lower_generics(&structure.params, &[]);
+                let fields = self.lower_fields(&structure.fields);
+
+                (Some(structure.name), structure.vis, hir::ItemKind::Struct(generics, fields))
+            }
+            ast::Item::Enum(enumeration) => {
+                let generics = self.lower_generics(&enumeration.params, &[]);
+                let variants = enumeration
+                    .variants
+                    .iter()
+                    .filter_map(|variant| self.lower_variant(variant))
+                    .collect();
+
+                (Some(enumeration.name), enumeration.vis, hir::ItemKind::Enum(generics, variants))
+            }
+            ast::Item::Fn(function) => {
+                let (sig, generics, body) = self.lower_fn(function);
+                (Some(function.name), function.vis, hir::ItemKind::Fn(sig, generics, body))
+            }
+            ast::Item::Type(alias) => {
+                let generics = self.lower_generics(&alias.params, &[]);
+                let ty = self.lower_ty(&alias.actual);
+
+                (Some(alias.name), alias.vis, hir::ItemKind::TypeAlias(generics, ty))
+            }
+            ast::Item::Const(constant) => {
+                let (ty, body) = self.lower_constant(constant);
+                (Some(constant.name), constant.vis, hir::ItemKind::Const(ty, body))
+            }
+            ast::Item::Static(constant) => {
+                let (ty, body) = self.lower_constant(constant);
+                (Some(constant.name), constant.vis, hir::ItemKind::Static(ty, body))
+            }
+            ast::Item::Trait(trait_) => {
+                let generics = self.lower_generics(&trait_.params, &trait_.where_clause);
+                let supertraits = self.lower_trait_refs(&trait_.supertraits);
+                let items = self.lower_assoc_items(&trait_.items);
+
+                let kind = hir::ItemKind::Trait {
+                    generics,
+                    supertraits,
+                    items,
+                };
+
+                (Some(trait_.name), trait_.vis, kind)
+            }
+            ast::Item::Impl(impl_) => {
+                let generics = self.lower_generics(&impl_.params, &impl_.where_clause);
+                let trait_ = impl_.trait_.as_ref().map(|trait_| self.lower_trait_ref(trait_));
+                let self_ty = self.lower_ty(&impl_.self_ty);
+                let items = self.lower_assoc_items(&impl_.items);
+
+                let kind = hir::ItemKind::Impl {
+                    generics,
+                    trait_,
+                    self_ty,
+                    items,
+                };
+
+                (None, ast::Visibility::Inherited, kind)
+            }
+            ast::Item::Mod(module) => {
+                let items = self.lower_items(module.items.as_deref().unwrap_or_default());
+                (Some(module.name), module.vis, hir::ItemKind::Mod(items))
+            }
+            ast::Item::Use(_) | ast::Item::Error(_) => unreachable!("not lowered"),
+        };
+
+        hir::Item {
+            hir_id,
+            name,
+            vis: lower_vis(vis),
+            kind,
+            span: item.span(),
+        }
+    }
+
+    fn lower_assoc_items(&mut self, items: &[ast::AssocItem]) -> Vec<DefIndex> {
+        let mut defs = vec![];
+
+        for item in items {
+            let Some(def) = self.def_at(item.span()) else {
+                continue;
+            };
+
+            let lowered = self.with_owner(def, |this| {
+                let (name, kind) = match item {
+                    ast::AssocItem::Fn(function) => {
+                        let (sig, generics, body) = this.lower_fn(function);
+                        (function.name, hir::AssocItemKind::Fn(sig, generics, body))
+                    }
+                    ast::AssocItem::Type(ty) => {
+                        let kind = hir::AssocItemKind::Type {
+                            generics: this.lower_generics(&ty.params, &[]),
+                            bounds: this.lower_trait_refs(&ty.bounds),
+                            ty: ty.ty.as_ref().map(|ty| this.lower_ty(ty)),
+                        };
+
+                        (ty.name, kind)
+                    }
+                };
+
+                hir::AssocItem {
+                    hir_id: HirId::make_owner(def),
+                    name,
+                    kind,
+                    span: item.span(),
+                }
+            });
+
+            self.owners[def] = Some(hir::Owner::AssocItem(lowered));
+            defs.push(def);
+        }
+
+        defs
+    }
+
+    fn lower_fields(&mut self, fields: &[ast::StructField]) -> Vec<hir::FieldDef> {
+        fields
+            .iter()
+            .map(|field| hir::FieldDef {
+                hir_id: self.next_id(),
+                name: field.name,
+                vis: lower_vis(field.vis),
+                ty: self.lower_ty(&field.ty),
+                span: field.span,
+            })
+            .collect()
+    }
+
+    fn lower_variant(&mut self, variant: &ast::EnumVariant) -> Option<hir::Variant> {
+        let def = self.def_at(variant.span)?;
+        let hir_id = self.next_id();
+
+        let fields = match &variant.fields {
+            ast::EnumFields::Unit => hir::VariantFields::Unit,
+            ast::EnumFields::Tuple(types) => {
+                hir::VariantFields::Tuple(types.iter().map(|ty| self.lower_ty(ty)).collect())
+            }
+            ast::EnumFields::Struct(fields) => hir::VariantFields::Struct(self.lower_fields(fields)),
+        };
+
+        Some(hir::Variant {
+            hir_id,
+            def,
+            name: variant.name,
+            fields,
+            span: variant.span,
+        })
+    }
+
+    fn lower_generics(
+        &mut self,
+        params: &[ast::TypePlaceholder],
+        where_clause: &[ast::WherePredicate],
+    ) -> hir::Generics {
+        let params = params
+            .iter()
+            .filter_map(|param| {
+                Some(hir::GenericParam {
+                    def: self.def_at(param.span)?,
+                    hir_id: self.next_id(),
+                    name: param.name,
+                    kind: match &param.kind {
+                        ast::PlaceholderKind::Type => hir::GenericParamKind::Type,
+                        ast::PlaceholderKind::Lifetime => hir::GenericParamKind::Lifetime,
+                        ast::PlaceholderKind::Const(ty) => hir::GenericParamKind::Const(self.lower_ty(ty)),
+                    },
+                    bounds: self.lower_trait_refs(&param.bounds),
+                    span: param.span,
+                })
+            })
+            .collect();
+
+        let predicates = where_clause
+            .iter()
+            .map(|predicate| match predicate {
+                ast::WherePredicate::Bound { ty, bounds } => hir::WherePredicate::Bound {
+                    ty: self.lower_ty(ty),
+                    bounds: self.lower_trait_refs(bounds),
+                },
+                ast::WherePredicate::Expr(expr) => hir::WherePredicate::Expr(self.lower_expr(expr)),
+            })
+            .collect();
+
+        hir::Generics { params, predicates }
+    }
+
+    fn lower_fn(&mut self, function: &ast::Function) -> (hir::FnSig, hir::Generics, Option<hir::Body>) {
+        let generics = self.lower_generics(&function.params, &[]);
+
+        let sig = hir::FnSig {
+            inputs: function.args.iter().map(|arg| self.lower_ty(arg)).collect(),
+            output: self.lower_ty(&function.returns),
+        };
+
+        let body = function.body.as_ref().map(|body| {
+            let mut params = vec![];
+
+            if let Some(self_) = function.args.iter().find_map(tangic_resolve::self_arg) {
+                params.push(self.lower_binding(self_, hir::BindingMode::default(), None, self_.span));
+            }
+
+            params.extend(
+                function
+                    .cap_args
+                    .iter()
+                    .map(|pattern| self.lower_pat(pattern, hir::BindingMode::default())),
+            );
+
+            let block = hir::Block {
+                hir_id: self.next_id(),
+                statements: body.statements.iter().map(|statement| self.lower_expr(statement)).collect(),
+                unsafe_: false,
+                span: body.span,
+            };
+
+            hir::Body {
+                params,
+                value: hir::Expr {
+                    hir_id: self.next_id(),
+                    kind: hir::ExprKind::Block(block),
+                    span: body.span,
+                },
+            }
+        });
+
+        (sig, generics, body)
+    }
+
+    fn lower_constant(&mut self, constant: &ast::Constant) -> (hir::Ty, hir::Body) {
+        let ty = self.lower_ty(&constant.ty);
+        let body = hir::Body {
+            params: vec![],
+            value: self.lower_expr(&constant.value),
+        };
+
+        (ty, body)
+    }
+
+    fn lower_trait_refs(&mut self, trait_refs: &[ast::TraitRef]) -> Vec<hir::TraitRef> {
+        trait_refs.iter().map(|trait_ref| self.lower_trait_ref(trait_ref)).collect()
+    }
+
+    fn lower_trait_ref(&mut self, trait_ref: &ast::TraitRef) -> hir::TraitRef {
+        hir::TraitRef {
+            hir_id: self.next_id(),
+            path: hir::Path {
+                res: self.lower_res(trait_ref.path.span),
+                segments: self.lower_segments(&trait_ref.path.segments),
+                span: trait_ref.path.span,
+            },
+            span: trait_ref.span,
+        }
+    }
+
+    fn lower_segments(&mut self, segments: &[ast::PathSegment]) -> Vec<hir::PathSegment> {
+        segments.iter().map(|segment| self.lower_segment(segment)).collect()
+    }
+
+    fn lower_segment(&mut self, segment: &ast::PathSegment) -> hir::PathSegment {
+        hir::PathSegment {
+            hir_id: self.next_id(),
+            name: segment.name,
+            args: segment.args.iter().map(|arg| self.lower_generic_arg(arg)).collect(),
+            span: segment.span,
+        }
+    }
+
+    fn ident_segment(&mut self, name: Ident) -> hir::PathSegment {
+        hir::PathSegment {
+            hir_id: self.next_id(),
+            name,
+            args: vec![],
+            span: name.span,
+        }
+    }
+
+    fn lower_generic_arg(&mut self, arg: &ast::GenericArg) -> hir::GenericArg {
+        match arg {
+            ast::GenericArg::Type(ty) => hir::GenericArg::Type(self.lower_ty(ty)),
+            ast::GenericArg::Lifetime(lifetime) => {
+                hir::GenericArg::Lifetime(self.lower_lifetime(*lifetime))
+            }
+            ast::GenericArg::Binding { name, ty } => hir::GenericArg::Binding {
+                name: *name,
+                ty: self.lower_ty(ty),
+            },
+        }
+    }
+
+    fn lower_lifetime(&mut self, name: Ident) -> hir::Lifetime {
+        hir::Lifetime {
+            hir_id: self.next_id(),
+            name,
+            res: self.lower_res(name.span),
+        }
+    }
+
+    /// A path resolved up to some segment, with the rest looked up on the type before them.
+    fn lower_qpath(&mut self, path: &ast::Path) -> hir::QPath {
+        let self_ty = matches!(self.resolutions.res(path.segments[0].name.span), Some(hir::Res::SelfTy(_)));
+
+        let resolved = if self_ty {
+            // `Self.Item` in a trait is looked up in the trait, but is a projection of `Self`,
+            // which type checking makes from the rest of the path
+            1
+        } else if self.is_resolved(path.span) {
+            path.segments.len()
+        } else {
+            path.segments
+                .iter()
+                .take_while(|segment| self.is_resolved(segment.name.span))
+                .count()
+                // an unresolved path is reported already, and lowered resolving to an error
+                .max(1)
+        };
+
+        let (prefix, rest) = path.segments.split_at(resolved);
+        let last = prefix.last().expect("paths have a segment");
+
+        let (res, mut span) = if rest.is_empty() {
+            (self.lower_res(path.span), path.span)
+        } else {
+            (self.lower_res(last.name.span), prefix[0].span.to(last.span))
+        };
+
+        let mut qpath = hir::QPath::Resolved(hir::Path {
+            res,
+            segments: self.lower_segments(prefix),
+            span,
+        });
+
+        for segment in rest {
+            let ty = hir::Ty {
+                hir_id: self.next_id(),
+                kind: hir::TyKind::Path(qpath),
+                span,
+            };
+
+            span = span.to(segment.span);
+            qpath = hir::QPath::TypeRelative(Box::new(ty), self.lower_segment(segment));
+        }
+
+        qpath
+    }
+
+    fn lower_ty(&mut self, ty: &ast::Type) -> hir::Ty {
+        let hir_id = self.next_id();
+
+        let kind = match &ty.kind {
+            ast::TypeKind::Primitive(ast::TypePrimitive::Void) => hir::TyKind::Tuple(vec![]),
+            ast::TypeKind::Primitive(ast::TypePrimitive::Never) => hir::TyKind::Never,
+            ast::TypeKind::Primitive(primitive) => {
+                hir::TyKind::Prim(lower_prim(primitive).expect("only `()` and `!` are not PrimTys"))
+            }
+            ast::TypeKind::Reference(reference) => hir::TyKind::Ref(
+                reference.lifetime.map(|lifetime| self.lower_lifetime(lifetime)),
+                lower_mutability(reference.mutable),
+                Box::new(self.lower_ty(&reference.ty)),
+            ),
+            ast::TypeKind::Opaque(path) => hir::TyKind::Path(self.lower_qpath(path)),
+            ast::TypeKind::Projection { ty, trait_, item } => hir::TyKind::Projection {
+                ty: Box::new(self.lower_ty(ty)),
+                trait_: self.lower_trait_ref(trait_),
+                item: self.lower_segment(item),
+            },
+            ast::TypeKind::Impl(bounds) => hir::TyKind::Impl(self.lower_trait_refs(bounds)),
+            ast::TypeKind::Dyn(bounds) => hir::TyKind::Dyn(self.lower_trait_refs(bounds)),
+            ast::TypeKind::Optional(inner) => self.lower_optional(inner, ty.span),
+            ast::TypeKind::Tuple(types) => {
+                hir::TyKind::Tuple(types.iter().map(|ty| self.lower_ty(ty)).collect())
+            }
+            ast::TypeKind::Array(ty, len) => {
+                hir::TyKind::Array(Box::new(self.lower_ty(ty)), Box::new(self.lower_expr(len)))
+            }
+            ast::TypeKind::Slice(ty) => hir::TyKind::Slice(Box::new(self.lower_ty(ty))),
+        };
+
+        hir::Ty {
+            hir_id,
+            kind,
+            span: ty.span,
+        }
+    }
+
+    /// `?T` is the option lang item, applied to `T`.
+    fn lower_optional(&mut self, inner: &ast::Type, span: Span) -> hir::TyKind {
+        let Some(&option) = self.lang_items.get(&LangItem::Option) else {
+            self.errors.push(LoweringError::MissingLangItem {
+                item: LangItem::Option.name(),
+                at: span.range().into(),
+                file: span.file,
+            });
+
+            return hir::TyKind::Err;
+        };
+
+        let segment = hir::PathSegment {
+            hir_id: self.next_id(),
+            name: Ident::new(LangItem::Option.name(), span),
+            args: vec![hir::GenericArg::Type(self.lower_ty(inner))],
+            span,
+        };
+
+        hir::TyKind::Path(hir::QPath::Resolved(hir::Path {
+            res: hir::Res::Def(DefKind::Enum, option),
+            segments: vec![segment],
+            span,
+        }))
+    }
+
+    fn lower_pat(&mut self, pattern: &ast::Pattern, mode: hir::BindingMode) -> hir::Pat {
+        let by_ref = hir::BindingMode {
+            by_ref: true,
+            ..mode
+        };
+        let mutable = hir::BindingMode {
+            mutable: true,
+            ..mode
+        };
+
+        match &pattern.kind {
+            ast::PatternKind::Void => hir::Pat {
+                hir_id: self.next_id(),
+                kind: hir::PatKind::Tuple(vec![]),
+                span: pattern.span,
+            },
+            // `ref`, `mut` and `ref mut` are how the bindings in the pattern after them bind
+            ast::PatternKind::Ref(inner) => hir::Pat {
+                span: pattern.span,
+                ..self.lower_pat(inner, by_ref)
+            },
+            ast::PatternKind::Mut(inner) => hir::Pat {
+                span: pattern.span,
+                ..self.lower_pat(inner, mutable)
+            },
+            ast::PatternKind::RefMut(inner) => {
+                let mode = hir::BindingMode {
+                    by_ref: true,
+                    mutable: true,
+                };
+
+                hir::Pat {
+                    span: pattern.span,
+                    ..self.lower_pat(inner, mode)
+                }
+            }
+            ast::PatternKind::Variable(name) => self.lower_binding(*name, mode, None, pattern.span),
+            ast::PatternKind::WithVariable(name, inner) => {
+                let sub = self.lower_pat(inner, hir::BindingMode::default());
+                self.lower_binding(*name, mode, Some(sub), pattern.span)
+            }
+        }
+    }
+
+    fn lower_binding(
+        &mut self,
+        name: Ident,
+        mode: hir::BindingMode,
+        sub: Option<hir::Pat>,
+        span: Span,
+    ) -> hir::Pat {
+        let hir_id = self.next_id();
+
+        if let Some(&local) = self.local_at.get(&name.span) {
+            self.locals.insert(local, hir_id);
+        }
+
+        hir::Pat {
+            hir_id,
+            kind: hir::PatKind::Binding {
+                mode,
+                name,
+                sub: sub.map(Box::new),
+            },
+            span,
+        }
+    }
+
+    fn lower_block(&mut self, block: &ast::Block, unsafe_: bool) -> hir::Block {
+        hir::Block {
+            hir_id: self.next_id(),
+            statements: block.statements.iter().map(|statement| self.lower_expr(statement)).collect(),
+            unsafe_,
+            span: block.span,
+        }
+    }
+
+    /// `e` as `{ e }`, which is what the bodies of `if` and `while` are.
+    fn lower_expr_to_block(&mut self, expr: &ast::Expr) -> hir::Block {
+        match &expr.kind {
+            ast::ExprKind::Block(block) => self.lower_block(block, false),
+            _ => hir::Block {
+                hir_id: self.next_id(),
+                statements: vec![self.lower_expr(expr)],
+                unsafe_: false,
+                span: expr.span,
+            },
+        }
+    }
+
+    /// `while condition => body` is `loop { if condition => body else break }`.
+    fn lower_while(&mut self, condition: &ast::Expr, body: &ast::Expr, span: Span) -> hir::ExprKind {
+        self.loop_depth += 1;
+
+        let condition = Box::new(self.lower_expr(condition));
+        let then = self.lower_expr_to_block(body);
+        let exit = hir::Expr {
+            hir_id: self.next_id(),
+            kind: hir::ExprKind::Break,
+            span: condition.span,
+        };
+        let branch = hir::Expr {
+            hir_id: self.next_id(),
+            kind: hir::ExprKind::If {
+                condition,
+                then,
+                otherwise: Some(Box::new(exit)),
+            },
+            span,
+        };
+
+        self.loop_depth -= 1;
+
+        hir::ExprKind::Loop(hir::Block {
+            hir_id: self.next_id(),
+            statements: vec![branch],
+            unsafe_: false,
+            span,
+        })
+    }
+
+    /// `break` or `continue`, which only mean something in a loop.
+    fn lower_loop_exit(&mut self, keyword: Symbol, kind: hir::ExprKind, span: Span) -> hir::ExprKind {
+        if self.loop_depth > 0 {
+            return kind;
+        }
+
+        self.errors.push(LoweringError::OutsideLoop {
+            keyword,
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        hir::ExprKind::Err
+    }
+
+    /// A path made of the names in `segments`, which resolved to what its last one did, with
+    /// `args` on its last segment.
+    fn lower_expr_path(&mut self, segments: &[Ident], args: Vec<hir::GenericArg>, span: Span) -> hir::ExprKind {
+        let last = segments.last().expect("paths have a segment");
+        let res = self.lower_res(last.span);
+        let mut segments: Vec<_> = segments.iter().map(|&name| self.ident_segment(name)).collect();
+        segments.last_mut().expect("paths have a segment").args = args;
+
+        hir::ExprKind::Path(hir::QPath::Resolved(hir::Path { res, segments, span }))
+    }
+
+    /// The intrinsic `receiver.method` is, if it is one like `std.typeName` and the crate has no
+    /// `std` of its own.
+    fn std_builtin(&self, receiver: &ast::Expr, method: Ident) -> Option<hir::Intrinsic> {
+        match receiver.kind {
+            ast::ExprKind::Opaque(std) if std.name == sym::std && !self.is_resolved(std.span) => {
+                hir::Intrinsic::from_std_name(method.name)
+            }
+            _ => None,
+        }
+    }
+
+    fn report_unknown_intrinsic(&mut self, name: Ident) {
+        let suggestion = if hir::Intrinsic::from_std_name(name.name).is_some() {
+            Some(format!("`{name}` is called as `std.{name}`"))
+        } else {
+            find_best_match([sym::compile_error, sym::fmt], name.name)
+                .map(|found| format!("did you mean `@{found}`?"))
+        };
+
+        self.errors.push(LoweringError::UnknownIntrinsic {
+            name: name.name,
+            suggestion,
+            at: name.span.range().into(),
+            file: name.span.file,
+        });
+    }
+
+    fn lower_exprs(&mut self, exprs: &[ast::Expr]) -> Vec<hir::Expr> {
+        exprs.iter().map(|expr| self.lower_expr(expr)).collect()
+    }
+
+    fn lower_expr(&mut self, expr: &ast::Expr) -> hir::Expr {
+        let hir_id = self.next_id();
+
+        let kind = match &expr.kind {
+            ast::ExprKind::Void => hir::ExprKind::Tuple(vec![]),
+            ast::ExprKind::Opaque(name) => self.lower_expr_path(&[*name], vec![], expr.span),
+            ast::ExprKind::Field(base, name) => match expr_path(expr) {
+                // `module.name`
+                Some(segments) if self.is_resolved(name.span) => {
+                    self.lower_expr_path(&segments, vec![], expr.span)
+                }
+                _ => hir::ExprKind::Field(Box::new(self.lower_expr(base)), *name),
+            },
+            ast::ExprKind::Generic(base, args) => match expr_path(base) {
+                // `a.b[T]`, a path like the ones above with the arguments on its last segment
+                Some(segments) if segments.len() == 1 || self.is_resolved(segments[segments.len() - 1].span) => {
+                    let args = args.iter().map(|arg| self.lower_generic_arg(arg)).collect();
+                    self.lower_expr_path(&segments, args, expr.span)
+                }
+                _ => {
+                    self.errors.push(LoweringError::GenericArgsOnValue {
+                        at: expr.span.range().into(),
+                        file: expr.span.file,
+                    });
+
+                    hir::ExprKind::Err
+                }
+            },
+            ast::ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => match (expr_path(receiver), self.std_builtin(receiver, *method)) {
+                (_, Some(intrinsic)) => hir::ExprKind::Intrinsic(intrinsic, self.lower_exprs(args)),
+                // `module.function args`
+                (Some(mut segments), None) if self.is_resolved(method.span) => {
+                    segments.push(*method);
+
+                    let callee = hir::Expr {
+                        hir_id: self.next_id(),
+                        kind: self.lower_expr_path(&segments, vec![], receiver.span.to(method.span)),
+                        span: receiver.span.to(method.span),
+                    };
+
+                    hir::ExprKind::Call(Box::new(callee), self.lower_exprs(args))
+                }
+                _ => hir::ExprKind::MethodCall {
+                    receiver: Box::new(self.lower_expr(receiver)),
+                    method: self.ident_segment(*method),
+                    args: self.lower_exprs(args),
+                },
+            },
+            ast::ExprKind::Call { callee, args } => {
+                hir::ExprKind::Call(Box::new(self.lower_expr(callee)), self.lower_exprs(args))
+            }
+            ast::ExprKind::Let(let_) => {
+                let ty = let_.ty.as_ref().map(|ty| self.lower_ty(ty));
+                let init = let_.value.as_ref().map(|value| self.lower_expr(value));
+                let mode = hir::BindingMode {
+                    by_ref: false,
+                    mutable: let_.mutable,
+                };
+                let pat = self.lower_pat(&let_.pattern, mode);
+
+                hir::ExprKind::Let(Box::new(hir::Local { pat, ty, init }))
+            }
+            ast::ExprKind::Primitive(primitive) => hir::ExprKind::Lit(lower_lit(primitive)),
+            ast::ExprKind::Return(value) => hir::ExprKind::Return(Box::new(self.lower_expr(value))),
+            ast::ExprKind::Tuple(exprs) => hir::ExprKind::Tuple(self.lower_exprs(exprs)),
+            ast::ExprKind::Block(block) => hir::ExprKind::Block(self.lower_block(block, false)),
+            ast::ExprKind::Unsafe(block) => hir::ExprKind::Block(self.lower_block(block, true)),
+            ast::ExprKind::Unary(op, operand) => {
+                let op = match op {
+                    ast::UnaryOp::Not => hir::UnOp::Not,
+                    ast::UnaryOp::Neg => hir::UnOp::Neg,
+                    ast::UnaryOp::Deref => hir::UnOp::Deref,
+                };
+
+                hir::ExprKind::Unary(op, Box::new(self.lower_expr(operand)))
+            }
+            ast::ExprKind::Ref { mutable, expr } => {
+                hir::ExprKind::AddrOf(lower_mutability(*mutable), Box::new(self.lower_expr(expr)))
+            }
+            ast::ExprKind::Binary(op, lhs, rhs) => hir::ExprKind::Binary(
+                lower_bin_op(*op),
+                Box::new(self.lower_expr(lhs)),
+                Box::new(self.lower_expr(rhs)),
+            ),
+            ast::ExprKind::Assign(place, value) => hir::ExprKind::Assign(
+                Box::new(self.lower_expr(place)),
+                Box::new(self.lower_expr(value)),
+            ),
+            ast::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let then = self.lower_expr_to_block(then);
+
+                hir::ExprKind::If {
+                    condition: Box::new(self.lower_expr(condition)),
+                    then,
+                    otherwise: otherwise.as_ref().map(|otherwise| Box::new(self.lower_expr(otherwise))),
+                }
+            }
+            ast::ExprKind::Loop(block) => {
+                self.loop_depth += 1;
+                let body = self.lower_block(block, false);
+                self.loop_depth -= 1;
+
+                hir::ExprKind::Loop(body)
+            }
+            ast::ExprKind::While { condition, body } => self.lower_while(condition, body, expr.span),
+            ast::ExprKind::Break => self.lower_loop_exit(kw::Break, hir::ExprKind::Break, expr.span),
+            ast::ExprKind::Continue => self.lower_loop_exit(kw::Continue, hir::ExprKind::Continue, expr.span),
+            ast::ExprKind::Intrinsic { name, args } => match hir::Intrinsic::from_name(name.name) {
+                Some(intrinsic) => hir::ExprKind::Intrinsic(intrinsic, self.lower_exprs(args)),
+                None => {
+                    self.report_unknown_intrinsic(*name);
+                    hir::ExprKind::Err
+                }
+            },
+            ast::ExprKind::Error => hir::ExprKind::Err,
+        };
+
+        hir::Expr {
+            hir_id,
+            kind,
+            span: expr.span,
+        }
+    }
+}
+
+/// The names in `module.name.name`, if `expr` is made of only those.
+fn expr_path(expr: &ast::Expr) -> Option<Vec<Ident>> {
+    match &expr.kind {
+        ast::ExprKind::Opaque(name) => Some(vec![*name]),
+        ast::ExprKind::Field(base, name) => {
+            let mut segments = expr_path(base)?;
+            segments.push(*name);
+
+            Some(segments)
+        }
+        _ => None,
+    }
+}
+
+/// `@lang(name)` or `@lang("name")`.
+fn lang_item_name(attribute: &ast::Attribute) -> Option<String> {
+    let [argument] = &attribute.arguments[..] else {
+        return None;
+    };
+
+    match &argument.kind {
+        ast::AttributeArgKind::Expr(ast::Expr {
+            kind: ast::ExprKind::Opaque(name),
+            ..
+        }) => Some(name.as_str().to_owned()),
+        ast::AttributeArgKind::Expr(ast::Expr {
+            kind: ast::ExprKind::Primitive(ast::PrimitiveExpr::Str(name)),
+            ..
+        }) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn lower_vis(vis: ast::Visibility) -> hir::Visibility {
+    match vis {
+        ast::Visibility::Public => hir::Visibility::Public,
+        ast::Visibility::Inherited => hir::Visibility::Inherited,
+    }
+}
+
+fn lower_mutability(mutable: bool) -> hir::Mutability {
+    if mutable {
+        hir::Mutability::Mut
+    } else {
+        hir::Mutability::Not
+    }
+}
+
+/// `None` for `()` and `!`, which are a tuple and a type of their own.
+fn lower_prim(primitive: &ast::TypePrimitive) -> Option<hir::PrimTy> {
+    use hir::{FloatTy, IntTy, PrimTy, UintTy};
+
+    Some(match primitive {
+        ast::TypePrimitive::Void | ast::TypePrimitive::Never => return None,
+        ast::TypePrimitive::Bool => PrimTy::Bool,
+        ast::TypePrimitive::Char => PrimTy::Char,
+        ast::TypePrimitive::Str => PrimTy::Str,
+        ast::TypePrimitive::Number(number) => match *number {
+            ast::TypeNumber::Int { signed: true, bits } => PrimTy::Int(match bits {
+                8 => IntTy::I8,
+                16 => IntTy::I16,
+                32 => IntTy::I32,
+                64 => IntTy::I64,
+                _ => IntTy::I128,
+            }),
+            ast::TypeNumber::Int { signed: false, bits } => PrimTy::Uint(match bits {
+                8 => UintTy::U8,
+                16 => UintTy::U16,
+                32 => UintTy::U32,
+                64 => UintTy::U64,
+                _ => UintTy::U128,
+            }),
+            ast::TypeNumber::Size { signed: true } => PrimTy::Int(IntTy::Isize),
+            ast::TypeNumber::Size { signed: false } => PrimTy::Uint(UintTy::Usize),
+            ast::TypeNumber::Float(ast::FloatBits::F32) => PrimTy::Float(FloatTy::F32),
+            ast::TypeNumber::Float(ast::FloatBits::F64) => PrimTy::Float(FloatTy::F64),
+        },
+    })
+}
+
+fn lower_lit(primitive: &ast::PrimitiveExpr) -> hir::Lit {
+    let suffix_ty = |suffix: &Option<Symbol>| {
+        suffix
+            .and_then(|suffix| ast::TypePrimitive::from_name(suffix.as_str()))
+            .and_then(|primitive| lower_prim(&primitive))
+    };
+
+    match primitive {
+        ast::PrimitiveExpr::Int { value, suffix } => hir::Lit::Int(*value, suffix_ty(suffix)),
+        ast::PrimitiveExpr::Float { value, suffix } => {
+            let ty = match suffix_ty(suffix) {
+                Some(hir::PrimTy::Float(float)) => Some(float),
+                _ => None,
+            };
+
+            hir::Lit::Float(*value, ty)
+        }
+        ast::PrimitiveExpr::Str(string) => hir::Lit::Str(string.clone()),
+        ast::PrimitiveExpr::ByteStr(bytes) => hir::Lit::ByteStr(bytes.clone()),
+        ast::PrimitiveExpr::Char(char) => hir::Lit::Char(*char),
+        ast::PrimitiveExpr::Byte(byte) => hir::Lit::Byte(*byte),
+        ast::PrimitiveExpr::Bool(bool) => hir::Lit::Bool(*bool),
+    }
+}
+
+fn lower_bin_op(op: ast::BinaryOp) -> hir::BinOp {
+    match op {
+        ast::BinaryOp::Add => hir::BinOp::Add,
+        ast::BinaryOp::Sub => hir::BinOp::Sub,
+        ast::BinaryOp::Mul => hir::BinOp::Mul,
+        ast::BinaryOp::Div => hir::BinOp::Div,
+        ast::BinaryOp::Rem => hir::BinOp::Rem,
+        ast::BinaryOp::And => hir::BinOp::And,
+        ast::BinaryOp::Or => hir::BinOp::Or,
+        ast::BinaryOp::BitAnd => hir::BinOp::BitAnd,
+        ast::BinaryOp::BitOr => hir::BinOp::BitOr,
+        ast::BinaryOp::BitXor => hir::BinOp::BitXor,
+        ast::BinaryOp::Shl => hir::BinOp::Shl,
+        ast::BinaryOp::Shr => hir::BinOp::Shr,
+        ast::BinaryOp::Eq => hir::BinOp::Eq,
+        ast::BinaryOp::Ne => hir::BinOp::Ne,
+        ast::BinaryOp::Lt => hir::BinOp::Lt,
+        ast::BinaryOp::Le => hir::BinOp::Le,
+        ast::BinaryOp::Gt => hir::BinOp::Gt,
+        ast::BinaryOp::Ge => hir::BinOp::Ge,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tangic_ast::build::Builder;
+
+    /// Resolves and lowers `krate`, expecting no errors from resolving it.
+    fn lower_ok(krate: &ast::File) -> (hir::Crate, Vec<LoweringError>) {
+        let (resolutions, errors) = tangic_resolve::resolve(krate);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        lower(krate, &resolutions)
+    }
+
+    /// The statements of the body of the function named `name`.
+    fn body<'hir>(hir: &'hir hir::Crate, name: &str) -> &'hir hir::Expr {
+        hir.owners
+            .iter()
+            .find_map(|owner| match owner {
+                Some(hir::Owner::Item(hir::Item {
+                    name: Some(item_name),
+                    kind: hir::ItemKind::Fn(_, _, Some(body)),
+                    ..
+                })) if item_name.as_str() == name => Some(&body.value),
+                _ => None,
+            })
+            .expect("the function has a body")
+    }
+
+    fn statements(body: &hir::Expr) -> &[hir::Expr] {
+        match &body.kind {
+            hir::ExprKind::Block(block) => &block.statements,
+            kind => panic!("expected a block, found {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn bodies_get_their_own_span() {
+        let ast = Builder::new();
+        let function = ast.function("f", vec![], ast.unit_ty(), vec![ast.str("hi")]);
+        let block_span = function.body.as_ref().unwrap().span;
+        let function_span = function.span;
+
+        let (hir, errors) = lower_ok(&ast.file(vec![ast::Item::Fn(function)]));
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let body = body(&hir, "f");
+        assert_eq!(body.span, block_span);
+        assert_ne!(body.span, function_span);
+        assert!(matches!(&body.kind, hir::ExprKind::Block(block) if block.span == block_span));
+        assert!(matches!(
+            statements(body),
+            [hir::Expr { kind: hir::ExprKind::Lit(hir::Lit::Str(string)), .. }] if string == "hi"
+        ));
+    }
+
+    #[test]
+    fn method_calls_on_modules_are_calls() {
+        let ast = Builder::new();
+        let g = ast::Item::Fn(ast.function("g", vec![], ast.unit_ty(), vec![]));
+        let module = ast.module("m", vec![g]);
+
+        // `m.g true`, `x.g true` with a local `x`, and `std.typeName true`
+        let statements_ = vec![
+            ast.let_(ast.pattern("x"), false, None, None),
+            ast.method_call(ast.name("m"), "g", vec![ast.bool(true)]),
+            ast.method_call(ast.name("x"), "g", vec![ast.bool(true)]),
+            ast.method_call(ast.name("std"), "typeName", vec![ast.bool(true)]),
+        ];
+        let function = ast.function("f", vec![], ast.unit_ty(), statements_);
+
+        let (hir, errors) = lower_ok(&ast.file(vec![module, ast::Item::Fn(function)]));
+        assert!(errors.is_empty(), "{errors:?}");
+
+        assert!(matches!(
+            statements(body(&hir, "f")),
+            [
+                _,
+                hir::Expr { kind: hir::ExprKind::Call(callee, _), .. },
+                hir::Expr { kind: hir::ExprKind::MethodCall { .. }, .. },
+                hir::Expr { kind: hir::ExprKind::Intrinsic(hir::Intrinsic::TypeName, _), .. },
+            ] if matches!(
+                callee.kind,
+                hir::ExprKind::Path(hir::QPath::Resolved(hir::Path { res: hir::Res::Def(DefKind::Fn, _), ref segments, .. }))
+                    if segments.len() == 2
+            )
+        ));
+    }
+
+    #[test]
+    fn unknown_intrinsics_get_a_suggestion() {
+        let ast = Builder::new();
+        let function = ast.function("f", vec![], ast.unit_ty(), vec![ast.intrinsic("compile_eror", vec![])]);
+
+        let (_, errors) = lower_ok(&ast.file(vec![ast::Item::Fn(function)]));
+
+        assert!(
+            matches!(
+                &errors[..],
+                [LoweringError::UnknownIntrinsic { suggestion: Some(suggestion), .. }]
+                    if suggestion == "did you mean `@compile_error`?"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn generic_arguments_go_on_the_last_segment() {
+        let ast = Builder::new();
+        let module = ast.module("m", vec![ast.structure("S", &["T"], vec![])]);
+
+        // `m.S[bool]`, and `x[bool]` on a field of a local
+        let on_path = ast.expr(ast::ExprKind::Generic(
+            Box::new(ast.name("m.S")),
+            vec![ast::GenericArg::Type(ast.ty("bool"))],
+        ));
+        let on_field = ast.expr(ast::ExprKind::Generic(
+            Box::new(ast.name("x.y")),
+            vec![ast::GenericArg::Type(ast.ty("bool"))],
+        ));
+        let statements_ = vec![ast.let_(ast.pattern("x"), false, None, None), on_path, on_field];
+        let function = ast.function("f", vec![], ast.unit_ty(), statements_);
+
+        let (hir, errors) = lower_ok(&ast.file(vec![module, ast::Item::Fn(function)]));
+
+        assert!(matches!(&errors[..], [LoweringError::GenericArgsOnValue { .. }]), "{errors:?}");
+        assert!(matches!(
+            statements(body(&hir, "f")),
+            [
+                _,
+                hir::Expr { kind: hir::ExprKind::Path(hir::QPath::Resolved(path)), .. },
+                hir::Expr { kind: hir::ExprKind::Err, .. },
+            ] if matches!(path.res, hir::Res::Def(DefKind::Struct, _))
+                && path.segments[0].args.is_empty()
+                && matches!(path.segments[1].args[..], [hir::GenericArg::Type(_)])
+        ));
+    }
+
+    #[test]
+    fn optional_types_need_the_option_lang_item() {
+        let ast = Builder::new();
+        let optional = |ast: &Builder| ast.ty_kind(ast::TypeKind::Optional(Box::new(ast.ty("S"))));
+        let structure = |ast: &Builder| ast.structure("S", &[], vec![]);
+
+        let function = ast.function("f", vec![("s", optional(&ast))], ast.unit_ty(), vec![]);
+        let (_, errors) = lower_ok(&ast.file(vec![structure(&ast), ast::Item::Fn(function)]));
+        assert!(
+            matches!(&errors[..], [LoweringError::MissingLangItem { item, .. }] if *item == sym::Option),
+            "{errors:?}"
+        );
+
+        // `@lang(Option) enum Option[T] { None }`
+        let mut option = ast.enumeration("Option", &["T"], vec![("None", ast::EnumFields::Unit)]);
+        if let ast::Item::Enum(option) = &mut option {
+            option.attributes.push(ast.attribute("lang", vec![ast.name("Option")]));
+        }
+
+        let function = ast.function("f", vec![("s", optional(&ast))], ast.unit_ty(), vec![]);
+        let (hir, errors) = lower_ok(&ast.file(vec![option, structure(&ast), ast::Item::Fn(function)]));
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let option = hir.lang_items[&LangItem::Option];
+        let inputs = hir
+            .owners
+            .iter()
+            .find_map(|owner| match owner {
+                Some(hir::Owner::Item(hir::Item {
+                    kind: hir::ItemKind::Fn(sig, ..),
+                    ..
+                })) => Some(&sig.inputs),
+                _ => None,
+            })
+            .unwrap();
+        assert!(matches!(
+            &inputs[..],
+            [hir::Ty {
+                kind: hir::TyKind::Path(hir::QPath::Resolved(hir::Path { res: hir::Res::Def(DefKind::Enum, def), segments, .. })),
+                ..
+            }] if *def == option && matches!(segments[0].args[..], [hir::GenericArg::Type(_)])
+        ));
+    }
+
+    #[test]
+    fn items_without_a_definition_are_reported() {
+        let ast = Builder::new();
+        let (resolutions, _) = tangic_resolve::resolve(&ast.file(vec![ast.structure("A", &[], vec![])]));
+
+        // lowered with the resolutions of another crate, `B` was never defined
+        let b = ast.structure("B", &[], vec![]);
+        let b_span = b.span();
+        let (hir, errors) = lower(&ast.file(vec![b]), &resolutions);
+
+        assert!(
+            matches!(&errors[..], [LoweringError::NoDefinition { at, .. }] if *at == b_span.range().into()),
+            "{errors:?}"
+        );
+        assert!(matches!(
+            hir.owners[DefIndex::from_raw(0)],
+            Some(hir::Owner::Item(hir::Item { kind: hir::ItemKind::Mod(ref items), .. })) if items.is_empty()
+        ));
+    }
+
+    #[test]
+    fn while_is_a_loop_around_an_if() {
+        let ast = Builder::new();
+        let while_ = ast.while_(ast.bool(true), ast.expr(ast::ExprKind::Continue));
+        let function = ast.function("f", vec![], ast.unit_ty(), vec![while_]);
+
+        let (hir, errors) = lower_ok(&ast.file(vec![ast::Item::Fn(function)]));
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let [hir::Expr {
+            kind: hir::ExprKind::Loop(body),
+            ..
+        }] = statements(body(&hir, "f"))
+        else {
+            panic!("expected a loop");
+        };
+        assert!(matches!(
+            &body.statements[..],
+            [hir::Expr {
+                kind: hir::ExprKind::If {
+                    condition,
+                    then,
+                    otherwise: Some(otherwise),
+                },
+                ..
+            }] if matches!(condition.kind, hir::ExprKind::Lit(hir::Lit::Bool(true)))
+                && matches!(then.statements[..], [hir::Expr { kind: hir::ExprKind::Continue, .. }])
+                && matches!(otherwise.kind, hir::ExprKind::Break)
+        ));
+    }
+
+    #[test]
+    fn break_outside_of_a_loop() {
+        let ast = Builder::new();
+        let statements_ = vec![
+            ast.expr(ast::ExprKind::Break),
+            ast.loop_(vec![ast.expr(ast::ExprKind::Break)]),
+        ];
+        let function = ast.function("f", vec![], ast.unit_ty(), statements_);
+
+        let (hir, errors) = lower_ok(&ast.file(vec![ast::Item::Fn(function)]));
+
+        assert!(
+            matches!(&errors[..], [LoweringError::OutsideLoop { keyword, .. }] if *keyword == kw::Break),
+            "{errors:?}"
+        );
+        assert!(matches!(
+            statements(body(&hir, "f")),
+            [
+                hir::Expr {
+                    kind: hir::ExprKind::Err,
+                    ..
+                },
+                hir::Expr {
+                    kind: hir::ExprKind::Loop(_),
+                    ..
+                }
+            ]
+        ));
+    }
+}
@@ -0,0 +1,31 @@
+use miette::SourceSpan;
+use tangic_middle::span::FileId;
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum MirBuildError {
+    #[error("{what} are not supported yet")]
+    #[diagnostic(code(tangic::mir_build::unsupported))]
+    Unsupported {
+        what: &'static str,
+        #[label("not supported")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("evaluation of a constant failed")]
+    #[diagnostic(code(tangic::mir_build::const_eval))]
+    ConstEval {
+        reason: String,
+        #[label("{reason}")]
+        at: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl MirBuildError {
+    /// The file the error is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::Unsupported { file, .. } | Self::ConstEval { file, .. } => *file,
+        }
+    }
+}
@@ -0,0 +1,1051 @@
+//! Expressions and patterns.
+
+use tangic_middle::{
+    hir::{self, BinOp, DefKind, Res, UnOp},
+    mir::{
+        self, AggregateKind, AssertKind, BasicBlockId, FieldIdx, Local, LocalDecl, LocalKind, NullOp, Operand, Place,
+        ProjectionElem, Rvalue, SwitchTargets, Terminator, VariantIdx, RETURN_PLACE,
+    },
+    span::Span,
+    ty::{Adjust, Adjustment, Const, GenericArgs, Instance, Region, ScalarInt, Size, Ty, TyKind, ValTree},
+    DefId,
+};
+use tangic_typeck::EvalError;
+
+use crate::{mutability, zst, Builder, LoopScope, MirBuildError};
+
+impl<'b> Builder<'_, 'b> {
+    /// Lowers `expr` so that its value, after its adjustments, ends up in `dest`. Returns the
+    /// block control goes on in.
+    pub(crate) fn expr_into_dest(&mut self, dest: Place, block: BasicBlockId, expr: &hir::Expr) -> BasicBlockId {
+        match self.adjustments(expr).first() {
+            None => self.expr_into_dest_unadjusted(dest, block, expr),
+            // `!` has no values, so nothing gets to `dest`
+            Some(Adjustment {
+                kind: Adjust::NeverToAny,
+                ..
+            }) => self.as_unadjusted_temp(block, expr).0,
+            Some(_) => {
+                let (block, place) = self.as_place(block, expr);
+                let operand = self.consume(place, expr);
+                self.push_assign(block, dest, Rvalue::Use(operand));
+
+                block
+            }
+        }
+    }
+
+    /// `expr` as an operand: a constant, or a copy or move of the place its value is in.
+    pub(crate) fn as_operand(&mut self, block: BasicBlockId, expr: &hir::Expr) -> (BasicBlockId, Operand) {
+        let adjusted = !self.adjustments(expr).is_empty();
+
+        if !adjusted {
+            if let Some(constant) = self.as_constant(expr) {
+                return (block, Operand::Const(constant, self.node_ty(expr.hir_id)));
+            }
+        }
+
+        if adjusted || self.is_place_expr(expr) {
+            let (block, place) = self.as_place(block, expr);
+            return (block, self.consume(place, expr));
+        }
+
+        let (block, temp) = self.as_temp(block, expr);
+        (block, Operand::Move(temp.into()))
+    }
+
+    /// The place `expr` is, after its adjustments, or a temporary with its value in it if it
+    /// isn't one.
+    pub(crate) fn as_place(&mut self, block: BasicBlockId, expr: &hir::Expr) -> (BasicBlockId, Place) {
+        let adjustments = self.adjustments(expr);
+
+        if let Some(&Adjustment {
+            kind: Adjust::NeverToAny,
+            target,
+        }) = adjustments.first()
+        {
+            let (block, _) = self.as_unadjusted_temp(block, expr);
+            return (block, self.new_scoped_temp(block, target, expr.span).into());
+        }
+
+        let (block, mut place) = self.as_place_unadjusted(block, expr);
+
+        for adjustment in adjustments {
+            match adjustment.kind {
+                Adjust::Deref => place = place.project(ProjectionElem::Deref),
+                Adjust::Borrow(mutability) => {
+                    let temp = self.new_scoped_temp(block, adjustment.target, expr.span);
+                    self.push_assign(block, temp.into(), Rvalue::Ref(Region::Erased, mutability, place));
+                    place = temp.into();
+                }
+                Adjust::NeverToAny => unreachable!("only the first adjustment makes `!` into another type"),
+            }
+        }
+
+        (block, place)
+    }
+
+    fn as_place_unadjusted(&mut self, block: BasicBlockId, expr: &hir::Expr) -> (BasicBlockId, Place) {
+        match &expr.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Local(id), ..
+            })) => (block, self.var_locals[id].into()),
+            hir::ExprKind::Field(base, _) if self.results.field_indices.contains_key(&expr.hir_id.local_id) => {
+                let index = self.results.field_indices[&expr.hir_id.local_id];
+                let ty = self.node_ty(expr.hir_id);
+                let (block, place) = self.as_place(block, base);
+
+                (block, place.project(ProjectionElem::Field(FieldIdx::new(index), ty)))
+            }
+            hir::ExprKind::Unary(UnOp::Deref, inner) => {
+                let (block, place) = self.as_place(block, inner);
+                (block, place.project(ProjectionElem::Deref))
+            }
+            _ => {
+                let (block, temp) = self.as_unadjusted_temp(block, expr);
+                (block, temp.into())
+            }
+        }
+    }
+
+    /// A temporary with the value of `expr` in it, after its adjustments, dropped at the end
+    /// of the innermost scope.
+    pub(crate) fn as_temp(&mut self, block: BasicBlockId, expr: &hir::Expr) -> (BasicBlockId, Local) {
+        let temp = self.new_scoped_temp(block, self.expr_ty(expr), expr.span);
+        (self.expr_into_dest(temp.into(), block, expr), temp)
+    }
+
+    fn as_unadjusted_temp(&mut self, block: BasicBlockId, expr: &hir::Expr) -> (BasicBlockId, Local) {
+        let temp = self.new_scoped_temp(block, self.node_ty(expr.hir_id), expr.span);
+
+        (self.expr_into_dest_unadjusted(temp.into(), block, expr), temp)
+    }
+
+    /// Uses the value in `place`, which `expr` evaluated to, by copying or moving it.
+    fn consume(&self, place: Place, expr: &hir::Expr) -> Operand {
+        if self.expr_ty(expr).is_copy() {
+            Operand::Copy(place)
+        } else {
+            Operand::Move(place)
+        }
+    }
+
+    fn adjustments(&self, expr: &hir::Expr) -> &'b [Adjustment] {
+        let results = self.results;
+        results.adjustments.get(&expr.hir_id.local_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// The value of `expr`, if it is known while building, like that of a literal, a const or
+    /// a function item.
+    fn as_constant(&mut self, expr: &hir::Expr) -> Option<Const> {
+        match &expr.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Def(DefKind::Fn | DefKind::AssocFn, _),
+                ..
+            }))
+            // `Type.function`
+            | hir::ExprKind::Path(hir::QPath::TypeRelative(..)) => Some(zst()),
+            // a tuple variant's constructor, rather than a call to it
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Def(DefKind::Variant, _),
+                ..
+            })) if matches!(self.node_ty(expr.hir_id).kind(), TyKind::FnDef(..)) => Some(zst()),
+            hir::ExprKind::Field(..) if !self.results.field_indices.contains_key(&expr.hir_id.local_id) => Some(zst()),
+            hir::ExprKind::Tuple(exprs) if exprs.is_empty() => Some(zst()),
+            hir::ExprKind::Lit(hir::Lit::Float(value, _)) => {
+                let scalar = match self.node_ty(expr.hir_id).kind() {
+                    TyKind::Float(hir::FloatTy::F32) => {
+                        ScalarInt::try_from_uint((*value as f32).to_bits(), Size::from_bytes(4))
+                    }
+                    _ => ScalarInt::try_from_uint(value.to_bits(), Size::from_bytes(8)),
+                };
+
+                Some(Const::Value(ValTree::Leaf(scalar.expect("floats fit in their size"))))
+            }
+            hir::ExprKind::Lit(hir::Lit::ByteStr(bytes)) => Some(Const::Value(ValTree::Branch(
+                bytes.iter().map(|&byte| ValTree::Leaf(ScalarInt::from(byte))).collect(),
+            ))),
+            // types are compared in functions over types
+            hir::ExprKind::Binary(BinOp::Eq | BinOp::Ne, lhs, rhs)
+                if self.results.type_exprs.contains_key(&lhs.hir_id.local_id)
+                    && self.results.type_exprs.contains_key(&rhs.hir_id.local_id) =>
+            {
+                Some(self.eval_constant(expr))
+            }
+            hir::ExprKind::Lit(_) | hir::ExprKind::Intrinsic(hir::Intrinsic::Fmt, _) => {
+                Some(self.eval_constant(expr))
+            }
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Def(DefKind::Const, def),
+                ..
+            })) => {
+                let result = self.ecx.eval_to_const(Instance::mono(*def), expr.span);
+                Some(self.const_or_report(result.map(Some), expr.span))
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates `expr` in the body, with its type parameters as themselves.
+    fn eval_constant(&mut self, expr: &hir::Expr) -> Const {
+        let result = self.ecx.eval_expr_in(self.def, self.identity_args, expr);
+        self.const_or_report(result.map(|value| value.to_valtree().map(Const::Value)), expr.span)
+    }
+
+    /// The constant evaluation found, or a stand-in for it after reporting why there is none.
+    fn const_or_report(&mut self, result: Result<Option<Const>, EvalError>, span: Span) -> Const {
+        match result {
+            Ok(Some(value)) => return value,
+            Ok(None) => self.errors.push(MirBuildError::ConstEval {
+                reason: "types can't be kept as constants".to_owned(),
+                at: span.range().into(),
+                file: span.file,
+            }),
+            Err(EvalError::TooGeneric) => {
+                self.report_unsupported("constants that depend on type parameters", span)
+            }
+            Err(EvalError::Failed { reason, span }) => self.errors.push(MirBuildError::ConstEval {
+                reason,
+                at: span.range().into(),
+                file: span.file,
+            }),
+            Err(EvalError::Reported) => {}
+        }
+
+        zst()
+    }
+
+    fn expr_into_dest_unadjusted(&mut self, dest: Place, block: BasicBlockId, expr: &hir::Expr) -> BasicBlockId {
+        if let Some(constant) = self.as_constant(expr) {
+            let ty = self.node_ty(expr.hir_id);
+            self.push_assign(block, dest, Rvalue::Use(Operand::Const(constant, ty)));
+
+            return block;
+        }
+
+        match &expr.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(path)) => match path.res {
+                Res::Local(_) => self.place_into_dest(dest, block, expr),
+                // a unit variant
+                Res::Def(DefKind::Variant, def) => {
+                    let TyKind::Adt(_, args) = *self.node_ty(expr.hir_id).kind() else {
+                        return block;
+                    };
+
+                    self.variant_into_dest(dest, block, def, args, &[])
+                }
+                Res::Def(DefKind::Static, _) => {
+                    self.report_unsupported("statics", expr.span);
+                    block
+                }
+                Res::Def(DefKind::ConstParam, _) => {
+                    self.report_unsupported("const parameters as values", expr.span);
+                    block
+                }
+                // reported by type checking
+                _ => block,
+            },
+            hir::ExprKind::Path(hir::QPath::TypeRelative(..)) => unreachable!("function items are constants"),
+            hir::ExprKind::Field(..) | hir::ExprKind::Unary(UnOp::Deref, _) => self.place_into_dest(dest, block, expr),
+            hir::ExprKind::Tuple(exprs) => {
+                let (block, operands) = self.as_operands(block, exprs.iter());
+                self.push_assign(block, dest, Rvalue::Aggregate(AggregateKind::Tuple, operands));
+
+                block
+            }
+            hir::ExprKind::Block(body) => self.block_into_dest(dest, block, body),
+            hir::ExprKind::Let(local) => {
+                let block = self.lower_let(block, local);
+                self.push_assign_unit(block, dest);
+
+                block
+            }
+            hir::ExprKind::Call(callee, args) => {
+                let inputs = match *self.expr_ty(callee).kind() {
+                    TyKind::FnDef(def, _) => self.items.fn_sigs.get(&def.index).map_or(0, |sig| sig.inputs().len()),
+                    TyKind::FnPtr(sig) => sig.inputs().len(),
+                    _ => return block,
+                };
+
+                // types passed to type parameters come first, and are not values
+                let args = &args[args.len().saturating_sub(inputs)..];
+
+                // calling a tuple variant's constructor makes the variant
+                if let (
+                    hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                        res: Res::Def(DefKind::Variant, def),
+                        ..
+                    })),
+                    &TyKind::FnDef(_, variant_args),
+                ) = (&callee.kind, self.expr_ty(callee).kind())
+                {
+                    return self.variant_into_dest(dest, block, *def, variant_args, args);
+                }
+
+                let (block, func) = self.as_operand(block, callee);
+                self.call_into_dest(dest, block, expr, func, args.iter())
+            }
+            hir::ExprKind::MethodCall { receiver, args, .. } => {
+                let Some(&(def, fn_args)) = self.results.type_dependent_defs.get(&expr.hir_id.local_id) else {
+                    return block;
+                };
+
+                // `Type.function args` has no receiver
+                let receiver = match self.results.type_exprs.contains_key(&receiver.hir_id.local_id) {
+                    true => None,
+                    false => Some(&**receiver),
+                };
+
+                let inputs = self.items.fn_sigs.get(&def.index).map_or(0, |sig| sig.inputs().len());
+                let args = &args[args.len().saturating_sub(inputs - receiver.is_some() as usize)..];
+
+                let func = Operand::Const(zst(), self.items.cx.mk_fn_def(def, &fn_args));
+                self.call_into_dest(dest, block, expr, func, receiver.into_iter().chain(args))
+            }
+            hir::ExprKind::AddrOf(mutability, inner) => {
+                let (block, place) = self.as_place(block, inner);
+                self.push_assign(block, dest, Rvalue::Ref(Region::Erased, *mutability, place));
+
+                block
+            }
+            hir::ExprKind::Binary(op @ (BinOp::And | BinOp::Or), lhs, rhs) => {
+                self.logical_op_into_dest(dest, block, *op, lhs, rhs)
+            }
+            hir::ExprKind::Binary(op, lhs, rhs) => self.binary_op_into_dest(dest, block, *op, lhs, rhs),
+            hir::ExprKind::Unary(op, operand) => self.unary_op_into_dest(dest, block, *op, operand),
+            hir::ExprKind::Assign(lhs, rhs) => {
+                let (block, value) = self.as_operand(block, rhs);
+                let (block, place) = self.as_place(block, lhs);
+
+                // the old value is dropped first, if there is one
+                let block = match crate::needs_drop(self.expr_ty(lhs)) {
+                    true => {
+                        let unwind = self.unwind_action();
+                        let next = self.new_block("assign");
+
+                        self.terminate(block, Terminator::Drop {
+                            place: place.clone(),
+                            target: next,
+                            unwind,
+                        });
+
+                        next
+                    }
+                    false => block,
+                };
+
+                self.push_assign(block, place, Rvalue::Use(value));
+                self.push_assign_unit(block, dest);
+
+                block
+            }
+            hir::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let (block, condition) = self.as_operand(block, condition);
+                let then_block = self.new_block("if_then");
+                let else_block = self.new_block("if_else");
+                let join = self.new_block("if_join");
+
+                self.terminate(block, Terminator::SwitchInt {
+                    discr: condition,
+                    targets: SwitchTargets::static_if(0, else_block, then_block),
+                });
+
+                let then_end = self.block_into_dest(dest.clone(), then_block, then);
+                self.goto(then_end, join);
+
+                let else_end = match otherwise {
+                    Some(otherwise) => self.expr_into_dest(dest, else_block, otherwise),
+                    None => {
+                        self.push_assign_unit(else_block, dest);
+                        else_block
+                    }
+                };
+                self.goto(else_end, join);
+
+                join
+            }
+            hir::ExprKind::Return(value) => {
+                let block = self.expr_into_dest(RETURN_PLACE.into(), block, value);
+                let block = self.exit_scopes(block, 0);
+                self.goto(block, self.return_block);
+
+                self.new_block("after_return")
+            }
+            hir::ExprKind::Loop(body) => {
+                let loop_block = self.new_block("loop");
+                let exit = self.new_block("loop_exit");
+                self.goto(block, loop_block);
+
+                self.loops.push(LoopScope {
+                    continue_block: loop_block,
+                    break_block: exit,
+                    scope_depth: self.scopes.len(),
+                });
+
+                // what the body evaluates to is thrown away each time around
+                self.push_scope();
+                let value = self.new_scoped_temp(loop_block, self.node_ty(body.hir_id), body.span);
+                let end = self.block_into_dest(value.into(), loop_block, body);
+                let end = self.pop_scope(end);
+                self.goto(end, loop_block);
+
+                self.loops.pop();
+
+                // a loop without a `break` never gets to its exit
+                if !self.node_ty(expr.hir_id).is_never() {
+                    self.push_assign_unit(exit, dest);
+                }
+
+                exit
+            }
+            hir::ExprKind::Break | hir::ExprKind::Continue => {
+                // lowering reports those outside of a loop
+                let Some(loop_scope) = self.loops.last() else {
+                    return block;
+                };
+
+                let target = match expr.kind {
+                    hir::ExprKind::Break => loop_scope.break_block,
+                    _ => loop_scope.continue_block,
+                };
+
+                let block = self.exit_scopes(block, loop_scope.scope_depth);
+                self.goto(block, target);
+
+                self.new_block("after_break")
+            }
+            hir::ExprKind::Intrinsic(hir::Intrinsic::CompileError, _) => {
+                // monomorphization reports the instances that get here
+                self.terminate(block, Terminator::Unreachable);
+                self.new_block("after_compile_error")
+            }
+            hir::ExprKind::Intrinsic(intrinsic @ (hir::Intrinsic::TypeName | hir::Intrinsic::CanCast), args) => {
+                let tys: Option<Vec<_>> = args
+                    .iter()
+                    .map(|arg| self.results.type_exprs.get(&arg.hir_id.local_id).copied())
+                    .collect();
+
+                let op = match (intrinsic, tys.as_deref()) {
+                    (hir::Intrinsic::TypeName, Some(&[ty])) => NullOp::TypeName(ty),
+                    (hir::Intrinsic::CanCast, Some(&[from, to])) => NullOp::CanCast(from, to),
+                    // reported by type checking
+                    _ => return block,
+                };
+
+                self.push_assign(block, dest, Rvalue::NullaryOp(op));
+                block
+            }
+            hir::ExprKind::Lit(_) | hir::ExprKind::Intrinsic(hir::Intrinsic::Fmt, _) => {
+                unreachable!("constants are lowered by `as_constant`")
+            }
+            hir::ExprKind::Err => block,
+        }
+    }
+
+    /// Copies or moves the value of the place expression `expr` to `dest`.
+    fn place_into_dest(&mut self, dest: Place, block: BasicBlockId, expr: &hir::Expr) -> BasicBlockId {
+        let (block, place) = self.as_place_unadjusted(block, expr);
+        let operand = match self.node_ty(expr.hir_id).is_copy() {
+            true => Operand::Copy(place),
+            false => Operand::Move(place),
+        };
+
+        self.push_assign(block, dest, Rvalue::Use(operand));
+        block
+    }
+
+    /// Lowers the statements of `body` in a scope of their own, with the value of the last one
+    /// going to `dest`.
+    pub(crate) fn block_into_dest(&mut self, dest: Place, mut block: BasicBlockId, body: &hir::Block) -> BasicBlockId {
+        self.push_scope();
+
+        match body.statements.split_last() {
+            Some((last, statements)) => {
+                for statement in statements {
+                    block = self.statement(block, statement);
+                }
+
+                block = self.expr_into_dest(dest, block, last);
+            }
+            None => self.push_assign_unit(block, dest),
+        }
+
+        self.pop_scope(block)
+    }
+
+    /// Lowers an expression whose value isn't used, in a scope of its own for it and the
+    /// temporaries made for it to be dropped right after.
+    fn statement(&mut self, block: BasicBlockId, expr: &hir::Expr) -> BasicBlockId {
+        if let hir::ExprKind::Let(local) = &expr.kind {
+            return self.lower_let(block, local);
+        }
+
+        self.push_scope();
+        let temp = self.new_scoped_temp(block, self.expr_ty(expr), expr.span);
+        let block = self.expr_into_dest(temp.into(), block, expr);
+
+        self.pop_scope(block)
+    }
+
+    /// `a && b` and `a || b`, which only evaluate `b` if `a` doesn't decide the value.
+    fn logical_op_into_dest(
+        &mut self,
+        dest: Place,
+        block: BasicBlockId,
+        op: BinOp,
+        lhs: &hir::Expr,
+        rhs: &hir::Expr,
+    ) -> BasicBlockId {
+        let (block, lhs) = self.as_operand(block, lhs);
+        let rhs_block = self.new_block("logical_rhs");
+        let short = self.new_block("logical_short");
+        let join = self.new_block("logical_join");
+
+        // `false && b` is `false`, `true || b` is `true`
+        let (targets, short_value) = match op {
+            BinOp::And => (SwitchTargets::static_if(0, short, rhs_block), false),
+            _ => (SwitchTargets::static_if(0, rhs_block, short), true),
+        };
+
+        self.terminate(block, Terminator::SwitchInt { discr: lhs, targets });
+
+        let value = Const::Value(ValTree::Leaf(ScalarInt::from(short_value)));
+        let value = Operand::Const(value, self.items.cx.types.bool);
+        self.push_assign(short, dest.clone(), Rvalue::Use(value));
+        self.goto(short, join);
+
+        let rhs_end = self.expr_into_dest(dest, rhs_block, rhs);
+        self.goto(rhs_end, join);
+
+        join
+    }
+
+    /// Calls `func` with `args`, storing what it returns in `dest`.
+    fn call_into_dest<'h>(
+        &mut self,
+        dest: Place,
+        block: BasicBlockId,
+        expr: &hir::Expr,
+        func: Operand,
+        args: impl Iterator<Item = &'h hir::Expr>,
+    ) -> BasicBlockId {
+        let (block, operands) = self.as_operands(block, args);
+
+        let target = match self.node_ty(expr.hir_id).is_never() {
+            true => None,
+            false => Some(self.new_block("call_return")),
+        };
+
+        let unwind = self.unwind_action();
+        self.terminate(block, Terminator::Call {
+            func,
+            args: operands,
+            destination: dest,
+            target,
+            unwind,
+        });
+
+        target.unwrap_or_else(|| self.new_block("after_diverging_call"))
+    }
+
+    /// Each of `exprs` as an operand, evaluated in order.
+    fn as_operands<'h>(
+        &mut self,
+        mut block: BasicBlockId,
+        exprs: impl Iterator<Item = &'h hir::Expr>,
+    ) -> (BasicBlockId, Vec<Operand>) {
+        let mut operands = vec![];
+
+        for expr in exprs {
+            let (next, operand) = self.as_operand(block, expr);
+            operands.push(operand);
+            block = next;
+        }
+
+        (block, operands)
+    }
+
+    /// Makes `variant` of its enum, with the enum's type parameters instantiated with `args`,
+    /// out of `fields`.
+    fn variant_into_dest(
+        &mut self,
+        dest: Place,
+        block: BasicBlockId,
+        variant: DefId,
+        args: GenericArgs,
+        fields: &[hir::Expr],
+    ) -> BasicBlockId {
+        let Some((enum_def, index)) = self.variant_index(variant) else {
+            return block;
+        };
+
+        let (block, operands) = self.as_operands(block, fields.iter());
+        let kind = AggregateKind::Adt(enum_def, index, args);
+        self.push_assign(block, dest, Rvalue::Aggregate(kind, operands));
+
+        block
+    }
+
+    /// The enum `variant` is of, and where it is among the enum's variants.
+    fn variant_index(&self, variant: DefId) -> Option<(DefId, VariantIdx)> {
+        let enum_index = *self.items.parents.get(&variant.index)?;
+        let hir::ItemKind::Enum(_, variants) = &self.items.item(enum_index)?.kind else {
+            return None;
+        };
+
+        let index = variants.iter().position(|candidate| candidate.def == variant.index)?;
+        let enum_def = DefId {
+            krate: variant.krate,
+            index: enum_index,
+        };
+
+        Some((enum_def, VariantIdx::new(index)))
+    }
+
+    /// `a op b`, checking that integer arithmetic doesn't overflow or divide by zero first.
+    fn binary_op_into_dest(
+        &mut self,
+        dest: Place,
+        block: BasicBlockId,
+        op: BinOp,
+        lhs: &hir::Expr,
+        rhs: &hir::Expr,
+    ) -> BasicBlockId {
+        let cx = self.items.cx;
+        let ty = self.expr_ty(lhs);
+        let span = lhs.span.to(rhs.span);
+
+        let (block, lhs) = self.as_operand(block, lhs);
+        let (block, rhs) = self.as_operand(block, rhs);
+        let op = mir_bin_op(op);
+
+        let Some((size, signed)) = self.int_layout(ty) else {
+            self.push_assign(block, dest, Rvalue::BinaryOp(op, lhs, rhs));
+            return block;
+        };
+
+        match op {
+            mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul | mir::BinOp::Shl | mir::BinOp::Shr => {
+                let checked = self.new_scoped_temp(block, cx.mk_tuple(&[ty, cx.types.bool]), span);
+                let result = Place::from(checked).project(ProjectionElem::Field(FieldIdx::new(0), ty));
+                let overflowed = Place::from(checked).project(ProjectionElem::Field(FieldIdx::new(1), cx.types.bool));
+
+                let rvalue = Rvalue::CheckedBinaryOp(op, lhs.to_copy(), rhs.to_copy());
+                self.push_assign(block, checked.into(), rvalue);
+
+                let msg = AssertKind::Overflow(op, lhs, rhs);
+                let block = self.assert(block, Operand::Move(overflowed), msg);
+                self.push_assign(block, dest, Rvalue::Use(Operand::Move(result)));
+
+                block
+            }
+            mir::BinOp::Div | mir::BinOp::Rem => {
+                let zero = int_operand(0, ty, size);
+                let is_zero = self.bool_temp(block, Rvalue::BinaryOp(mir::BinOp::Eq, rhs.to_copy(), zero), span);
+
+                let msg = match op {
+                    mir::BinOp::Div => AssertKind::DivisionByZero(lhs.to_copy()),
+                    _ => AssertKind::RemainderByZero(lhs.to_copy()),
+                };
+                let mut block = self.assert(block, Operand::Move(is_zero.into()), msg);
+
+                // `MIN / -1` is one more than `MAX`
+                if signed {
+                    let neg_one = int_operand(u128::MAX, ty, size);
+                    let min = int_operand(size.signed_int_min() as u128, ty, size);
+
+                    let is_neg_one = Rvalue::BinaryOp(mir::BinOp::Eq, rhs.to_copy(), neg_one);
+                    let is_neg_one = self.bool_temp(block, is_neg_one, span);
+                    let is_min = Rvalue::BinaryOp(mir::BinOp::Eq, lhs.to_copy(), min);
+                    let is_min = self.bool_temp(block, is_min, span);
+
+                    let overflows = Rvalue::BinaryOp(
+                        mir::BinOp::BitAnd,
+                        Operand::Move(is_neg_one.into()),
+                        Operand::Move(is_min.into()),
+                    );
+                    let overflows = self.bool_temp(block, overflows, span);
+
+                    let msg = AssertKind::Overflow(op, lhs.to_copy(), rhs.to_copy());
+                    block = self.assert(block, Operand::Move(overflows.into()), msg);
+                }
+
+                self.push_assign(block, dest, Rvalue::BinaryOp(op, lhs, rhs));
+                block
+            }
+            _ => {
+                self.push_assign(block, dest, Rvalue::BinaryOp(op, lhs, rhs));
+                block
+            }
+        }
+    }
+
+    /// `!a` or `-a`, checking that negating a signed integer doesn't overflow first.
+    fn unary_op_into_dest(&mut self, dest: Place, block: BasicBlockId, op: UnOp, operand: &hir::Expr) -> BasicBlockId {
+        let ty = self.expr_ty(operand);
+        let span = operand.span;
+        let (mut block, operand) = self.as_operand(block, operand);
+
+        let op = match op {
+            UnOp::Not => mir::UnOp::Not,
+            UnOp::Neg => mir::UnOp::Neg,
+            UnOp::Deref => unreachable!("dereferences are places"),
+        };
+
+        if let (mir::UnOp::Neg, Some((size, true))) = (op, self.int_layout(ty)) {
+            let min = int_operand(size.signed_int_min() as u128, ty, size);
+            let is_min = self.bool_temp(block, Rvalue::BinaryOp(mir::BinOp::Eq, operand.to_copy(), min), span);
+
+            block = self.assert(block, Operand::Move(is_min.into()), AssertKind::OverflowNeg(operand.to_copy()));
+        }
+
+        self.push_assign(block, dest, Rvalue::UnaryOp(op, operand));
+        block
+    }
+
+    /// A `bool` temporary with the value of `rvalue` in it.
+    fn bool_temp(&mut self, block: BasicBlockId, rvalue: Rvalue, span: Span) -> Local {
+        let temp = self.new_scoped_temp(block, self.items.cx.types.bool, span);
+        self.push_assign(block, temp.into(), rvalue);
+
+        temp
+    }
+
+    /// Panics with `msg` if `cond` is `true`. Returns the block control goes on in if it isn't.
+    fn assert(&mut self, block: BasicBlockId, cond: Operand, msg: AssertKind) -> BasicBlockId {
+        let target = self.new_block("assert_success");
+        let unwind = self.unwind_action();
+
+        self.terminate(block, Terminator::Assert {
+            cond,
+            expected: false,
+            msg,
+            target,
+            unwind,
+        });
+
+        target
+    }
+
+    fn lower_let(&mut self, block: BasicBlockId, local: &hir::Local) -> BasicBlockId {
+        self.declare_bindings(block, &local.pat);
+
+        let Some(init) = &local.init else {
+            return block;
+        };
+
+        match local.pat.kind {
+            hir::PatKind::Binding {
+                mode: hir::BindingMode { by_ref: false, .. },
+                sub: None,
+                ..
+            } => {
+                let var = self.var_locals[&local.pat.hir_id];
+                self.expr_into_dest(var.into(), block, init)
+            }
+            _ => {
+                let (block, place) = self.as_place(block, init);
+                self.bind_pat(block, &local.pat, place);
+
+                block
+            }
+        }
+    }
+
+    /// Makes a local for each variable `pat` binds, whose storage starts at the end of `block`
+    /// and which is dropped at the end of the innermost scope.
+    pub(crate) fn declare_bindings(&mut self, block: BasicBlockId, pat: &hir::Pat) {
+        match &pat.kind {
+            hir::PatKind::Binding { mode, name, sub } => {
+                let ty = self.node_ty(pat.hir_id);
+
+                let (ty, mutability) = match mode.by_ref {
+                    true => (self.items.cx.mk_ref(Region::Erased, ty, mutability(mode.mutable)), mutability(false)),
+                    false => (ty, mutability(mode.mutable)),
+                };
+
+                let local = self.local_decls.push(LocalDecl {
+                    ty,
+                    mutability,
+                    kind: LocalKind::Var(name.name),
+                    span: pat.span,
+                });
+
+                self.var_locals.insert(pat.hir_id, local);
+                self.storage_live(block, local);
+                self.schedule_drop(local);
+
+                if let Some(sub) = sub {
+                    self.declare_bindings(block, sub);
+                }
+            }
+            hir::PatKind::Tuple(pats) => {
+                for pat in pats {
+                    self.declare_bindings(block, pat);
+                }
+            }
+        }
+    }
+
+    /// Assigns the variables `pat` binds from the value in `place`, which it matches.
+    pub(crate) fn bind_pat(&mut self, block: BasicBlockId, pat: &hir::Pat, place: Place) {
+        match &pat.kind {
+            hir::PatKind::Binding { mode, sub, .. } => {
+                // `x @ y` binds `y` first, type checking made sure a value that is moved is bound once
+                if let Some(sub) = sub {
+                    self.bind_pat(block, sub, place.clone());
+                }
+
+                let ty = self.node_ty(pat.hir_id);
+                let rvalue = match (mode.by_ref, ty.is_copy()) {
+                    (true, _) => Rvalue::Ref(Region::Erased, mutability(mode.mutable), place),
+                    (false, true) => Rvalue::Use(Operand::Copy(place)),
+                    (false, false) => Rvalue::Use(Operand::Move(place)),
+                };
+
+                let local = self.var_locals[&pat.hir_id];
+                self.push_assign(block, local.into(), rvalue);
+            }
+            hir::PatKind::Tuple(pats) => {
+                for (i, pat) in pats.iter().enumerate() {
+                    let ty = self.node_ty(pat.hir_id);
+                    let field = place.clone().project(ProjectionElem::Field(FieldIdx::new(i), ty));
+
+                    self.bind_pat(block, pat, field);
+                }
+            }
+        }
+    }
+
+    /// Whether `expr` is a place, which is used by copying or moving out of it rather than by
+    /// storing it in a temporary first.
+    fn is_place_expr(&self, expr: &hir::Expr) -> bool {
+        match &expr.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Local(_), ..
+            }))
+            | hir::ExprKind::Unary(UnOp::Deref, _) => true,
+            // `Type.function` is a function item instead
+            hir::ExprKind::Field(..) => self.results.field_indices.contains_key(&expr.hir_id.local_id),
+            _ => false,
+        }
+    }
+}
+
+fn mir_bin_op(op: BinOp) -> mir::BinOp {
+    match op {
+        BinOp::Add => mir::BinOp::Add,
+        BinOp::Sub => mir::BinOp::Sub,
+        BinOp::Mul => mir::BinOp::Mul,
+        BinOp::Div => mir::BinOp::Div,
+        BinOp::Rem => mir::BinOp::Rem,
+        BinOp::BitAnd => mir::BinOp::BitAnd,
+        BinOp::BitOr => mir::BinOp::BitOr,
+        BinOp::BitXor => mir::BinOp::BitXor,
+        BinOp::Shl => mir::BinOp::Shl,
+        BinOp::Shr => mir::BinOp::Shr,
+        BinOp::Eq => mir::BinOp::Eq,
+        BinOp::Ne => mir::BinOp::Ne,
+        BinOp::Lt => mir::BinOp::Lt,
+        BinOp::Le => mir::BinOp::Le,
+        BinOp::Gt => mir::BinOp::Gt,
+        BinOp::Ge => mir::BinOp::Ge,
+        BinOp::And | BinOp::Or => unreachable!("`&&` and `||` are lowered to branches"),
+    }
+}
+
+/// The integer of type `ty` and size `size` whose bits are the low bits of `value`.
+fn int_operand(value: u128, ty: Ty, size: Size) -> Operand {
+    let scalar = ScalarInt::try_from_uint(size.truncate(value), size).expect("the value is truncated to fit");
+    Operand::Const(Const::Value(ValTree::Leaf(scalar)), ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+    use tangic_middle::mir::{Body, START_BLOCK};
+
+    use super::*;
+    use crate::tests::{block_named, build, local_named};
+
+    /// `f(a: ty, b: ty) -> returns { value }`
+    fn operator_fn(b: &Builder, ty: &str, returns: &str, value: ast::Expr) -> Body {
+        let f = b.function("f", vec![("a", b.ty(ty)), ("b", b.ty(ty))], b.ty(returns), vec![value]);
+        build(b, vec![ast::Item::Fn(f)], "f")
+    }
+
+    /// The value of each assignment in `body`, by block.
+    fn rvalues(body: &Body) -> Vec<&Rvalue> {
+        body.basic_blocks
+            .iter()
+            .flat_map(|block| &block.statements)
+            .filter_map(|statement| match statement {
+                mir::Statement::Assign(_, rvalue) => Some(rvalue),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Why each `Assert` in `body` panics, by block.
+    fn asserts(body: &Body) -> Vec<&AssertKind> {
+        body.basic_blocks
+            .iter()
+            .filter_map(|block| match &block.terminator {
+                Some(Terminator::Assert { msg, .. }) => Some(msg),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn is_copy_of(operand: &Operand, local: Local) -> bool {
+        matches!(operand, Operand::Copy(place) if place.local == local && place.projection.is_empty())
+    }
+
+    #[test]
+    fn arithmetic_asserts_it_does_not_overflow() {
+        let b = Builder::new();
+        let body = operator_fn(&b, "i32", "i32", b.binary(ast::BinaryOp::Add, b.name("a"), b.name("b")));
+        let [a, b] = ["a", "b"].map(|name| local_named(&body, name));
+
+        let [mir::Statement::Assign(checked, Rvalue::CheckedBinaryOp(mir::BinOp::Add, lhs, rhs))] =
+            &body.basic_blocks[START_BLOCK].statements[1..]
+        else {
+            panic!(
+                "expected a checked addition, found {:?}",
+                body.basic_blocks[START_BLOCK].statements
+            );
+        };
+        assert!(is_copy_of(lhs, a) && is_copy_of(rhs, b));
+
+        // the sum is only used if the flag next to it says it didn't overflow
+        let (success, block) = block_named(&body, "assert_success");
+        assert!(matches!(
+            &body.basic_blocks[START_BLOCK].terminator,
+            Some(Terminator::Assert {
+                cond: Operand::Move(flag),
+                expected: false,
+                msg: AssertKind::Overflow(mir::BinOp::Add, ..),
+                target,
+                ..
+            }) if flag.local == checked.local && *target == success
+        ));
+        assert!(matches!(
+            &block.statements[0],
+            mir::Statement::Assign(dest, Rvalue::Use(Operand::Move(sum)))
+                if dest.local == RETURN_PLACE && sum.local == checked.local && sum.projection.len() == 1
+        ));
+    }
+
+    #[test]
+    fn division_asserts_the_divisor_is_not_zero() {
+        let b = Builder::new();
+        let body = operator_fn(&b, "i32", "i32", b.binary(ast::BinaryOp::Div, b.name("a"), b.name("b")));
+
+        // and for signed integers, that it isn't `MIN / -1`
+        assert!(
+            matches!(
+                &asserts(&body)[..],
+                [AssertKind::DivisionByZero(_), AssertKind::Overflow(mir::BinOp::Div, ..)]
+            ),
+            "{body:#?}"
+        );
+        assert!(matches!(
+            rvalues(&body).last(),
+            Some(Rvalue::BinaryOp(mir::BinOp::Div, ..))
+        ));
+
+        let b = Builder::new();
+        let body = operator_fn(&b, "u32", "u32", b.binary(ast::BinaryOp::Rem, b.name("a"), b.name("b")));
+
+        assert!(
+            matches!(&asserts(&body)[..], [AssertKind::RemainderByZero(_)]),
+            "{body:#?}"
+        );
+    }
+
+    #[test]
+    fn comparisons_and_floats_are_not_checked() {
+        let b = Builder::new();
+        let body = operator_fn(&b, "i32", "bool", b.binary(ast::BinaryOp::Lt, b.name("a"), b.name("b")));
+
+        assert!(asserts(&body).is_empty(), "{body:#?}");
+        assert!(matches!(rvalues(&body)[..], [Rvalue::BinaryOp(mir::BinOp::Lt, ..)]));
+
+        let b = Builder::new();
+        let body = operator_fn(&b, "f64", "f64", b.binary(ast::BinaryOp::Mul, b.name("a"), b.name("b")));
+
+        assert!(asserts(&body).is_empty(), "{body:#?}");
+        assert!(matches!(rvalues(&body)[..], [Rvalue::BinaryOp(mir::BinOp::Mul, ..)]));
+    }
+
+    #[test]
+    fn negating_signed_integers_asserts_it_does_not_overflow() {
+        let b = Builder::new();
+        let body = operator_fn(&b, "i32", "i32", b.unary(ast::UnaryOp::Neg, b.name("a")));
+        let a = local_named(&body, "a");
+
+        assert!(
+            matches!(&asserts(&body)[..], [AssertKind::OverflowNeg(operand)] if is_copy_of(operand, a)),
+            "{body:#?}"
+        );
+        assert!(matches!(
+            rvalues(&body).last(),
+            Some(Rvalue::UnaryOp(mir::UnOp::Neg, operand)) if is_copy_of(operand, a)
+        ));
+
+        let b = Builder::new();
+        let body = operator_fn(&b, "bool", "bool", b.unary(ast::UnaryOp::Not, b.name("a")));
+
+        assert!(asserts(&body).is_empty(), "{body:#?}");
+        assert!(matches!(rvalues(&body)[..], [Rvalue::UnaryOp(mir::UnOp::Not, _)]));
+    }
+
+    #[test]
+    fn tuples_and_variants_are_aggregates() {
+        let b = Builder::new();
+        let tuple = b.tuple_ty(vec![b.ty("i32"), b.ty("bool")]);
+        let f = b.function(
+            "f",
+            vec![("a", b.ty("i32")), ("b", b.ty("bool"))],
+            tuple,
+            vec![b.tuple(vec![b.name("a"), b.name("b")])],
+        );
+        let body = build(&b, vec![ast::Item::Fn(f)], "f");
+        let [a, b] = ["a", "b"].map(|name| local_named(&body, name));
+
+        assert!(matches!(
+            rvalues(&body)[..],
+            [Rvalue::Aggregate(AggregateKind::Tuple, ref operands)]
+                if is_copy_of(&operands[0], a) && is_copy_of(&operands[1], b)
+        ));
+
+        let b = Builder::new();
+        let variants = vec![
+            ("A", ast::EnumFields::Unit),
+            ("B", ast::EnumFields::Tuple(vec![b.ty("i32")])),
+        ];
+        let statements = vec![
+            b.let_(b.pattern("x"), false, None, Some(b.name("E.A"))),
+            b.call(b.name("E.B"), vec![b.name("a")]),
+        ];
+        let f = b.function("f", vec![("a", b.ty("i32"))], b.ty("E"), statements);
+        let body = build(&b, vec![b.enumeration("E", &[], variants), ast::Item::Fn(f)], "f");
+        let a = local_named(&body, "a");
+
+        let variants: Vec<_> = rvalues(&body)
+            .into_iter()
+            .filter_map(|rvalue| match rvalue {
+                Rvalue::Aggregate(AggregateKind::Adt(_, variant, _), operands) => Some((variant.index(), operands)),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            matches!(&variants[..], [(0, unit), (1, fields)] if unit.is_empty() && is_copy_of(&fields[0], a)),
+            "{body:#?}"
+        );
+    }
+}
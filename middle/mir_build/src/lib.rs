@@ -0,0 +1,451 @@
+//! Builds the [MIR](tangic_middle::mir) of each function, const and static from its HIR and
+//! what type checking found out about it.
+//!
+//! Expressions are lowered into the place their value goes, or into a temporary when they are
+//! an operand of something else. Each block is a scope, and the variables and temporaries made
+//! in it are dropped when control leaves it: by falling off its end, by `break`, `continue` or
+//! `return`, or by unwinding out of a call that panicked, through cleanup blocks.
+//!
+//! Integer arithmetic is checked by an `Assert` before its result is used, for overflow and for
+//! division by zero.
+
+use std::collections::HashMap;
+
+use tangic_middle::{
+    hir::{self, HirId, Mutability},
+    index::IndexVec,
+    mir::{
+        BasicBlock, BasicBlockId, Body, BasicBlocks, Local, LocalDecl, LocalDecls, LocalKind, Operand, Place,
+        Rvalue, Statement, Terminator, RETURN_PLACE, START_BLOCK,
+    },
+    span::Span,
+    ty::{Const, GenericArgs, Size, Ty, TyKind, TypeckResults, ValTree},
+    DefIndex,
+};
+use tangic_typeck::{ConstEvalCtxt, CrateTypes, ItemTypes};
+
+mod error;
+mod expr;
+mod scope;
+
+pub use error::MirBuildError;
+
+use scope::{LoopScope, Scope};
+
+/// The MIR of each function, const and static with a body, by their definitions.
+pub fn build_mir(types: &CrateTypes) -> (HashMap<DefIndex, Body>, Vec<MirBuildError>) {
+    let mut bodies = HashMap::new();
+    let mut errors = vec![];
+
+    for (def, owner) in types.items.krate.owners.iter_enumerated() {
+        let (body, span) = match owner {
+            Some(hir::Owner::Item(item)) => match &item.kind {
+                hir::ItemKind::Fn(_, _, Some(body))
+                | hir::ItemKind::Const(_, body)
+                | hir::ItemKind::Static(_, body) => (body, item.span),
+                _ => continue,
+            },
+            Some(hir::Owner::AssocItem(item)) => match &item.kind {
+                hir::AssocItemKind::Fn(_, _, Some(body)) => (body, item.span),
+                _ => continue,
+            },
+            None => continue,
+        };
+
+        let Some(results) = types.bodies.get(&def) else {
+            continue;
+        };
+
+        let builder = Builder::new(types, results, def, span, &mut errors);
+        bodies.insert(def, builder.build(body, span));
+    }
+
+    (bodies, errors)
+}
+
+struct Builder<'a, 'b> {
+    items: &'b ItemTypes<'a>,
+    results: &'b TypeckResults,
+    ecx: ConstEvalCtxt<'a, 'b>,
+    def: DefIndex,
+    /// The type parameters of the body as themselves, which its constants are evaluated with.
+    identity_args: GenericArgs,
+    blocks: IndexVec<BasicBlockId, BasicBlock>,
+    local_decls: LocalDecls,
+    /// The local of each variable, by the pattern that binds it.
+    var_locals: HashMap<HirId, Local>,
+    /// The scopes control is in, innermost last.
+    scopes: Vec<Scope>,
+    /// The loops control is in, innermost last.
+    loops: Vec<LoopScope>,
+    /// Where control goes once the return place is assigned and everything is dropped.
+    return_block: BasicBlockId,
+    /// The cleanup block that carries on unwinding out of the body, once there is one.
+    resume_block: Option<BasicBlockId>,
+    errors: &'b mut Vec<MirBuildError>,
+}
+
+impl<'a, 'b> Builder<'a, 'b> {
+    fn new(
+        types: &'b CrateTypes<'a>,
+        results: &'b TypeckResults,
+        def: DefIndex,
+        span: Span,
+        errors: &'b mut Vec<MirBuildError>,
+    ) -> Self {
+        let items = &types.items;
+        let cx = items.cx;
+        let ret = match items.fn_sigs.get(&def) {
+            Some(sig) => sig.output(),
+            None => items.types.get(&def).copied().unwrap_or(cx.types.error),
+        };
+
+        let mut builder = Self {
+            items,
+            results,
+            ecx: ConstEvalCtxt::new(items, &types.bodies),
+            def,
+            identity_args: cx.mk_args(&items.identity_args(def)),
+            blocks: IndexVec::new(),
+            local_decls: IndexVec::new(),
+            var_locals: HashMap::new(),
+            scopes: vec![],
+            loops: vec![],
+            return_block: START_BLOCK,
+            resume_block: None,
+            errors,
+        };
+
+        builder.new_block("start");
+        builder.return_block = builder.new_block("return");
+        builder.local_decls.push(LocalDecl {
+            ty: ret,
+            mutability: Mutability::Mut,
+            kind: LocalKind::ReturnPlace,
+            span,
+        });
+
+        builder
+    }
+
+    fn build(mut self, body: &hir::Body, span: Span) -> Body {
+        // the arguments, and what they are bound to, are dropped last
+        self.push_scope();
+
+        let args: Vec<_> = body
+            .params
+            .iter()
+            .map(|param| {
+                let ty = self.node_ty(param.hir_id);
+
+                let (kind, mutability) = match param.kind {
+                    hir::PatKind::Binding {
+                        mode: hir::BindingMode { by_ref: false, mutable },
+                        name,
+                        sub: None,
+                    } => (LocalKind::Var(name.name), mutability(mutable)),
+                    _ => (LocalKind::Arg, Mutability::Not),
+                };
+
+                self.local_decls.push(LocalDecl {
+                    ty,
+                    mutability,
+                    kind,
+                    span: param.span,
+                })
+            })
+            .collect();
+
+        for (param, &arg) in body.params.iter().zip(&args) {
+            self.schedule_drop(arg);
+
+            match self.local_decls[arg].kind {
+                LocalKind::Var(_) => {
+                    self.var_locals.insert(param.hir_id, arg);
+                }
+                _ => {
+                    self.declare_bindings(START_BLOCK, param);
+                    self.bind_pat(START_BLOCK, param, arg.into());
+                }
+            }
+        }
+
+        let block = self.expr_into_dest(RETURN_PLACE.into(), START_BLOCK, &body.value);
+        let block = self.pop_scope(block);
+        self.goto(block, self.return_block);
+        self.terminate(self.return_block, Terminator::Return);
+
+        Body {
+            basic_blocks: BasicBlocks::new(self.blocks),
+            local_decls: self.local_decls,
+            arg_count: args.len(),
+            span,
+        }
+    }
+
+    fn new_block(&mut self, name: &str) -> BasicBlockId {
+        self.blocks.push(BasicBlock {
+            name: name.to_owned(),
+            statements: vec![],
+            terminator: None,
+            is_cleanup: false,
+        })
+    }
+
+    fn new_temp(&mut self, ty: Ty, span: Span) -> Local {
+        self.local_decls.push(LocalDecl {
+            ty,
+            mutability: Mutability::Mut,
+            kind: LocalKind::Temp,
+            span,
+        })
+    }
+
+    /// A temporary whose storage starts at the end of `block`, and which is dropped at the end
+    /// of the innermost scope.
+    fn new_scoped_temp(&mut self, block: BasicBlockId, ty: Ty, span: Span) -> Local {
+        let temp = self.new_temp(ty, span);
+        self.storage_live(block, temp);
+        self.schedule_drop(temp);
+
+        temp
+    }
+
+    fn push_statement(&mut self, block: BasicBlockId, statement: Statement) {
+        self.blocks[block].statements.push(statement);
+    }
+
+    fn push_assign(&mut self, block: BasicBlockId, place: Place, rvalue: Rvalue) {
+        self.push_statement(block, Statement::Assign(place, rvalue));
+    }
+
+    fn push_assign_unit(&mut self, block: BasicBlockId, place: Place) {
+        let unit = Operand::Const(zst(), self.items.cx.types.unit);
+        self.push_assign(block, place, Rvalue::Use(unit));
+    }
+
+    fn terminate(&mut self, block: BasicBlockId, terminator: Terminator) {
+        let block = &mut self.blocks[block];
+        debug_assert!(block.terminator.is_none(), "`{}` is terminated twice", block.name);
+
+        block.terminator = Some(terminator);
+    }
+
+    fn goto(&mut self, block: BasicBlockId, target: BasicBlockId) {
+        self.terminate(block, Terminator::Goto { target });
+    }
+
+    fn node_ty(&self, id: HirId) -> Ty {
+        self.results.node_type(id.local_id)
+    }
+
+    /// The type of `expr` after its adjustments, which is what it is used as.
+    fn expr_ty(&self, expr: &hir::Expr) -> Ty {
+        self.results.expr_ty_adjusted(expr.hir_id.local_id)
+    }
+
+    /// The size of the values of `ty` and whether they are signed, for integers.
+    fn int_layout(&self, ty: Ty) -> Option<(Size, bool)> {
+        let pointer_size = self.items.cx.data_layout.pointer_size;
+
+        match *ty.kind() {
+            TyKind::Int(int) => Some((int.size(pointer_size), true)),
+            TyKind::Uint(uint) => Some((uint.size(pointer_size), false)),
+            _ => None,
+        }
+    }
+
+    fn report_unsupported(&mut self, what: &'static str, span: Span) {
+        self.errors.push(MirBuildError::Unsupported {
+            what,
+            at: span.range().into(),
+            file: span.file,
+        });
+    }
+}
+
+/// The value of a type without anything in it, like `()` or a function item.
+fn zst() -> Const {
+    Const::Value(ValTree::Branch(vec![]))
+}
+
+fn mutability(mutable: bool) -> Mutability {
+    if mutable {
+        Mutability::Mut
+    } else {
+        Mutability::Not
+    }
+}
+
+/// Whether dropping a value of `ty` may do anything, which it may for structs and enums, and
+/// for type parameters, which may be instantiated with them.
+fn needs_drop(ty: Ty) -> bool {
+    match *ty.kind() {
+        TyKind::Adt(..) | TyKind::Param(_) | TyKind::Projection(_) => true,
+        TyKind::Tuple(tys) => tys.iter().any(|&ty| needs_drop(ty)),
+        TyKind::Array(ty, _) => needs_drop(ty),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+    use tangic_middle::{mir::UnwindAction, Cx, DataLayout};
+
+    use super::*;
+
+    /// Checks `items` and builds their MIR, which have to get that far without errors, giving
+    /// the body of the function `name`.
+    pub(crate) fn build(b: &Builder, items: Vec<ast::Item>, name: &str) -> Body {
+        let krate = b.file(items);
+
+        let (resolutions, errors) = tangic_resolve::resolve(&krate);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let (hir, errors) = tangic_ast_lowering::lower(&krate, &resolutions);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let cx = Box::leak(Box::new(Cx::new(DataLayout::host())));
+        let hir = Box::leak(Box::new(hir));
+        let (types, errors) = tangic_typeck::check_crate(cx, hir);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let (mut bodies, errors) = build_mir(&types);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let def = hir
+            .owners
+            .iter_enumerated()
+            .find_map(|(def, owner)| match owner {
+                Some(hir::Owner::Item(item)) => (item.name?.as_str() == name).then_some(def),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no item named `{name}`"));
+
+        bodies.remove(&def).expect("functions have a body")
+    }
+
+    pub(crate) fn block_named<'a>(body: &'a Body, name: &str) -> (BasicBlockId, &'a BasicBlock) {
+        body.basic_blocks
+            .iter_enumerated()
+            .find(|(_, block)| block.name == name)
+            .unwrap_or_else(|| panic!("no block named `{name}`"))
+    }
+
+    pub(crate) fn local_named(body: &Body, name: &str) -> Local {
+        body.local_decls
+            .iter_enumerated()
+            .find_map(|(local, decl)| match decl.kind {
+                LocalKind::Var(var) => (var.as_str() == name).then_some(local),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no variable named `{name}`"))
+    }
+
+    /// The blocks that go straight to `target`.
+    fn gotos(body: &Body, target: BasicBlockId) -> Vec<BasicBlockId> {
+        body.basic_blocks
+            .iter_enumerated()
+            .filter(|(_, block)| matches!(block.terminator, Some(Terminator::Goto { target: to }) if to == target))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn ifs_switch_on_the_condition_and_join() {
+        let b = Builder::new();
+        let if_ = b.if_(
+            b.name("c"),
+            b.block_expr(vec![b.int(1)]),
+            Some(b.block_expr(vec![b.int(2)])),
+        );
+        let f = b.function("f", vec![("c", b.ty("bool"))], b.ty("i32"), vec![if_]);
+        let body = build(&b, vec![ast::Item::Fn(f)], "f");
+
+        let (then, _) = block_named(&body, "if_then");
+        let (otherwise, _) = block_named(&body, "if_else");
+        let (join, _) = block_named(&body, "if_join");
+
+        let Some(Terminator::SwitchInt { discr, targets }) = &body.basic_blocks[START_BLOCK].terminator else {
+            panic!(
+                "expected a switch, found {:?}",
+                body.basic_blocks[START_BLOCK].terminator
+            );
+        };
+        assert!(matches!(discr, Operand::Copy(place) if place.local == local_named(&body, "c")));
+        assert_eq!(targets.iter().collect::<Vec<_>>(), [(0, otherwise)]);
+        assert_eq!(targets.otherwise(), then);
+
+        // both branches write the value of the `if` and go on after it
+        assert_eq!(gotos(&body, join), [then, otherwise]);
+    }
+
+    #[test]
+    fn loops_go_around_until_they_break() {
+        let b = Builder::new();
+        let break_ = b.if_(b.name("c"), b.block_expr(vec![b.expr(ast::ExprKind::Break)]), None);
+        let f = b.function("f", vec![("c", b.ty("bool"))], b.unit_ty(), vec![b.loop_(vec![break_])]);
+        let body = build(&b, vec![ast::Item::Fn(f)], "f");
+
+        let (loop_block, _) = block_named(&body, "loop");
+        let (exit, _) = block_named(&body, "loop_exit");
+        let (then, _) = block_named(&body, "if_then");
+
+        // into the loop, then around again from the end of its body
+        let around = gotos(&body, loop_block);
+        assert_eq!(around.len(), 2, "{around:?}");
+        assert_eq!(around[0], START_BLOCK);
+
+        // out of it from the `break` only
+        assert_eq!(gotos(&body, exit), [then]);
+    }
+
+    #[test]
+    fn whiles_are_loops_that_break_when_the_condition_is_false() {
+        let b = Builder::new();
+        let while_ = b.while_(b.name("c"), b.block_expr(vec![]));
+        let f = b.function("f", vec![("c", b.ty("bool"))], b.unit_ty(), vec![while_]);
+        let body = build(&b, vec![ast::Item::Fn(f)], "f");
+
+        let (exit, _) = block_named(&body, "loop_exit");
+        let (otherwise, _) = block_named(&body, "if_else");
+
+        assert_eq!(gotos(&body, exit), [otherwise]);
+    }
+
+    #[test]
+    fn values_are_dropped_in_reverse_order() {
+        let b = Builder::new();
+        let statements = vec![b.let_(b.pattern("c"), false, None, Some(b.name("a")))];
+        let args = vec![("a", b.ty("S")), ("n", b.ty("i32")), ("b", b.ty("S"))];
+        let f = b.function("f", args, b.unit_ty(), statements);
+        let body = build(&b, vec![b.structure("S", &[], vec![]), ast::Item::Fn(f)], "f");
+
+        let drops: Vec<_> = body
+            .basic_blocks
+            .iter()
+            .filter(|block| !block.is_cleanup)
+            .filter_map(|block| match &block.terminator {
+                Some(Terminator::Drop { place, unwind, .. }) => Some((place.local, *unwind)),
+                _ => None,
+            })
+            .collect();
+
+        // the variables of the body, then the arguments, and not the integer, which owns nothing
+        let locals: Vec<_> = drops.iter().map(|&(local, _)| local).collect();
+        let [local_c, local_a, local_b] = ["c", "a", "b"].map(|name| local_named(&body, name));
+        assert_eq!(locals, [local_c, local_b, local_a]);
+
+        // if a drop panics, what is after it is still dropped, while unwinding
+        let UnwindAction::Cleanup(cleanup) = drops[0].1 else {
+            panic!("expected a cleanup block, found {:?}", drops[0].1);
+        };
+        assert!(body.basic_blocks[cleanup].is_cleanup);
+        assert!(matches!(
+            &body.basic_blocks[cleanup].terminator,
+            Some(Terminator::Drop { place, .. }) if place.local == local_b
+        ));
+        assert_eq!(drops[2].1, UnwindAction::Continue);
+    }
+}
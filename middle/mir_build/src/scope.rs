@@ -0,0 +1,196 @@
+//! Scopes, and the drops that run when control leaves them.
+//!
+//! Leaving a scope normally drops what it scheduled, in the reverse of the order it was
+//! scheduled in, and ends the storage of its variables and temporaries. Unwinding out of a call
+//! that panicked drops what every scope around the call scheduled, through a chain of cleanup
+//! blocks that ends in `UnwindResume`. Chains are shared by the calls a scope makes until it
+//! schedules another drop.
+
+use tangic_middle::mir::{BasicBlock, BasicBlockId, Local, Statement, Terminator, UnwindAction};
+
+use crate::{needs_drop, Builder};
+
+#[derive(Default)]
+pub(crate) struct Scope {
+    /// What to do when control leaves the scope, in the order it was scheduled.
+    drops: Vec<DropData>,
+    /// The first block of the cleanup that drops all of `drops`, and then what the scopes
+    /// outside this one scheduled, once it is built.
+    cached_cleanup: Option<BasicBlockId>,
+}
+
+#[derive(Clone, Copy)]
+struct DropData {
+    local: Local,
+    kind: DropKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DropKind {
+    /// Drops the value of the local.
+    Value,
+    /// Ends the storage of the local, which unwinding doesn't need to.
+    Storage,
+}
+
+pub(crate) struct LoopScope {
+    /// Where `continue` goes.
+    pub continue_block: BasicBlockId,
+    /// Where `break` goes.
+    pub break_block: BasicBlockId,
+    /// How many scopes there are around the loop, which `break` and `continue` stay in.
+    pub scope_depth: usize,
+}
+
+impl Builder<'_, '_> {
+    pub(crate) fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    /// Ends the innermost scope, dropping what it scheduled at the end of `block`. Returns the
+    /// block control goes on in.
+    pub(crate) fn pop_scope(&mut self, block: BasicBlockId) -> BasicBlockId {
+        let block = self.exit_scopes(block, self.scopes.len() - 1);
+        self.scopes.pop();
+
+        block
+    }
+
+    /// Drops `local` when control leaves the innermost scope, if dropping it does anything.
+    pub(crate) fn schedule_drop(&mut self, local: Local) {
+        if !needs_drop(self.local_decls[local].ty) {
+            return;
+        }
+
+        let scope = self.scopes.last_mut().expect("drops are scheduled in a scope");
+        scope.drops.push(DropData {
+            local,
+            kind: DropKind::Value,
+        });
+        scope.cached_cleanup = None;
+    }
+
+    /// Starts the storage of `local` at the end of `block`, ending it when control leaves the
+    /// innermost scope, after its value is dropped.
+    pub(crate) fn storage_live(&mut self, block: BasicBlockId, local: Local) {
+        self.push_statement(block, Statement::StorageLive(local));
+
+        let scope = self.scopes.last_mut().expect("storage is scheduled in a scope");
+        scope.drops.push(DropData {
+            local,
+            kind: DropKind::Storage,
+        });
+    }
+
+    /// Drops what the scopes from the `depth`th one inward scheduled, innermost first, as
+    /// control leaves them from `block` to somewhere outside of them. The scopes stay, for the
+    /// code after a `break` to be built in.
+    pub(crate) fn exit_scopes(&mut self, mut block: BasicBlockId, depth: usize) -> BasicBlockId {
+        for scope in (depth..self.scopes.len()).rev() {
+            for i in (0..self.scopes[scope].drops.len()).rev() {
+                let DropData { local, kind } = self.scopes[scope].drops[i];
+
+                if kind == DropKind::Storage {
+                    self.push_statement(block, Statement::StorageDead(local));
+                    continue;
+                }
+
+                // if the drop panics, what is scheduled before it still needs dropping
+                let unwind = self.cleanup_action(scope, i);
+                let next = self.new_block("drop_next");
+
+                self.terminate(block, Terminator::Drop {
+                    place: local.into(),
+                    target: next,
+                    unwind,
+                });
+
+                block = next;
+            }
+        }
+
+        block
+    }
+
+    /// Where a call or drop that panics inside all of the scopes goes.
+    pub(crate) fn unwind_action(&mut self) -> UnwindAction {
+        match self.scopes.len().checked_sub(1) {
+            Some(scope) => self.cleanup_action(scope, self.scopes[scope].drops.len()),
+            None => UnwindAction::Continue,
+        }
+    }
+
+    /// Where unwinding goes from where the first `drops` drops of the `scope`th scope, and
+    /// those of the scopes outside of it, are scheduled.
+    fn cleanup_action(&mut self, scope: usize, drops: usize) -> UnwindAction {
+        match self.cleanup_block(scope, drops) {
+            Some(block) => UnwindAction::Cleanup(block),
+            None => UnwindAction::Continue,
+        }
+    }
+
+    /// The first block of the cleanup for [`Builder::cleanup_action`], or `None` if there is
+    /// nothing to drop.
+    fn cleanup_block(&mut self, scope: usize, drops: usize) -> Option<BasicBlockId> {
+        let whole = drops == self.scopes[scope].drops.len();
+
+        if let Some(block) = self.scopes[scope].cached_cleanup.filter(|_| whole) {
+            return Some(block);
+        }
+
+        let mut next = match scope.checked_sub(1) {
+            Some(outer) => self.cleanup_block(outer, self.scopes[outer].drops.len()),
+            None => None,
+        };
+
+        // the first one scheduled is dropped last, so the chain is built from it
+        for i in 0..drops {
+            let DropData { local, kind } = self.scopes[scope].drops[i];
+
+            if kind == DropKind::Storage {
+                continue;
+            }
+
+            let target = match next {
+                Some(target) => target,
+                None => self.resume_block(),
+            };
+
+            let block = self.new_cleanup_block("cleanup");
+            self.terminate(block, Terminator::Drop {
+                place: local.into(),
+                target,
+                unwind: UnwindAction::Terminate,
+            });
+
+            next = Some(block);
+        }
+
+        if whole {
+            self.scopes[scope].cached_cleanup = next;
+        }
+
+        next
+    }
+
+    fn resume_block(&mut self) -> BasicBlockId {
+        if let Some(block) = self.resume_block {
+            return block;
+        }
+
+        let block = self.new_cleanup_block("resume");
+        self.terminate(block, Terminator::UnwindResume);
+        self.resume_block = Some(block);
+
+        block
+    }
+
+    fn new_cleanup_block(&mut self, name: &str) -> BasicBlockId {
+        self.blocks.push(BasicBlock {
+            name: name.to_owned(),
+            statements: vec![],
+            terminator: None,
+            is_cleanup: true,
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use miette::SourceSpan;
+use tangic_middle::span::FileId;
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum MonoError {
+    #[error("reached the recursion limit while instantiating `{instance}`")]
+    #[diagnostic(code(tangic::monomorphize::recursion_limit))]
+    RecursionLimit {
+        instance: String,
+        #[label("instantiated past the limit of {limit} nested instantiations")]
+        at: SourceSpan,
+        limit: usize,
+        /// The instantiations from the entry point to this one.
+        #[help]
+        chain: String,
+        file: FileId,
+    },
+    #[error("{message}")]
+    #[diagnostic(code(tangic::monomorphize::compile_error))]
+    CompileError {
+        message: String,
+        instance: String,
+        #[label("instantiated as `{instance}`")]
+        at: SourceSpan,
+        #[label("raised by `@compile_error` here")]
+        raised: Option<SourceSpan>,
+        file: FileId,
+    },
+    #[error("a `where` clause of `{trait_}` doesn't hold for `{ty}`")]
+    #[diagnostic(code(tangic::monomorphize::where_clause_false))]
+    WhereClauseFalse {
+        trait_: String,
+        ty: String,
+        instance: String,
+        #[label("`{instance}` is used here")]
+        at: SourceSpan,
+        #[label("this is `false`")]
+        clause: Option<SourceSpan>,
+        file: FileId,
+    },
+    #[error("evaluation of an intrinsic failed while instantiating `{instance}`")]
+    #[diagnostic(code(tangic::monomorphize::const_eval))]
+    ConstEval {
+        reason: String,
+        instance: String,
+        #[label("{reason}")]
+        at: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl MonoError {
+    /// The file the error is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::RecursionLimit { file, .. }
+            | Self::CompileError { file, .. }
+            | Self::WhereClauseFalse { file, .. }
+            | Self::ConstEval { file, .. } => *file,
+        }
+    }
+}
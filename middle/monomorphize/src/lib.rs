@@ -0,0 +1,773 @@
+//! Monomorphization. Starting from the entry point, finds every function the program uses, with
+//! the types its type parameters are instantiated with, by walking their MIR. Each instance is
+//! generated as a function of its own.
+//!
+//! Intrinsics like `@compile_error` are evaluated for each instance as it is found, where the
+//! types of its type parameters are known, and so are the `where` clauses of traits that were too
+//! generic to evaluate for the impls of its methods.
+
+use std::collections::{HashMap, HashSet};
+
+use tangic_middle::{
+    hir,
+    mir::{self, AggregateKind, NullOp, Operand, Place, ProjectionElem, Rvalue, Statement, Terminator},
+    span::Span,
+    symbol::sym,
+    ty::{GenericArgs, Instance, TraitRef, Ty, TyKind, TypeckResults},
+    Cx, DefId, DefIndex,
+};
+use tangic_typeck::{ConstEvalCtxt, CrateTypes, EvalError, ItemTypes, Value};
+
+mod error;
+
+pub use error::MonoError;
+
+/// How deep instantiations can nest, like `f[T]` instantiating `f[&T]` in its body, before the
+/// collector gives up on them ever ending.
+pub const RECURSION_LIMIT: usize = 128;
+
+/// The `main` function at the root of the crate, which the program starts from. `None` for a
+/// crate without one.
+pub fn entry_fn(krate: &hir::Crate) -> Option<DefIndex> {
+    let Some(Some(hir::Owner::Item(hir::Item {
+        kind: hir::ItemKind::Mod(items),
+        ..
+    }))) = krate.owners.get(DefIndex::from_raw(0))
+    else {
+        return None;
+    };
+
+    items.iter().copied().find(|&def| {
+        matches!(
+            &krate.owners[def],
+            Some(hir::Owner::Item(hir::Item { name: Some(name), kind: hir::ItemKind::Fn(..), .. }))
+                if name.name == sym::main
+        )
+    })
+}
+
+/// The instances the program uses, in the order they are found from `entry`, which is the
+/// first of them.
+pub fn collect_mono_items(
+    types: &CrateTypes,
+    bodies: &HashMap<DefIndex, mir::Body>,
+    entry: DefIndex,
+) -> (Vec<Instance>, Vec<MonoError>) {
+    let mut collector = Collector {
+        items: &types.items,
+        typeck: &types.bodies,
+        bodies,
+        visited: HashSet::new(),
+        checked_impls: HashSet::new(),
+        instances: vec![],
+        errors: vec![],
+    };
+
+    collector.collect(Instance::mono(DefId::local(entry)), &mut vec![]);
+    (collector.instances, collector.errors)
+}
+
+struct Collector<'a, 'b> {
+    items: &'b ItemTypes<'a>,
+    typeck: &'b HashMap<DefIndex, TypeckResults>,
+    bodies: &'b HashMap<DefIndex, mir::Body>,
+    visited: HashSet<Instance>,
+    /// The impls whose trait's `where` clauses were evaluated, with their arguments.
+    checked_impls: HashSet<(DefIndex, GenericArgs)>,
+    instances: Vec<Instance>,
+    errors: Vec<MonoError>,
+}
+
+impl Collector<'_, '_> {
+    /// Collects `instance` and what it uses. `chain` is the instances that led to it, from the
+    /// entry point.
+    fn collect(&mut self, instance: Instance, chain: &mut Vec<Instance>) {
+        if !self.visited.insert(instance) {
+            return;
+        }
+
+        self.instances.push(instance);
+        self.check_compile_errors(instance, chain.last().copied());
+        self.check_where_clauses(instance, chain.last().copied());
+
+        if chain.len() == RECURSION_LIMIT {
+            self.report_recursion_limit(instance, chain);
+            return;
+        }
+
+        chain.push(instance);
+
+        for used in self.used_instances(instance) {
+            self.collect(used, chain);
+        }
+
+        chain.pop();
+    }
+
+    /// The functions the body of `instance` refers to, as the types of its locals and operands
+    /// have them.
+    fn used_instances(&self, instance: Instance) -> Vec<Instance> {
+        let Some(body) = self.bodies.get(&instance.def.index) else {
+            return vec![];
+        };
+
+        let mut tys: Vec<Ty> = body.local_decls.iter().map(|decl| decl.ty).collect();
+
+        for block in body.basic_blocks.iter() {
+            for statement in &block.statements {
+                match statement {
+                    Statement::Assign(place, rvalue) => {
+                        place_tys(place, &mut tys);
+                        rvalue_tys(self.items.cx, rvalue, &mut tys);
+                    }
+                    Statement::SetDiscriminant { place, .. } => place_tys(place, &mut tys),
+                    Statement::StorageLive(_) | Statement::StorageDead(_) | Statement::Nop => {}
+                }
+            }
+
+            match &block.terminator {
+                Some(Terminator::SwitchInt { discr: operand, .. } | Terminator::Assert { cond: operand, .. }) => {
+                    operand_tys(operand, &mut tys)
+                }
+                Some(Terminator::Drop { place, .. }) => place_tys(place, &mut tys),
+                Some(Terminator::Call {
+                    func,
+                    args,
+                    destination,
+                    ..
+                }) => {
+                    operand_tys(func, &mut tys);
+                    args.iter().for_each(|arg| operand_tys(arg, &mut tys));
+                    place_tys(destination, &mut tys);
+                }
+                Some(
+                    Terminator::Goto { .. } | Terminator::UnwindResume | Terminator::Return | Terminator::Unreachable,
+                )
+                | None => {}
+            }
+        }
+
+        let cx = self.items.cx;
+        let span = self.span_of(instance.def.index);
+        let mut used = vec![];
+
+        for ty in tys {
+            let ty = tangic_typeck::fully_normalize(self.items, instance.instantiate(cx, ty), span);
+
+            ty.any(&mut |ty| {
+                // tuple variants have the types of functions too, but are built in place
+                if let TyKind::FnDef(def, args) = *ty.kind() {
+                    if is_fn(self.items.krate, def.index) {
+                        used.extend(tangic_typeck::resolve_instance(self.items, def, args));
+                    }
+                }
+
+                false
+            });
+        }
+
+        used
+    }
+
+    /// Evaluates the `@compile_error`s the body of `instance` reaches, reporting them where
+    /// `caller` uses it. Only the branches an `if` can take are reached, which for a condition
+    /// known at compile time is one of them.
+    fn check_compile_errors(&mut self, instance: Instance, caller: Option<Instance>) {
+        let Some(body) = hir_body(self.items, instance.def.index) else {
+            return;
+        };
+
+        let mut raised = vec![];
+        self.find_compile_errors(instance, &body.value, &mut raised);
+
+        if raised.is_empty() {
+            return;
+        }
+
+        let name = self.items.instance_to_string(instance);
+        let site = caller.and_then(|caller| self.use_site(caller, instance));
+
+        for (message, span) in raised {
+            let error = match message {
+                Ok(Value::Str(message)) => {
+                    let at = site.unwrap_or(span);
+
+                    MonoError::CompileError {
+                        message,
+                        instance: name.clone(),
+                        at: at.range().into(),
+                        raised: (site.is_some() && span.file == at.file).then(|| span.range().into()),
+                        file: at.file,
+                    }
+                }
+                Err(EvalError::Failed { reason, span }) => MonoError::ConstEval {
+                    reason,
+                    instance: name.clone(),
+                    at: span.range().into(),
+                    file: span.file,
+                },
+                Ok(_) | Err(EvalError::TooGeneric | EvalError::Reported) => continue,
+            };
+
+            self.errors.push(error);
+        }
+    }
+
+    /// Evaluates the `where` clauses of the trait implemented by the impl `instance` is a method
+    /// of, for the types the impl is instantiated with, if they depend on its parameters. Those
+    /// that don't are checked with the impl, by type checking.
+    fn check_where_clauses(&mut self, instance: Instance, caller: Option<Instance>) {
+        let cx = self.items.cx;
+
+        let Some(&impl_def) = self.items.parents.get(&instance.def.index) else {
+            return;
+        };
+        let Some(&trait_ref) = self.items.impl_trait_refs.get(&impl_def) else {
+            return;
+        };
+
+        if !trait_ref.args.iter().any(|ty| ty.has_params()) {
+            return;
+        }
+
+        let impl_args = cx.mk_args(&instance.args[..self.items.type_params(impl_def).len()]);
+
+        if !self.checked_impls.insert((impl_def, impl_args)) {
+            return;
+        }
+
+        let trait_args: Vec<_> = trait_ref.args.iter().map(|ty| ty.subst(cx, &impl_args)).collect();
+        let trait_ref = TraitRef {
+            def: trait_ref.def,
+            args: cx.mk_args(&trait_args),
+        };
+
+        let Some(hir::ItemKind::Trait { generics, .. }) = self.items.item(trait_ref.def.index).map(|item| &item.kind)
+        else {
+            return;
+        };
+
+        let name = self.items.instance_to_string(instance);
+        let at = caller
+            .and_then(|caller| self.use_site(caller, instance))
+            .unwrap_or_else(|| self.span_of(instance.def.index));
+
+        for predicate in &generics.predicates {
+            let hir::WherePredicate::Expr(clause) = predicate else {
+                continue;
+            };
+
+            let mut ecx = ConstEvalCtxt::new(self.items, self.typeck);
+
+            let error = match ecx.eval_expr_in(trait_ref.def.index, trait_ref.args, clause) {
+                Ok(value) if value == Value::from_bool(false) => MonoError::WhereClauseFalse {
+                    trait_: self.items.trait_ref_to_string(trait_ref),
+                    ty: self.items.ty_to_string(trait_ref.self_ty()),
+                    instance: name.clone(),
+                    at: at.range().into(),
+                    clause: (clause.span.file == at.file).then(|| clause.span.range().into()),
+                    file: at.file,
+                },
+                Err(EvalError::Failed { reason, span }) => MonoError::ConstEval {
+                    reason,
+                    instance: name.clone(),
+                    at: span.range().into(),
+                    file: span.file,
+                },
+                Ok(_) | Err(EvalError::TooGeneric | EvalError::Reported) => continue,
+            };
+
+            self.errors.push(error);
+        }
+    }
+
+    /// The messages of the `@compile_error`s in `expr`, with where they are.
+    fn find_compile_errors(
+        &self,
+        instance: Instance,
+        expr: &hir::Expr,
+        raised: &mut Vec<(Result<Value, EvalError>, Span)>,
+    ) {
+        let mut ecx = ConstEvalCtxt::new(self.items, self.typeck);
+        let owner = instance.def.index;
+
+        match &expr.kind {
+            hir::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.find_compile_errors(instance, condition, raised);
+
+                // `None` if it is only known at run time
+                let taken = ecx.eval_expr_in(owner, instance.args, condition).ok();
+
+                if taken != Some(Value::from_bool(false)) {
+                    for statement in &then.statements {
+                        self.find_compile_errors(instance, statement, raised);
+                    }
+                }
+
+                if let Some(otherwise) = otherwise.as_deref().filter(|_| taken != Some(Value::from_bool(true))) {
+                    self.find_compile_errors(instance, otherwise, raised);
+                }
+            }
+            hir::ExprKind::Intrinsic(hir::Intrinsic::CompileError, args) if args.len() == 1 => {
+                raised.push((ecx.eval_expr_in(owner, instance.args, &args[0]), expr.span));
+            }
+            _ => for_each_child(expr, |child| self.find_compile_errors(instance, child, raised)),
+        }
+    }
+
+    /// Where the body of `caller` calls `callee`, if it does.
+    fn use_site(&self, caller: Instance, callee: Instance) -> Option<Span> {
+        let body = hir_body(self.items, caller.def.index)?;
+        let results = self.typeck.get(&caller.def.index)?;
+
+        let mut site = None;
+        self.find_use(caller, callee, results, &body.value, &mut site);
+        site
+    }
+
+    fn find_use(
+        &self,
+        caller: Instance,
+        callee: Instance,
+        results: &TypeckResults,
+        expr: &hir::Expr,
+        site: &mut Option<Span>,
+    ) {
+        if site.is_some() {
+            return;
+        }
+
+        let used = match &expr.kind {
+            hir::ExprKind::Call(callee_expr, _) => match results.node_types.get(&callee_expr.hir_id.local_id) {
+                Some(ty) => match *ty.kind() {
+                    TyKind::FnDef(def, args) => Some((def, args)),
+                    _ => None,
+                },
+                None => None,
+            },
+            hir::ExprKind::MethodCall { .. } => results.type_dependent_defs.get(&expr.hir_id.local_id).copied(),
+            _ => None,
+        };
+
+        if let Some((def, args)) = used {
+            if self.resolve(caller, def, args, expr.span) == Some(callee) {
+                *site = Some(expr.span);
+                return;
+            }
+        }
+
+        for_each_child(expr, |child| self.find_use(caller, callee, results, child, site));
+    }
+
+    /// The instance `def` with `args` is, used in the body of `caller`.
+    fn resolve(&self, caller: Instance, def: DefId, args: GenericArgs, span: Span) -> Option<Instance> {
+        let cx = self.items.cx;
+        let args: Vec<_> = args
+            .iter()
+            .map(|&arg| tangic_typeck::fully_normalize(self.items, caller.instantiate(cx, arg), span))
+            .collect();
+
+        tangic_typeck::resolve_instance(self.items, def, cx.mk_args(&args))
+    }
+
+    fn report_recursion_limit(&mut self, instance: Instance, chain: &[Instance]) {
+        let span = self.span_of(instance.def.index);
+
+        // the first and last few are enough to see where it starts repeating
+        let names: Vec<_> = chain.iter().map(|&instance| self.items.instance_to_string(instance)).collect();
+        let shown = if names.len() > 8 {
+            format!(
+                "`{}` → ... {} more ... → `{}`",
+                names[..4].join("` → `"),
+                names.len() - 8,
+                names[names.len() - 4..].join("` → `"),
+            )
+        } else {
+            format!("`{}`", names.join("` → `"))
+        };
+
+        self.errors.push(MonoError::RecursionLimit {
+            instance: self.items.instance_to_string(instance),
+            at: span.range().into(),
+            limit: RECURSION_LIMIT,
+            chain: format!("instantiated by {shown}"),
+            file: span.file,
+        });
+    }
+
+    fn span_of(&self, def: DefIndex) -> Span {
+        match self.items.krate.owners.get(def) {
+            Some(Some(hir::Owner::Item(item))) => item.span,
+            Some(Some(hir::Owner::AssocItem(item))) => item.span,
+            _ => panic!("instances are of functions, which are items"),
+        }
+    }
+}
+
+fn is_fn(krate: &hir::Crate, def: DefIndex) -> bool {
+    matches!(
+        krate.owners.get(def),
+        Some(Some(
+            hir::Owner::Item(hir::Item {
+                kind: hir::ItemKind::Fn(..),
+                ..
+            }) | hir::Owner::AssocItem(hir::AssocItem {
+                kind: hir::AssocItemKind::Fn(..),
+                ..
+            })
+        ))
+    )
+}
+
+fn hir_body<'a>(items: &ItemTypes<'a>, def: DefIndex) -> Option<&'a hir::Body> {
+    match items.krate.owners.get(def)? {
+        Some(hir::Owner::Item(hir::Item {
+            kind: hir::ItemKind::Fn(_, _, body),
+            ..
+        }))
+        | Some(hir::Owner::AssocItem(hir::AssocItem {
+            kind: hir::AssocItemKind::Fn(_, _, body),
+            ..
+        })) => body.as_ref(),
+        _ => None,
+    }
+}
+
+/// Calls `f` with each expression directly in `expr`.
+fn for_each_child<'h>(expr: &'h hir::Expr, mut f: impl FnMut(&'h hir::Expr)) {
+    match &expr.kind {
+        hir::ExprKind::Lit(_)
+        | hir::ExprKind::Path(_)
+        | hir::ExprKind::Break
+        | hir::ExprKind::Continue
+        | hir::ExprKind::Err => {}
+        hir::ExprKind::Tuple(exprs) | hir::ExprKind::Intrinsic(_, exprs) => exprs.iter().for_each(f),
+        hir::ExprKind::Block(block) | hir::ExprKind::Loop(block) => block.statements.iter().for_each(f),
+        hir::ExprKind::Let(local) => local.init.iter().for_each(f),
+        hir::ExprKind::Field(expr, _)
+        | hir::ExprKind::Unary(_, expr)
+        | hir::ExprKind::AddrOf(_, expr)
+        | hir::ExprKind::Return(expr) => f(expr),
+        hir::ExprKind::Call(callee, args) => {
+            f(callee);
+            args.iter().for_each(f);
+        }
+        hir::ExprKind::MethodCall { receiver, args, .. } => {
+            f(receiver);
+            args.iter().for_each(f);
+        }
+        hir::ExprKind::Binary(_, lhs, rhs) | hir::ExprKind::Assign(lhs, rhs) => {
+            f(lhs);
+            f(rhs);
+        }
+        hir::ExprKind::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            f(condition);
+            then.statements.iter().for_each(&mut f);
+            otherwise.iter().for_each(|otherwise| f(otherwise));
+        }
+    }
+}
+
+/// The types `rvalue` names, in its operands, places, casts and aggregates.
+fn rvalue_tys(cx: &Cx, rvalue: &Rvalue, tys: &mut Vec<Ty>) {
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::UnaryOp(_, operand) => operand_tys(operand, tys),
+        Rvalue::BinaryOp(_, lhs, rhs) | Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+            operand_tys(lhs, tys);
+            operand_tys(rhs, tys);
+        }
+        Rvalue::Cast(_, operand, ty) => {
+            operand_tys(operand, tys);
+            tys.push(*ty);
+        }
+        Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) | Rvalue::Len(place) | Rvalue::Discriminant(place) => {
+            place_tys(place, tys)
+        }
+        Rvalue::NullaryOp(NullOp::TypeName(ty)) => tys.push(*ty),
+        Rvalue::NullaryOp(NullOp::CanCast(from, to)) => tys.extend([*from, *to]),
+        Rvalue::Aggregate(kind, operands) => {
+            match kind {
+                AggregateKind::Tuple => {}
+                AggregateKind::Array(ty) => tys.push(*ty),
+                AggregateKind::Adt(def, _, args) => tys.push(cx.mk_adt(*def, args)),
+            }
+
+            operands.iter().for_each(|operand| operand_tys(operand, tys));
+        }
+    }
+}
+
+fn operand_tys(operand: &Operand, tys: &mut Vec<Ty>) {
+    match operand {
+        Operand::Copy(place) | Operand::Move(place) => place_tys(place, tys),
+        Operand::Const(_, ty) => tys.push(*ty),
+    }
+}
+
+/// The types in the projections of `place`.
+fn place_tys(place: &Place, tys: &mut Vec<Ty>) {
+    for elem in &place.projection {
+        match elem {
+            ProjectionElem::Field(_, ty) | ProjectionElem::OpaqueCast(ty) | ProjectionElem::Subtype(ty) => {
+                tys.push(*ty)
+            }
+            ProjectionElem::Deref
+            | ProjectionElem::Index(_)
+            | ProjectionElem::ConstantIndex { .. }
+            | ProjectionElem::Subslice { .. }
+            | ProjectionElem::Downcast(..) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+    use tangic_middle::DataLayout;
+
+    use super::*;
+
+    /// Checks `items` and builds their MIR, which have to get that far without errors, then
+    /// collects what `main` uses.
+    fn collect(b: &Builder, items: Vec<ast::Item>) -> (&'static CrateTypes<'static>, Vec<Instance>, Vec<MonoError>) {
+        let krate = b.file(items);
+
+        let (resolutions, errors) = tangic_resolve::resolve(&krate);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let (hir, errors) = tangic_ast_lowering::lower(&krate, &resolutions);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let cx = Box::leak(Box::new(Cx::new(DataLayout::host())));
+        let hir = Box::leak(Box::new(hir));
+        let (types, errors) = tangic_typeck::check_crate(cx, hir);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let types = Box::leak(Box::new(types));
+        let (bodies, errors) = tangic_mir_build::build_mir(types);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let entry = entry_fn(hir).expect("the crate has a `main`");
+        let (instances, errors) = collect_mono_items(types, &bodies, entry);
+
+        (types, instances, errors)
+    }
+
+    /// `main` calling each of `calls`, binding what they give.
+    fn main_fn(b: &Builder, calls: Vec<ast::Expr>) -> ast::Item {
+        let statements = calls
+            .into_iter()
+            .enumerate()
+            .map(|(i, call)| b.let_(b.pattern(&format!("x{i}")), false, None, Some(call)))
+            .collect();
+
+        ast::Item::Fn(b.function("main", vec![], b.unit_ty(), statements))
+    }
+
+    /// `name[T](arg: T) -> T { statements }`, giving `arg` back if `statements` is empty.
+    fn generic_fn(b: &Builder, name: &str, statements: Vec<ast::Expr>) -> ast::Item {
+        let statements = match statements.is_empty() {
+            true => vec![b.name("arg")],
+            false => statements,
+        };
+
+        ast::Item::Fn(ast::Function {
+            params: b.params(&["T"]),
+            ..b.function(name, vec![("arg", b.ty("T"))], b.ty("T"), statements)
+        })
+    }
+
+    fn names(types: &CrateTypes, instances: &[Instance]) -> Vec<String> {
+        instances
+            .iter()
+            .map(|&instance| types.items.instance_to_string(instance))
+            .collect()
+    }
+
+    #[test]
+    fn instances_are_collected_from_main() {
+        let b = Builder::new();
+        let items = vec![
+            generic_fn(&b, "id", vec![]),
+            generic_fn(
+                &b,
+                "twice",
+                vec![b.call(b.name("id"), vec![b.call(b.name("id"), vec![b.name("arg")])])],
+            ),
+            generic_fn(&b, "unused", vec![]),
+            main_fn(
+                &b,
+                vec![
+                    b.call(b.name("twice"), vec![b.int(1)]),
+                    b.call(b.name("id"), vec![b.bool(true)]),
+                    b.call(b.name("id"), vec![b.int(2)]),
+                ],
+            ),
+        ];
+        let (types, instances, errors) = collect(&b, items);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(names(types, &instances), ["main", "twice[i32]", "id[i32]", "id[bool]"]);
+    }
+
+    #[test]
+    fn tuple_variants_are_not_instances() {
+        let b = Builder::new();
+        let variants = vec![
+            ("Some", ast::EnumFields::Tuple(vec![b.ty("T")])),
+            ("None", ast::EnumFields::Unit),
+        ];
+        let items = vec![
+            b.enumeration("Maybe", &["T"], variants),
+            // a local of the type of the constructor, not only a call that builds the variant
+            main_fn(&b, vec![b.name("Maybe.Some"), b.call(b.name("x0"), vec![b.int(1)])]),
+        ];
+        let (types, instances, errors) = collect(&b, items);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(names(types, &instances), ["main"]);
+    }
+
+    #[test]
+    fn instantiations_that_never_end() {
+        let b = Builder::new();
+        let nested = b.call(b.name("nest"), vec![b.reference(b.name("arg"), false)]);
+        let items = vec![
+            ast::Item::Fn(ast::Function {
+                params: b.params(&["T"]),
+                ..b.function("nest", vec![("arg", b.ty("T"))], b.unit_ty(), vec![nested])
+            }),
+            main_fn(&b, vec![b.call(b.name("nest"), vec![b.int(1)])]),
+        ];
+        let (_, instances, errors) = collect(&b, items);
+
+        assert_eq!(instances.len(), RECURSION_LIMIT + 1);
+        assert!(
+            matches!(
+                &errors[..],
+                [MonoError::RecursionLimit { limit: RECURSION_LIMIT, chain, .. }]
+                    if chain.starts_with("instantiated by `main` → `nest[i32]` → `nest[&i32]`")
+                        && chain.contains(&format!("{} more", RECURSION_LIMIT - 8))
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn where_clauses_are_evaluated_for_the_impls_instances_use() {
+        let b = Builder::new();
+        let clause = b.binary(ast::BinaryOp::Ne, b.name("Self"), b.name("Marker"));
+        let distinct = ast::Trait {
+            where_clause: vec![ast::WherePredicate::Expr(clause)],
+            ..b.trait_(
+                "Distinct",
+                vec![],
+                vec![ast::AssocItem::Fn(b.fn_decl("make", vec![], b.unit_ty()))],
+            )
+        };
+        // too generic to evaluate for the impl itself
+        let make = b.function("make", vec![], b.unit_ty(), vec![b.unit()]);
+        let blanket = b.impl_(
+            &["T"],
+            Some(b.trait_ref("Distinct", vec![])),
+            b.ty("T"),
+            vec![ast::AssocItem::Fn(make)],
+        );
+
+        let use_site = b.method_call(b.name("Marker"), "make", vec![]);
+        let use_span = use_site.span;
+        let items = vec![
+            b.structure("Marker", &[], vec![]),
+            b.structure("Other", &[], vec![]),
+            ast::Item::Trait(distinct),
+            ast::Item::Impl(blanket),
+            main_fn(&b, vec![b.method_call(b.name("Other"), "make", vec![]), use_site]),
+        ];
+        let (_, _, errors) = collect(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [MonoError::WhereClauseFalse { trait_, ty, instance, at, clause: Some(_), .. }]
+                    if trait_ == "Distinct"
+                        && ty == "Marker"
+                        && instance == "Marker.make"
+                        && *at == use_span.range().into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn compile_errors_are_reported_where_the_function_is_instantiated() {
+        let b = Builder::new();
+        let message = b.intrinsic(
+            "fmt",
+            vec![
+                b.str("cannot cast {} to {}"),
+                b.method_call(b.name("std"), "typeName", vec![b.name("T")]),
+                b.method_call(b.name("std"), "typeName", vec![b.name("U")]),
+            ],
+        );
+        let compile_error = b.intrinsic("compile_error", vec![b.reference(message, false)]);
+        let raised_span = compile_error.span;
+        let can_cast = b.method_call(b.name("std"), "canCast", vec![b.name("T"), b.name("U")]);
+        let body = b.if_(
+            b.unary(ast::UnaryOp::Not, can_cast),
+            b.block_expr(vec![compile_error]),
+            None,
+        );
+        let force_cast = ast::Function {
+            params: b.params(&["T", "U"]),
+            ..b.function("force_cast", vec![], b.unit_ty(), vec![body])
+        };
+
+        let instantiation = b.call(b.name("force_cast"), vec![b.name("Marker"), b.name("Other")]);
+        let instantiation_span = instantiation.span;
+        let items = vec![
+            b.structure("Marker", &[], vec![]),
+            b.structure("Other", &[], vec![]),
+            ast::Item::Fn(force_cast),
+            main_fn(
+                &b,
+                vec![
+                    // can be cast, so the branch with the error isn't taken
+                    b.call(b.name("force_cast"), vec![b.name("Marker"), b.name("Marker")]),
+                    instantiation,
+                ],
+            ),
+        ];
+        let (_, instances, errors) = collect(&b, items);
+
+        assert_eq!(instances.len(), 3);
+        assert!(
+            matches!(
+                &errors[..],
+                [MonoError::CompileError { message, instance, at, raised: Some(raised), .. }]
+                    if message == "cannot cast Marker to Other"
+                        && instance == "force_cast[Marker, Other]"
+                        && *at == instantiation_span.range().into()
+                        && *raised == raised_span.range().into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn crates_without_main_have_no_entry() {
+        let b = Builder::new();
+        let krate = b.file(vec![generic_fn(&b, "id", vec![])]);
+        let (resolutions, _) = tangic_resolve::resolve(&krate);
+        let (hir, _) = tangic_ast_lowering::lower(&krate, &resolutions);
+
+        assert_eq!(entry_fn(&hir), None);
+    }
+}
@@ -0,0 +1,75 @@
+use miette::SourceSpan;
+use tangic_ast::{FileId, Symbol};
+
+use crate::Namespace;
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum ResolveError {
+    #[error("cannot find {namespace} `{name}` in this scope")]
+    #[diagnostic(code(tangic::resolve::unresolved))]
+    Unresolved {
+        name: Symbol,
+        namespace: Namespace,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not found")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("cannot find {namespace} `{name}` in `{parent}`")]
+    #[diagnostic(code(tangic::resolve::not_in_module))]
+    NotInModule {
+        name: Symbol,
+        namespace: Namespace,
+        parent: Symbol,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not found in `{parent}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("unresolved import `{name}`")]
+    #[diagnostic(code(tangic::resolve::unresolved_import))]
+    UnresolvedImport {
+        name: Symbol,
+        #[help]
+        suggestion: Option<String>,
+        #[label("not found")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{name}` is ambiguous")]
+    #[diagnostic(
+        code(tangic::resolve::ambiguous),
+        help("`{name}` is imported by more than one glob import, import it by name to pick one")
+    )]
+    Ambiguous {
+        name: Symbol,
+        #[label("ambiguous name")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{name}` is defined multiple times")]
+    #[diagnostic(code(tangic::resolve::duplicate))]
+    Duplicate {
+        name: Symbol,
+        #[label("redefined here")]
+        at: SourceSpan,
+        #[label("previous definition of `{name}` here")]
+        previous: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl ResolveError {
+    /// The file the error is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::Unresolved { file, .. }
+            | Self::NotInModule { file, .. }
+            | Self::UnresolvedImport { file, .. }
+            | Self::Ambiguous { file, .. }
+            | Self::Duplicate { file, .. } => *file,
+        }
+    }
+}
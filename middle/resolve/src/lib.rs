@@ -0,0 +1,1404 @@
+//! Name resolution. Gives every item, associated item, enum variant and generic parameter of a
+//! crate a [`DefId`], and finds the definition or local binding each name in its types and
+//! expressions refers to.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+};
+
+use tangic_ast::{self as ast, kw, sym, Ident, Span, Symbol};
+use tangic_middle::{
+    define_index_type,
+    hir::{self, DefKind},
+    index::IndexVec,
+    util::edit_distance::find_best_match,
+    DefId, DefIndex,
+};
+
+mod error;
+
+pub use error::ResolveError;
+
+define_index_type! {
+    pub struct LocalId = u32;
+}
+
+/// The namespace the name of a definition of `kind` is in. Impls have no name.
+pub fn namespace(kind: DefKind) -> Option<Namespace> {
+    match kind {
+        DefKind::Mod
+        | DefKind::Struct
+        | DefKind::Enum
+        | DefKind::TypeAlias
+        | DefKind::Trait
+        | DefKind::AssocType
+        | DefKind::TyParam => Some(Namespace::Type),
+        DefKind::Variant
+        | DefKind::Fn
+        | DefKind::Const
+        | DefKind::Static
+        | DefKind::AssocFn
+        | DefKind::ConstParam => Some(Namespace::Value),
+        DefKind::LifetimeParam => Some(Namespace::Lifetime),
+        DefKind::Impl => None,
+    }
+}
+
+/// Whether names can be looked up in definitions of `kind`, like `module.name`, `Enum.Variant`
+/// or `Trait.method`.
+pub fn has_scope(kind: DefKind) -> bool {
+    matches!(kind, DefKind::Mod | DefKind::Enum | DefKind::Trait)
+}
+
+/// Types and values can share a name, `A` can be both a struct and a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    Type,
+    Value,
+    Lifetime,
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Type => "type",
+            Self::Value => "value",
+            Self::Lifetime => "lifetime",
+        })
+    }
+}
+
+/// What a name refers to, with locals referred to by their [`LocalId`].
+pub type Res = hir::Res<LocalId>;
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub kind: DefKind,
+    /// `None` for impls and the crate root.
+    pub name: Option<Ident>,
+    /// The definition this one is nested in, `None` for the crate root.
+    pub parent: Option<DefIndex>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolutions {
+    pub defs: IndexVec<DefIndex, Definition>,
+    /// The definition made by each item, associated item, enum variant and generic parameter,
+    /// by its span.
+    def_at: HashMap<Span, DefIndex>,
+    /// What each resolved name refers to, by the span of the identifier, and for paths of more
+    /// than one segment also by the span of the whole path. Paths that continue past a type,
+    /// like `T.Item`, only have their resolved segments in here.
+    res: HashMap<Span, Res>,
+    /// The identifiers binding each local.
+    pub locals: IndexVec<LocalId, Ident>,
+}
+
+impl Resolutions {
+    /// The definition made by the item, associated item, enum variant or generic parameter at
+    /// `span`, `None` if nothing was defined there.
+    pub fn def_at(&self, span: Span) -> Option<DefIndex> {
+        self.def_at.get(&span).copied()
+    }
+
+    /// What the name or path at `span` refers to, `None` if it wasn't resolved.
+    pub fn res(&self, span: Span) -> Option<Res> {
+        self.res.get(&span).copied()
+    }
+}
+
+/// Resolves the names in a crate, given its root with all of its modules loaded.
+pub fn resolve(krate: &ast::File) -> (Resolutions, Vec<ResolveError>) {
+    let mut resolutions = Resolutions::default();
+    let root = resolutions.defs.push(Definition {
+        kind: DefKind::Mod,
+        name: None,
+        parent: None,
+        span: krate.span,
+    });
+
+    let mut resolver = Resolver {
+        resolutions,
+        scopes: HashMap::new(),
+        imports: vec![],
+        ribs: vec![],
+        module: root,
+        errors: vec![],
+    };
+
+    resolver.collect_items(&krate.items, root);
+    resolver.resolve_imports();
+    resolver.resolve_items(&krate.items);
+
+    (resolver.resolutions, resolver.errors)
+}
+
+struct Resolver {
+    resolutions: Resolutions,
+    /// The names defined in or imported into each module, and those of enums and traits.
+    scopes: HashMap<DefIndex, Scope>,
+    /// Imports that are yet to be resolved.
+    imports: Vec<Import>,
+    /// Generic parameters and local bindings in scope, innermost last.
+    ribs: Vec<Rib>,
+    /// The module the names being resolved are in.
+    module: DefIndex,
+    errors: Vec<ResolveError>,
+}
+
+#[derive(Default)]
+struct Scope {
+    names: HashMap<(Namespace, Symbol), Binding>,
+    globs: Vec<Glob>,
+}
+
+#[derive(Clone, Copy)]
+struct Binding {
+    res: Res,
+    /// The name that is bound, for duplicate definition errors.
+    span: Span,
+    /// Whether it can be named from outside the module, which imports without `pub` can't be.
+    exported: bool,
+}
+
+/// The names of `module` brought in with `use module.*`.
+#[derive(Clone, Copy)]
+struct Glob {
+    module: DefIndex,
+    exported: bool,
+}
+
+struct Import {
+    /// The module the import is in.
+    module: DefIndex,
+    /// The path to the module imported from, followed by the imported name unless this is a
+    /// glob import.
+    path: Vec<Ident>,
+    kind: ImportKind,
+    exported: bool,
+}
+
+#[derive(Clone, Copy)]
+enum ImportKind {
+    /// Binds the name to what the last segment of the path refers to.
+    Single(Ident),
+    Glob,
+}
+
+type Rib = HashMap<(Namespace, Symbol), Res>;
+
+enum Lookup {
+    Found(Res),
+    /// Brought in by more than one glob import, referring to different things.
+    Ambiguous,
+    NotFound,
+}
+
+impl Resolver {
+    fn define(&mut self, kind: DefKind, name: Option<Ident>, parent: DefIndex, span: Span) -> DefIndex {
+        let index = self.resolutions.defs.push(Definition {
+            kind,
+            name,
+            parent: Some(parent),
+            span,
+        });
+
+        let previous = self.resolutions.def_at.insert(span, index);
+        debug_assert!(previous.is_none(), "two definitions at {span:?}");
+
+        index
+    }
+
+    /// Defines `name`, and binds it in the scope of `parent`.
+    fn define_named(&mut self, kind: DefKind, name: Ident, parent: DefIndex, span: Span) -> DefIndex {
+        let index = self.define(kind, Some(name), parent, span);
+
+        if let Some(namespace) = namespace(kind) {
+            let binding = Binding {
+                res: Res::Def(kind, DefId::local(index)),
+                span: name.span,
+                exported: true,
+            };
+
+            self.bind(parent, namespace, name, binding);
+        }
+
+        index
+    }
+
+    fn bind(&mut self, scope: DefIndex, namespace: Namespace, name: Ident, binding: Binding) {
+        let names = &mut self.scopes.entry(scope).or_default().names;
+
+        match names.entry((namespace, name.name)) {
+            Entry::Occupied(previous) => self.errors.push(ResolveError::Duplicate {
+                name: name.name,
+                at: name.span.range().into(),
+                previous: previous.get().span.range().into(),
+                file: name.span.file,
+            }),
+            Entry::Vacant(entry) => {
+                entry.insert(binding);
+            }
+        }
+    }
+
+    fn record(&mut self, span: Span, res: Res) {
+        self.resolutions.res.insert(span, res);
+    }
+
+    /// The definition collected for the node at `span`, which resolving runs over after
+    /// collecting, so it is always there.
+    fn def_at(&self, span: Span) -> DefIndex {
+        self.resolutions
+            .def_at(span)
+            .unwrap_or_else(|| panic!("nothing was defined at {span:?}"))
+    }
+
+    fn collect_items(&mut self, items: &[ast::Item], module: DefIndex) {
+        self.scopes.entry(module).or_default();
+
+        for item in items {
+            match item {
+                ast::Item::Struct(structure) => {
+                    let def =
+                        self.define_named(DefKind::Struct, structure.name, module, structure.span);
+                    self.collect_params(&structure.params, def);
+                }
+                ast::Item::Enum(enumeration) => {
+                    let def =
+                        self.define_named(DefKind::Enum, enumeration.name, module, enumeration.span);
+                    self.collect_params(&enumeration.params, def);
+
+                    for variant in &enumeration.variants {
+                        self.define_named(DefKind::Variant, variant.name, def, variant.span);
+                    }
+                }
+                ast::Item::Fn(function) => {
+                    let def = self.define_named(DefKind::Fn, function.name, module, function.span);
+                    self.collect_params(&function.params, def);
+                }
+                ast::Item::Type(alias) => {
+                    let def = self.define_named(DefKind::TypeAlias, alias.name, module, alias.span);
+                    self.collect_params(&alias.params, def);
+                }
+                ast::Item::Const(constant) => {
+                    self.define_named(DefKind::Const, constant.name, module, constant.span);
+                }
+                ast::Item::Static(constant) => {
+                    self.define_named(DefKind::Static, constant.name, module, constant.span);
+                }
+                ast::Item::Trait(trait_) => {
+                    let def = self.define_named(DefKind::Trait, trait_.name, module, trait_.span);
+                    self.collect_params(&trait_.params, def);
+                    self.collect_assoc_items(&trait_.items, def, true);
+                }
+                ast::Item::Impl(impl_) => {
+                    let def = self.define(DefKind::Impl, None, module, impl_.span);
+                    self.collect_params(&impl_.params, def);
+                    // what these are the items of depends on the type, so they are looked up
+                    // once types are known
+                    self.collect_assoc_items(&impl_.items, def, false);
+                }
+                ast::Item::Mod(inner) => {
+                    let def = self.define_named(DefKind::Mod, inner.name, module, inner.span);
+                    self.collect_items(inner.items.as_deref().unwrap_or_default(), def);
+                }
+                ast::Item::Use(use_) => {
+                    let exported = matches!(use_.vis, ast::Visibility::Public);
+                    self.collect_imports(&use_.tree, &[], module, exported);
+                }
+                ast::Item::Error(_) => {}
+            }
+        }
+    }
+
+    fn collect_assoc_items(&mut self, items: &[ast::AssocItem], parent: DefIndex, bind: bool) {
+        for item in items {
+            let (kind, name, params, span) = match item {
+                ast::AssocItem::Fn(function) => {
+                    (DefKind::AssocFn, function.name, &function.params, function.span)
+                }
+                ast::AssocItem::Type(ty) => (DefKind::AssocType, ty.name, &ty.params, ty.span),
+            };
+
+            let def = if bind {
+                self.define_named(kind, name, parent, span)
+            } else {
+                self.define(kind, Some(name), parent, span)
+            };
+
+            self.collect_params(params, def);
+        }
+    }
+
+    fn collect_params(&mut self, params: &[ast::TypePlaceholder], parent: DefIndex) {
+        for param in params {
+            let kind = match param.kind {
+                ast::PlaceholderKind::Type => DefKind::TyParam,
+                ast::PlaceholderKind::Lifetime => DefKind::LifetimeParam,
+                ast::PlaceholderKind::Const(_) => DefKind::ConstParam,
+            };
+
+            self.define(kind, Some(param.name), parent, param.span);
+        }
+    }
+
+    fn collect_imports(&mut self, tree: &ast::UseTree, prefix: &[Ident], module: DefIndex, exported: bool) {
+        let path: Vec<Ident> = prefix.iter().chain(&tree.prefix).copied().collect();
+
+        match &tree.kind {
+            ast::UseTreeKind::Simple(rename) => {
+                let Some(&last) = path.last() else {
+                    return;
+                };
+
+                self.imports.push(Import {
+                    module,
+                    kind: ImportKind::Single(rename.unwrap_or(last)),
+                    path,
+                    exported,
+                });
+            }
+            ast::UseTreeKind::Nested(trees) => {
+                for tree in trees {
+                    self.collect_imports(tree, &path, module, exported);
+                }
+            }
+            ast::UseTreeKind::Glob => self.imports.push(Import {
+                module,
+                path,
+                kind: ImportKind::Glob,
+                exported,
+            }),
+        }
+    }
+
+    /// Resolves imports until none of the rest can be, since imports can import what other
+    /// imports do.
+    fn resolve_imports(&mut self) {
+        let module = self.module;
+        let mut imports = std::mem::take(&mut self.imports);
+
+        loop {
+            let before = imports.len();
+            imports.retain(|import| !self.try_import(import));
+
+            if imports.len() == before {
+                break;
+            }
+        }
+
+        for import in &imports {
+            self.report_import(import);
+        }
+
+        self.module = module;
+    }
+
+    /// Binds what `import` imports, if it can be resolved yet.
+    fn try_import(&mut self, import: &Import) -> bool {
+        self.module = import.module;
+
+        let module_path = match import.kind {
+            ImportKind::Single(_) => &import.path[..import.path.len() - 1],
+            ImportKind::Glob => &import.path[..],
+        };
+
+        let scope = match self.resolve_segments(module_path, Namespace::Type, false) {
+            None if module_path.is_empty() => None,
+            Some((Res::Def(kind, def), resolved))
+                if resolved == module_path.len() && has_scope(kind) =>
+            {
+                Some(def.index)
+            }
+            _ => return false,
+        };
+
+        let ImportKind::Single(name) = import.kind else {
+            let module = scope.expect("glob imports have a path");
+            let glob = Glob {
+                module,
+                exported: import.exported,
+            };
+
+            self.scopes.entry(import.module).or_default().globs.push(glob);
+            return true;
+        };
+
+        let last = *import.path.last().expect("imports import something");
+        let mut found = false;
+
+        for namespace in [Namespace::Type, Namespace::Value] {
+            let lookup = match scope {
+                Some(scope) => self.lookup_in(scope, namespace, last.name),
+                None => self.lookup_lexical(namespace, last.name),
+            };
+
+            if let Lookup::Found(res) = lookup {
+                let binding = Binding {
+                    res,
+                    span: name.span,
+                    exported: import.exported,
+                };
+
+                self.record(last.span, res);
+                self.bind(import.module, namespace, name, binding);
+                found = true;
+            }
+        }
+
+        found
+    }
+
+    /// Reports the first segment of `import` that couldn't be resolved.
+    fn report_import(&mut self, import: &Import) {
+        self.module = import.module;
+
+        let modules = match import.kind {
+            ImportKind::Single(_) => import.path.len() - 1,
+            ImportKind::Glob => import.path.len(),
+        };
+
+        let mut scope = None;
+
+        for (i, &segment) in import.path.iter().enumerate() {
+            let found = [Namespace::Type, Namespace::Value].into_iter().find_map(|namespace| {
+                let lookup = match scope {
+                    Some(scope) => self.lookup_in(scope, namespace, segment.name),
+                    None => self.lookup_lexical(namespace, segment.name),
+                };
+
+                match lookup {
+                    Lookup::Found(res) => Some(res),
+                    _ => None,
+                }
+            });
+
+            match found {
+                Some(Res::Def(kind, def)) if i < modules && has_scope(kind) => {
+                    scope = Some(def.index)
+                }
+                _ => {
+                    let candidates = self.candidates(scope, None);
+                    self.errors.push(ResolveError::UnresolvedImport {
+                        name: segment.name,
+                        suggestion: suggest(candidates, segment.name),
+                        at: segment.span.range().into(),
+                        file: segment.span.file,
+                    });
+
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Looks `name` up among the names of `scope`, then among the ones its glob imports bring
+    /// in. Outside of the module a name is bound in, or the ones nested in it, only exported
+    /// names can be found.
+    fn lookup_in(&self, scope: DefIndex, namespace: Namespace, name: Symbol) -> Lookup {
+        let exported_only = !self.is_within(self.module, scope);
+        self.lookup_in_inner(scope, namespace, name, exported_only, &mut vec![])
+    }
+
+    fn lookup_in_inner(
+        &self,
+        scope: DefIndex,
+        namespace: Namespace,
+        name: Symbol,
+        exported_only: bool,
+        visited: &mut Vec<DefIndex>,
+    ) -> Lookup {
+        let Some(scope_names) = self.scopes.get(&scope) else {
+            return Lookup::NotFound;
+        };
+
+        if let Some(binding) = scope_names.names.get(&(namespace, name)) {
+            if binding.exported || !exported_only {
+                return Lookup::Found(binding.res);
+            }
+        }
+
+        visited.push(scope);
+
+        let mut found = None;
+
+        for glob in &scope_names.globs {
+            if (exported_only && !glob.exported) || visited.contains(&glob.module) {
+                continue;
+            }
+
+            match self.lookup_in_inner(glob.module, namespace, name, true, visited) {
+                Lookup::Found(res) if found.is_some_and(|found| found != res) => {
+                    return Lookup::Ambiguous
+                }
+                Lookup::Found(res) => found = Some(res),
+                Lookup::Ambiguous => return Lookup::Ambiguous,
+                Lookup::NotFound => {}
+            }
+        }
+
+        found.map_or(Lookup::NotFound, Lookup::Found)
+    }
+
+    /// Looks `name` up in the ribs, innermost first, then in the current module and the ones
+    /// it is nested in.
+    fn lookup_lexical(&self, namespace: Namespace, name: Symbol) -> Lookup {
+        for rib in self.ribs.iter().rev() {
+            if let Some(&res) = rib.get(&(namespace, name)) {
+                return Lookup::Found(res);
+            }
+        }
+
+        let mut module = Some(self.module);
+
+        while let Some(current) = module {
+            match self.lookup_in(current, namespace, name) {
+                Lookup::NotFound => module = self.parent_module(current),
+                found => return found,
+            }
+        }
+
+        Lookup::NotFound
+    }
+
+    fn parent_module(&self, def: DefIndex) -> Option<DefIndex> {
+        let mut parent = self.resolutions.defs[def].parent;
+
+        while let Some(current) = parent {
+            if self.resolutions.defs[current].kind == DefKind::Mod {
+                return Some(current);
+            }
+
+            parent = self.resolutions.defs[current].parent;
+        }
+
+        None
+    }
+
+    /// Whether `def` is `ancestor` or nested in it.
+    fn is_within(&self, def: DefIndex, ancestor: DefIndex) -> bool {
+        let mut current = Some(def);
+
+        while let Some(def) = current {
+            if def == ancestor {
+                return true;
+            }
+
+            current = self.resolutions.defs[def].parent;
+        }
+
+        false
+    }
+
+    /// The names a misspelled name could have meant, in `scope`, or in the ribs and modules in
+    /// scope if `None`.
+    fn candidates(&self, scope: Option<DefIndex>, namespace: Option<Namespace>) -> Vec<Symbol> {
+        let in_namespace = |ns: &Namespace| namespace.is_none() || namespace == Some(*ns);
+
+        let mut modules = vec![];
+        let mut candidates = vec![];
+
+        match scope {
+            Some(scope) => modules.push(scope),
+            None => {
+                for rib in &self.ribs {
+                    candidates.extend(rib.keys().filter(|(ns, _)| in_namespace(ns)).map(|&(_, name)| name));
+                }
+
+                let mut module = Some(self.module);
+                while let Some(current) = module {
+                    modules.push(current);
+                    module = self.parent_module(current);
+                }
+            }
+        }
+
+        for module in modules {
+            if let Some(scope) = self.scopes.get(&module) {
+                candidates.extend(scope.names.keys().filter(|(ns, _)| in_namespace(ns)).map(|&(_, name)| name));
+            }
+        }
+
+        candidates
+    }
+
+    /// Resolves the segments of a path, up to the first one that isn't looked up in a
+    /// module, enum or trait, recording what each refers to. The last segment is looked up in
+    /// `namespace`, the ones before it are types. Gives what the last resolved segment refers
+    /// to, and how many of them were.
+    fn resolve_segments(
+        &mut self,
+        segments: &[Ident],
+        namespace: Namespace,
+        report: bool,
+    ) -> Option<(Res, usize)> {
+        let (&first, rest) = segments.split_first()?;
+        let first_namespace = if rest.is_empty() {
+            namespace
+        } else {
+            Namespace::Type
+        };
+
+        let mut res = match self.lookup_lexical(first_namespace, first.name) {
+            Lookup::Found(res) => res,
+            lookup => {
+                if report {
+                    self.report_lookup(lookup, None, first_namespace, first);
+                }
+
+                return None;
+            }
+        };
+
+        self.record(first.span, res);
+
+        for (i, &segment) in rest.iter().enumerate() {
+            let scope = match res {
+                Res::Def(kind, def) if has_scope(kind) => def.index,
+                // `Self.Item` in a trait
+                Res::SelfTy(def)
+                    if self.resolutions.defs[def.index].kind == DefKind::Trait =>
+                {
+                    def.index
+                }
+                _ => return Some((res, i + 1)),
+            };
+
+            let segment_namespace = if i + 1 == rest.len() {
+                namespace
+            } else {
+                Namespace::Type
+            };
+
+            res = match self.lookup_in(scope, segment_namespace, segment.name) {
+                Lookup::Found(res) => res,
+                lookup => {
+                    if report {
+                        self.report_lookup(lookup, Some(scope), segment_namespace, segment);
+                    }
+
+                    return None;
+                }
+            };
+
+            self.record(segment.span, res);
+        }
+
+        Some((res, segments.len()))
+    }
+
+    fn report_lookup(&mut self, lookup: Lookup, scope: Option<DefIndex>, namespace: Namespace, name: Ident) {
+        let at = name.span.range().into();
+        let file = name.span.file;
+
+        let error = match (lookup, scope) {
+            (Lookup::Ambiguous, _) => ResolveError::Ambiguous {
+                name: name.name,
+                at,
+                file,
+            },
+            (_, None) => ResolveError::Unresolved {
+                name: name.name,
+                namespace,
+                suggestion: suggest(self.candidates(None, Some(namespace)), name.name),
+                at,
+                file,
+            },
+            (_, Some(scope)) => ResolveError::NotInModule {
+                name: name.name,
+                namespace,
+                parent: self.resolutions.defs[scope]
+                    .name
+                    .expect("only named definitions have scopes")
+                    .name,
+                suggestion: suggest(self.candidates(Some(scope), Some(namespace)), name.name),
+                at,
+                file,
+            },
+        };
+
+        self.errors.push(error);
+    }
+
+    /// Resolves a name used as an expression. Types can be passed as values, so they are
+    /// looked for when there is no value by that name.
+    fn resolve_value(&mut self, scope: Option<DefIndex>, name: Ident) -> Option<Res> {
+        let mut lookup = Lookup::NotFound;
+
+        for namespace in [Namespace::Value, Namespace::Type] {
+            lookup = match scope {
+                Some(scope) => self.lookup_in(scope, namespace, name.name),
+                None => self.lookup_lexical(namespace, name.name),
+            };
+
+            if let Lookup::Found(res) = lookup {
+                self.record(name.span, res);
+                return Some(res);
+            }
+        }
+
+        self.report_lookup(lookup, scope, Namespace::Value, name);
+        None
+    }
+
+    fn with_rib<T>(&mut self, rib: Rib, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.ribs.push(rib);
+        let result = f(self);
+        self.ribs.pop();
+
+        result
+    }
+
+    /// Brings `params` into scope for `f`, after resolving their bounds and types.
+    fn with_params<T>(&mut self, params: &[ast::TypePlaceholder], f: impl FnOnce(&mut Self) -> T) -> T {
+        let mut rib = Rib::new();
+
+        for param in params {
+            let def = self.def_at(param.span);
+            let kind = self.resolutions.defs[def].kind;
+            let namespace = namespace(kind).expect("generic parameters have names");
+
+            self.record(param.name.span, Res::Def(kind, DefId::local(def)));
+            rib.insert((namespace, param.name.name), Res::Def(kind, DefId::local(def)));
+        }
+
+        self.with_rib(rib, |this| {
+            for param in params {
+                if let ast::PlaceholderKind::Const(ty) = &param.kind {
+                    this.resolve_type(ty);
+                }
+
+                this.resolve_bounds(&param.bounds);
+            }
+
+            f(this)
+        })
+    }
+
+    /// Brings `Self` into scope for `f`, for the trait or impl `def`.
+    fn with_self<T>(&mut self, def: DefIndex, f: impl FnOnce(&mut Self) -> T) -> T {
+        let res = Res::SelfTy(DefId::local(def));
+        let rib = Rib::from([
+            ((Namespace::Type, kw::SelfUpper), res),
+            ((Namespace::Type, kw::SelfLower), res),
+        ]);
+
+        self.with_rib(rib, f)
+    }
+
+    fn resolve_items(&mut self, items: &[ast::Item]) {
+        for item in items {
+            self.resolve_item(item);
+        }
+    }
+
+    fn resolve_item(&mut self, item: &ast::Item) {
+        match item {
+            ast::Item::Struct(structure) => self.with_params(&structure.params, |this| {
+                for field in &structure.fields {
+                    this.resolve_type(&field.ty);
+                }
+            }),
+            ast::Item::Enum(enumeration) => self.with_params(&enumeration.params, |this| {
+                for variant in &enumeration.variants {
+                    match &variant.fields {
+                        ast::EnumFields::Unit => {}
+                        ast::EnumFields::Tuple(types) => {
+                            for ty in types {
+                                this.resolve_type(ty);
+                            }
+                        }
+                        ast::EnumFields::Struct(fields) => {
+                            for field in fields {
+                                this.resolve_type(&field.ty);
+                            }
+                        }
+                    }
+                }
+            }),
+            ast::Item::Fn(function) => self.resolve_fn(function),
+            ast::Item::Type(alias) => {
+                self.with_params(&alias.params, |this| this.resolve_type(&alias.actual))
+            }
+            ast::Item::Const(constant) | ast::Item::Static(constant) => {
+                self.resolve_type(&constant.ty);
+                self.with_rib(Rib::new(), |this| this.resolve_expr(&constant.value));
+            }
+            ast::Item::Trait(trait_) => {
+                let def = self.def_at(trait_.span);
+
+                self.with_params(&trait_.params, |this| {
+                    this.with_self(def, |this| {
+                        this.resolve_bounds(&trait_.supertraits);
+                        this.resolve_where_clause(&trait_.where_clause);
+                        this.resolve_assoc_items(&trait_.items);
+                    })
+                });
+            }
+            ast::Item::Impl(impl_) => {
+                let def = self.def_at(impl_.span);
+
+                self.with_params(&impl_.params, |this| {
+                    this.resolve_type(&impl_.self_ty);
+
+                    if let Some(trait_) = &impl_.trait_ {
+                        this.resolve_path(&trait_.path, Namespace::Type);
+                    }
+
+                    this.with_self(def, |this| {
+                        this.resolve_where_clause(&impl_.where_clause);
+                        this.resolve_assoc_items(&impl_.items);
+                    })
+                });
+            }
+            ast::Item::Mod(module) => {
+                let inner = self.def_at(module.span);
+                let outer = std::mem::replace(&mut self.module, inner);
+                self.resolve_items(module.items.as_deref().unwrap_or_default());
+                self.module = outer;
+            }
+            ast::Item::Use(_) | ast::Item::Error(_) => {}
+        }
+    }
+
+    fn resolve_assoc_items(&mut self, items: &[ast::AssocItem]) {
+        for item in items {
+            match item {
+                ast::AssocItem::Fn(function) => self.resolve_fn(function),
+                ast::AssocItem::Type(ty) => self.with_params(&ty.params, |this| {
+                    this.resolve_bounds(&ty.bounds);
+
+                    if let Some(ty) = &ty.ty {
+                        this.resolve_type(ty);
+                    }
+                }),
+            }
+        }
+    }
+
+    fn resolve_fn(&mut self, function: &ast::Function) {
+        self.with_params(&function.params, |this| {
+            let mut rib = Rib::new();
+
+            for arg in &function.args {
+                this.resolve_type(arg);
+
+                if let Some(self_) = self_arg(arg) {
+                    let local = this.resolutions.locals.push(self_);
+                    rib.insert((Namespace::Value, kw::SelfLower), Res::Local(local));
+                }
+            }
+
+            this.resolve_type(&function.returns);
+
+            this.with_rib(rib, |this| {
+                for pattern in &function.cap_args {
+                    this.bind_pattern(pattern);
+                }
+
+                for statement in function.body.iter().flat_map(|body| &body.statements) {
+                    this.resolve_expr(statement);
+                }
+            });
+        });
+    }
+
+    fn resolve_where_clause(&mut self, predicates: &[ast::WherePredicate]) {
+        for predicate in predicates {
+            match predicate {
+                ast::WherePredicate::Bound { ty, bounds } => {
+                    self.resolve_type(ty);
+                    self.resolve_bounds(bounds);
+                }
+                ast::WherePredicate::Expr(expr) => {
+                    self.with_rib(Rib::new(), |this| this.resolve_expr(expr));
+                }
+            }
+        }
+    }
+
+    fn resolve_bounds(&mut self, bounds: &[ast::TraitRef]) {
+        for bound in bounds {
+            self.resolve_path(&bound.path, Namespace::Type);
+        }
+    }
+
+    fn resolve_path(&mut self, path: &ast::Path, namespace: Namespace) {
+        let segments: Vec<Ident> = path.segments.iter().map(|segment| segment.name).collect();
+
+        if let Some((res, resolved)) = self.resolve_segments(&segments, namespace, true) {
+            if resolved == segments.len() {
+                self.record(path.span, res);
+            }
+        }
+
+        for segment in &path.segments {
+            self.resolve_generic_args(&segment.args);
+        }
+    }
+
+    fn resolve_generic_args(&mut self, args: &[ast::GenericArg]) {
+        for arg in args {
+            match arg {
+                ast::GenericArg::Type(ty) => self.resolve_type(ty),
+                ast::GenericArg::Lifetime(lifetime) => self.resolve_lifetime(*lifetime),
+                // the name is of an associated type of the trait, which is found with it
+                ast::GenericArg::Binding { ty, .. } => self.resolve_type(ty),
+            }
+        }
+    }
+
+    fn resolve_lifetime(&mut self, lifetime: Ident) {
+        if lifetime.name == kw::Underscore {
+            return;
+        }
+
+        match self.lookup_lexical(Namespace::Lifetime, lifetime.name) {
+            Lookup::Found(res) => self.record(lifetime.span, res),
+            lookup => self.report_lookup(lookup, None, Namespace::Lifetime, lifetime),
+        }
+    }
+
+    fn resolve_type(&mut self, ty: &ast::Type) {
+        match &ty.kind {
+            ast::TypeKind::Primitive(_) => {}
+            ast::TypeKind::Reference(reference) => {
+                if let Some(lifetime) = reference.lifetime {
+                    self.resolve_lifetime(lifetime);
+                }
+
+                self.resolve_type(&reference.ty);
+            }
+            ast::TypeKind::Opaque(path) => self.resolve_path(path, Namespace::Type),
+            ast::TypeKind::Projection { ty, trait_, item } => {
+                self.resolve_type(ty);
+                self.resolve_path(&trait_.path, Namespace::Type);
+
+                if let Some(&Res::Def(DefKind::Trait, def)) = self.resolutions.res.get(&trait_.path.span) {
+                    match self.lookup_in(def.index, Namespace::Type, item.name.name) {
+                        Lookup::Found(res) => self.record(item.name.span, res),
+                        lookup => {
+                            self.report_lookup(lookup, Some(def.index), Namespace::Type, item.name)
+                        }
+                    }
+                }
+
+                self.resolve_generic_args(&item.args);
+            }
+            ast::TypeKind::Impl(bounds) | ast::TypeKind::Dyn(bounds) => self.resolve_bounds(bounds),
+            ast::TypeKind::Optional(ty) | ast::TypeKind::Slice(ty) => self.resolve_type(ty),
+            ast::TypeKind::Tuple(types) => {
+                for ty in types {
+                    self.resolve_type(ty);
+                }
+            }
+            ast::TypeKind::Array(ty, len) => {
+                self.resolve_type(ty);
+                self.with_rib(Rib::new(), |this| this.resolve_expr(len));
+            }
+        }
+    }
+
+    /// Binds the variables in `pattern` in the innermost rib.
+    fn bind_pattern(&mut self, pattern: &ast::Pattern) {
+        match &pattern.kind {
+            ast::PatternKind::Void => {}
+            ast::PatternKind::Ref(inner)
+            | ast::PatternKind::Mut(inner)
+            | ast::PatternKind::RefMut(inner) => self.bind_pattern(inner),
+            ast::PatternKind::Variable(name) => self.bind_local(*name),
+            ast::PatternKind::WithVariable(name, inner) => {
+                self.bind_local(*name);
+                self.bind_pattern(inner);
+            }
+        }
+    }
+
+    fn bind_local(&mut self, name: Ident) {
+        let res = Res::Local(self.resolutions.locals.push(name));
+
+        self.record(name.span, res);
+        self.ribs
+            .last_mut()
+            .expect("locals are bound in a body")
+            .insert((Namespace::Value, name.name), res);
+    }
+
+    fn resolve_block(&mut self, block: &ast::Block) {
+        self.with_rib(Rib::new(), |this| {
+            for statement in &block.statements {
+                this.resolve_expr(statement);
+            }
+        });
+    }
+
+    /// Resolves the names in `expr`, giving what it refers to if it is a name or a path.
+    fn resolve_expr(&mut self, expr: &ast::Expr) -> Option<Res> {
+        match &expr.kind {
+            ast::ExprKind::Opaque(name) => return self.resolve_value(None, *name),
+            ast::ExprKind::Field(base, name) => {
+                // `module.name`, and otherwise a field
+                if let Some(scope) = self.resolve_expr(base).and_then(|res| self.scope_of(res)) {
+                    return self.resolve_value(Some(scope), *name);
+                }
+            }
+            ast::ExprKind::Generic(expr, args) => {
+                // the arguments don't change what is named, `a.B[T].c` looks in `a.B`
+                let res = self.resolve_expr(expr);
+                self.resolve_generic_args(args);
+
+                return res;
+            }
+            ast::ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => {
+                // `module.function args`, and otherwise a method, unless it is `std.typeName args`
+                if !self.is_std_builtin(receiver, *method) {
+                    if let Some(scope) = self.resolve_expr(receiver).and_then(|res| self.scope_of(res)) {
+                        self.resolve_value(Some(scope), *method);
+                    }
+                }
+
+                for arg in args {
+                    self.resolve_expr(arg);
+                }
+            }
+            ast::ExprKind::Let(let_) => {
+                if let Some(ty) = &let_.ty {
+                    self.resolve_type(ty);
+                }
+
+                if let Some(value) = &let_.value {
+                    self.resolve_expr(value);
+                }
+
+                // after the value, which can't refer to what it is bound to
+                self.bind_pattern(&let_.pattern);
+            }
+            ast::ExprKind::Block(block) | ast::ExprKind::Unsafe(block) | ast::ExprKind::Loop(block) => {
+                self.resolve_block(block)
+            }
+            ast::ExprKind::Call { callee, args } => {
+                self.resolve_expr(callee);
+
+                for arg in args {
+                    self.resolve_expr(arg);
+                }
+            }
+            ast::ExprKind::Tuple(exprs) | ast::ExprKind::Intrinsic { args: exprs, .. } => {
+                for expr in exprs {
+                    self.resolve_expr(expr);
+                }
+            }
+            ast::ExprKind::Return(expr)
+            | ast::ExprKind::Unary(_, expr)
+            | ast::ExprKind::Ref { expr, .. } => {
+                self.resolve_expr(expr);
+            }
+            ast::ExprKind::Binary(_, lhs, rhs)
+            | ast::ExprKind::Assign(lhs, rhs)
+            | ast::ExprKind::While {
+                condition: lhs,
+                body: rhs,
+            } => {
+                self.resolve_expr(lhs);
+                self.resolve_expr(rhs);
+            }
+            ast::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_expr(then);
+
+                if let Some(otherwise) = otherwise {
+                    self.resolve_expr(otherwise);
+                }
+            }
+            ast::ExprKind::Void
+            | ast::ExprKind::Primitive(_)
+            | ast::ExprKind::Break
+            | ast::ExprKind::Continue
+            | ast::ExprKind::Error => {}
+        }
+
+        None
+    }
+
+    /// Whether `receiver.name` is a builtin that reflects on types, like `std.typeName`, which
+    /// it is unless the crate has a `std` of its own.
+    fn is_std_builtin(&self, receiver: &ast::Expr, name: Ident) -> bool {
+        let ast::ExprKind::Opaque(std) = receiver.kind else {
+            return false;
+        };
+
+        std.name == sym::std
+            && hir::Intrinsic::from_std_name(name.name).is_some()
+            && [Namespace::Value, Namespace::Type]
+                .into_iter()
+                .all(|namespace| matches!(self.lookup_lexical(namespace, sym::std), Lookup::NotFound))
+    }
+
+    /// The definition names can be looked up in, if `res` is a module, enum or trait.
+    fn scope_of(&self, res: Res) -> Option<DefIndex> {
+        match res {
+            Res::Def(kind, def) if has_scope(kind) => Some(def.index),
+            _ => None,
+        }
+    }
+}
+
+/// The `self` in an argument of type `self`, `&self` or `&mut self`, which binds a local named
+/// `self` in the function's body.
+pub fn self_arg(ty: &ast::Type) -> Option<Ident> {
+    match &ty.kind {
+        ast::TypeKind::Reference(reference) => self_arg(&reference.ty),
+        ast::TypeKind::Opaque(path) => match &path.segments[..] {
+            [segment] if segment.name.name == kw::SelfLower && segment.args.is_empty() => {
+                Some(segment.name)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn suggest(candidates: Vec<Symbol>, name: Symbol) -> Option<String> {
+    find_best_match(candidates, name).map(|found| format!("did you mean `{found}`?"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tangic_ast::build::Builder;
+
+    /// `a.b.c` as an expression, out of identifiers made beforehand to look up by their spans.
+    fn path(ast: &Builder, segments: &[Ident]) -> ast::Expr {
+        let (&first, rest) = segments.split_first().expect("paths have a segment");
+        let mut expr = ast.expr(ast::ExprKind::Opaque(first));
+
+        for &segment in rest {
+            expr = ast.expr(ast::ExprKind::Field(Box::new(expr), segment));
+        }
+
+        expr
+    }
+
+    fn function(ast: &Builder, name: &str, statements: Vec<ast::Expr>) -> ast::Item {
+        ast::Item::Fn(ast.function(name, vec![], ast.unit_ty(), statements))
+    }
+
+    fn structure(ast: &Builder, name: &str) -> ast::Item {
+        ast.structure(name, &[], vec![])
+    }
+
+    fn def_named(resolutions: &Resolutions, kind: DefKind, name: &str) -> Res {
+        let (index, _) = resolutions
+            .defs
+            .iter_enumerated()
+            .find(|(_, def)| def.kind == kind && def.name.is_some_and(|ident| ident.name.as_str() == name))
+            .expect("the definition exists");
+
+        Res::Def(kind, DefId::local(index))
+    }
+
+    #[test]
+    fn names_resolve_to_their_definitions() {
+        let ast = Builder::new();
+        let [m, f, s] = ["m", "f", "S"].map(|name| ast.ident(name));
+
+        let in_f = path(&ast, &[s]);
+        let in_main = path(&ast, &[m, f]);
+        let items = vec![
+            ast.module("m", vec![structure(&ast, "S"), function(&ast, "f", vec![in_f])]),
+            function(&ast, "main", vec![in_main]),
+        ];
+        let krate = ast.file(items);
+
+        let (resolutions, errors) = resolve(&krate);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(resolutions.res(s.span), Some(def_named(&resolutions, DefKind::Struct, "S")));
+        assert_eq!(resolutions.res(m.span), Some(def_named(&resolutions, DefKind::Mod, "m")));
+        assert_eq!(resolutions.res(f.span), Some(def_named(&resolutions, DefKind::Fn, "f")));
+
+        for item in &krate.items {
+            assert!(resolutions.def_at(item.span()).is_some());
+        }
+        assert_eq!(resolutions.def_at(krate.span), None);
+        assert_eq!(resolutions.res(krate.span), None);
+    }
+
+    #[test]
+    fn locals_shadow_items() {
+        let ast = Builder::new();
+        let [binding, x] = ["x", "x"].map(|name| ast.ident(name));
+
+        let pattern = ast.pattern_kind(ast::PatternKind::Variable(binding));
+        let statements = vec![ast.let_(pattern, false, None, None), path(&ast, &[x])];
+        let items = vec![function(&ast, "x", vec![]), function(&ast, "main", statements)];
+        let (resolutions, errors) = resolve(&ast.file(items));
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(resolutions.res(x.span), Some(Res::Local(LocalId::from_raw(0))));
+        assert_eq!(resolutions.locals[LocalId::from_raw(0)], binding);
+    }
+
+    #[test]
+    fn unresolved_names_get_a_suggestion() {
+        let ast = Builder::new();
+        let [helpr, m, g] = ["helpr", "m", "g"].map(|name| ast.ident(name));
+
+        let statements = vec![path(&ast, &[helpr]), path(&ast, &[m, g])];
+        let items = vec![
+            ast.module("m", vec![function(&ast, "f", vec![])]),
+            function(&ast, "helper", vec![]),
+            function(&ast, "main", statements),
+        ];
+        let (resolutions, errors) = resolve(&ast.file(items));
+
+        assert!(matches!(
+            &errors[..],
+            [
+                ResolveError::Unresolved { name, suggestion: Some(suggestion), .. },
+                ResolveError::NotInModule { parent, suggestion: Some(in_m), .. },
+            ] if name.as_str() == "helpr"
+                && suggestion == "did you mean `helper`?"
+                && parent.as_str() == "m"
+                && in_m == "did you mean `f`?"
+        ), "{errors:?}");
+        assert_eq!(resolutions.res(helpr.span), None);
+        assert_eq!(resolutions.res(g.span), None);
+    }
+
+    #[test]
+    fn imports() {
+        let ast = Builder::new();
+        let [s, t] = ["S", "T"].map(|name| ast.ident(name));
+
+        let statements = vec![path(&ast, &[s]), path(&ast, &[t])];
+        let items = vec![
+            ast.import("m.S", false),
+            ast.import("m.*", false),
+            ast.module("m", vec![structure(&ast, "S"), structure(&ast, "T")]),
+            function(&ast, "main", statements),
+        ];
+        let (resolutions, errors) = resolve(&ast.file(items));
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(resolutions.res(s.span), Some(def_named(&resolutions, DefKind::Struct, "S")));
+        assert_eq!(resolutions.res(t.span), Some(def_named(&resolutions, DefKind::Struct, "T")));
+    }
+
+    #[test]
+    fn imports_of_imports_resolve_in_any_order() {
+        let ast = Builder::new();
+        let s = ast.ident("S");
+
+        let statements = vec![path(&ast, &[s])];
+        let items = vec![
+            ast.import("a.S", false),
+            ast.module("a", vec![ast.import("b.S", true)]),
+            ast.module("b", vec![structure(&ast, "S")]),
+            function(&ast, "main", statements),
+        ];
+        let (resolutions, errors) = resolve(&ast.file(items));
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(resolutions.res(s.span), Some(def_named(&resolutions, DefKind::Struct, "S")));
+    }
+
+    #[test]
+    fn import_errors() {
+        let ast = Builder::new();
+
+        let items = vec![
+            ast.import("m.T", false),
+            ast.import("n.S", false),
+            // not `pub`, so it can't be imported from outside of `a`
+            ast.import("a.S", false),
+            ast.module("a", vec![ast.import("m.S", false)]),
+            ast.module("m", vec![structure(&ast, "S")]),
+        ];
+        let (_, errors) = resolve(&ast.file(items));
+
+        let unresolved: Vec<&str> = errors
+            .iter()
+            .map(|error| match error {
+                ResolveError::UnresolvedImport { name, .. } => name.as_str(),
+                error => panic!("unexpected error {error:?}"),
+            })
+            .collect();
+        assert_eq!(unresolved, ["T", "n", "S"]);
+    }
+
+    #[test]
+    fn ambiguous_glob_imports() {
+        let ast = Builder::new();
+        let s = ast.ident("S");
+
+        let statements = vec![path(&ast, &[s])];
+        let items = vec![
+            ast.import("a.*", false),
+            ast.import("b.*", false),
+            ast.module("a", vec![structure(&ast, "S")]),
+            ast.module("b", vec![structure(&ast, "S")]),
+            function(&ast, "main", statements),
+        ];
+        let (_, errors) = resolve(&ast.file(items));
+
+        assert!(
+            matches!(&errors[..], [ResolveError::Ambiguous { name, .. }] if name.as_str() == "S"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn duplicate_definitions() {
+        let ast = Builder::new();
+
+        // a struct and a function can share a name, two functions can't
+        let items = vec![
+            structure(&ast, "f"),
+            function(&ast, "f", vec![]),
+            function(&ast, "f", vec![]),
+        ];
+        let (_, errors) = resolve(&ast.file(items));
+
+        assert!(
+            matches!(&errors[..], [ResolveError::Duplicate { name, .. }] if name.as_str() == "f"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn generic_arguments_of_expressions() {
+        let ast = Builder::new();
+        let s = ast.ident("S");
+        let t = ast.ty("T");
+        let ast::TypeKind::Opaque(t_path) = &t.kind else {
+            unreachable!("`T` is not a primitive");
+        };
+        let t_name = t_path.segments[0].name;
+
+        // `S[T]`
+        let generic = ast.expr(ast::ExprKind::Generic(Box::new(path(&ast, &[s])), vec![ast::GenericArg::Type(t)]));
+        let items = vec![
+            structure(&ast, "S"),
+            structure(&ast, "T"),
+            function(&ast, "main", vec![generic]),
+        ];
+        let (resolutions, errors) = resolve(&ast.file(items));
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(resolutions.res(s.span), Some(def_named(&resolutions, DefKind::Struct, "S")));
+        assert_eq!(resolutions.res(t_name.span), Some(def_named(&resolutions, DefKind::Struct, "T")));
+    }
+}
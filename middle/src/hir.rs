@@ -1,14 +1,578 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+//! The high-level IR: the AST with names resolved and sugar removed. `if c => e` becomes an
+//! `if` with a block, `module.function args` becomes a call of a path, `std.typeName T` becomes
+//! an [`Intrinsic`] call, `?T` becomes the option type, `()` becomes the empty tuple, and
+//! patterns carry their binding modes.
+//!
+//! Every node has a [`HirId`], made of the definition that owns it and its index within that
+//! owner, so that ids within one item stay the same when other items change.
+
+use std::collections::HashMap;
+
+use crate::{
+    define_index_type,
+    index::IndexVec,
+    span::Span,
+    symbol::{sym, Ident, Symbol},
+    ty::Size,
+    DefId, DefIndex,
+};
+
+define_index_type! {
+    pub struct ItemLocalId = u32;
+}
+
+/// The owner of a node, and its index among the nodes of that owner. The owner itself is always
+/// [`ItemLocalId`] 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HirId {
+    pub owner: DefIndex,
+    pub local_id: ItemLocalId,
+}
+
+impl HirId {
+    pub fn make_owner(owner: DefIndex) -> Self {
+        Self {
+            owner,
+            local_id: ItemLocalId::from_raw(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefKind {
+    Mod,
+    Struct,
+    Enum,
+    Variant,
+    Fn,
+    TypeAlias,
+    Const,
+    Static,
+    Trait,
+    Impl,
+    AssocType,
+    AssocFn,
+    TyParam,
+    LifetimeParam,
+    ConstParam,
+}
+
+/// What a name refers to. Name resolution refers to locals by ids of its own, the HIR by the id
+/// of the pattern that binds them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Res<Id = HirId> {
+    Def(DefKind, DefId),
+    /// `Self`, or the type of `self`, in the trait or impl given.
+    SelfTy(DefId),
+    Local(Id),
+    /// A name that didn't resolve, which is already reported.
+    Err,
+}
+
+/// Items the compiler needs to know of, marked with `@lang(name)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LangItem {
+    /// The type `?T` stands for, an enum with a single type parameter.
+    Option,
+}
+
+impl LangItem {
+    pub fn from_name(name: Symbol) -> Option<Self> {
+        match name {
+            sym::Option => Some(Self::Option),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> Symbol {
+        match self {
+            Self::Option => sym::Option,
+        }
+    }
+}
+
+/// Functions built into the compiler. `@name(args)` calls the ones in [`Intrinsic::from_name`],
+/// and `std.name args` the ones that reflect on types, in [`Intrinsic::from_std_name`]. They are
+/// evaluated at compile time, for each instance of the function calling them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Intrinsic {
+    /// `@compile_error(message)`, an error where the function is instantiated.
+    CompileError,
+    /// `@fmt("{} and {}", a, b)`, the arguments formatted into a `str`.
+    Fmt,
+    /// `std.typeName T`, the name of the type `T` as a `&str`.
+    TypeName,
+    /// `std.canCast T U`, whether values of `T` can be cast to `U`.
+    CanCast,
+}
+
+impl Intrinsic {
+    pub fn from_name(name: Symbol) -> Option<Self> {
+        match name {
+            sym::compile_error => Some(Self::CompileError),
+            sym::fmt => Some(Self::Fmt),
+            _ => None,
+        }
+    }
+
+    pub fn from_std_name(name: Symbol) -> Option<Self> {
+        match name {
+            sym::typeName => Some(Self::TypeName),
+            sym::canCast => Some(Self::CanCast),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> Symbol {
+        match self {
+            Self::CompileError => sym::compile_error,
+            Self::Fmt => sym::fmt,
+            Self::TypeName => sym::typeName,
+            Self::CanCast => sym::canCast,
+        }
+    }
+    /// How many arguments it takes, or `None` for `@fmt`, which takes the format string and one
+    /// for each `{}` in it.
+    pub fn arg_count(self) -> Option<usize> {
+        match self {
+            Self::CompileError | Self::TypeName => Some(1),
+            Self::CanCast => Some(2),
+            Self::Fmt => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Crate {
+    /// The item or associated item of each definition that is one, with the crate root module
+    /// at index 0. Generic parameters and enum variants are nodes of their owners.
+    pub owners: IndexVec<DefIndex, Option<Owner>>,
+    pub lang_items: HashMap<LangItem, DefId>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Owner {
+    Item(Item),
+    AssocItem(AssocItem),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Inherited,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutability {
+    Not,
+    Mut,
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub hir_id: HirId,
+    /// `None` for impls and the crate root.
+    pub name: Option<Ident>,
+    pub vis: Visibility,
+    pub kind: ItemKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ItemKind {
+    /// The items of the module, which are owners of their own.
+    Mod(Vec<DefIndex>),
+    Struct(Generics, Vec<FieldDef>),
+    Enum(Generics, Vec<Variant>),
+    Fn(FnSig, Generics, Option<Body>),
+    TypeAlias(Generics, Ty),
+    Const(Ty, Body),
+    Static(Ty, Body),
+    Trait {
+        generics: Generics,
+        supertraits: Vec<TraitRef>,
+        items: Vec<DefIndex>,
+    },
+    Impl {
+        generics: Generics,
+        trait_: Option<TraitRef>,
+        self_ty: Ty,
+        items: Vec<DefIndex>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct AssocItem {
+    pub hir_id: HirId,
+    pub name: Ident,
+    pub kind: AssocItemKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum AssocItemKind {
+    /// Without a body, only in traits.
+    Fn(FnSig, Generics, Option<Body>),
+    /// Without a type, only in traits.
+    Type {
+        generics: Generics,
+        bounds: Vec<TraitRef>,
+        ty: Option<Ty>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDef {
+    pub hir_id: HirId,
+    pub name: Ident,
+    pub vis: Visibility,
+    pub ty: Ty,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub hir_id: HirId,
+    pub def: DefIndex,
+    pub name: Ident,
+    pub fields: VariantFields,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum VariantFields {
+    Unit,
+    Tuple(Vec<Ty>),
+    Struct(Vec<FieldDef>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Generics {
+    pub params: Vec<GenericParam>,
+    pub predicates: Vec<WherePredicate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GenericParam {
+    pub hir_id: HirId,
+    pub def: DefIndex,
+    pub name: Ident,
+    pub kind: GenericParamKind,
+    pub bounds: Vec<TraitRef>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum GenericParamKind {
+    Type,
+    Lifetime,
+    Const(Ty),
+}
+
+#[derive(Debug, Clone)]
+pub enum WherePredicate {
+    Bound { ty: Ty, bounds: Vec<TraitRef> },
+    /// A `bool` that has to hold, evaluated at compile time.
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+pub struct FnSig {
+    pub inputs: Vec<Ty>,
+    pub output: Ty,
+}
+
+/// The code of a function, constant or static. Functions bind their arguments with `params`,
+/// constants and statics have none.
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub params: Vec<Pat>,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct TraitRef {
+    pub hir_id: HirId,
+    pub path: Path,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub res: Res,
+    pub segments: Vec<PathSegment>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathSegment {
+    pub hir_id: HirId,
+    pub name: Ident,
+    pub args: Vec<GenericArg>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum GenericArg {
+    Type(Ty),
+    Lifetime(Lifetime),
+    /// `Item = T`, for an associated type of a trait.
+    Binding { name: Ident, ty: Ty },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lifetime {
+    pub hir_id: HirId,
+    pub name: Ident,
+    pub res: Res,
+}
+
+/// A path that is either resolved all the way, or only up to a type, after which the rest is
+/// looked up on that type, like `T.Item`.
+#[derive(Debug, Clone)]
+pub enum QPath {
+    Resolved(Path),
+    TypeRelative(Box<Ty>, PathSegment),
+}
+
+#[derive(Debug, Clone)]
+pub struct Ty {
+    pub hir_id: HirId,
+    pub kind: TyKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum TyKind {
+    Prim(PrimTy),
+    Never,
+    /// `()` is the empty tuple.
+    Tuple(Vec<Ty>),
+    Ref(Option<Lifetime>, Mutability, Box<Ty>),
+    Path(QPath),
+    /// `(T as Trait).Item`
+    Projection {
+        ty: Box<Ty>,
+        trait_: TraitRef,
+        item: PathSegment,
+    },
+    Array(Box<Ty>, Box<Expr>),
+    Slice(Box<Ty>),
+    Impl(Vec<TraitRef>),
+    Dyn(Vec<TraitRef>),
+    /// A type that failed to lower, which is already reported.
+    Err,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimTy {
+    Int(IntTy),
+    Uint(UintTy),
+    Float(FloatTy),
+    Bool,
+    Char,
+    Str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntTy {
+    Isize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+}
+
+impl IntTy {
+    /// How big the integer is, where `isize` is as big as a pointer.
+    pub fn size(self, pointer_size: Size) -> Size {
+        match self {
+            Self::Isize => pointer_size,
+            Self::I8 => Size::from_bits(8),
+            Self::I16 => Size::from_bits(16),
+            Self::I32 => Size::from_bits(32),
+            Self::I64 => Size::from_bits(64),
+            Self::I128 => Size::from_bits(128),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UintTy {
+    Usize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+}
+
+impl UintTy {
+    /// How big the integer is, where `usize` is as big as a pointer.
+    pub fn size(self, pointer_size: Size) -> Size {
+        match self {
+            Self::Usize => pointer_size,
+            Self::U8 => Size::from_bits(8),
+            Self::U16 => Size::from_bits(16),
+            Self::U32 => Size::from_bits(32),
+            Self::U64 => Size::from_bits(64),
+            Self::U128 => Size::from_bits(128),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatTy {
+    F32,
+    F64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pat {
+    pub hir_id: HirId,
+    pub kind: PatKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatKind {
+    /// Binds a local, which [`Res::Local`] refers to by the id of this pattern.
+    Binding {
+        mode: BindingMode,
+        name: Ident,
+        sub: Option<Box<Pat>>,
+    },
+    Tuple(Vec<Pat>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BindingMode {
+    pub by_ref: bool,
+    pub mutable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub hir_id: HirId,
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Lit(Lit),
+    Path(QPath),
+    /// `()` is the empty tuple.
+    Tuple(Vec<Expr>),
+    Block(Block),
+    Let(Box<Local>),
+    Field(Box<Expr>, Ident),
+    Call(Box<Expr>, Vec<Expr>),
+    MethodCall {
+        receiver: Box<Expr>,
+        method: PathSegment,
+        args: Vec<Expr>,
+    },
+    Unary(UnOp, Box<Expr>),
+    AddrOf(Mutability, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Assign(Box<Expr>, Box<Expr>),
+    If {
+        condition: Box<Expr>,
+        then: Block,
+        otherwise: Option<Box<Expr>>,
+    },
+    Return(Box<Expr>),
+    /// `loop { statements }`, which `while` loops are lowered to as well.
+    Loop(Block),
+    Break,
+    Continue,
+    Intrinsic(Intrinsic, Vec<Expr>),
+    /// An expression that failed to parse or lower, which is already reported.
+    Err,
+}
+
+/// `statements`, evaluating to the last of them, or to `()` if there are none.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub hir_id: HirId,
+    pub statements: Vec<Expr>,
+    pub unsafe_: bool,
+    pub span: Span,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// `let pat: ty = init`
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub pat: Pat,
+    pub ty: Option<Ty>,
+    pub init: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Lit {
+    /// Suffixed literals have their type already.
+    Int(u128, Option<PrimTy>),
+    Float(f64, Option<FloatTy>),
+    Str(String),
+    ByteStr(Vec<u8>),
+    Char(char),
+    Byte(u8),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Not,
+    Neg,
+    Deref,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+impl BinOp {
+    /// The operator as written, like `+`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
     }
 }
@@ -8,6 +8,7 @@ define_index_type! {
 const CRATE_DEF_INDEX: CrateNum = CrateNum::from_raw_unchecked(0);
 
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DefId {
     pub krate: CrateNum,
     pub index: DefIndex,
@@ -29,15 +30,59 @@ impl DefId {
 pub mod hir;
 pub mod index;
 pub mod mir;
+pub mod span;
+pub mod symbol;
 pub mod ty;
 pub mod util;
 
+/// The context of a compilation, which types are interned in.
 pub struct Cx {
     pub data_layout: DataLayout,
+    pub types: ty::CommonTypes,
+    interners: ty::CtxtInterners,
 }
+
+#[derive(Debug, Clone)]
 pub struct DataLayout {
-    pub pointer_size: u32,
-    pub pointer_align: u32,
+    pub pointer_size: ty::Size,
+    pub pointer_align: ty::Align,
+}
+
+impl DataLayout {
+    /// The layout of the target the compiler itself runs on.
+    pub fn host() -> Self {
+        Self {
+            pointer_size: ty::Size::from_bytes(std::mem::size_of::<usize>()),
+            pointer_align: ty::Align::from_bytes(std::mem::align_of::<usize>() as u64)
+                .expect("alignments are powers of two"),
+        }
+    }
+
+    /// The size above which objects can't be, for the size of pointers of the target.
+    pub fn obj_size_bound(&self) -> u64 {
+        match self.pointer_size.bits() {
+            16 => 1 << 15,
+            32 => 1 << 31,
+            64 => 1 << 47,
+            bits => panic!("obj_size_bound: unknown pointer bit size {bits}"),
+        }
+    }
+}
+
+pub trait HasDataLayout {
+    fn data_layout(&self) -> &DataLayout;
+}
+
+impl HasDataLayout for DataLayout {
+    fn data_layout(&self) -> &DataLayout {
+        self
+    }
+}
+
+impl HasDataLayout for Cx {
+    fn data_layout(&self) -> &DataLayout {
+        &self.data_layout
+    }
 }
 
 #[macro_export]
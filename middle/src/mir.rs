@@ -1,5 +1,15 @@
+//! The MIR, the body of a function, const or static as a graph of basic blocks. Each block is a
+//! list of statements, which only assign to places, and ends with a terminator, which is where
+//! control flow branches, calls and drops happen.
+
+use std::ops::Deref;
+
+use crate::hir::Mutability;
 use crate::index::IndexVec;
-use crate::ty::Type;
+use crate::span::Span;
+use crate::symbol::Symbol;
+use crate::ty::{Const, GenericArgs, Region, Ty};
+use crate::DefId;
 
 crate::define_index_type! {
     pub struct BasicBlockId = u32;
@@ -17,12 +27,35 @@ crate::define_index_type! {
     pub struct VariantIdx = u32;
 }
 
+/// The local a body returns, which is assigned before its `Return`.
+pub const RETURN_PLACE: Local = Local::from_raw_unchecked(0);
+
+/// The block a body starts in.
+pub const START_BLOCK: BasicBlockId = BasicBlockId::from_raw_unchecked(0);
+
 #[derive(Debug, Clone)]
 pub struct Place {
     pub local: Local,
     pub projection: Vec<PlaceItem>
 }
 
+impl Place {
+    /// This place with `elem` applied to it, like `(*place)` or `place.0`.
+    pub fn project(mut self, elem: PlaceItem) -> Self {
+        self.projection.push(elem);
+        self
+    }
+}
+
+impl From<Local> for Place {
+    fn from(local: Local) -> Self {
+        Self {
+            local,
+            projection: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ProjectionElem<V, T> {
     Deref,
@@ -43,8 +76,49 @@ pub enum ProjectionElem<V, T> {
     Subtype(T),
 }
 
-pub type PlaceItem = ProjectionElem<Local, Type>;
+pub type PlaceItem = ProjectionElem<Local, Ty>;
+
+/// The MIR of a function, const or static.
+#[derive(Debug)]
+pub struct Body {
+    pub basic_blocks: BasicBlocks,
+    /// The return place, then the arguments, then the other locals and temporaries.
+    pub local_decls: LocalDecls,
+    /// How many arguments there are, which are the locals right after the return place.
+    pub arg_count: usize,
+    pub span: Span,
+}
+
+impl Body {
+    pub fn args_iter(&self) -> impl Iterator<Item = Local> {
+        (1..=self.arg_count).map(Local::new)
+    }
+}
+
+pub type LocalDecls = IndexVec<Local, LocalDecl>;
 
+#[derive(Debug, Clone)]
+pub struct LocalDecl {
+    pub ty: Ty,
+    /// Whether the local can be assigned again after it is initialized. Temporaries can be.
+    pub mutability: Mutability,
+    pub kind: LocalKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalKind {
+    ReturnPlace,
+    /// An argument that isn't bound to a variable of its own, like one taken apart by a tuple
+    /// pattern.
+    Arg,
+    /// A variable bound by a pattern, which may be an argument too.
+    Var(Symbol),
+    /// A value made on the way to another one, like the callee of a call.
+    Temp,
+}
+
+#[derive(Debug)]
 pub struct BasicBlocks {
     blocks: IndexVec<BasicBlockId, BasicBlock>,
 }
@@ -59,32 +133,230 @@ impl BasicBlocks {
     }
 }
 
+impl Deref for BasicBlocks {
+    type Target = IndexVec<BasicBlockId, BasicBlock>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.blocks
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BasicBlock {
     pub name: String,
     pub statements: Vec<Statement>,
     pub terminator: Option<Terminator>,
+    /// Whether the block only runs while unwinding, to drop what is live where a call or drop
+    /// panicked.
+    pub is_cleanup: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Operand {
     Copy(Place),
     Move(Place),
-    Const(Const)
+    /// A constant of the given type, which is a function item for the callee of most calls.
+    Const(Const, Ty),
+}
+
+impl Operand {
+    /// This operand, copying out of its place instead of moving, for using it again.
+    pub fn to_copy(&self) -> Self {
+        match self {
+            Self::Move(place) => Self::Copy(place.clone()),
+            _ => self.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Rvalue {
     Use(Operand),
+    /// `&place` or `&mut place`.
+    Ref(Region, Mutability, Place),
+    /// The address of `place`, without borrowing it.
+    AddressOf(Mutability, Place),
+    /// The length of the array or slice in `place`, a `usize`.
+    Len(Place),
+    /// `operand as ty`.
+    Cast(CastKind, Operand, Ty),
+    /// An operator that can't overflow, or whose overflow is already checked. Both operands
+    /// have the same type, but for shifts.
+    BinaryOp(BinOp, Operand, Operand),
+    /// `(a op b, overflowed)`, which an `Assert` checks before the result is used.
+    CheckedBinaryOp(BinOp, Operand, Operand),
+    /// A value that only depends on types, like `std.typeName T`.
+    NullaryOp(NullOp),
+    UnaryOp(UnOp, Operand),
+    /// The discriminant of the enum in `place`.
+    Discriminant(Place),
+    /// A tuple, array or enum variant made of the operands, which are its fields.
+    Aggregate(AggregateKind, Vec<Operand>),
+}
+
+/// An intrinsic over types. Its value is only known once the type parameters in them are.
+#[derive(Debug, Clone, Copy)]
+pub enum NullOp {
+    /// `std.typeName T`, a `&'static str`.
+    TypeName(Ty),
+    /// `std.canCast T U`, a `bool`.
+    CanCast(Ty, Ty),
+}
+
+/// The casts `std.canCast` allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastKind {
+    /// Between integers, and from `bool` and `char` to integers and `u8` to `char`.
+    IntToInt,
+    IntToFloat,
+    FloatToInt,
+    FloatToFloat,
+    /// From a reference to one to the same type, dropping `mut`.
+    PtrToPtr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone)]
+pub enum AggregateKind {
+    Tuple,
+    /// An array of the given element type.
+    Array(Ty),
+    /// A variant of the enum, with the arguments of the enum's type parameters.
+    Adt(DefId, VariantIdx, GenericArgs),
 }
 
 #[derive(Debug, Clone)]
 pub enum Statement {
-    Assign(Place, Rvalue)
+    Assign(Place, Rvalue),
+    /// Marks where `local` starts being used, before it is first assigned.
+    StorageLive(Local),
+    /// Marks where `local` stops being used, after it is dropped.
+    StorageDead(Local),
+    /// Sets the discriminant of the enum in `place`, whose fields of that variant are already
+    /// assigned.
+    SetDiscriminant { place: Place, variant_index: VariantIdx },
+    /// A statement that does nothing, in place of one that was removed.
+    Nop,
 }
 
 #[derive(Debug, Clone)]
 pub enum Terminator {
     Goto { target: BasicBlockId },
+    /// Goes to the target of the value of `discr`, an integer, `bool` or `char`.
+    SwitchInt { discr: Operand, targets: SwitchTargets },
+    /// Ends a cleanup block, carrying on unwinding out of the body.
+    UnwindResume,
     Return,
+    /// Marks where control can't get to, like after a `@compile_error`.
+    Unreachable,
+    /// Goes to `target` if `cond` is `expected`, and panics with `msg` if it isn't.
+    Assert {
+        cond: Operand,
+        expected: bool,
+        msg: AssertKind,
+        target: BasicBlockId,
+        unwind: UnwindAction,
+    },
+    /// Drops the value in `place` if it is still initialized, which isn't known for sure until
+    /// moves are tracked.
+    Drop {
+        place: Place,
+        target: BasicBlockId,
+        unwind: UnwindAction,
+    },
+    /// Calls `func`, storing what it returns in `destination` and going to `target`, which
+    /// functions returning `!` have none of.
+    Call {
+        func: Operand,
+        args: Vec<Operand>,
+        destination: Place,
+        target: Option<BasicBlockId>,
+        unwind: UnwindAction,
+    },
+}
+
+/// Why an `Assert` panics.
+#[derive(Debug, Clone)]
+pub enum AssertKind {
+    /// `a op b` overflowed, or shifted by at least as many bits as `a` has.
+    Overflow(BinOp, Operand, Operand),
+    /// `-a` overflowed, for the smallest value of a signed integer.
+    OverflowNeg(Operand),
+    /// The operand was divided by zero.
+    DivisionByZero(Operand),
+    /// The remainder of dividing the operand by zero was taken.
+    RemainderByZero(Operand),
+}
+
+/// Where a call or drop that panics goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwindAction {
+    /// Out of the body, since there is nothing to drop.
+    Continue,
+    /// Aborts, for drops in cleanup blocks, which are already unwinding.
+    Terminate,
+    /// To a cleanup block.
+    Cleanup(BasicBlockId),
+}
+
+/// The values a `SwitchInt` goes to each target for, and the target for any other value.
+#[derive(Debug, Clone)]
+pub struct SwitchTargets {
+    values: Vec<u128>,
+    /// One for each value, then the one for any other value.
+    targets: Vec<BasicBlockId>,
+}
+
+impl SwitchTargets {
+    pub fn new(branches: impl IntoIterator<Item = (u128, BasicBlockId)>, otherwise: BasicBlockId) -> Self {
+        let (values, mut targets): (Vec<_>, Vec<_>) = branches.into_iter().unzip();
+        targets.push(otherwise);
+
+        Self { values, targets }
+    }
+
+    /// Goes to `then` if the value is `value`, and to `otherwise` if it isn't, like `if` does
+    /// with `0` for `false`.
+    pub fn static_if(value: u128, then: BasicBlockId, otherwise: BasicBlockId) -> Self {
+        Self::new([(value, then)], otherwise)
+    }
+
+    pub fn otherwise(&self) -> BasicBlockId {
+        *self.targets.last().expect("there is always an otherwise target")
+    }
+
+    /// Each value with its target, without the otherwise target.
+    pub fn iter(&self) -> impl Iterator<Item = (u128, BasicBlockId)> + '_ {
+        self.values.iter().copied().zip(self.targets.iter().copied())
+    }
+
+    /// All of the targets, the otherwise target last.
+    pub fn all_targets(&self) -> &[BasicBlockId] {
+        &self.targets
+    }
 }
@@ -0,0 +1,122 @@
+use std::ops::Range;
+
+use crate::index::IndexVec;
+
+crate::define_index_type! {
+    pub struct FileId = u32;
+}
+
+/// A range of bytes in one of the files of the [`SourceMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub fn new(file: FileId, range: Range<usize>) -> Self {
+        Self {
+            file,
+            start: range.start as u32,
+            end: range.end as u32,
+        }
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    pub fn len(self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    /// The empty span right before this one.
+    pub fn shrink_to_lo(self) -> Self {
+        Self {
+            end: self.start,
+            ..self
+        }
+    }
+
+    /// The empty span right after this one.
+    pub fn shrink_to_hi(self) -> Self {
+        Self {
+            start: self.end,
+            ..self
+        }
+    }
+
+    /// The span covering both `self` and `other`, and everything in between.
+    pub fn to(self, other: Self) -> Self {
+        debug_assert_eq!(self.file, other.file, "joining spans of different files");
+
+        Self {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+}
+
+/// All the files that make up a crate, so that a [`Span`] can find its way back to the source.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: IndexVec<FileId, SourceFile>,
+}
+
+impl SourceMap {
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(SourceFile {
+            name: name.into(),
+            source: source.into(),
+        })
+    }
+
+    pub fn get(&self, file: FileId) -> &SourceFile {
+        &self.files[file]
+    }
+
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.get(span.file).source[span.range()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinking_and_joining() {
+        let file = FileId::from_raw(0);
+        let span = Span::new(file, 3..7);
+
+        assert_eq!(span.len(), 4);
+        assert_eq!(span.shrink_to_lo(), Span::new(file, 3..3));
+        assert_eq!(span.shrink_to_hi(), Span::new(file, 7..7));
+        assert!(span.shrink_to_hi().is_empty());
+        assert_eq!(span.to(Span::new(file, 10..12)), Span::new(file, 3..12));
+        assert_eq!(Span::new(file, 10..12).to(span), Span::new(file, 3..12));
+    }
+
+    #[test]
+    fn snippets_come_from_the_right_file() {
+        let mut files = SourceMap::default();
+        let a = files.add("a.tn", "struct A {}");
+        let b = files.add("b.tn", "struct B {}");
+
+        assert_eq!(files.get(b).name, "b.tn");
+        assert_eq!(files.snippet(Span::new(a, 7..8)), "A");
+        assert_eq!(files.snippet(Span::new(b, 7..8)), "B");
+    }
+}
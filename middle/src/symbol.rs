@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{index::IndexVec, span::Span};
+
+crate::define_index_type! {
+    pub struct SymbolIndex = u32;
+}
+
+/// An interned string, used for identifiers. Comparing two symbols is comparing two integers.
+///
+/// The strings are interned for the whole run of the compiler (and leaked), so
+/// [`Symbol::as_str`] can hand out `&'static str`s.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(SymbolIndex);
+
+struct Interner {
+    strings: IndexVec<SymbolIndex, &'static str>,
+    symbols: HashMap<&'static str, Symbol>,
+}
+
+impl Interner {
+    fn prefilled() -> Self {
+        let mut interner = Self {
+            strings: IndexVec::with_capacity(PREFILLED.len()),
+            symbols: HashMap::with_capacity(PREFILLED.len()),
+        };
+
+        for string in PREFILLED {
+            interner.intern(string);
+        }
+
+        interner
+    }
+
+    fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(string) {
+            return symbol;
+        }
+
+        let string: &'static str = Box::leak(string.into());
+        let symbol = Symbol(self.strings.push(string));
+        self.symbols.insert(string, symbol);
+
+        symbol
+    }
+}
+
+fn with_interner<R>(f: impl FnOnce(&mut Interner) -> R) -> R {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+    let mut interner = INTERNER
+        .get_or_init(|| Mutex::new(Interner::prefilled()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    f(&mut interner)
+}
+
+impl Symbol {
+    pub fn intern(string: &str) -> Self {
+        with_interner(|interner| interner.intern(string))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        with_interner(|interner| interner.strings[self.0])
+    }
+
+    /// Whether this is one of the keywords in [`kw`].
+    pub fn is_keyword(self) -> bool {
+        self.0.index() < KEYWORD_COUNT
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Self::intern(string)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A name as written in the source. Two identifiers are equal if their names are, wherever they
+/// were written.
+#[derive(Debug, Clone, Copy)]
+pub struct Ident {
+    pub name: Symbol,
+    pub span: Span,
+}
+
+impl Ident {
+    pub fn new(name: Symbol, span: Span) -> Self {
+        Self { name, span }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        self.name.as_str()
+    }
+}
+
+impl PartialEq for Ident {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Ident {}
+
+impl Hash for Ident {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.name, f)
+    }
+}
+
+/// Pre-interns the given keywords and symbols, in that order, so that they get fixed indices
+/// which can be named through constants in [`kw`] and [`sym`].
+macro_rules! symbols {
+    (
+        Keywords { $($kw:ident: $kw_string:literal),* $(,)? }
+        Symbols { $($sym:ident $(: $sym_string:literal)?),* $(,)? }
+    ) => {
+        #[allow(non_camel_case_types)]
+        #[repr(u32)]
+        enum Prefilled {
+            $($kw,)*
+            $($sym,)*
+        }
+
+        const PREFILLED: &[&str] = &[
+            $($kw_string,)*
+            $(symbols!(@string $sym $($sym_string)?),)*
+        ];
+
+        const KEYWORD_COUNT: usize = [$($kw_string),*].len();
+
+        /// Keywords, including the ones that the lexer gives tokens of their own.
+        #[allow(non_upper_case_globals)]
+        pub mod kw {
+            use super::*;
+
+            $(pub const $kw: Symbol = Symbol(SymbolIndex::from_raw_unchecked(Prefilled::$kw as u32));)*
+        }
+
+        /// Well-known names that the compiler looks for.
+        #[allow(non_upper_case_globals)]
+        pub mod sym {
+            use super::*;
+
+            $(pub const $sym: Symbol = Symbol(SymbolIndex::from_raw_unchecked(Prefilled::$sym as u32));)*
+        }
+    };
+    (@string $sym:ident $string:literal) => { $string };
+    (@string $sym:ident) => { stringify!($sym) };
+}
+
+symbols! {
+    Keywords {
+        Const: "const",
+        Ref: "ref",
+        Pub: "pub",
+        Let: "let",
+        Mut: "mut",
+        True: "true",
+        False: "false",
+        Struct: "struct",
+        Enum: "enum",
+        Trait: "trait",
+        Impl: "impl",
+        Type: "type",
+        Static: "static",
+        Where: "where",
+        For: "for",
+        If: "if",
+        Else: "else",
+        Unsafe: "unsafe",
+        Return: "return",
+        Loop: "loop",
+        While: "while",
+        Break: "break",
+        Continue: "continue",
+        Dyn: "dyn",
+        As: "as",
+        Mod: "mod",
+        Use: "use",
+        SelfLower: "self",
+        SelfUpper: "Self",
+        Underscore: "_",
+    }
+
+    Symbols {
+        std,
+        core,
+        main,
+        doc,
+        allow,
+        warn,
+        deny,
+        deprecated,
+        feature,
+        inline,
+        cold,
+        must_use,
+        repr,
+        test,
+        lang,
+        compile_error,
+        fmt,
+        typeName,
+        canCast,
+        Option,
+        bool,
+        char,
+        str,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        isize,
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        usize,
+        f32,
+        f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::FileId;
+
+    #[test]
+    fn interning_is_idempotent() {
+        let a = Symbol::intern("interning_is_idempotent");
+
+        assert_eq!(a, Symbol::intern("interning_is_idempotent"));
+        assert_ne!(a, Symbol::intern("interning_is_idempotent2"));
+        assert_eq!(a.as_str(), "interning_is_idempotent");
+    }
+
+    #[test]
+    fn prefilled_symbols() {
+        assert_eq!(Symbol::intern("const"), kw::Const);
+        assert_eq!(Symbol::intern("Self"), kw::SelfUpper);
+        assert_eq!(Symbol::intern("doc"), sym::doc);
+        assert_eq!(sym::typeName.as_str(), "typeName");
+        assert_eq!(sym::f64.as_str(), "f64");
+    }
+
+    #[test]
+    fn keywords() {
+        assert!(kw::Const.is_keyword());
+        assert!(kw::Underscore.is_keyword());
+        assert!(!sym::std.is_keyword());
+        assert!(!Symbol::intern("keywords").is_keyword());
+    }
+
+    #[test]
+    fn idents_compare_by_name() {
+        let file = FileId::from_raw(0);
+        let a = Ident::new(sym::main, Span::new(file, 0..4));
+        let b = Ident::new(sym::main, Span::new(file, 10..14));
+
+        assert_eq!(a, b);
+        assert_ne!(a, Ident::new(sym::std, a.span));
+    }
+}
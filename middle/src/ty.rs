@@ -1,20 +1,52 @@
 use core::fmt;
-use std::num::NonZeroU8;
-
-use crate::{Cx, explode};
-
-#[derive(Clone, Debug)]
-pub enum Type {
-    Xd
-}
-
-#[derive(Clone, Debug)]
+use std::{
+    num::NonZeroU8,
+    ops::{Add, AddAssign, Mul, Sub},
+};
+
+use crate::{define_index_type, explode, symbol::Symbol, Cx, HasDataLayout};
+
+mod context;
+mod fold;
+mod generics;
+mod instance;
+mod kind;
+mod predicate;
+mod typeck_results;
+
+pub(crate) use context::CtxtInterners;
+pub use context::CommonTypes;
+pub use fold::TypeFolder;
+pub use generics::{GenericParamDef, GenericParamDefKind, Generics};
+pub use instance::Instance;
+pub use kind::*;
+pub use predicate::{Predicate, ProjectionPredicate, TraitRef};
+pub use typeck_results::{Adjust, Adjustment, TypeckResults};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Const {
     Param(ParamConst),
     Infer(InferConst),
     Value(ValTree)
 }
 
+/// A const parameter, by its index among the const parameters of its item and its parents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamConst {
+    pub index: u32,
+    pub name: Symbol,
+}
+
+define_index_type! {
+    pub struct ConstVid = u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InferConst {
+    Var(ConstVid),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ValTree {
     Leaf(ScalarInt),
     Branch(Vec<ValTree>)
@@ -95,7 +127,7 @@ impl ScalarInt {
     }
 
     #[inline]
-    pub fn try_from_target_usize(i: impl Into<u128>, cx: Cx) -> Option<Self> {
+    pub fn try_from_target_usize(i: impl Into<u128>, cx: &Cx) -> Option<Self> {
         Self::try_from_uint(i, cx.data_layout.pointer_size)
     }
 
@@ -118,7 +150,7 @@ impl ScalarInt {
     }
 
     #[inline]
-    pub fn try_to_target_usize(&self, cx: Cx) -> Result<u64, Size> {
+    pub fn try_to_target_usize(&self, cx: &Cx) -> Result<u64, Size> {
         Ok(self.to_bits(cx.data_layout.pointer_size)? as u64)
     }
 
@@ -301,38 +333,6 @@ impl TryFrom<ScalarInt> for char {
     }
 }
 
-impl From<Single> for ScalarInt {
-    #[inline]
-    fn from(f: Single) -> Self {
-        // We trust apfloat to give us properly truncated data.
-        Self { data: f.to_bits(), size: NonZeroU8::new((Single::BITS / 8) as u8).unwrap() }
-    }
-}
-
-impl TryFrom<ScalarInt> for Single {
-    type Error = Size;
-    #[inline]
-    fn try_from(int: ScalarInt) -> Result<Self, Size> {
-        int.to_bits(Size::from_bytes(4)).map(Self::from_bits)
-    }
-}
-
-impl From<Double> for ScalarInt {
-    #[inline]
-    fn from(f: Double) -> Self {
-        // We trust apfloat to give us properly truncated data.
-        Self { data: f.to_bits(), size: NonZeroU8::new((Double::BITS / 8) as u8).unwrap() }
-    }
-}
-
-impl TryFrom<ScalarInt> for Double {
-    type Error = Size;
-    #[inline]
-    fn try_from(int: ScalarInt) -> Result<Self, Size> {
-        int.to_bits(Size::from_bytes(8)).map(Self::from_bits)
-    }
-}
-
 impl fmt::Debug for ScalarInt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Dispatch to LowerHex below.
@@ -392,6 +392,32 @@ impl fmt::Debug for Size {
     }
 }
 
+/// Alignment of a type in bytes, always a power of two.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Align {
+    pow2: u8,
+}
+
+impl Align {
+    pub const ONE: Align = Align { pow2: 0 };
+
+    /// `None` if `align` is not a power of two.
+    #[inline]
+    pub fn from_bytes(align: u64) -> Option<Align> {
+        align.is_power_of_two().then(|| Align { pow2: align.trailing_zeros() as u8 })
+    }
+
+    #[inline]
+    pub fn bytes(self) -> u64 {
+        1 << self.pow2
+    }
+
+    #[inline]
+    pub fn bits(self) -> u64 {
+        self.bytes() * 8
+    }
+}
+
 impl Size {
     pub const ZERO: Size = Size { raw: 0 };
 
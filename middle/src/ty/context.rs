@@ -0,0 +1,259 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use super::{
+    AliasTy, Const, FloatVid, FnSig, GenericArgs, InferTy, IntVid, List, ParamTy, Region, Ty, TyKind, TyVid,
+};
+use crate::{
+    hir::{FloatTy, IntTy, Mutability, PrimTy, UintTy},
+    symbol::Symbol,
+    Cx, DataLayout, DefId,
+};
+
+/// The sets of everything interned so far. Like symbols, interned types live for the whole run
+/// of the compiler (and are leaked), so they can be `'static`.
+#[derive(Default)]
+pub(crate) struct CtxtInterners {
+    types: RefCell<HashSet<&'static TyKind>>,
+    type_lists: RefCell<HashSet<&'static [Ty]>>,
+}
+
+impl CtxtInterners {
+    fn intern_ty(&self, kind: TyKind) -> Ty {
+        let mut types = self.types.borrow_mut();
+
+        if let Some(&interned) = types.get(&kind) {
+            return Ty(interned);
+        }
+
+        let interned: &'static TyKind = Box::leak(Box::new(kind));
+        types.insert(interned);
+
+        Ty(interned)
+    }
+
+    fn intern_ty_list(&self, tys: &[Ty]) -> List<Ty> {
+        if tys.is_empty() {
+            return List::empty();
+        }
+
+        let mut type_lists = self.type_lists.borrow_mut();
+
+        if let Some(&interned) = type_lists.get(tys) {
+            return List(interned);
+        }
+
+        let interned: &'static [Ty] = Box::leak(tys.into());
+        type_lists.insert(interned);
+
+        List(interned)
+    }
+}
+
+/// Types that are made often enough to be made once, up front.
+pub struct CommonTypes {
+    pub unit: Ty,
+    pub bool: Ty,
+    pub char: Ty,
+    pub str: Ty,
+    pub never: Ty,
+    pub isize: Ty,
+    pub i8: Ty,
+    pub i16: Ty,
+    pub i32: Ty,
+    pub i64: Ty,
+    pub i128: Ty,
+    pub usize: Ty,
+    pub u8: Ty,
+    pub u16: Ty,
+    pub u32: Ty,
+    pub u64: Ty,
+    pub u128: Ty,
+    pub f32: Ty,
+    pub f64: Ty,
+    pub error: Ty,
+}
+
+impl CommonTypes {
+    fn new(interners: &CtxtInterners) -> Self {
+        let mk = |kind| interners.intern_ty(kind);
+
+        Self {
+            unit: mk(TyKind::Tuple(List::empty())),
+            bool: mk(TyKind::Bool),
+            char: mk(TyKind::Char),
+            str: mk(TyKind::Str),
+            never: mk(TyKind::Never),
+            isize: mk(TyKind::Int(IntTy::Isize)),
+            i8: mk(TyKind::Int(IntTy::I8)),
+            i16: mk(TyKind::Int(IntTy::I16)),
+            i32: mk(TyKind::Int(IntTy::I32)),
+            i64: mk(TyKind::Int(IntTy::I64)),
+            i128: mk(TyKind::Int(IntTy::I128)),
+            usize: mk(TyKind::Uint(UintTy::Usize)),
+            u8: mk(TyKind::Uint(UintTy::U8)),
+            u16: mk(TyKind::Uint(UintTy::U16)),
+            u32: mk(TyKind::Uint(UintTy::U32)),
+            u64: mk(TyKind::Uint(UintTy::U64)),
+            u128: mk(TyKind::Uint(UintTy::U128)),
+            f32: mk(TyKind::Float(FloatTy::F32)),
+            f64: mk(TyKind::Float(FloatTy::F64)),
+            error: mk(TyKind::Error),
+        }
+    }
+}
+
+impl Cx {
+    pub fn new(data_layout: DataLayout) -> Self {
+        let interners = CtxtInterners::default();
+        let types = CommonTypes::new(&interners);
+
+        Self {
+            data_layout,
+            types,
+            interners,
+        }
+    }
+
+    pub fn mk_ty(&self, kind: TyKind) -> Ty {
+        self.interners.intern_ty(kind)
+    }
+
+    pub fn mk_ty_list(&self, tys: &[Ty]) -> List<Ty> {
+        self.interners.intern_ty_list(tys)
+    }
+
+    pub fn mk_args(&self, args: &[Ty]) -> GenericArgs {
+        self.interners.intern_ty_list(args)
+    }
+
+    pub fn mk_prim(&self, prim: PrimTy) -> Ty {
+        match prim {
+            PrimTy::Int(int) => self.mk_ty(TyKind::Int(int)),
+            PrimTy::Uint(uint) => self.mk_ty(TyKind::Uint(uint)),
+            PrimTy::Float(float) => self.mk_ty(TyKind::Float(float)),
+            PrimTy::Bool => self.types.bool,
+            PrimTy::Char => self.types.char,
+            PrimTy::Str => self.types.str,
+        }
+    }
+
+    pub fn mk_ref(&self, region: Region, ty: Ty, mutability: Mutability) -> Ty {
+        self.mk_ty(TyKind::Ref(region, ty, mutability))
+    }
+
+    pub fn mk_adt(&self, def: DefId, args: &[Ty]) -> Ty {
+        self.mk_ty(TyKind::Adt(def, self.mk_args(args)))
+    }
+
+    pub fn mk_fn_def(&self, def: DefId, args: &[Ty]) -> Ty {
+        self.mk_ty(TyKind::FnDef(def, self.mk_args(args)))
+    }
+
+    pub fn mk_fn_sig(&self, inputs: &[Ty], output: Ty) -> FnSig {
+        let inputs_and_output: Vec<_> = inputs.iter().copied().chain([output]).collect();
+
+        FnSig {
+            inputs_and_output: self.mk_ty_list(&inputs_and_output),
+        }
+    }
+
+    pub fn mk_fn_ptr(&self, sig: FnSig) -> Ty {
+        self.mk_ty(TyKind::FnPtr(sig))
+    }
+
+    pub fn mk_tuple(&self, tys: &[Ty]) -> Ty {
+        self.mk_ty(TyKind::Tuple(self.mk_ty_list(tys)))
+    }
+
+    pub fn mk_array(&self, ty: Ty, len: Const) -> Ty {
+        self.mk_ty(TyKind::Array(ty, len))
+    }
+
+    pub fn mk_slice(&self, ty: Ty) -> Ty {
+        self.mk_ty(TyKind::Slice(ty))
+    }
+
+    pub fn mk_param(&self, index: u32, name: Symbol) -> Ty {
+        self.mk_ty(TyKind::Param(ParamTy { index, name }))
+    }
+
+    pub fn mk_ty_var(&self, vid: TyVid) -> Ty {
+        self.mk_ty(TyKind::Infer(InferTy::TyVar(vid)))
+    }
+
+    pub fn mk_int_var(&self, vid: IntVid) -> Ty {
+        self.mk_ty(TyKind::Infer(InferTy::IntVar(vid)))
+    }
+
+    pub fn mk_float_var(&self, vid: FloatVid) -> Ty {
+        self.mk_ty(TyKind::Infer(InferTy::FloatVar(vid)))
+    }
+
+    /// `(args[0] as Trait).Item`, where `def` is the associated type `Item` of `Trait`.
+    pub fn mk_projection(&self, def: DefId, args: &[Ty]) -> Ty {
+        self.mk_ty(TyKind::Projection(AliasTy {
+            def,
+            args: self.mk_args(args),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ty::{Align, Size},
+        DefIndex,
+    };
+
+    /// A context for a target with 64 bit pointers.
+    fn cx() -> Cx {
+        Cx::new(DataLayout {
+            pointer_size: Size::from_bytes(8),
+            pointer_align: Align::from_bytes(8).unwrap(),
+        })
+    }
+
+    #[test]
+    fn equal_types_are_interned_once() {
+        let cx = cx();
+        let adt = DefId::local(DefIndex::from_raw(1));
+
+        assert_eq!(cx.mk_prim(PrimTy::Int(IntTy::I32)), cx.types.i32);
+        assert_eq!(cx.mk_tuple(&[]), cx.types.unit);
+        assert_eq!(
+            cx.mk_ref(Region::Static, cx.types.str, Mutability::Not),
+            cx.mk_ref(Region::Static, cx.types.str, Mutability::Not)
+        );
+        assert_eq!(cx.mk_adt(adt, &[cx.types.bool]), cx.mk_adt(adt, &[cx.types.bool]));
+
+        assert_ne!(cx.types.i32, cx.types.u32);
+        assert_ne!(
+            cx.mk_ref(Region::Static, cx.types.str, Mutability::Not),
+            cx.mk_ref(Region::Static, cx.types.str, Mutability::Mut)
+        );
+        assert_ne!(cx.mk_adt(adt, &[cx.types.bool]), cx.mk_adt(adt, &[cx.types.char]));
+        assert_ne!(cx.mk_adt(adt, &[]), cx.mk_fn_def(adt, &[]));
+    }
+
+    #[test]
+    fn equal_lists_are_interned_once() {
+        let cx = cx();
+        let list = cx.mk_ty_list(&[cx.types.bool, cx.types.char]);
+
+        assert_eq!(list, cx.mk_ty_list(&[cx.types.bool, cx.types.char]));
+        assert_eq!(list.as_ptr(), cx.mk_args(&[cx.types.bool, cx.types.char]).as_ptr());
+        assert_ne!(list, cx.mk_ty_list(&[cx.types.char, cx.types.bool]));
+        assert_eq!(cx.mk_ty_list(&[]), List::empty());
+    }
+
+    #[test]
+    fn fn_sigs_end_in_the_return_type() {
+        let cx = cx();
+        let sig = cx.mk_fn_sig(&[cx.types.bool, cx.types.char], cx.types.unit);
+
+        assert_eq!(sig.inputs(), [cx.types.bool, cx.types.char]);
+        assert_eq!(sig.output(), cx.types.unit);
+        assert_eq!(cx.mk_fn_ptr(sig), cx.mk_fn_ptr(cx.mk_fn_sig(&[cx.types.bool, cx.types.char], cx.types.unit)));
+    }
+}
@@ -0,0 +1,194 @@
+use super::{AliasTy, FnSig, GenericArgs, List, Ty, TyKind};
+use crate::Cx;
+
+/// Rebuilds types bottom-up, replacing the parts of them that [`TypeFolder::fold_ty`] does.
+pub trait TypeFolder {
+    fn cx(&self) -> &Cx;
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        ty.super_fold_with(self)
+    }
+}
+
+impl Ty {
+    /// Folds the types this one is made of, and makes the same kind of type out of them.
+    pub fn super_fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> Ty {
+        let kind = match *self.kind() {
+            TyKind::Bool
+            | TyKind::Char
+            | TyKind::Int(_)
+            | TyKind::Uint(_)
+            | TyKind::Float(_)
+            | TyKind::Str
+            | TyKind::Never
+            | TyKind::Param(_)
+            | TyKind::Infer(_)
+            | TyKind::Error => return self,
+            TyKind::Ref(region, ty, mutability) => TyKind::Ref(region, folder.fold_ty(ty), mutability),
+            TyKind::Adt(def, args) => TyKind::Adt(def, fold_list(args, folder)),
+            TyKind::FnDef(def, args) => TyKind::FnDef(def, fold_list(args, folder)),
+            TyKind::FnPtr(sig) => TyKind::FnPtr(sig.fold_with(folder)),
+            TyKind::Tuple(tys) => TyKind::Tuple(fold_list(tys, folder)),
+            TyKind::Array(ty, ref len) => TyKind::Array(folder.fold_ty(ty), len.clone()),
+            TyKind::Slice(ty) => TyKind::Slice(folder.fold_ty(ty)),
+            TyKind::Projection(alias) => TyKind::Projection(AliasTy {
+                def: alias.def,
+                args: fold_list(alias.args, folder),
+            }),
+        };
+
+        folder.cx().mk_ty(kind)
+    }
+
+    /// Whether `f` holds for this type, or any type it is made of.
+    pub fn any(self, f: &mut impl FnMut(Ty) -> bool) -> bool {
+        if f(self) {
+            return true;
+        }
+
+        match *self.kind() {
+            TyKind::Bool
+            | TyKind::Char
+            | TyKind::Int(_)
+            | TyKind::Uint(_)
+            | TyKind::Float(_)
+            | TyKind::Str
+            | TyKind::Never
+            | TyKind::Param(_)
+            | TyKind::Infer(_)
+            | TyKind::Error => false,
+            TyKind::Ref(_, ty, _) | TyKind::Array(ty, _) | TyKind::Slice(ty) => ty.any(f),
+            TyKind::Adt(_, tys)
+            | TyKind::FnDef(_, tys)
+            | TyKind::FnPtr(FnSig {
+                inputs_and_output: tys,
+            })
+            | TyKind::Tuple(tys)
+            | TyKind::Projection(AliasTy { args: tys, .. }) => tys.iter().any(|ty| ty.any(f)),
+        }
+    }
+
+    /// Whether there is a type in this one that inference has not found yet.
+    pub fn has_infer(self) -> bool {
+        self.any(&mut |ty| matches!(ty.kind(), TyKind::Infer(_)))
+    }
+
+    pub fn has_params(self) -> bool {
+        self.any(&mut |ty| matches!(ty.kind(), TyKind::Param(_)))
+    }
+
+    /// Replaces each type parameter in this type with the argument at its index.
+    pub fn subst(self, cx: &Cx, args: &[Ty]) -> Ty {
+        if args.is_empty() {
+            return self;
+        }
+
+        self.fold_with(&mut Substituter { cx, args })
+    }
+
+    pub fn fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> Ty {
+        folder.fold_ty(self)
+    }
+}
+
+impl FnSig {
+    pub fn fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> FnSig {
+        FnSig {
+            inputs_and_output: fold_list(self.inputs_and_output, folder),
+        }
+    }
+
+    pub fn subst(self, cx: &Cx, args: &[Ty]) -> FnSig {
+        if args.is_empty() {
+            return self;
+        }
+
+        self.fold_with(&mut Substituter { cx, args })
+    }
+}
+
+impl GenericArgs {
+    pub fn fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> GenericArgs {
+        fold_list(self, folder)
+    }
+
+    /// Replaces each type parameter in these arguments with the argument at its index, like
+    /// instantiating a generic item inside another one with the arguments of the outer one.
+    pub fn subst(self, cx: &Cx, args: &[Ty]) -> GenericArgs {
+        if args.is_empty() {
+            return self;
+        }
+
+        self.fold_with(&mut Substituter { cx, args })
+    }
+}
+
+fn fold_list<F: TypeFolder + ?Sized>(list: List<Ty>, folder: &mut F) -> List<Ty> {
+    let tys: Vec<_> = list.iter().map(|&ty| folder.fold_ty(ty)).collect();
+    folder.cx().mk_ty_list(&tys)
+}
+
+struct Substituter<'a> {
+    cx: &'a Cx,
+    args: &'a [Ty],
+}
+
+impl TypeFolder for Substituter<'_> {
+    fn cx(&self) -> &Cx {
+        self.cx
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        match ty.kind() {
+            TyKind::Param(param) => match self.args.get(param.index as usize) {
+                Some(&arg) => arg,
+                None => ty,
+            },
+            _ => ty.super_fold_with(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hir::Mutability,
+        symbol::Symbol,
+        ty::{Region, TyVid},
+        DataLayout, DefId, DefIndex,
+    };
+
+    #[test]
+    fn subst_replaces_params_by_index() {
+        let cx = Cx::new(DataLayout::host());
+        let [t, u] = [(0, "T"), (1, "U")].map(|(index, name)| cx.mk_param(index, Symbol::intern(name)));
+        let adt = DefId::local(DefIndex::from_raw(1));
+
+        // `&Adt[T, (U, T)]`
+        let ty = cx.mk_ref(Region::Static, cx.mk_adt(adt, &[t, cx.mk_tuple(&[u, t])]), Mutability::Not);
+        let substituted = ty.subst(&cx, &[cx.types.bool, cx.types.char]);
+
+        let expected = cx.mk_ref(
+            Region::Static,
+            cx.mk_adt(adt, &[cx.types.bool, cx.mk_tuple(&[cx.types.char, cx.types.bool])]),
+            Mutability::Not,
+        );
+        assert_eq!(substituted, expected);
+        assert!(ty.has_params());
+        assert!(!substituted.has_params());
+
+        // parameters without an argument are left alone
+        assert_eq!(cx.mk_tuple(&[t, u]).subst(&cx, &[cx.types.bool]), cx.mk_tuple(&[cx.types.bool, u]));
+    }
+
+    #[test]
+    fn inference_variables_are_found_anywhere() {
+        let cx = Cx::new(DataLayout::host());
+        let var = cx.mk_ty_var(TyVid::from_raw(0));
+
+        assert!(cx.mk_slice(cx.mk_tuple(&[cx.types.bool, var])).has_infer());
+        assert!(cx.mk_fn_ptr(cx.mk_fn_sig(&[], var)).has_infer());
+        assert!(!cx.mk_slice(cx.types.bool).has_infer());
+    }
+}
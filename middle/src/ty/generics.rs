@@ -0,0 +1,46 @@
+use crate::{symbol::Symbol, DefId};
+
+/// The generic parameters of an item, after those of its parents. Associated items are children
+/// of their trait or impl, and enum variants have the parameters of their enum.
+#[derive(Debug, Clone, Default)]
+pub struct Generics {
+    pub parent: Option<DefId>,
+    pub params: Vec<GenericParamDef>,
+    /// How many type parameters there are, with those of the parents.
+    pub type_count: u32,
+    /// How many lifetime parameters there are, with those of the parents.
+    pub lifetime_count: u32,
+    /// How many const parameters there are, with those of the parents.
+    pub const_count: u32,
+}
+
+impl Generics {
+    /// The type parameters of the item itself, without those of its parents.
+    pub fn own_types(&self) -> impl Iterator<Item = &GenericParamDef> {
+        self.params
+            .iter()
+            .filter(|param| param.kind == GenericParamDefKind::Type)
+    }
+
+    /// How many type parameters the parents have, which come first.
+    pub fn parent_type_count(&self) -> u32 {
+        self.type_count - self.own_types().count() as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GenericParamDef {
+    /// The parameter, or the trait for the `Self` of a trait.
+    pub def: DefId,
+    pub name: Symbol,
+    /// The index among the parameters of its kind, like [`ParamTy::index`](super::ParamTy).
+    pub index: u32,
+    pub kind: GenericParamDefKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenericParamDefKind {
+    Type,
+    Lifetime,
+    Const,
+}
@@ -0,0 +1,25 @@
+use super::{GenericArgs, List, Ty};
+use crate::{Cx, DefId};
+
+/// A function with a type for each of its type parameters, which is what code is generated for.
+/// Calls to methods of traits are to the method of the impl that applies to the types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instance {
+    pub def: DefId,
+    pub args: GenericArgs,
+}
+
+impl Instance {
+    /// The one instance of a function without type parameters.
+    pub fn mono(def: DefId) -> Self {
+        Self {
+            def,
+            args: List::empty(),
+        }
+    }
+
+    /// A type in the body of the function, as it is in this instance.
+    pub fn instantiate(&self, cx: &Cx, ty: Ty) -> Ty {
+        ty.subst(cx, &self.args)
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    ptr,
+};
+
+use super::Const;
+use crate::{
+    define_index_type,
+    hir::{FloatTy, IntTy, Mutability, UintTy},
+    symbol::Symbol,
+    DefId,
+};
+
+/// An interned type. Types are only made by interning them in a [`Cx`](crate::Cx), so two
+/// types are equal exactly when they are the same allocation, and comparing them is comparing
+/// two pointers.
+#[derive(Clone, Copy)]
+pub struct Ty(pub(super) &'static TyKind);
+
+impl Ty {
+    pub fn kind(self) -> &'static TyKind {
+        self.0
+    }
+
+    pub fn is_unit(self) -> bool {
+        matches!(self.kind(), TyKind::Tuple(tys) if tys.is_empty())
+    }
+
+    pub fn is_never(self) -> bool {
+        matches!(self.kind(), TyKind::Never)
+    }
+
+    pub fn is_bool(self) -> bool {
+        matches!(self.kind(), TyKind::Bool)
+    }
+
+    /// Whether this is an integer type, or an integer whose type is not known yet.
+    pub fn is_integral(self) -> bool {
+        matches!(
+            self.kind(),
+            TyKind::Int(_) | TyKind::Uint(_) | TyKind::Infer(InferTy::IntVar(_))
+        )
+    }
+
+    /// Whether this is a float type, or a float whose type is not known yet.
+    pub fn is_floating_point(self) -> bool {
+        matches!(self.kind(), TyKind::Float(_) | TyKind::Infer(InferTy::FloatVar(_)))
+    }
+
+    pub fn is_numeric(self) -> bool {
+        self.is_integral() || self.is_floating_point()
+    }
+
+    pub fn is_ty_var(self) -> bool {
+        matches!(self.kind(), TyKind::Infer(InferTy::TyVar(_)))
+    }
+
+    pub fn is_error(self) -> bool {
+        matches!(self.kind(), TyKind::Error)
+    }
+
+    /// Whether values of this type are copied when they are used, rather than moved. Without
+    /// `Copy` impls, these are the types that own nothing: scalars, shared references,
+    /// functions, and tuples and arrays of them.
+    pub fn is_copy(self) -> bool {
+        match *self.kind() {
+            TyKind::Bool
+            | TyKind::Char
+            | TyKind::Int(_)
+            | TyKind::Uint(_)
+            | TyKind::Float(_)
+            | TyKind::Never
+            | TyKind::FnDef(..)
+            | TyKind::FnPtr(_)
+            | TyKind::Ref(_, _, Mutability::Not) => true,
+            TyKind::Tuple(tys) => tys.iter().all(|ty| ty.is_copy()),
+            TyKind::Array(ty, _) => ty.is_copy(),
+            TyKind::Ref(_, _, Mutability::Mut)
+            | TyKind::Str
+            | TyKind::Slice(_)
+            | TyKind::Adt(..)
+            | TyKind::Param(_)
+            | TyKind::Projection(_)
+            | TyKind::Infer(_)
+            | TyKind::Error => false,
+        }
+    }
+}
+
+impl PartialEq for Ty {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Ty {}
+
+impl Hash for Ty {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0, state)
+    }
+}
+
+impl fmt::Debug for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
+
+/// An interned list, compared by pointer like [`Ty`].
+pub struct List<T: 'static>(pub(super) &'static [T]);
+
+impl<T> List<T> {
+    pub fn empty() -> Self {
+        Self(&[])
+    }
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for List<T> {}
+
+impl<T> Deref for List<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.0
+    }
+}
+
+impl<T> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        // Empty slices can point anywhere, so all of them are the same list.
+        self.0.len() == other.0.len() && (self.0.is_empty() || ptr::eq(self.0, other.0))
+    }
+}
+
+impl<T> Eq for List<T> {}
+
+impl<T> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if !self.0.is_empty() {
+            ptr::hash(self.0, state)
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
+
+/// The generic arguments of an item: a type for each of its type parameters, at the index of
+/// the parameter, so those of its parents come first.
+pub type GenericArgs = List<Ty>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TyKind {
+    Bool,
+    Char,
+    Int(IntTy),
+    Uint(UintTy),
+    Float(FloatTy),
+    Str,
+    Never,
+    Ref(Region, Ty, Mutability),
+    /// A struct or enum, with its generic arguments.
+    Adt(DefId, GenericArgs),
+    /// The zero-sized type of a function item, with its generic arguments.
+    FnDef(DefId, GenericArgs),
+    FnPtr(FnSig),
+    /// `()` is the empty tuple.
+    Tuple(List<Ty>),
+    Array(Ty, Const),
+    Slice(Ty),
+    /// A type parameter of the item being checked.
+    Param(ParamTy),
+    /// A type that inference has not found yet.
+    Infer(InferTy),
+    /// `(T as Trait).Item`, which the trait solver normalizes to the type an impl gives it.
+    Projection(AliasTy),
+    /// A type that failed to check, which is already reported.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FnSig {
+    /// The argument types, followed by the return type.
+    pub inputs_and_output: List<Ty>,
+}
+
+impl FnSig {
+    pub fn inputs(&self) -> &[Ty] {
+        &self.inputs_and_output[..self.inputs_and_output.len() - 1]
+    }
+
+    pub fn output(&self) -> Ty {
+        self.inputs_and_output[self.inputs_and_output.len() - 1]
+    }
+}
+
+/// A type parameter, by its index among the type parameters of its item and its parents. `Self`
+/// is the first type parameter of a trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamTy {
+    pub index: u32,
+    pub name: Symbol,
+}
+
+define_index_type! {
+    pub struct TyVid = u32;
+}
+
+define_index_type! {
+    pub struct IntVid = u32;
+}
+
+define_index_type! {
+    pub struct FloatVid = u32;
+}
+
+define_index_type! {
+    pub struct RegionVid = u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InferTy {
+    TyVar(TyVid),
+    /// An integer literal, which falls back to `i32` if nothing else is inferred for it.
+    IntVar(IntVid),
+    /// A float literal, which falls back to `f64` if nothing else is inferred for it.
+    FloatVar(FloatVid),
+}
+
+/// An associated type of a trait, for the given arguments of the trait. The first argument is
+/// the self type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AliasTy {
+    /// The associated type.
+    pub def: DefId,
+    pub args: GenericArgs,
+}
+
+impl AliasTy {
+    pub fn self_ty(&self) -> Ty {
+        self.args[0]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Static,
+    /// A lifetime parameter, by its index among the lifetime parameters of its item and its
+    /// parents.
+    EarlyParam(EarlyParamRegion),
+    /// A region that inference has not found yet.
+    Var(RegionVid),
+    /// A region that doesn't matter anymore, after type checking.
+    Erased,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EarlyParamRegion {
+    pub index: u32,
+    pub name: Symbol,
+}
@@ -0,0 +1,68 @@
+use super::{AliasTy, GenericArgs, Ty, TypeFolder};
+use crate::{Cx, DefId};
+
+/// `Self: Trait[args]`, with the self type as the first argument, like [`AliasTy`] has it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraitRef {
+    pub def: DefId,
+    pub args: GenericArgs,
+}
+
+impl TraitRef {
+    pub fn self_ty(&self) -> Ty {
+        self.args[0]
+    }
+
+    pub fn fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> TraitRef {
+        TraitRef {
+            def: self.def,
+            args: self.args.fold_with(folder),
+        }
+    }
+}
+
+/// `(T as Trait).Item == term`, from a bound like `T: Trait[Item = term]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProjectionPredicate {
+    pub alias: AliasTy,
+    pub term: Ty,
+}
+
+/// What has to hold for an item to be used, written in its bounds and `where` clauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Predicate {
+    Trait(TraitRef),
+    Projection(ProjectionPredicate),
+}
+
+impl Predicate {
+    pub fn fold_with<F: TypeFolder + ?Sized>(self, folder: &mut F) -> Predicate {
+        match self {
+            Self::Trait(trait_ref) => Self::Trait(trait_ref.fold_with(folder)),
+            Self::Projection(projection) => Self::Projection(ProjectionPredicate {
+                alias: AliasTy {
+                    def: projection.alias.def,
+                    args: projection.alias.args.fold_with(folder),
+                },
+                term: folder.fold_ty(projection.term),
+            }),
+        }
+    }
+
+    /// Replaces each type parameter in this predicate with the argument at its index.
+    pub fn subst(self, cx: &Cx, args: &[Ty]) -> Predicate {
+        match self {
+            Self::Trait(trait_ref) => Self::Trait(TraitRef {
+                def: trait_ref.def,
+                args: trait_ref.args.subst(cx, args),
+            }),
+            Self::Projection(projection) => Self::Projection(ProjectionPredicate {
+                alias: AliasTy {
+                    def: projection.alias.def,
+                    args: projection.alias.args.subst(cx, args),
+                },
+                term: projection.term.subst(cx, args),
+            }),
+        }
+    }
+}
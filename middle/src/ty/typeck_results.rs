@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use super::{GenericArgs, Ty};
+use crate::{
+    hir::{ItemLocalId, Mutability},
+    DefId,
+};
+
+/// What type checking found out about a body, by the local ids of its nodes.
+#[derive(Debug, Clone, Default)]
+pub struct TypeckResults {
+    /// The type of each expression, block and pattern, before adjustments. Types passed to
+    /// type parameters as the first arguments of a call are not values, and have none.
+    pub node_types: HashMap<ItemLocalId, Ty>,
+    /// The method each method call, and the associated function each `Type.function`, resolved
+    /// to, with the generic arguments it is instantiated with.
+    pub type_dependent_defs: HashMap<ItemLocalId, (DefId, GenericArgs)>,
+    /// The type each expression naming a type stands for, like the operands of `A == B` in a
+    /// function over types, or the types passed to type parameters as arguments of a call.
+    pub type_exprs: HashMap<ItemLocalId, Ty>,
+    /// The index of the field each field access reads, in its struct or tuple.
+    pub field_indices: HashMap<ItemLocalId, usize>,
+    /// The coercions and autoderefs applied to expressions, in order.
+    pub adjustments: HashMap<ItemLocalId, Vec<Adjustment>>,
+}
+
+impl TypeckResults {
+    pub fn node_type(&self, id: ItemLocalId) -> Ty {
+        self.node_types[&id]
+    }
+
+    /// The type of the expression `id` after its adjustments.
+    pub fn expr_ty_adjusted(&self, id: ItemLocalId) -> Ty {
+        self.adjustments
+            .get(&id)
+            .and_then(|adjustments| adjustments.last())
+            .map_or_else(|| self.node_type(id), |adjustment| adjustment.target)
+    }
+}
+
+/// One step in turning the value of an expression into the value it is used as.
+#[derive(Debug, Clone, Copy)]
+pub struct Adjustment {
+    pub kind: Adjust,
+    /// The type after this step.
+    pub target: Ty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjust {
+    /// `!` becomes any type, since it has no values.
+    NeverToAny,
+    /// Dereferences a reference, like the receiver of a method is.
+    Deref,
+    /// Borrows the place, like the receiver of a method taking `&self` is. `&mut T` coerces to
+    /// `&T` by a deref, then a shared borrow.
+    Borrow(Mutability),
+}
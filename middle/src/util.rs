@@ -1 +1,2 @@
+pub mod edit_distance;
 pub mod explode;
@@ -0,0 +1,83 @@
+use crate::symbol::Symbol;
+
+/// The Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate closest to `lookup`, for "did you mean" suggestions. Candidates that differ
+/// only in case always match, others only if they are at most a third of `lookup` away.
+pub fn find_best_match(candidates: impl IntoIterator<Item = Symbol>, lookup: Symbol) -> Option<Symbol> {
+    let lookup_str = lookup.as_str();
+    let max_distance = (lookup_str.chars().count() / 3).max(1);
+
+    let mut best = None;
+
+    for candidate in candidates {
+        if candidate == lookup {
+            continue;
+        }
+
+        let candidate_str = candidate.as_str();
+
+        if candidate_str.eq_ignore_ascii_case(lookup_str) {
+            return Some(candidate);
+        }
+
+        let distance = edit_distance(candidate_str, lookup_str);
+        if distance <= max_distance && best.is_none_or(|(_, best)| distance < best) {
+            best = Some((candidate, distance));
+        }
+    }
+
+    best.map(|(candidate, _)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("inline", "inlnie"), 2);
+        // counted in chars, not bytes
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn best_matches() {
+        let candidates = || ["inline", "cold", "deprecated"].map(Symbol::intern);
+
+        assert_eq!(find_best_match(candidates(), Symbol::intern("inlin")), Some(Symbol::intern("inline")));
+        assert_eq!(find_best_match(candidates(), Symbol::intern("Cold")), Some(Symbol::intern("cold")));
+        assert_eq!(find_best_match(candidates(), Symbol::intern("hot")), None);
+        // an exact match is not a suggestion
+        assert_eq!(find_best_match(candidates(), Symbol::intern("cold")), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_candidate() {
+        let [abc, abd] = ["abc", "abd"].map(Symbol::intern);
+
+        assert_eq!(find_best_match([abc, abd], Symbol::intern("abx")), Some(abc));
+        assert_eq!(find_best_match([abd, abc], Symbol::intern("abx")), Some(abd));
+    }
+}
@@ -0,0 +1,1754 @@
+//! Type checking of bodies. Every expression gets a type, with variables for what is not known
+//! yet, and the types that have to be the same are unified as they are found. Literals without
+//! a suffix fall back to `i32` and `f64` at the end, if nothing else was inferred for them.
+
+use std::collections::HashMap;
+
+use tangic_middle::{
+    hir::{self, BinOp, DefKind, HirId, Intrinsic, ItemLocalId, Mutability, Res, UnOp},
+    span::Span,
+    symbol::{Ident, Symbol},
+    ty::{
+        Adjust, Adjustment, Const, FnSig, GenericParamDefKind, InferTy, Predicate, Region, ScalarInt,
+        TraitRef, Ty, TyKind, TypeckResults, ValTree,
+    },
+    DefId, DefIndex,
+};
+
+use crate::{
+    collect::ItemTypes,
+    eval,
+    infer::InferCtxt,
+    lower::{describe, TyLowerer},
+    traits::{self, FulfillmentCtxt, Obligation, SelectionCtxt},
+    TypeckError,
+};
+
+/// Checks the body of the function `def`, binding its parameters to the inputs of `sig`.
+pub(crate) fn check_fn(
+    items: &ItemTypes,
+    def: DefIndex,
+    span: Span,
+    hir_sig: &hir::FnSig,
+    generics: &hir::Generics,
+    body: &hir::Body,
+    errors: &mut Vec<TypeckError>,
+) -> TypeckResults {
+    let cx = items.cx;
+    let sig = items.fn_sigs.get(&def).copied().unwrap_or_else(|| cx.mk_fn_sig(&[], cx.types.error));
+
+    if body.params.len() != sig.inputs().len() {
+        let at = match (body.params.first(), body.params.last()) {
+            (Some(first), Some(last)) => first.span.to(last.span),
+            _ => span,
+        };
+
+        errors.push(TypeckError::ParamCount {
+            name: items.name(def).unwrap_or_else(|| Symbol::intern("<unnamed>")),
+            expected: sig.inputs().len(),
+            found: body.params.len(),
+            at: at.range().into(),
+            file: at.file,
+        });
+    }
+
+    let origin = Origin {
+        span: hir_sig.output.span,
+        reason: "expected because of the return type",
+    };
+
+    let mut fcx = FnCtxt::new(items, def, (sig.output(), origin), errors);
+    let sig = fcx.normalize_sig(sig, span);
+    fcx.ret.0 = sig.output();
+    fcx.check_where_exprs(generics);
+
+    for (i, param) in body.params.iter().enumerate() {
+        let ty = sig.inputs().get(i).copied().unwrap_or(cx.types.error);
+        fcx.check_pat(param, ty);
+    }
+
+    fcx.check_expr_coercible_to_type(&body.value, sig.output(), Some(origin));
+    fcx.finish()
+}
+
+/// Checks the value of a const or static, whose type is written as `hir_ty`.
+pub(crate) fn check_const(
+    items: &ItemTypes,
+    def: DefIndex,
+    hir_ty: &hir::Ty,
+    body: &hir::Body,
+    errors: &mut Vec<TypeckError>,
+) -> TypeckResults {
+    let ty = items.types.get(&def).copied().unwrap_or(items.cx.types.error);
+    let origin = Origin {
+        span: hir_ty.span,
+        reason: "expected due to this",
+    };
+
+    let mut fcx = FnCtxt::new(items, def, (ty, origin), errors);
+    fcx.check_expr_coercible_to_type(&body.value, ty, Some(origin));
+    fcx.finish()
+}
+
+/// Checks the `where` clauses of `def` that are expressions, for items without a body to check
+/// them with, like traits.
+pub(crate) fn check_where_clauses(
+    items: &ItemTypes,
+    def: DefIndex,
+    generics: &hir::Generics,
+    span: Span,
+    errors: &mut Vec<TypeckError>,
+) -> TypeckResults {
+    let origin = Origin {
+        span,
+        reason: "expected due to this",
+    };
+
+    let mut fcx = FnCtxt::new(items, def, (items.cx.types.bool, origin), errors);
+    fcx.check_where_exprs(generics);
+    fcx.finish()
+}
+
+/// An associated function a path or method call may be to.
+#[derive(Debug, Clone, Copy)]
+enum Candidate {
+    Inherent { def: DefIndex, impl_def: DefIndex },
+    Trait { def: DefIndex, trait_def: DefIndex },
+}
+
+impl Candidate {
+    fn def(self) -> DefIndex {
+        match self {
+            Self::Inherent { def, .. } | Self::Trait { def, .. } => def,
+        }
+    }
+}
+
+/// Where an expected type comes from, to point at in mismatches.
+#[derive(Debug, Clone, Copy)]
+struct Origin {
+    span: Span,
+    reason: &'static str,
+}
+
+/// A value bound with `@` and again by the pattern after it. Unless the value is copied, none
+/// of the bindings may move it.
+struct BoundTwice {
+    ty: Ty,
+    span: Span,
+    by_ref: bool,
+    /// The types of the bindings after the `@`, or `None` for those by reference.
+    bindings: Vec<(Option<Ty>, Span)>,
+}
+
+struct FnCtxt<'a, 'b> {
+    items: &'b ItemTypes<'a>,
+    infcx: InferCtxt<'a>,
+    /// The type of each local, by the pattern that binds it.
+    locals: HashMap<HirId, Ty>,
+    /// What `return` coerces its value to.
+    ret: (Ty, Origin),
+    /// For each loop the expression being checked is in, innermost last, whether it has a
+    /// `break`, without which it never ends.
+    loops: Vec<bool>,
+    results: TypeckResults,
+    /// Where each node with a type is, in the order they were checked, to point at the first
+    /// one whose type could not be inferred.
+    spans: Vec<(ItemLocalId, Span)>,
+    /// Integers that were negated before their type was known, which must not turn out to be
+    /// unsigned.
+    negated_ints: Vec<(Ty, Span)>,
+    bound_twice: Vec<BoundTwice>,
+    /// The predicates that hold in the body, from the bounds and `where` clauses of the item.
+    param_env: Vec<Predicate>,
+    fulfillment: FulfillmentCtxt,
+    errors: &'b mut Vec<TypeckError>,
+    /// How many errors there were before this body, to tell if it has any.
+    prior_errors: usize,
+}
+
+impl<'a> TyLowerer<'a> for FnCtxt<'a, '_> {
+    fn items(&self) -> &ItemTypes<'a> {
+        self.items
+    }
+
+    fn type_of(&mut self, def: DefIndex, _span: Span) -> Ty {
+        // every alias and impl is lowered while collecting, cycles are reported there
+        self.items.types.get(&def).copied().unwrap_or(self.items.cx.types.error)
+    }
+
+    fn re_infer(&mut self) -> Region {
+        self.infcx.next_region_var()
+    }
+
+    fn ty_infer(&mut self) -> Option<Ty> {
+        Some(self.infcx.next_ty_var())
+    }
+
+    fn report(&mut self, error: TypeckError) {
+        self.errors.push(error);
+    }
+}
+
+impl<'a, 'b> FnCtxt<'a, 'b> {
+    fn new(items: &'b ItemTypes<'a>, def: DefIndex, ret: (Ty, Origin), errors: &'b mut Vec<TypeckError>) -> Self {
+        Self {
+            items,
+            infcx: InferCtxt::new(items.cx),
+            locals: HashMap::new(),
+            ret,
+            loops: vec![],
+            results: TypeckResults::default(),
+            spans: vec![],
+            negated_ints: vec![],
+            bound_twice: vec![],
+            param_env: traits::elaborate(items, items.predicates_of(def)),
+            fulfillment: FulfillmentCtxt::default(),
+            prior_errors: errors.len(),
+            errors,
+        }
+    }
+
+    fn selcx(&mut self) -> SelectionCtxt<'a, '_> {
+        SelectionCtxt {
+            items: self.items,
+            infcx: &mut self.infcx,
+            param_env: &self.param_env,
+        }
+    }
+
+    /// `ty` with the projections in it normalized, as far as they can be yet.
+    fn normalize(&mut self, ty: Ty, span: Span) -> Ty {
+        let mut obligations = vec![];
+        let ty = traits::normalize(&mut self.selcx(), ty, span, &mut obligations);
+
+        for obligation in obligations {
+            self.fulfillment.register(obligation);
+        }
+
+        ty
+    }
+
+    fn normalize_sig(&mut self, sig: FnSig, span: Span) -> FnSig {
+        let tys: Vec<_> = sig
+            .inputs_and_output
+            .iter()
+            .map(|&ty| self.normalize(ty, span))
+            .collect();
+
+        let (&output, inputs) = tys.split_last().expect("signatures have an output");
+        self.items.cx.mk_fn_sig(inputs, output)
+    }
+
+    /// Requires the predicates of `def` to hold for `args`, where it is used at `span`.
+    fn register_predicates(&mut self, def: DefIndex, args: &[Ty], span: Span) {
+        let cx = self.items.cx;
+
+        for predicate in self.items.predicates_of(def) {
+            self.fulfillment.register(Obligation {
+                predicate: predicate.subst(cx, args),
+                span,
+            });
+        }
+    }
+
+    /// Proves the obligations that can be proven so far, which may infer types.
+    fn select_obligations_where_possible(&mut self) {
+        let items = self.items;
+        let errors = self.fulfillment.select_where_possible(&mut SelectionCtxt {
+            items,
+            infcx: &mut self.infcx,
+            param_env: &self.param_env,
+        });
+
+        traits::report_fulfillment_errors(items, &self.infcx, errors, false, self.errors);
+    }
+
+    /// Whether the trait `trait_def` holds or might hold for `self_ty`, with the rest of its
+    /// arguments from `args`.
+    fn trait_may_hold(&mut self, trait_def: DefIndex, args: &[Ty], self_ty: Ty) -> bool {
+        let cx = self.items.cx;
+        let count = self.items.generics_of(trait_def).type_count as usize;
+        let mut args = args[..count].to_vec();
+        args[0] = self_ty;
+
+        self.selcx().may_hold(TraitRef {
+            def: DefId::local(trait_def),
+            args: cx.mk_args(&args),
+        })
+    }
+
+    fn write_ty(&mut self, id: HirId, span: Span, ty: Ty) {
+        self.results.node_types.insert(id.local_id, ty);
+        self.spans.push((id.local_id, span));
+    }
+
+    fn apply_adjustments(&mut self, id: HirId, adjustments: Vec<Adjustment>) {
+        if !adjustments.is_empty() {
+            self.results
+                .adjustments
+                .entry(id.local_id)
+                .or_default()
+                .extend(adjustments);
+        }
+    }
+
+    /// What `ty` is, which has to be known at `span`, like the type of a receiver is to find
+    /// its methods.
+    fn structurally_resolve(&mut self, ty: Ty, span: Span) -> Ty {
+        let mut ty = self.infcx.shallow_resolve(ty);
+
+        // it may be what a projection normalizes to, once an impl is selected
+        if ty.is_ty_var() {
+            self.select_obligations_where_possible();
+            ty = self.infcx.shallow_resolve(ty);
+        }
+
+        if !ty.is_ty_var() {
+            return ty;
+        }
+
+        self.errors.push(TypeckError::AnnotationsNeeded {
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        self.items.cx.types.error
+    }
+
+    /// Checks that the `where` clauses in `generics` that are expressions are `bool`s, which are
+    /// evaluated where they have to hold.
+    fn check_where_exprs(&mut self, generics: &hir::Generics) {
+        for predicate in &generics.predicates {
+            if let hir::WherePredicate::Expr(expr) = predicate {
+                self.check_expr_coercible_to_type(expr, self.items.cx.types.bool, None);
+            }
+        }
+    }
+
+    /// Fresh variables for the type parameters of `def`, with those of its parents.
+    fn fresh_args(&mut self, def: DefIndex) -> Vec<Ty> {
+        let count = self.items.type_params(def).len();
+        (0..count).map(|_| self.infcx.next_ty_var()).collect()
+    }
+
+    fn check_expr(&mut self, expr: &hir::Expr) -> Ty {
+        self.check_expr_with_expectation(expr, None)
+    }
+
+    fn check_expr_coercible_to_type(&mut self, expr: &hir::Expr, expected: Ty, origin: Option<Origin>) -> Ty {
+        let ty = self.check_expr_with_expectation(expr, Some(expected));
+        self.coerce(expr.hir_id, tail_span(expr), ty, expected, origin, None);
+
+        expected
+    }
+
+    /// Checks `expr`, using `expected` for what can't be known from the expression itself, like
+    /// the type of an integer literal.
+    fn check_expr_with_expectation(&mut self, expr: &hir::Expr, expected: Option<Ty>) -> Ty {
+        let ty = self.check_expr_kind(expr, expected);
+        self.write_ty(expr.hir_id, expr.span, ty);
+
+        ty
+    }
+
+    fn check_expr_kind(&mut self, expr: &hir::Expr, expected: Option<Ty>) -> Ty {
+        let cx = self.items.cx;
+        let expected = expected.map(|ty| self.infcx.shallow_resolve(ty));
+
+        match &expr.kind {
+            hir::ExprKind::Lit(lit) => self.check_lit(lit, expected),
+            hir::ExprKind::Path(qpath) => self.check_path(qpath, expr.span),
+            hir::ExprKind::Tuple(exprs) => {
+                let expected_tys = match expected.map(|ty| ty.kind()) {
+                    Some(TyKind::Tuple(tys)) if tys.len() == exprs.len() => Some(*tys),
+                    _ => None,
+                };
+
+                let tys: Vec<_> = exprs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, expr)| {
+                        self.check_expr_with_expectation(expr, expected_tys.map(|tys| tys[i]))
+                    })
+                    .collect();
+
+                cx.mk_tuple(&tys)
+            }
+            hir::ExprKind::Block(block) => self.check_block(block, expected),
+            hir::ExprKind::Let(local) => {
+                self.check_local(local);
+                cx.types.unit
+            }
+            hir::ExprKind::Field(base, name) => self.check_field(expr, base, *name),
+            hir::ExprKind::Call(callee, args) => self.check_call(expr, callee, args),
+            hir::ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => self.check_method_call(expr, receiver, method, args),
+            hir::ExprKind::Unary(op, operand) => self.check_unary(*op, operand, expected),
+            hir::ExprKind::AddrOf(mutability, inner) => {
+                let expected_inner = match expected.map(|ty| ty.kind()) {
+                    Some(&TyKind::Ref(_, inner, _)) => Some(inner),
+                    _ => None,
+                };
+
+                let inner = self.check_expr_with_expectation(inner, expected_inner);
+                let region = self.infcx.next_region_var();
+
+                cx.mk_ref(region, inner, *mutability)
+            }
+            hir::ExprKind::Binary(op, lhs, rhs) => self.check_binary(*op, lhs, rhs, expected),
+            hir::ExprKind::Assign(place, value) => {
+                if !is_place(place) {
+                    self.errors.push(TypeckError::InvalidAssign {
+                        at: place.span.range().into(),
+                        file: place.span.file,
+                    });
+                }
+
+                let place_ty = self.check_expr(place);
+                let origin = Origin {
+                    span: place.span,
+                    reason: "expected due to the type of this",
+                };
+
+                self.check_expr_coercible_to_type(value, place_ty, Some(origin));
+                cx.types.unit
+            }
+            hir::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => self.check_if(condition, then, otherwise.as_deref(), expected),
+            hir::ExprKind::Return(value) => {
+                let (ret, origin) = self.ret;
+                self.check_expr_coercible_to_type(value, ret, Some(origin));
+
+                cx.types.never
+            }
+            hir::ExprKind::Loop(body) => {
+                self.loops.push(false);
+                self.check_block(body, None);
+
+                match self.loops.pop() {
+                    Some(true) => cx.types.unit,
+                    _ => cx.types.never,
+                }
+            }
+            hir::ExprKind::Break => {
+                // lowering reports those outside of a loop
+                if let Some(broken) = self.loops.last_mut() {
+                    *broken = true;
+                }
+
+                cx.types.never
+            }
+            hir::ExprKind::Continue => cx.types.never,
+            hir::ExprKind::Intrinsic(intrinsic, args) => self.check_intrinsic(expr.span, *intrinsic, args),
+            hir::ExprKind::Err => cx.types.error,
+        }
+    }
+
+    fn check_lit(&mut self, lit: &hir::Lit, expected: Option<Ty>) -> Ty {
+        let cx = self.items.cx;
+
+        match *lit {
+            hir::Lit::Int(_, Some(prim)) => cx.mk_prim(prim),
+            hir::Lit::Int(_, None) => match expected {
+                Some(ty) if ty.is_integral() => ty,
+                _ => self.infcx.next_int_var(),
+            },
+            hir::Lit::Float(_, Some(float)) => cx.mk_prim(hir::PrimTy::Float(float)),
+            hir::Lit::Float(_, None) => match expected {
+                Some(ty) if ty.is_floating_point() => ty,
+                _ => self.infcx.next_float_var(),
+            },
+            hir::Lit::Str(_) => cx.mk_ref(Region::Static, cx.types.str, Mutability::Not),
+            hir::Lit::ByteStr(ref bytes) => {
+                let len = ScalarInt::try_from_target_usize(bytes.len() as u128, cx)
+                    .expect("byte strings fit in `usize`");
+                let array = cx.mk_array(cx.types.u8, Const::Value(ValTree::Leaf(len)));
+
+                cx.mk_ref(Region::Static, array, Mutability::Not)
+            }
+            hir::Lit::Char(_) => cx.types.char,
+            hir::Lit::Byte(_) => cx.types.u8,
+            hir::Lit::Bool(_) => cx.types.bool,
+        }
+    }
+
+    /// The type of a path used as a value.
+    fn check_path(&mut self, qpath: &hir::QPath, span: Span) -> Ty {
+        let cx = self.items.cx;
+
+        let path = match qpath {
+            hir::QPath::Resolved(path) => path,
+            hir::QPath::TypeRelative(base, segment) => {
+                let self_ty = self.lower_ty(base);
+                let self_ty = self.normalize(self_ty, base.span);
+                return self.check_assoc_fn(self_ty, segment.name);
+            }
+        };
+
+        let last = path.segments.last().expect("paths have a segment");
+
+        match path.res {
+            Res::Local(id) => self.locals.get(&id).copied().unwrap_or(cx.types.error),
+            Res::Def(DefKind::Fn | DefKind::AssocFn, def) => {
+                let mut args = self.fresh_args(def.index);
+                let generics = self.items.generics_of(def.index);
+                let parent_count = generics.parent_type_count() as usize;
+
+                if !last.args.is_empty() {
+                    let own = self.lower_generic_args(def.index, last, path.span);
+                    args.truncate(parent_count);
+                    args.extend(own);
+                }
+
+                self.register_predicates(def.index, &args, span);
+                cx.mk_fn_def(def, &args)
+            }
+            Res::Def(DefKind::Const | DefKind::Static | DefKind::ConstParam, def) => {
+                self.items.types.get(&def.index).copied().unwrap_or(cx.types.error)
+            }
+            Res::Def(DefKind::Variant, def) => {
+                let Some(&enum_def) = self.items.parents.get(&def.index) else {
+                    return cx.types.error;
+                };
+
+                let args = self.fresh_args(enum_def);
+                self.register_predicates(enum_def, &args, span);
+
+                if let Some(ty) = self.items.types.get(&def.index) {
+                    ty.subst(cx, &args)
+                } else if self.items.fn_sigs.contains_key(&def.index) {
+                    cx.mk_fn_def(def, &args)
+                } else {
+                    self.report_unsupported("struct variants", span)
+                }
+            }
+            Res::Def(kind, _) => {
+                self.errors.push(TypeckError::ExpectedValue {
+                    found: format!("{} `{}`", describe(kind), last.name),
+                    at: span.range().into(),
+                    file: span.file,
+                });
+
+                cx.types.error
+            }
+            Res::SelfTy(_) => {
+                self.errors.push(TypeckError::ExpectedValue {
+                    found: "self type `Self`".to_owned(),
+                    at: span.range().into(),
+                    file: span.file,
+                });
+
+                cx.types.error
+            }
+            Res::Err => cx.types.error,
+        }
+    }
+
+    /// The type `expr` names, if it is a path to one, like the `Point` of `Point.new`.
+    fn expr_as_ty(&mut self, expr: &hir::Expr) -> Option<Ty> {
+        match &expr.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(
+                path @ hir::Path {
+                    res:
+                        Res::Def(DefKind::Struct | DefKind::Enum | DefKind::TypeAlias | DefKind::TyParam, _)
+                        | Res::SelfTy(_),
+                    ..
+                },
+            )) => {
+                let ty = self.lower_path(path);
+                let ty = self.normalize(ty, expr.span);
+                self.results.type_exprs.insert(expr.hir_id.local_id, ty);
+
+                Some(ty)
+            }
+            _ => None,
+        }
+    }
+
+    /// `Type.name`, an associated function of an impl of `self_ty`, or of a trait it
+    /// implements.
+    fn check_assoc_fn(&mut self, self_ty: Ty, name: Ident) -> Ty {
+        let cx = self.items.cx;
+
+        if self_ty.is_error() {
+            return self_ty;
+        }
+
+        for candidate in self.assoc_fn_candidates(name.name, false) {
+            let def = candidate.def();
+            let args = self.fresh_args(def);
+
+            let Some(candidate_ty) = self.candidate_self_ty(candidate, &args, self_ty) else {
+                continue;
+            };
+
+            if self.infcx.unify(candidate_ty, self_ty).is_ok() {
+                self.register_predicates(def, &args, name.span);
+                return cx.mk_fn_def(DefId::local(def), &args);
+            }
+        }
+
+        self.errors.push(TypeckError::NoAssocItem {
+            name: name.name,
+            ty: self.ty_to_string(self_ty),
+            at: name.span.range().into(),
+            file: name.span.file,
+        });
+
+        cx.types.error
+    }
+
+    fn check_field(&mut self, expr: &hir::Expr, base: &hir::Expr, name: Ident) -> Ty {
+        let cx = self.items.cx;
+
+        if let Some(self_ty) = self.expr_as_ty(base) {
+            let ty = self.check_assoc_fn(self_ty, name);
+
+            if let &TyKind::FnDef(def, args) = ty.kind() {
+                self.results.type_dependent_defs.insert(expr.hir_id.local_id, (def, args));
+            }
+
+            return ty;
+        }
+
+        let base_ty = self.check_expr(base);
+        let base_ty = self.structurally_resolve(base_ty, base.span);
+        let steps = self.autoderef_steps(base_ty);
+
+        for (i, &step) in steps.iter().enumerate() {
+            let found = match *step.kind() {
+                TyKind::Adt(def, args) => self.items.fields.get(&def.index).and_then(|fields| {
+                    let index = fields.iter().position(|&(field, _)| field == name.name)?;
+                    Some((index, fields[index].1.subst(cx, &args)))
+                }),
+                TyKind::Tuple(tys) => name
+                    .as_str()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| Some((index, *tys.get(index)?))),
+                TyKind::Error => return step,
+                _ => None,
+            };
+
+            if let Some((index, ty)) = found {
+                self.results.field_indices.insert(expr.hir_id.local_id, index);
+                self.apply_adjustments(base.hir_id, deref_adjustments(&steps[..=i]));
+
+                return self.normalize(ty, name.span);
+            }
+        }
+
+        self.errors.push(TypeckError::NoField {
+            name: name.name,
+            ty: self.ty_to_string(base_ty),
+            at: name.span.range().into(),
+            file: name.span.file,
+        });
+
+        cx.types.error
+    }
+
+    /// `ty`, then what it dereferences to, and so on, until it's not a reference.
+    fn autoderef_steps(&self, ty: Ty) -> Vec<Ty> {
+        let mut steps = vec![];
+        let mut ty = self.infcx.shallow_resolve(ty);
+
+        loop {
+            steps.push(ty);
+
+            match *ty.kind() {
+                TyKind::Ref(_, inner, _) => ty = self.infcx.shallow_resolve(inner),
+                _ => return steps,
+            }
+        }
+    }
+
+    fn check_call(&mut self, expr: &hir::Expr, callee: &hir::Expr, args: &[hir::Expr]) -> Ty {
+        let cx = self.items.cx;
+        let callee_ty = self.check_expr(callee);
+        let callee_ty = self.structurally_resolve(callee_ty, callee.span);
+
+        let (sig, def, fn_args) = match *callee_ty.kind() {
+            TyKind::FnDef(def, fn_args) => match self.items.fn_sigs.get(&def.index) {
+                Some(sig) => {
+                    let sig = self.normalize_sig(sig.subst(cx, &fn_args), callee.span);
+                    (sig, Some(def.index), fn_args.to_vec())
+                }
+                None => return self.check_args_erroneous(args),
+            },
+            TyKind::FnPtr(sig) => (sig, None, vec![]),
+            TyKind::Error => return self.check_args_erroneous(args),
+            _ => {
+                self.errors.push(TypeckError::NotCallable {
+                    ty: self.ty_to_string(callee_ty),
+                    at: callee.span.range().into(),
+                    file: callee.span.file,
+                });
+
+                return self.check_args_erroneous(args);
+            }
+        };
+
+        self.check_args(expr.span, callee.span, def, &fn_args, sig.inputs(), args);
+        sig.output()
+    }
+
+    fn check_method_call(
+        &mut self,
+        expr: &hir::Expr,
+        receiver: &hir::Expr,
+        method: &hir::PathSegment,
+        args: &[hir::Expr],
+    ) -> Ty {
+        let cx = self.items.cx;
+
+        // `Type.function args` calls an associated function
+        if let Some(self_ty) = self.expr_as_ty(receiver) {
+            let callee_ty = self.check_assoc_fn(self_ty, method.name);
+
+            let TyKind::FnDef(def, fn_args) = *callee_ty.kind() else {
+                return self.check_args_erroneous(args);
+            };
+
+            self.results.type_dependent_defs.insert(expr.hir_id.local_id, (def, fn_args));
+
+            let sig = self.items.fn_sigs[&def.index].subst(cx, &fn_args);
+            let sig = self.normalize_sig(sig, method.span);
+            self.check_args(expr.span, method.span, Some(def.index), &fn_args, sig.inputs(), args);
+
+            return sig.output();
+        }
+
+        let receiver_ty = self.check_expr(receiver);
+        let receiver_ty = self.structurally_resolve(receiver_ty, receiver.span);
+
+        if receiver_ty.is_error() {
+            return self.check_args_erroneous(args);
+        }
+
+        let Some((def, fn_args, sig)) = self.lookup_method(receiver, receiver_ty, method.name) else {
+            self.errors.push(TypeckError::NoMethod {
+                name: method.name.name,
+                ty: self.ty_to_string(receiver_ty),
+                at: method.span.range().into(),
+                file: method.span.file,
+            });
+
+            return self.check_args_erroneous(args);
+        };
+
+        self.results
+            .type_dependent_defs
+            .insert(expr.hir_id.local_id, (DefId::local(def), cx.mk_args(&fn_args)));
+
+        self.check_args(expr.span, method.span, Some(def), &fn_args, &sig.inputs()[1..], args);
+        sig.output()
+    }
+
+    /// The method named `name` of an inherent impl of the type of `receiver` or of what it
+    /// dereferences to, or of a trait it implements, with the generic arguments it is called
+    /// with and its signature with those. The receiver is adjusted to the type the method takes
+    /// `self` as.
+    fn lookup_method(
+        &mut self,
+        receiver: &hir::Expr,
+        receiver_ty: Ty,
+        name: Ident,
+    ) -> Option<(DefIndex, Vec<Ty>, FnSig)> {
+        let cx = self.items.cx;
+        let steps = self.autoderef_steps(receiver_ty);
+        let candidates = self.assoc_fn_candidates(name.name, true);
+
+        for (i, &step) in steps.iter().enumerate() {
+            if step.is_ty_var() {
+                break;
+            }
+
+            for &candidate in &candidates {
+                let def = candidate.def();
+                let fn_args = self.fresh_args(def);
+
+                let Some(candidate_ty) = self.candidate_self_ty(candidate, &fn_args, step) else {
+                    continue;
+                };
+
+                let sig = self.items.fn_sigs[&def].subst(cx, &fn_args);
+
+                let autoref = self.infcx.commit_if_ok(|infcx| {
+                    infcx.unify(candidate_ty, step)?;
+
+                    // `self`, `&self` or `&mut self`
+                    match *infcx.shallow_resolve(sig.inputs()[0]).kind() {
+                        TyKind::Ref(region, inner, mutability) => {
+                            infcx.unify(inner, step)?;
+                            Ok(Some((region, mutability)))
+                        }
+                        _ => infcx.unify(sig.inputs()[0], step).map(|()| None),
+                    }
+                });
+
+                let Ok(autoref) = autoref else {
+                    continue;
+                };
+
+                let mut adjustments = deref_adjustments(&steps[..=i]);
+
+                if let Some((region, mutability)) = autoref {
+                    adjustments.push(Adjustment {
+                        kind: Adjust::Borrow(mutability),
+                        target: cx.mk_ref(region, step, mutability),
+                    });
+                }
+
+                self.apply_adjustments(receiver.hir_id, adjustments);
+                self.register_predicates(def, &fn_args, name.span);
+
+                let sig = self.normalize_sig(sig, name.span);
+                return Some((def, fn_args, sig));
+            }
+        }
+
+        None
+    }
+
+    /// The associated functions named `name`, or only the methods if `methods`, of inherent
+    /// impls and then of traits.
+    fn assoc_fn_candidates(&self, name: Symbol, methods: bool) -> Vec<Candidate> {
+        let items = self.items;
+        let find = |parent: DefIndex| {
+            items
+                .find_assoc_item(parent, name)
+                .filter(|assoc| matches!(assoc.kind, hir::AssocItemKind::Fn(..)))
+                .map(|assoc| assoc.hir_id.owner)
+                .filter(|def| !methods || items.methods.contains(def))
+        };
+
+        let inherent = items.inherent_impls.iter().filter_map(|&impl_def| {
+            find(impl_def).map(|def| Candidate::Inherent { def, impl_def })
+        });
+
+        let traits = items.traits.iter().filter_map(|&trait_def| {
+            find(trait_def).map(|def| Candidate::Trait { def, trait_def })
+        });
+
+        inherent.chain(traits).collect()
+    }
+
+    /// The type `candidate` is an associated function of, for `args`, which `self_ty` has to
+    /// be, or `None` if its trait can't hold for `self_ty`.
+    fn candidate_self_ty(&mut self, candidate: Candidate, args: &[Ty], self_ty: Ty) -> Option<Ty> {
+        match candidate {
+            Candidate::Inherent { impl_def, .. } => Some(self.items.types[&impl_def].subst(self.items.cx, args)),
+            Candidate::Trait { trait_def, .. } => {
+                // `Self` is the first parameter of a trait
+                self.trait_may_hold(trait_def, args, self_ty).then_some(args[0])
+            }
+        }
+    }
+
+    /// Checks the arguments of a call to `inputs`. Functions with generic parameters take their
+    /// types and constants first, as arguments too, unless all of them are left to infer.
+    fn check_args(
+        &mut self,
+        call_span: Span,
+        callee_span: Span,
+        def: Option<DefIndex>,
+        fn_args: &[Ty],
+        inputs: &[Ty],
+        args: &[hir::Expr],
+    ) {
+        let cx = self.items.cx;
+
+        let generic_params: Vec<_> = def
+            .iter()
+            .flat_map(|&def| &self.items.generics_of(def).params)
+            .filter(|param| param.kind != GenericParamDefKind::Lifetime)
+            .copied()
+            .collect();
+
+        let args = if !generic_params.is_empty() && args.len() == generic_params.len() + inputs.len() {
+            let (generic_args, args) = args.split_at(generic_params.len());
+
+            for (param, arg) in generic_params.iter().zip(generic_args) {
+                match param.kind {
+                    GenericParamDefKind::Type => {
+                        let Some(ty) = self.expr_as_ty(arg) else {
+                            self.report_expected_type(arg);
+                            continue;
+                        };
+
+                        let param_ty = fn_args[param.index as usize];
+
+                        if self.infcx.unify(param_ty, ty).is_err() {
+                            self.report_mismatch(arg.span, param_ty, ty, None, None);
+                        }
+                    }
+                    GenericParamDefKind::Const => {
+                        let ty = self.items.types.get(&param.def.index).copied();
+                        let ty = ty.map_or(cx.types.error, |ty| ty.subst(cx, fn_args));
+
+                        self.check_expr_coercible_to_type(arg, ty, None);
+                    }
+                    GenericParamDefKind::Lifetime => {}
+                }
+            }
+
+            args
+        } else {
+            args
+        };
+
+        if args.len() != inputs.len() {
+            self.errors.push(TypeckError::ArgCount {
+                expected: inputs.len(),
+                found: args.len(),
+                at: call_span.range().into(),
+                file: call_span.file,
+            });
+        }
+
+        let origin = Origin {
+            span: callee_span,
+            reason: "arguments to this function are incorrect",
+        };
+
+        for (i, arg) in args.iter().enumerate() {
+            match inputs.get(i) {
+                Some(&input) => {
+                    self.check_expr_coercible_to_type(arg, input, Some(origin));
+                }
+                None => {
+                    self.check_expr(arg);
+                }
+            }
+        }
+    }
+
+    /// Checks a call of an intrinsic, which is evaluated for each instance of the function.
+    fn check_intrinsic(&mut self, span: Span, intrinsic: Intrinsic, args: &[hir::Expr]) -> Ty {
+        let cx = self.items.cx;
+        let static_str = cx.mk_ref(Region::Static, cx.types.str, Mutability::Not);
+
+        let expected = intrinsic.arg_count().unwrap_or(args.len().max(1));
+        let output = match intrinsic {
+            Intrinsic::CompileError => cx.types.never,
+            Intrinsic::Fmt => cx.types.str,
+            Intrinsic::TypeName => static_str,
+            Intrinsic::CanCast => cx.types.bool,
+        };
+
+        if args.len() != expected {
+            self.errors.push(TypeckError::ArgCount {
+                expected,
+                found: args.len(),
+                at: span.range().into(),
+                file: span.file,
+            });
+
+            self.check_args_erroneous(args);
+            return output;
+        }
+
+        match intrinsic {
+            Intrinsic::CompileError => {
+                let message = cx.mk_ref(self.infcx.next_region_var(), cx.types.str, Mutability::Not);
+                self.check_expr_coercible_to_type(&args[0], message, None);
+            }
+            Intrinsic::Fmt => {
+                let (format, args) = args.split_first().expect("`@fmt` has a format string");
+                self.check_expr(format);
+
+                let placeholders = match &format.kind {
+                    hir::ExprKind::Lit(hir::Lit::Str(format_str)) => eval::fmt_pieces(format_str)
+                        .map(|pieces| pieces.len() - 1)
+                        .map_err(|reason| (reason, format.span)),
+                    _ => Err(("not a string literal", format.span)),
+                };
+
+                match placeholders {
+                    Ok(placeholders) if placeholders != args.len() => {
+                        self.errors.push(TypeckError::FmtArgCount {
+                            placeholders,
+                            found: args.len(),
+                            at: span.range().into(),
+                            file: span.file,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err((reason, at)) => self.errors.push(TypeckError::InvalidFmt {
+                        reason,
+                        at: at.range().into(),
+                        file: at.file,
+                    }),
+                }
+
+                // types are formatted by their name
+                for arg in args {
+                    if self.expr_as_ty(arg).is_none() {
+                        self.check_expr(arg);
+                    }
+                }
+            }
+            Intrinsic::TypeName | Intrinsic::CanCast => {
+                for arg in args {
+                    if self.expr_as_ty(arg).is_none() {
+                        self.report_expected_type(arg);
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Checks the arguments of a call to something that is not a function, which is reported
+    /// already.
+    fn check_args_erroneous(&mut self, args: &[hir::Expr]) -> Ty {
+        for arg in args {
+            self.check_expr(arg);
+        }
+
+        self.items.cx.types.error
+    }
+
+    fn check_unary(&mut self, op: UnOp, operand: &hir::Expr, expected: Option<Ty>) -> Ty {
+        let cx = self.items.cx;
+
+        let expected_operand = match op {
+            UnOp::Deref => None,
+            UnOp::Not | UnOp::Neg => expected,
+        };
+
+        let ty = self.check_expr_with_expectation(operand, expected_operand);
+        let ty = self.structurally_resolve(ty, operand.span);
+
+        match (op, ty.kind()) {
+            (_, TyKind::Error) => ty,
+            (UnOp::Deref, &TyKind::Ref(_, inner, _)) => inner,
+            (UnOp::Deref, _) => {
+                self.errors.push(TypeckError::CannotDeref {
+                    ty: self.ty_to_string(ty),
+                    at: operand.span.range().into(),
+                    file: operand.span.file,
+                });
+
+                cx.types.error
+            }
+            (UnOp::Not, _) if ty.is_bool() || ty.is_integral() => ty,
+            (UnOp::Neg, TyKind::Infer(InferTy::IntVar(_))) => {
+                self.negated_ints.push((ty, operand.span));
+                ty
+            }
+            (UnOp::Neg, TyKind::Int(_) | TyKind::Float(_) | TyKind::Infer(InferTy::FloatVar(_))) => ty,
+            (UnOp::Not | UnOp::Neg, _) => {
+                self.errors.push(TypeckError::UnaryOp {
+                    op: match op {
+                        UnOp::Not => "!",
+                        _ => "-",
+                    },
+                    ty: self.ty_to_string(ty),
+                    at: operand.span.range().into(),
+                    file: operand.span.file,
+                });
+
+                cx.types.error
+            }
+        }
+    }
+
+    fn check_binary(&mut self, op: BinOp, lhs: &hir::Expr, rhs: &hir::Expr, expected: Option<Ty>) -> Ty {
+        let cx = self.items.cx;
+
+        if let BinOp::And | BinOp::Or = op {
+            self.check_expr_coercible_to_type(lhs, cx.types.bool, None);
+            self.check_expr_coercible_to_type(rhs, cx.types.bool, None);
+
+            return cx.types.bool;
+        }
+
+        // types are compared by whether they are the same type, in functions over types
+        if let BinOp::Eq | BinOp::Ne = op {
+            if let (Some(_), Some(_)) = (self.expr_as_ty(lhs), self.expr_as_ty(rhs)) {
+                return cx.types.bool;
+            }
+        }
+
+        let expected_lhs = match op {
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => None,
+            _ => expected,
+        };
+
+        let lhs_ty = self.check_expr_with_expectation(lhs, expected_lhs);
+
+        // shifts can shift by any integer, everything else takes the same type on both sides
+        let rhs_ty = match op {
+            BinOp::Shl | BinOp::Shr => self.check_expr(rhs),
+            _ => self.check_expr_coercible_to_type(rhs, lhs_ty, None),
+        };
+
+        let lhs_ty = self.structurally_resolve(lhs_ty, lhs.span);
+        let rhs_ty = self.structurally_resolve(rhs_ty, rhs.span);
+
+        if lhs_ty.is_error() || rhs_ty.is_error() {
+            return cx.types.error;
+        }
+
+        let (valid, output) = match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => (lhs_ty.is_numeric(), lhs_ty),
+            BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => {
+                (lhs_ty.is_integral() || lhs_ty.is_bool(), lhs_ty)
+            }
+            BinOp::Shl | BinOp::Shr => (lhs_ty.is_integral() && rhs_ty.is_integral(), lhs_ty),
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => (
+                lhs_ty.is_numeric() || lhs_ty.is_bool() || matches!(lhs_ty.kind(), TyKind::Char),
+                cx.types.bool,
+            ),
+            BinOp::And | BinOp::Or => unreachable!("checked above"),
+        };
+
+        if !valid {
+            let (ty, span) = match op {
+                BinOp::Shl | BinOp::Shr if lhs_ty.is_integral() => (rhs_ty, rhs.span),
+                _ => (lhs_ty, lhs.span),
+            };
+
+            self.errors.push(TypeckError::BinaryOp {
+                op: op.as_str(),
+                ty: self.ty_to_string(ty),
+                at: span.range().into(),
+                file: span.file,
+            });
+
+            return cx.types.error;
+        }
+
+        output
+    }
+
+    fn check_if(
+        &mut self,
+        condition: &hir::Expr,
+        then: &hir::Block,
+        otherwise: Option<&hir::Expr>,
+        expected: Option<Ty>,
+    ) -> Ty {
+        let cx = self.items.cx;
+
+        self.check_expr_coercible_to_type(condition, cx.types.bool, None);
+
+        let Some(otherwise) = otherwise else {
+            let then_ty = self.check_block(then, Some(cx.types.unit));
+            self.coerce(
+                then.hir_id,
+                block_tail_span(then),
+                then_ty,
+                cx.types.unit,
+                None,
+                Some("`if` without `else` evaluates to `()`"),
+            );
+
+            return cx.types.unit;
+        };
+
+        let then_ty = self.check_block(then, expected);
+        let else_ty = self.check_expr_with_expectation(otherwise, expected);
+
+        // the branches have the type of the first one that doesn't diverge
+        if self.infcx.shallow_resolve(then_ty).is_never() {
+            self.coerce(then.hir_id, block_tail_span(then), then_ty, else_ty, None, None);
+            else_ty
+        } else {
+            let origin = Origin {
+                span: block_tail_span(then),
+                reason: "expected because of this",
+            };
+
+            self.coerce(otherwise.hir_id, tail_span(otherwise), else_ty, then_ty, Some(origin), None);
+            then_ty
+        }
+    }
+
+    fn check_block(&mut self, block: &hir::Block, expected: Option<Ty>) -> Ty {
+        let ty = match block.statements.split_last() {
+            Some((last, statements)) => {
+                for statement in statements {
+                    self.check_expr(statement);
+                }
+
+                self.check_expr_with_expectation(last, expected)
+            }
+            None => self.items.cx.types.unit,
+        };
+
+        self.write_ty(block.hir_id, block.span, ty);
+        ty
+    }
+
+    fn check_local(&mut self, local: &hir::Local) {
+        let annotation = local.ty.as_ref().map(|ty| {
+            let lowered = self.lower_ty(ty);
+            (self.normalize(lowered, ty.span), ty.span)
+        });
+
+        let ty = match (annotation, &local.init) {
+            (Some((ty, span)), Some(init)) => {
+                let origin = Origin {
+                    span,
+                    reason: "expected due to this",
+                };
+
+                self.check_expr_coercible_to_type(init, ty, Some(origin))
+            }
+            (Some((ty, _)), None) => ty,
+            (None, Some(init)) => self.check_expr(init),
+            (None, None) => self.infcx.next_ty_var(),
+        };
+
+        self.check_pat(&local.pat, ty);
+    }
+
+    /// Checks that `pat` can match values of type `expected`, and gives the locals it binds
+    /// their types.
+    fn check_pat(&mut self, pat: &hir::Pat, expected: Ty) {
+        let cx = self.items.cx;
+
+        match &pat.kind {
+            hir::PatKind::Binding { mode, sub, .. } => {
+                let local_ty = if mode.by_ref {
+                    let mutability = if mode.mutable {
+                        Mutability::Mut
+                    } else {
+                        Mutability::Not
+                    };
+
+                    let region = self.infcx.next_region_var();
+                    cx.mk_ref(region, expected, mutability)
+                } else {
+                    expected
+                };
+
+                self.locals.insert(pat.hir_id, local_ty);
+
+                if let Some(sub) = sub {
+                    self.check_pat(sub, expected);
+
+                    let mut bindings = vec![];
+                    self.collect_bindings(sub, &mut bindings);
+                    self.bound_twice.push(BoundTwice {
+                        ty: expected,
+                        span: pat.span,
+                        by_ref: mode.by_ref,
+                        bindings,
+                    });
+                }
+            }
+            hir::PatKind::Tuple(pats) => {
+                let tys: Vec<_> = pats.iter().map(|_| self.infcx.next_ty_var()).collect();
+                let tuple = cx.mk_tuple(&tys);
+
+                if self.infcx.unify(tuple, expected).is_err() {
+                    self.report_mismatch(pat.span, expected, tuple, None, None);
+                }
+
+                for (pat, ty) in pats.iter().zip(tys) {
+                    self.check_pat(pat, ty);
+                }
+            }
+        }
+
+        self.write_ty(pat.hir_id, pat.span, expected);
+    }
+
+    /// Pushes the type and span of each binding in `pat`, or `None` for the type of those by
+    /// reference.
+    fn collect_bindings(&self, pat: &hir::Pat, bindings: &mut Vec<(Option<Ty>, Span)>) {
+        match &pat.kind {
+            hir::PatKind::Binding { mode, sub, .. } => {
+                let ty = (!mode.by_ref).then(|| self.locals[&pat.hir_id]);
+                bindings.push((ty, pat.span));
+
+                if let Some(sub) = sub {
+                    self.collect_bindings(sub, bindings);
+                }
+            }
+            hir::PatKind::Tuple(pats) => {
+                for pat in pats {
+                    self.collect_bindings(pat, bindings);
+                }
+            }
+        }
+    }
+
+    /// Makes the value of `id`, of type `source`, a value of type `target`. `!` coerces to any
+    /// type and `&mut T` to `&T`, other types have to be the same.
+    fn coerce(
+        &mut self,
+        id: HirId,
+        span: Span,
+        source: Ty,
+        target: Ty,
+        origin: Option<Origin>,
+        help: Option<&'static str>,
+    ) -> bool {
+        let cx = self.items.cx;
+        let source = self.infcx.shallow_resolve(source);
+        let resolved_target = self.infcx.shallow_resolve(target);
+
+        if source.is_never() {
+            if let TyKind::Infer(InferTy::TyVar(vid)) = *resolved_target.kind() {
+                self.infcx.set_diverging(vid);
+            }
+
+            if !resolved_target.is_never() {
+                self.apply_adjustments(id, vec![Adjustment {
+                    kind: Adjust::NeverToAny,
+                    target,
+                }]);
+            }
+
+            return true;
+        }
+
+        if let (&TyKind::Ref(_, source_inner, Mutability::Mut), &TyKind::Ref(region, target_inner, Mutability::Not)) =
+            (source.kind(), resolved_target.kind())
+        {
+            if self.infcx.unify(source_inner, target_inner).is_ok() {
+                self.apply_adjustments(id, vec![
+                    Adjustment {
+                        kind: Adjust::Deref,
+                        target: source_inner,
+                    },
+                    Adjustment {
+                        kind: Adjust::Borrow(Mutability::Not),
+                        target: cx.mk_ref(region, source_inner, Mutability::Not),
+                    },
+                ]);
+
+                return true;
+            }
+        }
+
+        if self.infcx.unify(source, target).is_ok() {
+            return true;
+        }
+
+        self.report_mismatch(span, target, source, origin, help);
+        false
+    }
+
+    fn report_mismatch(&mut self, span: Span, expected: Ty, found: Ty, origin: Option<Origin>, help: Option<&'static str>) {
+        let expected = self.infcx.resolve_vars(expected);
+        let found = self.infcx.resolve_vars(found);
+
+        // one of them is wrong already, which is reported
+        if expected.any(&mut |ty| ty.is_error()) || found.any(&mut |ty| ty.is_error()) {
+            return;
+        }
+
+        // miette labels spans of one file only
+        let origin = origin.filter(|origin| origin.span.file == span.file);
+
+        self.errors.push(TypeckError::Mismatch {
+            expected: self.items.ty_to_string(expected),
+            found: self.items.ty_to_string(found),
+            at: span.range().into(),
+            origin: origin.map(|origin| origin.span.range().into()),
+            reason: origin.map_or("", |origin| origin.reason),
+            help,
+            file: span.file,
+        });
+    }
+
+    fn report_expected_type(&mut self, arg: &hir::Expr) {
+        let found = match &arg.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(path)) => {
+                let last = path.segments.last().expect("paths have a segment");
+
+                match path.res {
+                    Res::Def(kind, _) => format!("{} `{}`", describe(kind), last.name),
+                    Res::Local(_) => format!("local variable `{}`", last.name),
+                    // reported already
+                    Res::Err => return,
+                    Res::SelfTy(_) => unreachable!("`Self` is a type"),
+                }
+            }
+            hir::ExprKind::Err => return,
+            _ => "expression".to_owned(),
+        };
+
+        self.errors.push(TypeckError::ExpectedType {
+            found,
+            at: arg.span.range().into(),
+            file: arg.span.file,
+        });
+    }
+
+    fn ty_to_string(&self, ty: Ty) -> String {
+        self.items.ty_to_string(self.infcx.resolve_vars(ty))
+    }
+
+    /// Falls back the variables nothing was inferred for, and replaces them with what they are
+    /// bound to in the results.
+    fn finish(mut self) -> TypeckResults {
+        let cx = self.items.cx;
+        self.select_obligations_where_possible();
+        self.infcx.fallback();
+
+        // what falls back can make more obligations provable, like `i32: Trait` for integers
+        let items = self.items;
+        let errors = self.fulfillment.select_all_or_error(&mut SelectionCtxt {
+            items,
+            infcx: &mut self.infcx,
+            param_env: &self.param_env,
+        });
+
+        let unresolved = self.spans.iter().find(|(id, _)| {
+            self.infcx.resolve_vars(self.results.node_types[id]).has_infer()
+        });
+
+        for &(ty, span) in &self.negated_ints {
+            let ty = self.infcx.resolve_vars(ty);
+
+            if let TyKind::Uint(_) = ty.kind() {
+                self.errors.push(TypeckError::UnaryOp {
+                    op: "-",
+                    ty: self.items.ty_to_string(ty),
+                    at: span.range().into(),
+                    file: span.file,
+                });
+            }
+        }
+
+        for bound in &self.bound_twice {
+            let ty = self.infcx.resolve_vars(bound.ty);
+
+            if ty.is_copy() || ty.is_error() || ty.has_infer() {
+                continue;
+            }
+
+            // after a move, the value can be neither borrowed nor moved again
+            let moved = bound.bindings.iter().find(|(binding, _)| match binding {
+                None => !bound.by_ref,
+                Some(binding) => {
+                    let binding = self.infcx.resolve_vars(*binding);
+                    !binding.is_copy() && !binding.is_error()
+                }
+            });
+
+            if let Some(&(_, part)) = moved {
+                self.errors.push(TypeckError::MovedAndBound {
+                    ty: self.items.ty_to_string(ty),
+                    at: bound.span.range().into(),
+                    part: part.range().into(),
+                    file: bound.span.file,
+                });
+            }
+        }
+
+        let report_ambiguity = unresolved.is_none() && self.errors.len() == self.prior_errors;
+        traits::report_fulfillment_errors(items, &self.infcx, errors, report_ambiguity, self.errors);
+
+        // one is enough, the others likely have the same cause
+        if let Some(&(_, span)) = unresolved {
+            self.errors.push(TypeckError::AnnotationsNeeded {
+                at: span.range().into(),
+                file: span.file,
+            });
+        }
+
+        let infcx = &self.infcx;
+        let mut results = self.results;
+
+        for ty in results.node_types.values_mut() {
+            *ty = infcx.resolve_vars_fully(*ty);
+        }
+
+        for ty in results.type_exprs.values_mut() {
+            *ty = infcx.resolve_vars_fully(*ty);
+        }
+
+        for (_, args) in results.type_dependent_defs.values_mut() {
+            let resolved: Vec<_> = args.iter().map(|&arg| infcx.resolve_vars_fully(arg)).collect();
+            *args = cx.mk_args(&resolved);
+        }
+
+        for adjustment in results.adjustments.values_mut().flatten() {
+            adjustment.target = infcx.resolve_vars_fully(adjustment.target);
+        }
+
+        results
+    }
+}
+
+/// The derefs from the first of `steps` to the last.
+fn deref_adjustments(steps: &[Ty]) -> Vec<Adjustment> {
+    steps[1..]
+        .iter()
+        .map(|&target| Adjustment {
+            kind: Adjust::Deref,
+            target,
+        })
+        .collect()
+}
+
+/// The expression a block evaluates to, to point at instead of the whole block.
+fn tail_span(expr: &hir::Expr) -> Span {
+    match &expr.kind {
+        hir::ExprKind::Block(block) => block_tail_span(block),
+        _ => expr.span,
+    }
+}
+
+fn block_tail_span(block: &hir::Block) -> Span {
+    block.statements.last().map_or(block.span, tail_span)
+}
+
+/// Whether `expr` can be assigned to.
+fn is_place(expr: &hir::Expr) -> bool {
+    match &expr.kind {
+        hir::ExprKind::Path(hir::QPath::Resolved(path)) => {
+            matches!(path.res, Res::Local(_) | Res::Def(DefKind::Static, _) | Res::Err)
+        }
+        hir::ExprKind::Field(base, _) => is_place(base),
+        hir::ExprKind::Unary(UnOp::Deref, _) | hir::ExprKind::Err => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+
+    use super::*;
+    use crate::{
+        tests::{check, def_named, statements},
+        CrateTypes,
+    };
+
+    fn function(b: &Builder, name: &str, statements: Vec<ast::Expr>) -> ast::Item {
+        ast::Item::Fn(b.function(name, vec![], b.unit_ty(), statements))
+    }
+
+    /// The type of the value of each `let` in the function `name`.
+    fn let_types(types: &CrateTypes, hir: &hir::Crate, name: &str) -> Vec<Ty> {
+        let def = def_named(hir, name);
+
+        statements(hir, def)
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                hir::ExprKind::Let(local) => Some(local.init.as_ref()?.hir_id.local_id),
+                _ => None,
+            })
+            .map(|id| types.bodies[&def].node_type(id))
+            .collect()
+    }
+
+    #[test]
+    fn mismatches_point_at_what_was_expected() {
+        let b = Builder::new();
+        let annotation = b.ty("i32");
+        let (annotation_span, value) = (annotation.span, b.bool(true));
+        let value_span = value.span;
+
+        let statements = vec![b.let_(b.pattern("x"), false, Some(annotation), Some(value))];
+        let (_, _, errors) = check(&b, vec![function(&b, "f", statements)]);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, at, origin: Some(origin), .. }]
+                    if expected == "i32"
+                        && found == "bool"
+                        && *at == value_span.range().into()
+                        && *origin == annotation_span.range().into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn return_types_are_checked() {
+        let b = Builder::new();
+        let returns = b.ty("i32");
+        let returns_span = returns.span;
+
+        let function = b.function("f", vec![], returns, vec![b.bool(false)]);
+        let (_, _, errors) = check(&b, vec![ast::Item::Fn(function)]);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, origin: Some(origin), .. }]
+                    if expected == "i32" && found == "bool" && *origin == returns_span.range().into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn literals_fall_back_when_nothing_is_inferred() {
+        let b = Builder::new();
+        let statements = vec![
+            b.let_(b.pattern("a"), false, None, Some(b.int(1))),
+            b.let_(b.pattern("b"), false, None, Some(b.float(2.5))),
+            // inferred from where it is used later
+            b.let_(b.pattern("c"), false, None, Some(b.int(3))),
+            b.let_(b.pattern("d"), false, Some(b.ty("u8")), Some(b.name("c"))),
+            b.let_(b.pattern("e"), false, None, Some(b.int_suffixed(4, "i64"))),
+        ];
+        let (types, hir, errors) = check(&b, vec![function(&b, "f", statements)]);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let common = &types.items.cx.types;
+        assert_eq!(
+            let_types(&types, hir, "f"),
+            [common.i32, common.f64, common.u8, common.u8, common.i64]
+        );
+    }
+
+    #[test]
+    fn literals_of_the_wrong_kind() {
+        let b = Builder::new();
+        let statements = vec![b.let_(b.pattern("x"), false, Some(b.ty("f32")), Some(b.int(1)))];
+        let (_, _, errors) = check(&b, vec![function(&b, "f", statements)]);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, .. }] if expected == "f32" && found == "{integer}"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn coercions() {
+        let b = Builder::new();
+        let args = vec![("x", b.ref_ty(b.ty("i32"), true))];
+        let statements = vec![
+            // `&mut i32` to `&i32`
+            b.let_(
+                b.pattern("y"),
+                false,
+                Some(b.ref_ty(b.ty("i32"), false)),
+                Some(b.name("x")),
+            ),
+            // `!` to anything
+            b.let_(b.pattern("z"), false, Some(b.ty("bool")), Some(b.return_(b.unit()))),
+        ];
+        let function = b.function("f", args, b.unit_ty(), statements);
+        let (_, _, errors) = check(&b, vec![ast::Item::Fn(function)]);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        // not the other way around
+        let b = Builder::new();
+        let args = vec![("x", b.ref_ty(b.ty("i32"), false))];
+        let statements = vec![b.let_(
+            b.pattern("y"),
+            false,
+            Some(b.ref_ty(b.ty("i32"), true)),
+            Some(b.name("x")),
+        )];
+        let function = b.function("f", args, b.unit_ty(), statements);
+        let (_, _, errors) = check(&b, vec![ast::Item::Fn(function)]);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, .. }] if expected == "&mut i32" && found == "&i32"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn negating_unsigned_integers() {
+        let b = Builder::new();
+        let operand = b.int(1);
+        let operand_span = operand.span;
+        let statements = vec![
+            // only found to be `u8` after it was negated
+            b.let_(b.pattern("x"), false, None, Some(b.unary(ast::UnaryOp::Neg, operand))),
+            b.let_(b.pattern("y"), false, Some(b.ty("u8")), Some(b.name("x"))),
+        ];
+        let (_, _, errors) = check(&b, vec![function(&b, "f", statements)]);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::UnaryOp { op: "-", ty, at, .. }]
+                    if ty == "u8" && *at == operand_span.range().into()
+            ),
+            "{errors:?}"
+        );
+
+        let b = Builder::new();
+        let statements = vec![
+            b.let_(
+                b.pattern("x"),
+                false,
+                Some(b.ty("u8")),
+                Some(b.unary(ast::UnaryOp::Neg, b.int(1))),
+            ),
+            // falls back to `i32`, which can be negated
+            b.let_(b.pattern("y"), false, None, Some(b.unary(ast::UnaryOp::Neg, b.int(1)))),
+        ];
+        let (types, hir, errors) = check(&b, vec![function(&b, "f", statements)]);
+
+        assert!(
+            matches!(&errors[..], [TypeckError::UnaryOp { op: "-", ty, .. }] if ty == "u8"),
+            "{errors:?}"
+        );
+        assert_eq!(let_types(&types, hir, "f")[1], types.items.cx.types.i32);
+    }
+
+    #[test]
+    fn values_moved_by_a_binding_are_bound_once() {
+        /// `f(s: S, n: i32) { let (pattern) = s; let (pattern) = n }`, where `pattern` binds twice.
+        fn bind_twice(outer_ref: bool, inner_ref: bool) -> Vec<TypeckError> {
+            let b = Builder::new();
+            let bind = |name: &str, by_ref: bool| {
+                let pattern = b.pattern(name);
+                match by_ref {
+                    true => b.pattern_kind(ast::PatternKind::Ref(Box::new(pattern))),
+                    false => pattern,
+                }
+            };
+            let pattern = |outer: &str, inner: &str| {
+                let inner = bind(inner, inner_ref);
+                let pattern = b.pattern_kind(ast::PatternKind::WithVariable(b.ident(outer), Box::new(inner)));
+                match outer_ref {
+                    true => b.pattern_kind(ast::PatternKind::Ref(Box::new(pattern))),
+                    false => pattern,
+                }
+            };
+
+            let statements = vec![
+                b.let_(pattern("x", "y"), false, None, Some(b.name("s"))),
+                // integers are copied, so can be bound any number of times
+                b.let_(pattern("a", "c"), false, None, Some(b.name("n"))),
+            ];
+            let f = b.function("f", vec![("s", b.ty("S")), ("n", b.ty("i32"))], b.unit_ty(), statements);
+            let (_, _, errors) = check(&b, vec![b.structure("S", &[], vec![]), ast::Item::Fn(f)]);
+            errors
+        }
+
+        for (outer_ref, inner_ref) in [(false, false), (false, true), (true, false)] {
+            let errors = bind_twice(outer_ref, inner_ref);
+            assert!(
+                matches!(&errors[..], [TypeckError::MovedAndBound { ty, .. }] if ty == "S"),
+                "{errors:?}"
+            );
+        }
+
+        let errors = bind_twice(true, true);
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+}
@@ -0,0 +1,582 @@
+use std::collections::{HashMap, HashSet};
+
+use tangic_middle::{
+    hir::{self, DefKind, Res},
+    span::Span,
+    symbol::{kw, Symbol},
+    ty::{self, GenericParamDef, GenericParamDefKind, Generics, Predicate, Region, Ty},
+    Cx, DefId, DefIndex,
+};
+
+use crate::{lower::TyLowerer, TypeckError};
+
+/// The items of a crate as seen from outside their bodies: their generic parameters, signatures
+/// and the types of their fields, by the definitions they are of.
+pub struct ItemTypes<'a> {
+    pub cx: &'a Cx,
+    pub krate: &'a hir::Crate,
+    /// The trait or impl of each associated item, and the item each generic parameter and enum
+    /// variant is of. Items in modules have no parent here.
+    pub parents: HashMap<DefIndex, DefIndex>,
+    pub generics: HashMap<DefIndex, Generics>,
+    /// Each generic parameter, by its definition.
+    pub params: HashMap<DefIndex, GenericParamDef>,
+    /// The traits each type parameter is bounded by, in its list of parameters or in a `where`
+    /// clause.
+    pub bounds: HashMap<DefIndex, Vec<hir::TraitRef>>,
+    /// The type of each const, static and const parameter, what each type alias and associated
+    /// type of an impl stands for, the self type of each impl, and the enum type of each unit
+    /// variant.
+    pub types: HashMap<DefIndex, Ty>,
+    /// The signature of each function, associated function and tuple variant.
+    pub fn_sigs: HashMap<DefIndex, ty::FnSig>,
+    /// The names and types of the fields of each struct.
+    pub fields: HashMap<DefIndex, Vec<(Symbol, Ty)>>,
+    /// Impls without a trait, whose functions are found by the self type.
+    pub inherent_impls: Vec<DefIndex>,
+    /// Associated functions taking `self`, which can be called as methods.
+    pub methods: HashSet<DefIndex>,
+    /// The bounds and `where` clauses of each item, without those of its parents. A trait has
+    /// `Self: Trait` and its supertraits, as bounds of `Self`.
+    pub predicates: HashMap<DefIndex, Vec<Predicate>>,
+    /// The bounds of each associated type of a trait, on the associated type of `Self`.
+    pub item_bounds: HashMap<DefIndex, Vec<Predicate>>,
+    /// The trait each impl of a trait implements, for its self type.
+    pub impl_trait_refs: HashMap<DefIndex, ty::TraitRef>,
+    /// The impls of each trait.
+    pub trait_impls: HashMap<DefIndex, Vec<DefIndex>>,
+    /// Every trait, whose methods are candidates for method calls.
+    pub traits: Vec<DefIndex>,
+}
+
+impl<'a> ItemTypes<'a> {
+    pub fn item(&self, def: DefIndex) -> Option<&'a hir::Item> {
+        match self.krate.owners.get(def)? {
+            Some(hir::Owner::Item(item)) => Some(item),
+            _ => None,
+        }
+    }
+
+    pub fn assoc_item(&self, def: DefIndex) -> Option<&'a hir::AssocItem> {
+        match self.krate.owners.get(def)? {
+            Some(hir::Owner::AssocItem(item)) => Some(item),
+            _ => None,
+        }
+    }
+
+    /// The name of an item, associated item, enum variant or generic parameter.
+    pub fn name(&self, def: DefIndex) -> Option<Symbol> {
+        if let Some(param) = self.params.get(&def) {
+            return Some(param.name);
+        }
+
+        match self.krate.owners.get(def) {
+            Some(Some(hir::Owner::Item(item))) => item.name.map(|name| name.name),
+            Some(Some(hir::Owner::AssocItem(item))) => Some(item.name.name),
+            // enum variants are nodes of their enums
+            _ => match &self.item(*self.parents.get(&def)?)?.kind {
+                hir::ItemKind::Enum(_, variants) => variants
+                    .iter()
+                    .find(|variant| variant.def == def)
+                    .map(|variant| variant.name.name),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn generics_of(&self, def: DefIndex) -> &Generics {
+        static EMPTY: Generics = Generics {
+            parent: None,
+            params: vec![],
+            type_count: 0,
+            lifetime_count: 0,
+            const_count: 0,
+        };
+
+        self.generics.get(&def).unwrap_or(&EMPTY)
+    }
+
+    /// The type parameters of `def`, with those of its parents first, as they are indexed.
+    pub fn type_params(&self, def: DefIndex) -> Vec<GenericParamDef> {
+        let generics = self.generics_of(def);
+        let mut params = match generics.parent {
+            Some(parent) => self.type_params(parent.index),
+            None => vec![],
+        };
+
+        params.extend(generics.own_types().copied());
+        params
+    }
+
+    /// Each type parameter of `def` as itself, which is how the item sees them from inside.
+    pub fn identity_args(&self, def: DefIndex) -> Vec<Ty> {
+        self.type_params(def)
+            .iter()
+            .map(|param| self.cx.mk_param(param.index, param.name))
+            .collect()
+    }
+
+    /// The struct or enum `def`, applied to its own type parameters.
+    pub fn adt_ty(&self, def: DefIndex) -> Ty {
+        self.cx.mk_adt(DefId::local(def), &self.identity_args(def))
+    }
+
+    /// The predicates of `def` and of its parents, which hold wherever it is used.
+    pub fn predicates_of(&self, def: DefIndex) -> Vec<Predicate> {
+        let mut predicates = match self.generics_of(def).parent {
+            Some(parent) => self.predicates_of(parent.index),
+            None => vec![],
+        };
+
+        predicates.extend(self.predicates.get(&def).into_iter().flatten().copied());
+        predicates
+    }
+
+    /// The associated item named `name` in the trait or impl `parent`.
+    pub fn find_assoc_item(&self, parent: DefIndex, name: Symbol) -> Option<&'a hir::AssocItem> {
+        let items = match &self.item(parent)?.kind {
+            hir::ItemKind::Trait { items, .. } | hir::ItemKind::Impl { items, .. } => items,
+            _ => return None,
+        };
+
+        items
+            .iter()
+            .filter_map(|&item| self.assoc_item(item))
+            .find(|item| item.name.name == name)
+    }
+}
+
+/// Collects the generic parameters, signatures and field types of every item of `krate`.
+pub fn collect<'a>(cx: &'a Cx, krate: &'a hir::Crate) -> (ItemTypes<'a>, Vec<TypeckError>) {
+    let mut items = ItemTypes {
+        cx,
+        krate,
+        parents: HashMap::new(),
+        generics: HashMap::new(),
+        params: HashMap::new(),
+        bounds: HashMap::new(),
+        types: HashMap::new(),
+        fn_sigs: HashMap::new(),
+        fields: HashMap::new(),
+        inherent_impls: vec![],
+        methods: HashSet::new(),
+        predicates: HashMap::new(),
+        item_bounds: HashMap::new(),
+        impl_trait_refs: HashMap::new(),
+        trait_impls: HashMap::new(),
+        traits: vec![],
+    };
+
+    for (def, owner) in krate.owners.iter_enumerated() {
+        if let Some(owner) = owner {
+            collect_parents(&mut items, def, owner);
+        }
+    }
+
+    for def in krate.owners.indices() {
+        collect_generics(&mut items, def);
+    }
+
+    let mut icx = ItemCtxt {
+        items,
+        expanding: vec![],
+        errors: vec![],
+    };
+
+    for (def, owner) in krate.owners.iter_enumerated() {
+        match owner {
+            Some(hir::Owner::Item(item)) => icx.collect_item(def, item),
+            Some(hir::Owner::AssocItem(item)) => icx.collect_assoc_item(def, item),
+            None => {}
+        }
+    }
+
+    // after the types, which bounds project to
+    for (def, owner) in krate.owners.iter_enumerated() {
+        if let Some(owner) = owner {
+            icx.collect_predicates(def, owner);
+        }
+    }
+
+    (icx.items, icx.errors)
+}
+
+fn collect_parents(items: &mut ItemTypes, def: DefIndex, owner: &hir::Owner) {
+    let generics = match owner {
+        hir::Owner::Item(item) => match &item.kind {
+            hir::ItemKind::Trait { generics, items: children, .. }
+            | hir::ItemKind::Impl { generics, items: children, .. } => {
+                for &child in children {
+                    items.parents.insert(child, def);
+                }
+
+                Some(generics)
+            }
+            hir::ItemKind::Enum(generics, variants) => {
+                for variant in variants {
+                    items.parents.insert(variant.def, def);
+                }
+
+                Some(generics)
+            }
+            hir::ItemKind::Struct(generics, _)
+            | hir::ItemKind::Fn(_, generics, _)
+            | hir::ItemKind::TypeAlias(generics, _) => Some(generics),
+            hir::ItemKind::Mod(_) | hir::ItemKind::Const(..) | hir::ItemKind::Static(..) => None,
+        },
+        hir::Owner::AssocItem(item) => match &item.kind {
+            hir::AssocItemKind::Fn(_, generics, _) | hir::AssocItemKind::Type { generics, .. } => {
+                Some(generics)
+            }
+        },
+    };
+
+    for param in generics.iter().flat_map(|generics| &generics.params) {
+        items.parents.insert(param.def, def);
+    }
+}
+
+/// The generics of `def`, after those of its parent, which are collected first.
+fn collect_generics(items: &mut ItemTypes, def: DefIndex) {
+    if items.generics.contains_key(&def) {
+        return;
+    }
+
+    let (hir_generics, is_trait) = match &items.krate.owners[def] {
+        Some(hir::Owner::Item(item)) => match &item.kind {
+            hir::ItemKind::Struct(generics, _)
+            | hir::ItemKind::Enum(generics, _)
+            | hir::ItemKind::Fn(_, generics, _)
+            | hir::ItemKind::TypeAlias(generics, _)
+            | hir::ItemKind::Impl { generics, .. } => (generics, false),
+            hir::ItemKind::Trait { generics, .. } => (generics, true),
+            hir::ItemKind::Mod(_) | hir::ItemKind::Const(..) | hir::ItemKind::Static(..) => return,
+        },
+        Some(hir::Owner::AssocItem(item)) => match &item.kind {
+            hir::AssocItemKind::Fn(_, generics, _) | hir::AssocItemKind::Type { generics, .. } => {
+                (generics, false)
+            }
+        },
+        None => return,
+    };
+
+    let parent = items.parents.get(&def).copied();
+    let mut generics = match parent {
+        Some(parent) => {
+            collect_generics(items, parent);
+
+            let parent_generics = items.generics_of(parent);
+            Generics {
+                parent: Some(DefId::local(parent)),
+                params: vec![],
+                type_count: parent_generics.type_count,
+                lifetime_count: parent_generics.lifetime_count,
+                const_count: parent_generics.const_count,
+            }
+        }
+        None => Generics::default(),
+    };
+
+    if is_trait {
+        // `Self` comes before the parameters of the trait
+        generics.params.push(GenericParamDef {
+            def: DefId::local(def),
+            name: kw::SelfUpper,
+            index: generics.type_count,
+            kind: GenericParamDefKind::Type,
+        });
+        generics.type_count += 1;
+    }
+
+    for param in &hir_generics.params {
+        let (kind, count) = match param.kind {
+            hir::GenericParamKind::Type => (GenericParamDefKind::Type, &mut generics.type_count),
+            hir::GenericParamKind::Lifetime => {
+                (GenericParamDefKind::Lifetime, &mut generics.lifetime_count)
+            }
+            hir::GenericParamKind::Const(_) => (GenericParamDefKind::Const, &mut generics.const_count),
+        };
+
+        let param_def = GenericParamDef {
+            def: DefId::local(param.def),
+            name: param.name.name,
+            index: *count,
+            kind,
+        };
+        *count += 1;
+
+        items.params.insert(param.def, param_def);
+        items.bounds.insert(param.def, param.bounds.clone());
+        generics.params.push(param_def);
+    }
+
+    for predicate in &hir_generics.predicates {
+        let hir::WherePredicate::Bound { ty, bounds } = predicate else {
+            continue;
+        };
+
+        if let hir::TyKind::Path(hir::QPath::Resolved(hir::Path {
+            res: Res::Def(DefKind::TyParam, param),
+            ..
+        })) = ty.kind
+        {
+            items
+                .bounds
+                .entry(param.index)
+                .or_default()
+                .extend(bounds.iter().cloned());
+        }
+    }
+
+    items.generics.insert(def, generics);
+}
+
+/// Lowers the types in the signatures of items, where nothing is left to infer.
+struct ItemCtxt<'a> {
+    items: ItemTypes<'a>,
+    /// The type aliases and impls whose types are being lowered, to catch aliases that expand
+    /// to themselves.
+    expanding: Vec<DefIndex>,
+    errors: Vec<TypeckError>,
+}
+
+impl<'a> TyLowerer<'a> for ItemCtxt<'a> {
+    fn items(&self) -> &ItemTypes<'a> {
+        &self.items
+    }
+
+    fn type_of(&mut self, def: DefIndex, span: Span) -> Ty {
+        if let Some(&ty) = self.items.types.get(&def) {
+            return ty;
+        }
+
+        let hir_ty = match self.items.krate.owners.get(def) {
+            Some(Some(hir::Owner::Item(item))) => match &item.kind {
+                hir::ItemKind::TypeAlias(_, ty) | hir::ItemKind::Impl { self_ty: ty, .. } => ty,
+                _ => return self.items.cx.types.error,
+            },
+            Some(Some(hir::Owner::AssocItem(item))) => match &item.kind {
+                hir::AssocItemKind::Type { ty: Some(ty), .. } => ty,
+                _ => return self.items.cx.types.error,
+            },
+            _ => return self.items.cx.types.error,
+        };
+
+        if self.expanding.contains(&def) {
+            self.report(TypeckError::AliasCycle {
+                name: self.items.name(def).unwrap_or(kw::Impl),
+                at: span.range().into(),
+                file: span.file,
+            });
+
+            return self.items.cx.types.error;
+        }
+
+        self.expanding.push(def);
+        let ty = self.lower_ty(hir_ty);
+        self.expanding.pop();
+
+        self.items.types.insert(def, ty);
+        ty
+    }
+
+    fn re_infer(&mut self) -> Region {
+        // there is no borrow checker to check lifetimes yet, elided ones are left out
+        Region::Erased
+    }
+
+    fn ty_infer(&mut self) -> Option<Ty> {
+        None
+    }
+
+    fn report(&mut self, error: TypeckError) {
+        self.errors.push(error);
+    }
+}
+
+impl<'a> ItemCtxt<'a> {
+    fn collect_item(&mut self, def: DefIndex, item: &'a hir::Item) {
+        match &item.kind {
+            hir::ItemKind::Mod(_) => {}
+            hir::ItemKind::Trait { .. } => self.items.traits.push(def),
+            hir::ItemKind::Struct(_, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| (field.name.name, self.lower_ty(&field.ty)))
+                    .collect();
+
+                self.items.fields.insert(def, fields);
+            }
+            hir::ItemKind::Enum(_, variants) => {
+                let adt = self.items.adt_ty(def);
+
+                for variant in variants {
+                    match &variant.fields {
+                        hir::VariantFields::Unit => {
+                            self.items.types.insert(variant.def, adt);
+                        }
+                        hir::VariantFields::Tuple(types) => {
+                            let inputs: Vec<_> = types.iter().map(|ty| self.lower_ty(ty)).collect();
+                            let sig = self.items.cx.mk_fn_sig(&inputs, adt);
+
+                            self.items.fn_sigs.insert(variant.def, sig);
+                        }
+                        hir::VariantFields::Struct(fields) => {
+                            for field in fields {
+                                self.lower_ty(&field.ty);
+                            }
+                        }
+                    }
+                }
+            }
+            hir::ItemKind::Fn(sig, generics, _) => self.collect_fn(def, sig, generics),
+            hir::ItemKind::TypeAlias(generics, _) => {
+                self.collect_const_params(generics);
+                self.type_of(def, item.span);
+            }
+            hir::ItemKind::Const(ty, _) | hir::ItemKind::Static(ty, _) => {
+                let ty = self.lower_ty(ty);
+                self.items.types.insert(def, ty);
+            }
+            hir::ItemKind::Impl { generics, trait_, .. } => {
+                self.collect_const_params(generics);
+                let self_ty = self.type_of(def, item.span);
+
+                match trait_ {
+                    Some(trait_) => {
+                        if let Some(trait_ref) = self.lower_trait_ref(self_ty, trait_) {
+                            self.items.impl_trait_refs.insert(def, trait_ref);
+                            self.items
+                                .trait_impls
+                                .entry(trait_ref.def.index)
+                                .or_default()
+                                .push(def);
+                        }
+                    }
+                    None => self.items.inherent_impls.push(def),
+                }
+            }
+        }
+    }
+
+    fn collect_assoc_item(&mut self, def: DefIndex, item: &'a hir::AssocItem) {
+        match &item.kind {
+            hir::AssocItemKind::Fn(sig, generics, _) => {
+                self.collect_fn(def, sig, generics);
+
+                if sig.inputs.first().is_some_and(is_self_ty) {
+                    self.items.methods.insert(def);
+                }
+            }
+            hir::AssocItemKind::Type { generics, ty, .. } => {
+                self.collect_const_params(generics);
+
+                // in a trait, the type is a default for impls, which is left to them
+                if ty.is_some() {
+                    self.type_of(def, item.span);
+                }
+            }
+        }
+    }
+
+    fn collect_fn(&mut self, def: DefIndex, sig: &hir::FnSig, generics: &hir::Generics) {
+        self.collect_const_params(generics);
+
+        let inputs: Vec<_> = sig.inputs.iter().map(|ty| self.lower_ty(ty)).collect();
+        let output = self.lower_ty(&sig.output);
+
+        self.items.fn_sigs.insert(def, self.items.cx.mk_fn_sig(&inputs, output));
+    }
+
+    fn collect_predicates(&mut self, def: DefIndex, owner: &'a hir::Owner) {
+        let cx = self.items.cx;
+        let mut predicates = vec![];
+
+        let generics = match owner {
+            hir::Owner::Item(item) => match &item.kind {
+                hir::ItemKind::Trait {
+                    generics,
+                    supertraits,
+                    ..
+                } => {
+                    let self_ty = cx.mk_param(0, kw::SelfUpper);
+                    let args = self.items.identity_args(def);
+
+                    predicates.push(Predicate::Trait(ty::TraitRef {
+                        def: DefId::local(def),
+                        args: cx.mk_args(&args),
+                    }));
+                    self.lower_bounds(self_ty, supertraits, &mut predicates);
+
+                    generics
+                }
+                hir::ItemKind::Struct(generics, _)
+                | hir::ItemKind::Enum(generics, _)
+                | hir::ItemKind::Fn(_, generics, _)
+                | hir::ItemKind::TypeAlias(generics, _)
+                | hir::ItemKind::Impl { generics, .. } => generics,
+                hir::ItemKind::Mod(_) | hir::ItemKind::Const(..) | hir::ItemKind::Static(..) => return,
+            },
+            hir::Owner::AssocItem(item) => match &item.kind {
+                hir::AssocItemKind::Fn(_, generics, _) => generics,
+                hir::AssocItemKind::Type { generics, bounds, .. } => {
+                    let args = self.items.identity_args(def);
+                    let projection = cx.mk_projection(DefId::local(def), &args);
+                    let mut item_bounds = vec![];
+
+                    self.lower_bounds(projection, bounds, &mut item_bounds);
+
+                    if !item_bounds.is_empty() {
+                        self.items.item_bounds.insert(def, item_bounds);
+                    }
+
+                    generics
+                }
+            },
+        };
+
+        for param in &generics.params {
+            if let Some(&GenericParamDef {
+                index,
+                name,
+                kind: GenericParamDefKind::Type,
+                ..
+            }) = self.items.params.get(&param.def)
+            {
+                self.lower_bounds(cx.mk_param(index, name), &param.bounds, &mut predicates);
+            }
+        }
+
+        for predicate in &generics.predicates {
+            if let hir::WherePredicate::Bound { ty, bounds } = predicate {
+                let self_ty = self.lower_ty(ty);
+                self.lower_bounds(self_ty, bounds, &mut predicates);
+            }
+        }
+
+        if !predicates.is_empty() {
+            self.items.predicates.insert(def, predicates);
+        }
+    }
+
+    fn collect_const_params(&mut self, generics: &hir::Generics) {
+        for param in &generics.params {
+            if let hir::GenericParamKind::Const(ty) = &param.kind {
+                let ty = self.lower_ty(ty);
+                self.items.types.insert(param.def, ty);
+            }
+        }
+    }
+}
+
+/// Whether `ty` is `self`, `&self` or `&mut self`.
+fn is_self_ty(ty: &hir::Ty) -> bool {
+    match &ty.kind {
+        hir::TyKind::Ref(_, _, inner) => is_self_ty(inner),
+        hir::TyKind::Path(hir::QPath::Resolved(path)) => {
+            matches!(path.res, Res::SelfTy(_))
+                && matches!(&path.segments[..], [segment] if segment.name.name == kw::SelfLower)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,298 @@
+use miette::SourceSpan;
+use tangic_middle::{span::FileId, symbol::Symbol};
+
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum TypeckError {
+    #[error("mismatched types")]
+    #[diagnostic(code(tangic::typeck::mismatch))]
+    Mismatch {
+        expected: String,
+        found: String,
+        #[label("expected `{expected}`, found `{found}`")]
+        at: SourceSpan,
+        /// Why `expected` is expected, if that is written somewhere.
+        #[label("{reason}")]
+        origin: Option<SourceSpan>,
+        reason: &'static str,
+        #[help]
+        help: Option<&'static str>,
+        file: FileId,
+    },
+    #[error("this function takes {expected} arguments but {found} were supplied")]
+    #[diagnostic(code(tangic::typeck::arg_count))]
+    ArgCount {
+        expected: usize,
+        found: usize,
+        #[label("expected {expected} arguments")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{name}` takes {expected} arguments but binds {found}")]
+    #[diagnostic(code(tangic::typeck::param_count))]
+    ParamCount {
+        name: Symbol,
+        expected: usize,
+        found: usize,
+        #[label("binds {found} arguments")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{name}` takes {expected} generic arguments but {found} were supplied")]
+    #[diagnostic(code(tangic::typeck::generic_arg_count))]
+    GenericArgCount {
+        name: Symbol,
+        expected: usize,
+        found: usize,
+        #[label("expected {expected} generic arguments")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("expected function, found `{ty}`")]
+    #[diagnostic(code(tangic::typeck::not_callable))]
+    NotCallable {
+        ty: String,
+        #[label("not a function")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("no field `{name}` on type `{ty}`")]
+    #[diagnostic(code(tangic::typeck::no_field))]
+    NoField {
+        name: Symbol,
+        ty: String,
+        #[label("unknown field")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("no method named `{name}` found for `{ty}`")]
+    #[diagnostic(code(tangic::typeck::no_method))]
+    NoMethod {
+        name: Symbol,
+        ty: String,
+        #[label("method not found in `{ty}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("no associated item named `{name}` found for `{ty}`")]
+    #[diagnostic(code(tangic::typeck::no_assoc_item))]
+    NoAssocItem {
+        name: Symbol,
+        ty: String,
+        #[label("associated item not found in `{ty}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("type `{ty}` cannot be dereferenced")]
+    #[diagnostic(code(tangic::typeck::cannot_deref))]
+    CannotDeref {
+        ty: String,
+        #[label("not a reference")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("cannot apply unary operator `{op}` to type `{ty}`")]
+    #[diagnostic(code(tangic::typeck::unary_op))]
+    UnaryOp {
+        op: &'static str,
+        ty: String,
+        #[label("cannot apply unary operator `{op}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("binary operation `{op}` cannot be applied to type `{ty}`")]
+    #[diagnostic(code(tangic::typeck::binary_op))]
+    BinaryOp {
+        op: &'static str,
+        ty: String,
+        #[label("`{ty}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("invalid left-hand side of assignment")]
+    #[diagnostic(code(tangic::typeck::invalid_assign))]
+    InvalidAssign {
+        #[label("cannot assign to this expression")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("cannot bind a value of type `{ty}` twice when a binding moves it")]
+    #[diagnostic(
+        code(tangic::typeck::moved_and_bound),
+        help("bind it with `ref` both times, or only once")
+    )]
+    MovedAndBound {
+        ty: String,
+        #[label("bound here")]
+        at: SourceSpan,
+        #[label("and here")]
+        part: SourceSpan,
+        file: FileId,
+    },
+    #[error("type annotations needed")]
+    #[diagnostic(code(tangic::typeck::annotations_needed))]
+    AnnotationsNeeded {
+        #[label("cannot infer the type of this")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("expected value, found {found}")]
+    #[diagnostic(code(tangic::typeck::expected_value))]
+    ExpectedValue {
+        /// What was found, like ``struct `Point` ``.
+        found: String,
+        #[label("not a value")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("expected type, found {found}")]
+    #[diagnostic(code(tangic::typeck::expected_type))]
+    ExpectedType {
+        found: String,
+        #[label("not a type")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("associated type `{name}` not found for `{ty}`")]
+    #[diagnostic(code(tangic::typeck::no_assoc_type))]
+    NoAssocType {
+        name: Symbol,
+        ty: String,
+        #[label("associated type not found")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("type alias `{name}` refers to itself")]
+    #[diagnostic(code(tangic::typeck::alias_cycle))]
+    AliasCycle {
+        name: Symbol,
+        #[label("expanding `{name}` needs `{name}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("expected trait, found {found}")]
+    #[diagnostic(code(tangic::typeck::expected_trait))]
+    ExpectedTrait {
+        found: String,
+        #[label("not a trait")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("the trait bound `{ty}: {trait_}` is not satisfied")]
+    #[diagnostic(code(tangic::typeck::unsatisfied))]
+    Unsatisfied {
+        ty: String,
+        trait_: String,
+        #[label("the trait `{trait_}` is not implemented for `{ty}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("conflicting implementations of trait `{trait_}` for type `{ty}`")]
+    #[diagnostic(code(tangic::typeck::conflicting_impls))]
+    ConflictingImpls {
+        trait_: String,
+        ty: String,
+        #[label("first implementation here")]
+        first: Option<SourceSpan>,
+        #[label("conflicting implementation for `{ty}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("evaluation of a constant failed")]
+    #[diagnostic(code(tangic::typeck::const_eval))]
+    ConstEval {
+        reason: String,
+        #[label("{reason}")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("invalid format string")]
+    #[diagnostic(code(tangic::typeck::invalid_fmt))]
+    InvalidFmt {
+        reason: &'static str,
+        #[label("{reason}")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("{placeholders} placeholders but {found} arguments to format")]
+    #[diagnostic(code(tangic::typeck::fmt_arg_count))]
+    FmtArgCount {
+        placeholders: usize,
+        found: usize,
+        #[label("expected {placeholders} arguments")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("a `where` clause of `{trait_}` doesn't hold for `{ty}`")]
+    #[diagnostic(code(tangic::typeck::where_clause_false))]
+    WhereClauseFalse {
+        trait_: String,
+        ty: String,
+        #[label("implemented here")]
+        at: SourceSpan,
+        #[label("this is `false`")]
+        clause: Option<SourceSpan>,
+        file: FileId,
+    },
+    #[error("not all trait items implemented, missing: {missing}")]
+    #[diagnostic(code(tangic::typeck::missing_items))]
+    MissingItems {
+        /// The names of the missing items, quoted and separated by commas.
+        missing: String,
+        #[label("missing {missing} in implementation")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("`{name}` is not a member of trait `{trait_}`")]
+    #[diagnostic(code(tangic::typeck::not_member_of_trait))]
+    NotMemberOfTrait {
+        name: Symbol,
+        trait_: Symbol,
+        #[label("not a member of trait `{trait_}`")]
+        at: SourceSpan,
+        file: FileId,
+    },
+    #[error("{what} are not supported yet")]
+    #[diagnostic(code(tangic::typeck::unsupported))]
+    Unsupported {
+        what: &'static str,
+        #[label("not supported")]
+        at: SourceSpan,
+        file: FileId,
+    },
+}
+
+impl TypeckError {
+    /// The file the error is in.
+    pub fn file(&self) -> FileId {
+        match self {
+            Self::Mismatch { file, .. }
+            | Self::ArgCount { file, .. }
+            | Self::ParamCount { file, .. }
+            | Self::GenericArgCount { file, .. }
+            | Self::NotCallable { file, .. }
+            | Self::NoField { file, .. }
+            | Self::NoMethod { file, .. }
+            | Self::NoAssocItem { file, .. }
+            | Self::CannotDeref { file, .. }
+            | Self::UnaryOp { file, .. }
+            | Self::BinaryOp { file, .. }
+            | Self::InvalidAssign { file, .. }
+            | Self::MovedAndBound { file, .. }
+            | Self::AnnotationsNeeded { file, .. }
+            | Self::ExpectedValue { file, .. }
+            | Self::ExpectedType { file, .. }
+            | Self::NoAssocType { file, .. }
+            | Self::AliasCycle { file, .. }
+            | Self::ExpectedTrait { file, .. }
+            | Self::Unsatisfied { file, .. }
+            | Self::ConflictingImpls { file, .. }
+            | Self::ConstEval { file, .. }
+            | Self::InvalidFmt { file, .. }
+            | Self::FmtArgCount { file, .. }
+            | Self::WhereClauseFalse { file, .. }
+            | Self::MissingItems { file, .. }
+            | Self::NotMemberOfTrait { file, .. }
+            | Self::Unsupported { file, .. } => *file,
+        }
+    }
+}
@@ -0,0 +1,892 @@
+//! Compile-time evaluation of bodies, like functions over types such as
+//! `(type A, type B)bool TypeEq = A == B`, and of the `where` clauses of traits that are `bool`
+//! expressions. Types are values here, equal if they are the same type.
+
+use std::collections::HashMap;
+
+use tangic_middle::{
+    hir::{self, BinOp, DefKind, HirId, Intrinsic, Res, UintTy, UnOp},
+    span::Span,
+    ty::{Const, GenericArgs, Instance, ScalarInt, Size, Ty, TyKind, TypeckResults, ValTree},
+    DefId, DefIndex,
+};
+
+use crate::{collect::ItemTypes, traits, TypeckError};
+
+/// How deep calls can nest while evaluating, before evaluation gives up on them ever returning.
+pub const EVAL_DEPTH_LIMIT: usize = 64;
+
+/// How many times a loop can repeat while evaluating, before evaluation gives up on it ever
+/// ending.
+pub const EVAL_LOOP_LIMIT: usize = 1 << 16;
+
+/// A value computed at compile time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// An integer, `bool` or `char`.
+    Scalar(ScalarInt),
+    /// A type, from an expression naming one.
+    Type(Ty),
+    /// A `str`, like the ones `@fmt` makes. References to values are the values themselves.
+    Str(String),
+    /// `()` is the empty tuple.
+    Tuple(Vec<Value>),
+}
+
+impl Value {
+    pub fn from_bool(value: bool) -> Self {
+        Self::Scalar(ScalarInt::from(value))
+    }
+
+    fn unit() -> Self {
+        Self::Tuple(vec![])
+    }
+
+    /// The value as a constant, or `None` if there is a type in it.
+    pub fn to_valtree(&self) -> Option<ValTree> {
+        match self {
+            Self::Scalar(scalar) => Some(ValTree::Leaf(*scalar)),
+            Self::Type(_) => None,
+            Self::Str(string) => Some(ValTree::Branch(
+                string.bytes().map(|byte| ValTree::Leaf(ScalarInt::from(byte))).collect(),
+            )),
+            Self::Tuple(values) => values.iter().map(Value::to_valtree).collect::<Option<_>>().map(ValTree::Branch),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EvalError {
+    /// The value depends on type parameters, and is only known for the types they are
+    /// instantiated with.
+    TooGeneric,
+    /// The body has errors, which are reported already.
+    Reported,
+    Failed { reason: String, span: Span },
+}
+
+impl EvalError {
+    fn failed(reason: impl Into<String>, span: Span) -> Self {
+        Self::Failed {
+            reason: reason.into(),
+            span,
+        }
+    }
+}
+
+/// How evaluating an expression stopped other than with its value.
+enum Interrupt {
+    Return(Value),
+    Break,
+    Continue,
+    Error(EvalError),
+}
+
+impl From<EvalError> for Interrupt {
+    fn from(error: EvalError) -> Self {
+        Self::Error(error)
+    }
+}
+
+type EvalResult<T = Value> = Result<T, Interrupt>;
+
+pub struct ConstEvalCtxt<'a, 'b> {
+    items: &'b ItemTypes<'a>,
+    bodies: &'b HashMap<DefIndex, TypeckResults>,
+    depth: usize,
+}
+
+/// The function, const or `where` clause being evaluated.
+struct Frame<'b> {
+    /// The arguments its type parameters are instantiated with.
+    args: GenericArgs,
+    results: &'b TypeckResults,
+    locals: HashMap<HirId, Value>,
+}
+
+impl<'a, 'b> ConstEvalCtxt<'a, 'b> {
+    pub fn new(items: &'b ItemTypes<'a>, bodies: &'b HashMap<DefIndex, TypeckResults>) -> Self {
+        Self {
+            items,
+            bodies,
+            depth: 0,
+        }
+    }
+
+    /// Evaluates a function without arguments, like `TypeEq[i32, i32]`, or a const, to a
+    /// constant. `span` is where it is used.
+    pub fn eval_to_const(&mut self, instance: Instance, span: Span) -> Result<Const, EvalError> {
+        let value = match self.items.item(instance.def.index).map(|item| &item.kind) {
+            Some(hir::ItemKind::Const(_, body)) => self.eval_body(instance, body, vec![], span)?,
+            _ => self.eval_fn(instance, vec![], span)?,
+        };
+
+        match value.to_valtree() {
+            Some(valtree) => Ok(Const::Value(valtree)),
+            None => Err(EvalError::failed("types can't be kept as constants", span)),
+        }
+    }
+
+    /// Calls the function `instance` with `args`, from `span`.
+    pub fn eval_fn(&mut self, instance: Instance, args: Vec<Value>, span: Span) -> Result<Value, EvalError> {
+        let def = instance.def.index;
+
+        let body = match self.items.krate.owners.get(def) {
+            Some(Some(hir::Owner::Item(hir::Item {
+                kind: hir::ItemKind::Fn(_, _, body),
+                ..
+            })))
+            | Some(Some(hir::Owner::AssocItem(hir::AssocItem {
+                kind: hir::AssocItemKind::Fn(_, _, body),
+                ..
+            }))) => body.as_ref(),
+            _ => None,
+        };
+
+        let Some(body) = body else {
+            let name = self.items.instance_to_string(instance);
+            return Err(EvalError::failed(format!("`{name}` has no body to evaluate"), span));
+        };
+
+        self.eval_body(instance, body, args, span)
+    }
+
+    /// Evaluates `expr`, in the body or a `where` clause of `owner`, with `args` for the type
+    /// parameters of `owner`. Locals of the body have no value.
+    pub fn eval_expr_in(
+        &mut self,
+        owner: DefIndex,
+        args: GenericArgs,
+        expr: &hir::Expr,
+    ) -> Result<Value, EvalError> {
+        let results = self.bodies.get(&owner).ok_or(EvalError::Reported)?;
+        let mut frame = Frame {
+            args,
+            results,
+            locals: HashMap::new(),
+        };
+
+        match self.eval_expr(&mut frame, expr) {
+            Ok(value) | Err(Interrupt::Return(value)) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Err(Interrupt::Break | Interrupt::Continue) => unreachable!("lowering rejects them outside of loops"),
+        }
+    }
+
+    fn eval_body(
+        &mut self,
+        instance: Instance,
+        body: &hir::Body,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, EvalError> {
+        let results = self.bodies.get(&instance.def.index).ok_or(EvalError::Reported)?;
+
+        if self.depth == EVAL_DEPTH_LIMIT {
+            return Err(EvalError::failed(
+                format!("reached the limit of {EVAL_DEPTH_LIMIT} nested calls"),
+                span,
+            ));
+        }
+
+        let mut frame = Frame {
+            args: instance.args,
+            results,
+            locals: HashMap::new(),
+        };
+
+        for (param, arg) in body.params.iter().zip(args) {
+            self.bind_pat(&mut frame, param, arg)?;
+        }
+
+        self.depth += 1;
+        let result = self.eval_expr(&mut frame, &body.value);
+        self.depth -= 1;
+
+        match result {
+            Ok(value) | Err(Interrupt::Return(value)) => Ok(value),
+            Err(Interrupt::Error(error)) => Err(error),
+            Err(Interrupt::Break | Interrupt::Continue) => unreachable!("lowering rejects them outside of loops"),
+        }
+    }
+
+    fn bind_pat(&mut self, frame: &mut Frame, pat: &hir::Pat, value: Value) -> Result<(), EvalError> {
+        match &pat.kind {
+            hir::PatKind::Binding { mode, sub, .. } => {
+                if mode.by_ref {
+                    return Err(EvalError::failed("references can't be evaluated at compile time", pat.span));
+                }
+
+                if let Some(sub) = sub {
+                    self.bind_pat(frame, sub, value.clone())?;
+                }
+
+                frame.locals.insert(pat.hir_id, value);
+                Ok(())
+            }
+            hir::PatKind::Tuple(pats) => {
+                let Value::Tuple(values) = value else {
+                    return Err(EvalError::Reported);
+                };
+
+                pats.iter()
+                    .zip(values)
+                    .try_for_each(|(pat, value)| self.bind_pat(frame, pat, value))
+            }
+        }
+    }
+
+    /// The type of `expr` in the instance being evaluated.
+    fn expr_ty(&self, frame: &Frame, expr: &hir::Expr) -> Result<Ty, EvalError> {
+        let ty = frame.results.node_types.get(&expr.hir_id.local_id).ok_or(EvalError::Reported)?;
+        self.instantiate(frame, *ty, expr.span)
+    }
+
+    fn instantiate(&self, frame: &Frame, ty: Ty, span: Span) -> Result<Ty, EvalError> {
+        let ty = traits::fully_normalize(self.items, ty.subst(self.items.cx, &frame.args), span);
+
+        if ty.any(&mut |ty| ty.is_error()) {
+            return Err(EvalError::Reported);
+        }
+
+        Ok(ty)
+    }
+
+    fn eval_expr(&mut self, frame: &mut Frame, expr: &hir::Expr) -> EvalResult {
+        if let Some(&ty) = frame.results.type_exprs.get(&expr.hir_id.local_id) {
+            return Ok(Value::Type(self.instantiate(frame, ty, expr.span)?));
+        }
+
+        match &expr.kind {
+            hir::ExprKind::Lit(lit) => self.eval_lit(frame, expr, lit),
+            hir::ExprKind::Path(hir::QPath::Resolved(path)) => match path.res {
+                Res::Local(id) => match frame.locals.get(&id) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(unsupported("this variable", expr.span)),
+                },
+                Res::Def(DefKind::Const, def) => {
+                    let Some(hir::ItemKind::Const(_, body)) = self.items.item(def.index).map(|item| &item.kind)
+                    else {
+                        return Err(EvalError::Reported.into());
+                    };
+
+                    Ok(self.eval_body(Instance::mono(def), body, vec![], expr.span)?)
+                }
+                _ => Err(unsupported("this value", expr.span)),
+            },
+            hir::ExprKind::Tuple(exprs) => {
+                let values = exprs
+                    .iter()
+                    .map(|expr| self.eval_expr(frame, expr))
+                    .collect::<EvalResult<_>>()?;
+
+                Ok(Value::Tuple(values))
+            }
+            hir::ExprKind::Block(block) => self.eval_block(frame, block),
+            hir::ExprKind::Let(local) => {
+                let Some(init) = &local.init else {
+                    return Err(unsupported("`let` without a value", expr.span));
+                };
+
+                let value = self.eval_expr(frame, init)?;
+                self.bind_pat(frame, &local.pat, value)?;
+
+                Ok(Value::unit())
+            }
+            hir::ExprKind::Field(base, _) => {
+                let index = frame.results.field_indices.get(&expr.hir_id.local_id);
+
+                match (self.eval_expr(frame, base)?, index) {
+                    (Value::Tuple(mut values), Some(&index)) if index < values.len() => Ok(values.swap_remove(index)),
+                    _ => Err(unsupported("this field", expr.span)),
+                }
+            }
+            hir::ExprKind::Call(callee, args) => {
+                let TyKind::FnDef(def, fn_args) = *self.expr_ty(frame, callee)?.kind() else {
+                    return Err(unsupported("calls to function pointers", expr.span));
+                };
+
+                // types passed to type parameters come first, and are in `fn_args` already
+                let inputs = self.items.fn_sigs.get(&def.index).map_or(0, |sig| sig.inputs().len());
+                let args = &args[args.len().saturating_sub(inputs)..];
+
+                self.eval_call(frame, def, fn_args, None, args, expr.span)
+            }
+            hir::ExprKind::MethodCall { receiver, args, .. } => {
+                let Some(&(def, fn_args)) = frame.results.type_dependent_defs.get(&expr.hir_id.local_id) else {
+                    return Err(EvalError::Reported.into());
+                };
+
+                let fn_args = fn_args.subst(self.items.cx, &frame.args);
+
+                // `Type.function args` has no receiver
+                let receiver = match frame.results.type_exprs.contains_key(&receiver.hir_id.local_id) {
+                    true => None,
+                    false => Some(&**receiver),
+                };
+
+                self.eval_call(frame, def, fn_args, receiver, args, expr.span)
+            }
+            hir::ExprKind::Unary(op, operand) => self.eval_unary(frame, *op, operand, expr.span),
+            hir::ExprKind::Binary(op, lhs, rhs) => self.eval_binary(frame, *op, lhs, rhs, expr.span),
+            hir::ExprKind::Assign(place, value) => {
+                let value = self.eval_expr(frame, value)?;
+
+                match &place.kind {
+                    hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                        res: Res::Local(id), ..
+                    })) => {
+                        frame.locals.insert(*id, value);
+                        Ok(Value::unit())
+                    }
+                    _ => Err(unsupported("assigning to this place", place.span)),
+                }
+            }
+            hir::ExprKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.eval_bool(frame, condition)? {
+                    self.eval_block(frame, then)
+                } else if let Some(otherwise) = otherwise {
+                    self.eval_expr(frame, otherwise)
+                } else {
+                    Ok(Value::unit())
+                }
+            }
+            hir::ExprKind::Return(value) => {
+                let value = self.eval_expr(frame, value)?;
+                Err(Interrupt::Return(value))
+            }
+            hir::ExprKind::Loop(body) => {
+                for _ in 0..EVAL_LOOP_LIMIT {
+                    match self.eval_block(frame, body) {
+                        Ok(_) | Err(Interrupt::Continue) => {}
+                        Err(Interrupt::Break) => return Ok(Value::unit()),
+                        Err(interrupt) => return Err(interrupt),
+                    }
+                }
+
+                Err(EvalError::failed(format!("loop repeated more than {EVAL_LOOP_LIMIT} times"), expr.span).into())
+            }
+            hir::ExprKind::Break => Err(Interrupt::Break),
+            hir::ExprKind::Continue => Err(Interrupt::Continue),
+            hir::ExprKind::Path(hir::QPath::TypeRelative(..)) => Err(unsupported("this value", expr.span)),
+            hir::ExprKind::AddrOf(hir::Mutability::Not, inner) => self.eval_expr(frame, inner),
+            hir::ExprKind::AddrOf(hir::Mutability::Mut, _) => Err(unsupported("mutable references", expr.span)),
+            hir::ExprKind::Intrinsic(intrinsic, args) => self.eval_intrinsic(frame, *intrinsic, args, expr.span),
+            hir::ExprKind::Err => Err(EvalError::Reported.into()),
+        }
+    }
+
+    fn eval_block(&mut self, frame: &mut Frame, block: &hir::Block) -> EvalResult {
+        let mut value = Value::unit();
+
+        for statement in &block.statements {
+            value = self.eval_expr(frame, statement)?;
+        }
+
+        Ok(value)
+    }
+
+    fn eval_bool(&mut self, frame: &mut Frame, expr: &hir::Expr) -> EvalResult<bool> {
+        match self.eval_expr(frame, expr)? {
+            Value::Scalar(scalar) => scalar.try_to_bool().map_err(|_| EvalError::Reported.into()),
+            _ => Err(EvalError::Reported.into()),
+        }
+    }
+
+    fn eval_call(
+        &mut self,
+        frame: &mut Frame,
+        def: DefId,
+        fn_args: GenericArgs,
+        receiver: Option<&hir::Expr>,
+        args: &[hir::Expr],
+        span: Span,
+    ) -> EvalResult {
+        let fn_args = fn_args.subst(self.items.cx, &frame.args);
+
+        let Some(instance) = traits::resolve_instance(self.items, def, fn_args) else {
+            return Err(if fn_args.iter().any(|ty| ty.has_params()) {
+                EvalError::TooGeneric
+            } else {
+                EvalError::Reported
+            }
+            .into());
+        };
+
+        let values = receiver
+            .into_iter()
+            .chain(args)
+            .map(|arg| self.eval_expr(frame, arg))
+            .collect::<EvalResult<_>>()?;
+
+        Ok(self.eval_fn(instance, values, span)?)
+    }
+
+    fn eval_lit(&mut self, frame: &Frame, expr: &hir::Expr, lit: &hir::Lit) -> EvalResult {
+        let scalar = match *lit {
+            hir::Lit::Int(value, _) => {
+                let ty = self.expr_ty(frame, expr)?;
+                let (size, _) = self.int_layout(ty).ok_or(EvalError::Reported)?;
+
+                ScalarInt::try_from_uint(value, size)
+                    .ok_or_else(|| EvalError::failed("literal out of range for its type", expr.span))?
+            }
+            hir::Lit::Bool(value) => ScalarInt::from(value),
+            hir::Lit::Char(value) => ScalarInt::from(value),
+            hir::Lit::Byte(value) => ScalarInt::from(value),
+            hir::Lit::Float(..) => return Err(unsupported("floats", expr.span)),
+            hir::Lit::Str(ref string) => return Ok(Value::Str(string.clone())),
+            hir::Lit::ByteStr(_) => return Err(unsupported("byte strings", expr.span)),
+        };
+
+        Ok(Value::Scalar(scalar))
+    }
+
+    fn eval_unary(&mut self, frame: &mut Frame, op: UnOp, operand: &hir::Expr, span: Span) -> EvalResult {
+        let ty = self.expr_ty(frame, operand)?;
+        let Value::Scalar(scalar) = self.eval_expr(frame, operand)? else {
+            return Err(EvalError::Reported.into());
+        };
+
+        let Some((size, signed)) = self.int_layout(ty) else {
+            return Err(unsupported("this operation", span));
+        };
+
+        let bits = scalar.assert_bits(size);
+
+        let result = match op {
+            UnOp::Not if ty.is_bool() => Some(ScalarInt::from(bits == 0)),
+            UnOp::Not => ScalarInt::try_from_uint(size.truncate(!bits), size),
+            UnOp::Neg if signed => (size.sign_extend(bits) as i128)
+                .checked_neg()
+                .and_then(|value| ScalarInt::try_from_int(value, size)),
+            UnOp::Neg | UnOp::Deref => return Err(unsupported("this operation", span)),
+        };
+
+        match result {
+            Some(scalar) => Ok(Value::Scalar(scalar)),
+            None => Err(EvalError::failed("attempt to negate with overflow", span).into()),
+        }
+    }
+
+    fn eval_binary(
+        &mut self,
+        frame: &mut Frame,
+        op: BinOp,
+        lhs: &hir::Expr,
+        rhs: &hir::Expr,
+        span: Span,
+    ) -> EvalResult {
+        match op {
+            BinOp::And => return Ok(Value::from_bool(self.eval_bool(frame, lhs)? && self.eval_bool(frame, rhs)?)),
+            BinOp::Or => return Ok(Value::from_bool(self.eval_bool(frame, lhs)? || self.eval_bool(frame, rhs)?)),
+            _ => {}
+        }
+
+        let lhs_value = self.eval_expr(frame, lhs)?;
+        let rhs_value = self.eval_expr(frame, rhs)?;
+
+        if let BinOp::Eq | BinOp::Ne = op {
+            if let Value::Type(_) | Value::Str(_) | Value::Tuple(_) = lhs_value {
+                let equal = values_eq(&lhs_value, &rhs_value)?;
+                return Ok(Value::from_bool(equal == (op == BinOp::Eq)));
+            }
+        }
+
+        let (Value::Scalar(lhs_scalar), Value::Scalar(rhs_scalar)) = (lhs_value, rhs_value) else {
+            return Err(EvalError::Reported.into());
+        };
+
+        let lhs_ty = self.expr_ty(frame, lhs)?;
+        let rhs_ty = self.expr_ty(frame, rhs)?;
+
+        let (Some((size, signed)), Some((rhs_size, _))) = (self.int_layout(lhs_ty), self.int_layout(rhs_ty)) else {
+            return Err(unsupported("this operation", span));
+        };
+
+        let (a, b) = (lhs_scalar.assert_bits(size), rhs_scalar.assert_bits(rhs_size));
+        let (signed_a, signed_b) = (size.sign_extend(a) as i128, rhs_size.sign_extend(b) as i128);
+
+        let ordering = if signed { signed_a.cmp(&signed_b) } else { a.cmp(&b) };
+
+        let arithmetic = |unsigned: fn(u128, u128) -> Option<u128>, signed_op: fn(i128, i128) -> Option<i128>| {
+            if signed {
+                signed_op(signed_a, signed_b).and_then(|value| ScalarInt::try_from_int(value, size))
+            } else {
+                unsigned(a, b).and_then(|value| ScalarInt::try_from_uint(value, size))
+            }
+        };
+
+        let result = match op {
+            BinOp::Eq => return Ok(Value::from_bool(a == b)),
+            BinOp::Ne => return Ok(Value::from_bool(a != b)),
+            BinOp::Lt => return Ok(Value::from_bool(ordering.is_lt())),
+            BinOp::Gt => return Ok(Value::from_bool(ordering.is_gt())),
+            BinOp::Le => return Ok(Value::from_bool(ordering.is_le())),
+            BinOp::Ge => return Ok(Value::from_bool(ordering.is_ge())),
+            BinOp::BitAnd => ScalarInt::try_from_uint(a & b, size),
+            BinOp::BitOr => ScalarInt::try_from_uint(a | b, size),
+            BinOp::BitXor => ScalarInt::try_from_uint(a ^ b, size),
+            BinOp::Div | BinOp::Rem if b == 0 => {
+                return Err(EvalError::failed("attempt to divide by zero", span).into());
+            }
+            BinOp::Add => arithmetic(u128::checked_add, i128::checked_add),
+            BinOp::Sub => arithmetic(u128::checked_sub, i128::checked_sub),
+            BinOp::Mul => arithmetic(u128::checked_mul, i128::checked_mul),
+            BinOp::Div => arithmetic(u128::checked_div, i128::checked_div),
+            BinOp::Rem => arithmetic(u128::checked_rem, i128::checked_rem),
+            BinOp::Shl | BinOp::Shr if b >= u128::from(size.bits()) => None,
+            BinOp::Shl => ScalarInt::try_from_uint(size.truncate(a << b), size),
+            BinOp::Shr if signed => ScalarInt::try_from_int(signed_a >> b, size),
+            BinOp::Shr => ScalarInt::try_from_uint(a >> b, size),
+            BinOp::And | BinOp::Or => unreachable!("evaluated above"),
+        };
+
+        match result {
+            Some(scalar) => Ok(Value::Scalar(scalar)),
+            None => {
+                let reason = format!("attempt to compute `{}` with overflow", op.as_str());
+                Err(EvalError::failed(reason, span).into())
+            }
+        }
+    }
+
+    fn eval_intrinsic(
+        &mut self,
+        frame: &mut Frame,
+        intrinsic: Intrinsic,
+        args: &[hir::Expr],
+        span: Span,
+    ) -> EvalResult {
+        // reported by type checking, but the `where` clauses of traits are evaluated anyway
+        if intrinsic.arg_count().is_some_and(|count| args.len() != count) {
+            return Err(EvalError::Reported.into());
+        }
+
+        match intrinsic {
+            Intrinsic::CompileError => match self.eval_expr(frame, &args[0])? {
+                Value::Str(message) => Err(EvalError::failed(message, span).into()),
+                _ => Err(EvalError::Reported.into()),
+            },
+            Intrinsic::Fmt => {
+                let (format, args) = args.split_first().ok_or(EvalError::Reported)?;
+
+                let hir::ExprKind::Lit(hir::Lit::Str(format)) = &format.kind else {
+                    return Err(EvalError::Reported.into());
+                };
+
+                let pieces = fmt_pieces(format).map_err(|_| EvalError::Reported)?;
+                let mut formatted = pieces[0].clone();
+
+                for (arg, piece) in args.iter().zip(&pieces[1..]) {
+                    let value = self.eval_expr(frame, arg)?;
+
+                    let ty = match value {
+                        Value::Type(_) => None,
+                        _ => Some(self.expr_ty(frame, arg)?),
+                    };
+
+                    formatted.push_str(&self.display(&value, ty)?);
+                    formatted.push_str(piece);
+                }
+
+                Ok(Value::Str(formatted))
+            }
+            Intrinsic::TypeName => match self.eval_expr(frame, &args[0])? {
+                Value::Type(ty) if ty.has_params() => Err(EvalError::TooGeneric.into()),
+                Value::Type(ty) => Ok(Value::Str(self.items.ty_to_string(ty))),
+                _ => Err(EvalError::Reported.into()),
+            },
+            Intrinsic::CanCast => match (self.eval_expr(frame, &args[0])?, self.eval_expr(frame, &args[1])?) {
+                (Value::Type(from), Value::Type(to)) if from.has_params() || to.has_params() => {
+                    Err(EvalError::TooGeneric.into())
+                }
+                (Value::Type(from), Value::Type(to)) => Ok(Value::from_bool(can_cast(from, to))),
+                _ => Err(EvalError::Reported.into()),
+            },
+        }
+    }
+
+    /// `value`, of type `ty`, as `@fmt` formats it. Types are formatted by their name.
+    fn display(&self, value: &Value, ty: Option<Ty>) -> Result<String, EvalError> {
+        let string = match (value, ty) {
+            (Value::Type(ty), _) if ty.has_params() => return Err(EvalError::TooGeneric),
+            (Value::Type(ty), _) => self.items.ty_to_string(*ty),
+            (Value::Str(string), _) => string.clone(),
+            (Value::Scalar(scalar), Some(ty)) => {
+                let (size, signed) = self.int_layout(ty).ok_or(EvalError::Reported)?;
+                let bits = scalar.assert_bits(size);
+
+                match ty.kind() {
+                    TyKind::Bool => (bits != 0).to_string(),
+                    TyKind::Char => char::from_u32(bits as u32).ok_or(EvalError::Reported)?.to_string(),
+                    _ if signed => (size.sign_extend(bits) as i128).to_string(),
+                    _ => bits.to_string(),
+                }
+            }
+            (Value::Tuple(values), Some(ty)) => {
+                let TyKind::Tuple(tys) = ty.kind() else {
+                    return Err(EvalError::Reported);
+                };
+
+                let values = values
+                    .iter()
+                    .zip(tys.iter())
+                    .map(|(value, ty)| self.display(value, Some(*ty)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                format!("({})", values.join(", "))
+            }
+            _ => return Err(EvalError::Reported),
+        };
+
+        Ok(string)
+    }
+
+    /// The size of the values of `ty` and whether they are signed, for integers, `bool` and
+    /// `char`.
+    fn int_layout(&self, ty: Ty) -> Option<(Size, bool)> {
+        let cx = self.items.cx;
+
+        match *ty.kind() {
+            TyKind::Int(int) => Some((int.size(cx.data_layout.pointer_size), true)),
+            TyKind::Uint(uint) => Some((uint.size(cx.data_layout.pointer_size), false)),
+            TyKind::Bool => Some((Size::from_bytes(1), false)),
+            TyKind::Char => Some((Size::from_bytes(4), false)),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `a` and `b` are equal, which for types is only known if they are the same type or
+/// have no parameters.
+fn values_eq(a: &Value, b: &Value) -> Result<bool, EvalError> {
+    match (a, b) {
+        (Value::Type(a), Value::Type(b)) => {
+            if a == b {
+                Ok(true)
+            } else if a.has_params() || b.has_params() {
+                Err(EvalError::TooGeneric)
+            } else {
+                Ok(false)
+            }
+        }
+        (Value::Tuple(a), Value::Tuple(b)) if a.len() == b.len() => {
+            for (a, b) in a.iter().zip(b) {
+                if !values_eq(a, b)? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+        _ => Ok(a == b),
+    }
+}
+
+/// Whether values of `from` can be cast to `to`: between numbers, from `bool` and `char` to
+/// integers, from `u8` to `char`, and from references to references to the same type.
+fn can_cast(from: Ty, to: Ty) -> bool {
+    let numeric = |ty: Ty| ty.is_integral() || ty.is_floating_point();
+
+    match (from.kind(), to.kind()) {
+        _ if from == to => true,
+        _ if numeric(from) && numeric(to) => true,
+        (TyKind::Bool | TyKind::Char, _) => to.is_integral(),
+        (TyKind::Uint(UintTy::U8), TyKind::Char) => true,
+        (&TyKind::Ref(_, from, from_mutability), &TyKind::Ref(_, to, to_mutability)) => {
+            from == to && (from_mutability == to_mutability || from_mutability == hir::Mutability::Mut)
+        }
+        _ => false,
+    }
+}
+
+/// The literal pieces of a format string around its `{}` placeholders, with `{{` and `}}`
+/// unescaped. There is one more piece than there are placeholders.
+pub(crate) fn fmt_pieces(format: &str) -> Result<Vec<String>, &'static str> {
+    let mut pieces = vec![String::new()];
+    let mut chars = format.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match (char, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                pieces.last_mut().unwrap().push(char);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                pieces.push(String::new());
+            }
+            ('{', _) => return Err("expected `}` after `{`, use `{{` for a literal `{`"),
+            ('}', _) => return Err("unmatched `}`, use `}}` for a literal `}`"),
+            _ => pieces.last_mut().unwrap().push(char),
+        }
+    }
+
+    Ok(pieces)
+}
+
+fn unsupported(what: &str, span: Span) -> Interrupt {
+    EvalError::failed(format!("{what} can't be evaluated at compile time"), span).into()
+}
+
+/// Checks that the `where` clauses of traits that are expressions evaluate to `true` for each
+/// impl of the trait. Those that depend on the parameters of the impl are checked by
+/// monomorphization instead, for the types each instance of its methods gives them.
+pub(crate) fn check_trait_where_clauses(
+    items: &ItemTypes,
+    bodies: &HashMap<DefIndex, TypeckResults>,
+    errors: &mut Vec<TypeckError>,
+) {
+    for &trait_def in &items.traits {
+        let Some(hir::ItemKind::Trait { generics, .. }) = items.item(trait_def).map(|item| &item.kind) else {
+            continue;
+        };
+
+        let clauses: Vec<_> = generics
+            .predicates
+            .iter()
+            .filter_map(|predicate| match predicate {
+                hir::WherePredicate::Expr(expr) => Some(expr),
+                hir::WherePredicate::Bound { .. } => None,
+            })
+            .collect();
+
+        if clauses.is_empty() {
+            continue;
+        }
+
+        for &impl_def in items.trait_impls.get(&trait_def).into_iter().flatten() {
+            let trait_ref = items.impl_trait_refs[&impl_def];
+            let span = match items.item(impl_def).map(|item| &item.kind) {
+                Some(hir::ItemKind::Impl {
+                    trait_: Some(trait_), ..
+                }) => trait_.span,
+                _ => continue,
+            };
+
+            for clause in &clauses {
+                let mut ecx = ConstEvalCtxt::new(items, bodies);
+
+                let error = match ecx.eval_expr_in(trait_def, trait_ref.args, clause) {
+                    Ok(value) if value == Value::from_bool(false) => TypeckError::WhereClauseFalse {
+                        trait_: items.trait_ref_to_string(trait_ref),
+                        ty: items.ty_to_string(trait_ref.self_ty()),
+                        at: span.range().into(),
+                        clause: (clause.span.file == span.file).then(|| clause.span.range().into()),
+                        file: span.file,
+                    },
+                    Ok(_) | Err(EvalError::TooGeneric | EvalError::Reported) => continue,
+                    Err(EvalError::Failed { reason, span }) => TypeckError::ConstEval {
+                        reason,
+                        at: span.range().into(),
+                        file: span.file,
+                    },
+                };
+
+                errors.push(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+
+    use super::*;
+    use crate::tests::check;
+
+    /// `(type A, type B)bool TypeEq = A == B`, and `trait Distinct where !TypeEq(Self, Marker)`.
+    fn type_eq(b: &Builder) -> Vec<ast::Item> {
+        let type_eq = ast::Function {
+            params: b.params(&["A", "B"]),
+            ..b.function(
+                "TypeEq",
+                vec![],
+                b.ty("bool"),
+                vec![b.binary(ast::BinaryOp::Eq, b.name("A"), b.name("B"))],
+            )
+        };
+
+        let clause = b.unary(
+            ast::UnaryOp::Not,
+            b.call(b.name("TypeEq"), vec![b.name("Self"), b.name("Marker")]),
+        );
+
+        vec![
+            ast::Item::Fn(type_eq),
+            b.structure("Marker", &[], vec![]),
+            b.structure("Other", &[], vec![]),
+            b.structure("Wrap", &["T"], vec![]),
+            ast::Item::Trait(ast::Trait {
+                where_clause: vec![ast::WherePredicate::Expr(clause)],
+                ..b.trait_("Distinct", vec![], vec![])
+            }),
+        ]
+    }
+
+    fn impl_distinct(b: &Builder, params: &[&str], self_ty: ast::Type) -> ast::Item {
+        ast::Item::Impl(b.impl_(params, Some(b.trait_ref("Distinct", vec![])), self_ty, vec![]))
+    }
+
+    #[test]
+    fn where_clauses_of_traits_are_evaluated_for_impls() {
+        let b = Builder::new();
+        let mut items = type_eq(&b);
+        items.push(impl_distinct(&b, &[], b.ty("Other")));
+
+        let marker_impl = impl_distinct(&b, &[], b.ty("Marker"));
+        let ast::Item::Impl(impl_) = &marker_impl else {
+            unreachable!()
+        };
+        let impl_span = impl_.trait_.as_ref().unwrap().span;
+        items.push(marker_impl);
+
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::WhereClauseFalse { trait_, ty, at, clause: Some(_), .. }]
+                    if trait_ == "Distinct" && ty == "Marker" && *at == impl_span.range().into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn where_clauses_too_generic_for_the_impl_are_left_for_its_uses() {
+        let b = Builder::new();
+        let mut items = type_eq(&b);
+        // `TypeEq(Wrap[T], Marker)` isn't known before `T` is
+        items.push(impl_distinct(&b, &["T"], b.ty_args("Wrap", vec![b.ty("T")])));
+
+        let (_, _, errors) = check(&b, items);
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn intrinsics_with_the_wrong_argument_count_are_not_evaluated() {
+        let b = Builder::new();
+        let clause = b.intrinsic("compile_error", vec![]);
+        let items = vec![
+            b.structure("Marker", &[], vec![]),
+            ast::Item::Trait(ast::Trait {
+                where_clause: vec![ast::WherePredicate::Expr(clause)],
+                ..b.trait_("Broken", vec![], vec![])
+            }),
+            ast::Item::Impl(b.impl_(&[], Some(b.trait_ref("Broken", vec![])), b.ty("Marker"), vec![])),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(&errors[..], [TypeckError::ArgCount { expected: 1, found: 0, .. }]),
+            "{errors:?}"
+        );
+    }
+}
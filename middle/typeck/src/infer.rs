@@ -0,0 +1,366 @@
+//! Unification of inference variables. Variables are bound to the types they are unified with,
+//! which can be other variables, so what a variable stands for is found by following its
+//! bindings.
+
+use tangic_middle::{
+    index::IndexVec,
+    ty::{FloatVid, InferTy, IntVid, Region, RegionVid, Ty, TyKind, TyVid, TypeFolder},
+    Cx,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TypeError {
+    Mismatch,
+    /// A variable would have to contain itself, like `_ = &_`.
+    Cyclic,
+}
+
+#[derive(Clone, Copy)]
+struct TyVarValue {
+    value: Option<Ty>,
+    /// Whether `!` was coerced to it, so that it falls back to `!`.
+    diverging: bool,
+}
+
+#[derive(Clone, Default)]
+struct Tables {
+    ty_vars: IndexVec<TyVid, TyVarValue>,
+    int_vars: IndexVec<IntVid, Option<Ty>>,
+    float_vars: IndexVec<FloatVid, Option<Ty>>,
+}
+
+pub(crate) struct InferCtxt<'a> {
+    pub cx: &'a Cx,
+    tables: Tables,
+    next_region: u32,
+}
+
+impl<'a> InferCtxt<'a> {
+    pub fn new(cx: &'a Cx) -> Self {
+        Self {
+            cx,
+            tables: Tables::default(),
+            next_region: 0,
+        }
+    }
+
+    pub fn next_ty_var(&mut self) -> Ty {
+        let vid = self.tables.ty_vars.push(TyVarValue {
+            value: None,
+            diverging: false,
+        });
+
+        self.cx.mk_ty_var(vid)
+    }
+
+    pub fn next_int_var(&mut self) -> Ty {
+        self.cx.mk_int_var(self.tables.int_vars.push(None))
+    }
+
+    pub fn next_float_var(&mut self) -> Ty {
+        self.cx.mk_float_var(self.tables.float_vars.push(None))
+    }
+
+    pub fn next_region_var(&mut self) -> Region {
+        let vid = RegionVid::from_raw(self.next_region);
+        self.next_region += 1;
+
+        Region::Var(vid)
+    }
+
+    /// Makes the variable `vid` fall back to `!` if nothing else is inferred for it.
+    pub fn set_diverging(&mut self, vid: TyVid) {
+        self.tables.ty_vars[vid].diverging = true;
+    }
+
+    /// What `ty` is, if it is a variable that is bound. The types in it may still be unbound
+    /// variables.
+    pub fn shallow_resolve(&self, ty: Ty) -> Ty {
+        let value = match *ty.kind() {
+            TyKind::Infer(InferTy::TyVar(vid)) => self.tables.ty_vars[vid].value,
+            TyKind::Infer(InferTy::IntVar(vid)) => self.tables.int_vars[vid],
+            TyKind::Infer(InferTy::FloatVar(vid)) => self.tables.float_vars[vid],
+            _ => None,
+        };
+
+        value.map_or(ty, |value| self.shallow_resolve(value))
+    }
+
+    /// `ty` with every bound variable in it replaced by what it is bound to.
+    pub fn resolve_vars(&self, ty: Ty) -> Ty {
+        if !ty.has_infer() {
+            return ty;
+        }
+
+        ty.fold_with(&mut Resolver {
+            infcx: self,
+            fully: false,
+        })
+    }
+
+    /// `ty` with every variable in it replaced by what it is bound to, or by an error if it is
+    /// not bound, for when nothing more is going to be inferred.
+    pub fn resolve_vars_fully(&self, ty: Ty) -> Ty {
+        if !ty.has_infer() {
+            return ty;
+        }
+
+        ty.fold_with(&mut Resolver {
+            infcx: self,
+            fully: true,
+        })
+    }
+
+    /// Runs `f`, undoing what it bound if it fails.
+    pub fn commit_if_ok<T, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, E>) -> Result<T, E> {
+        let snapshot = self.tables.clone();
+        let result = f(self);
+
+        if result.is_err() {
+            self.tables = snapshot;
+        }
+
+        result
+    }
+
+    /// Runs `f`, undoing what it bound either way.
+    pub fn probe<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let snapshot = self.tables.clone();
+        let result = f(self);
+        self.tables = snapshot;
+
+        result
+    }
+
+    /// Makes `a` and `b` the same type, binding the variables in them, or leaves them as they
+    /// were if they can't be.
+    pub fn unify(&mut self, a: Ty, b: Ty) -> Result<(), TypeError> {
+        self.commit_if_ok(|infcx| infcx.unify_inner(a, b))
+    }
+
+    /// Makes each type of `a` the same as the one of `b` at its index, like [`Self::unify`].
+    pub fn unify_args(&mut self, a: &[Ty], b: &[Ty]) -> Result<(), TypeError> {
+        self.commit_if_ok(|infcx| infcx.unify_lists(a, b))
+    }
+
+    fn unify_inner(&mut self, a: Ty, b: Ty) -> Result<(), TypeError> {
+        let (a, b) = (self.shallow_resolve(a), self.shallow_resolve(b));
+
+        if a == b {
+            return Ok(());
+        }
+
+        match (a.kind(), b.kind()) {
+            // errors are reported already
+            (TyKind::Error, _) | (_, TyKind::Error) => Ok(()),
+            (&TyKind::Infer(InferTy::TyVar(vid)), _) => self.bind_ty_var(vid, b),
+            (_, &TyKind::Infer(InferTy::TyVar(vid))) => self.bind_ty_var(vid, a),
+            (
+                &TyKind::Infer(InferTy::IntVar(vid)),
+                TyKind::Infer(InferTy::IntVar(_)) | TyKind::Int(_) | TyKind::Uint(_),
+            ) => {
+                self.tables.int_vars[vid] = Some(b);
+                Ok(())
+            }
+            (TyKind::Int(_) | TyKind::Uint(_), &TyKind::Infer(InferTy::IntVar(vid))) => {
+                self.tables.int_vars[vid] = Some(a);
+                Ok(())
+            }
+            (
+                &TyKind::Infer(InferTy::FloatVar(vid)),
+                TyKind::Infer(InferTy::FloatVar(_)) | TyKind::Float(_),
+            ) => {
+                self.tables.float_vars[vid] = Some(b);
+                Ok(())
+            }
+            (TyKind::Float(_), &TyKind::Infer(InferTy::FloatVar(vid))) => {
+                self.tables.float_vars[vid] = Some(a);
+                Ok(())
+            }
+            (&TyKind::Ref(_, a, a_mut), &TyKind::Ref(_, b, b_mut)) if a_mut == b_mut => {
+                self.unify_inner(a, b)
+            }
+            (TyKind::Adt(a_def, a_args), TyKind::Adt(b_def, b_args)) if a_def == b_def => {
+                self.unify_lists(a_args, b_args)
+            }
+            (TyKind::FnDef(a_def, a_args), TyKind::FnDef(b_def, b_args)) if a_def == b_def => {
+                self.unify_lists(a_args, b_args)
+            }
+            (TyKind::FnPtr(a_sig), TyKind::FnPtr(b_sig)) => {
+                self.unify_lists(&a_sig.inputs_and_output, &b_sig.inputs_and_output)
+            }
+            (TyKind::Tuple(a_tys), TyKind::Tuple(b_tys)) => self.unify_lists(a_tys, b_tys),
+            (TyKind::Array(a, a_len), TyKind::Array(b, b_len)) if a_len == b_len => {
+                self.unify_inner(*a, *b)
+            }
+            (&TyKind::Slice(a), &TyKind::Slice(b)) => self.unify_inner(a, b),
+            (TyKind::Projection(a_alias), TyKind::Projection(b_alias)) if a_alias.def == b_alias.def => {
+                self.unify_lists(&a_alias.args, &b_alias.args)
+            }
+            _ => Err(TypeError::Mismatch),
+        }
+    }
+
+    fn unify_lists(&mut self, a: &[Ty], b: &[Ty]) -> Result<(), TypeError> {
+        if a.len() != b.len() {
+            return Err(TypeError::Mismatch);
+        }
+
+        a.iter().zip(b).try_for_each(|(&a, &b)| self.unify_inner(a, b))
+    }
+
+    fn bind_ty_var(&mut self, vid: TyVid, ty: Ty) -> Result<(), TypeError> {
+        let ty = self.resolve_vars(ty);
+
+        if ty.any(&mut |ty| matches!(ty.kind(), TyKind::Infer(InferTy::TyVar(v)) if *v == vid)) {
+            return Err(TypeError::Cyclic);
+        }
+
+        self.tables.ty_vars[vid].value = Some(ty);
+        Ok(())
+    }
+
+    /// Binds the variables that nothing was inferred for to what they fall back to: integers to
+    /// `i32`, floats to `f64`, and variables that `!` was coerced to to `!`.
+    pub fn fallback(&mut self) {
+        let types = &self.cx.types;
+
+        for value in self.tables.int_vars.iter_mut().filter(|value| value.is_none()) {
+            *value = Some(types.i32);
+        }
+
+        for value in self.tables.float_vars.iter_mut().filter(|value| value.is_none()) {
+            *value = Some(types.f64);
+        }
+
+        for var in self.tables.ty_vars.iter_mut() {
+            if var.value.is_none() && var.diverging {
+                var.value = Some(types.never);
+            }
+        }
+    }
+}
+
+struct Resolver<'b, 'a> {
+    infcx: &'b InferCtxt<'a>,
+    /// Whether unbound variables are replaced by errors.
+    fully: bool,
+}
+
+impl TypeFolder for Resolver<'_, '_> {
+    fn cx(&self) -> &Cx {
+        self.infcx.cx
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        if !ty.has_infer() {
+            return ty;
+        }
+
+        let ty = self.infcx.shallow_resolve(ty);
+
+        match ty.kind() {
+            TyKind::Infer(_) if self.fully => self.infcx.cx.types.error,
+            TyKind::Infer(_) => ty,
+            _ => ty.super_fold_with(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_middle::{hir::Mutability, DataLayout};
+
+    use super::*;
+
+    #[test]
+    fn variables_unify_with_what_they_stand_for() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let [a, b] = [infcx.next_ty_var(), infcx.next_ty_var()];
+
+        // `(a, &b)` and `(bool, &a)`
+        let lhs = cx.mk_tuple(&[a, cx.mk_ref(Region::Static, b, Mutability::Not)]);
+        let rhs = cx.mk_tuple(&[cx.types.bool, cx.mk_ref(Region::Static, a, Mutability::Not)]);
+
+        assert_eq!(infcx.unify(lhs, rhs), Ok(()));
+        assert_eq!(infcx.resolve_vars(lhs), infcx.resolve_vars(rhs));
+        assert_eq!(infcx.shallow_resolve(b), cx.types.bool);
+    }
+
+    #[test]
+    fn failed_unification_binds_nothing() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let a = infcx.next_ty_var();
+
+        // `a` would be bound to `bool` before `char` fails to unify with `i32`
+        let lhs = cx.mk_tuple(&[a, cx.types.char]);
+        let rhs = cx.mk_tuple(&[cx.types.bool, cx.types.i32]);
+
+        assert_eq!(infcx.unify(lhs, rhs), Err(TypeError::Mismatch));
+        assert_eq!(infcx.shallow_resolve(a), a);
+    }
+
+    #[test]
+    fn variables_cannot_contain_themselves() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let a = infcx.next_ty_var();
+
+        assert_eq!(infcx.unify(a, cx.mk_slice(a)), Err(TypeError::Cyclic));
+    }
+
+    #[test]
+    fn literals_only_unify_with_numbers_of_their_kind() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let (int, float) = (infcx.next_int_var(), infcx.next_float_var());
+
+        assert_eq!(infcx.unify(int, cx.types.bool), Err(TypeError::Mismatch));
+        assert_eq!(infcx.unify(int, cx.types.f32), Err(TypeError::Mismatch));
+        assert_eq!(infcx.unify(float, cx.types.u8), Err(TypeError::Mismatch));
+
+        assert_eq!(infcx.unify(int, cx.types.u8), Ok(()));
+        assert_eq!(infcx.unify(float, cx.types.f32), Ok(()));
+        assert_eq!(
+            infcx.resolve_vars(cx.mk_tuple(&[int, float])),
+            cx.mk_tuple(&[cx.types.u8, cx.types.f32])
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let (int, float, diverging, unbound) = (
+            infcx.next_int_var(),
+            infcx.next_float_var(),
+            infcx.next_ty_var(),
+            infcx.next_ty_var(),
+        );
+
+        let TyKind::Infer(InferTy::TyVar(vid)) = *diverging.kind() else {
+            unreachable!()
+        };
+        infcx.set_diverging(vid);
+        infcx.fallback();
+
+        assert_eq!(infcx.shallow_resolve(int), cx.types.i32);
+        assert_eq!(infcx.shallow_resolve(float), cx.types.f64);
+        assert_eq!(infcx.shallow_resolve(diverging), cx.types.never);
+        // nothing to fall back to, once nothing more is inferred it is an error
+        assert_eq!(infcx.shallow_resolve(unbound), unbound);
+        assert_eq!(infcx.resolve_vars_fully(unbound), cx.types.error);
+    }
+
+    #[test]
+    fn probes_are_undone() {
+        let cx = Cx::new(DataLayout::host());
+        let mut infcx = InferCtxt::new(&cx);
+        let a = infcx.next_ty_var();
+
+        assert_eq!(infcx.probe(|infcx| infcx.unify(a, cx.types.bool)), Ok(()));
+        assert_eq!(infcx.shallow_resolve(a), a);
+    }
+}
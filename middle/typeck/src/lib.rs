@@ -0,0 +1,150 @@
+//! Type checking. Collects the types of the items of a [HIR](tangic_middle::hir) crate from
+//! their signatures, then infers the type of every expression in their bodies and checks that
+//! they fit together.
+
+use std::collections::HashMap;
+
+use tangic_middle::{hir, ty::TypeckResults, Cx, DefIndex};
+
+mod check;
+mod collect;
+mod error;
+mod eval;
+mod infer;
+mod lower;
+mod print;
+mod traits;
+
+pub use collect::ItemTypes;
+pub use error::TypeckError;
+pub use eval::{ConstEvalCtxt, EvalError, Value};
+pub use traits::{fully_normalize, resolve_instance};
+
+/// The types of the items of a crate, and of the expressions in their bodies.
+pub struct CrateTypes<'a> {
+    pub items: ItemTypes<'a>,
+    /// The results of checking each function, const and static with a body, and the `where`
+    /// clauses that are expressions of other items.
+    pub bodies: HashMap<DefIndex, TypeckResults>,
+}
+
+pub fn check_crate<'a>(cx: &'a Cx, krate: &'a hir::Crate) -> (CrateTypes<'a>, Vec<TypeckError>) {
+    let (items, mut errors) = collect::collect(cx, krate);
+    traits::check_impls(&items, &mut errors);
+
+    let mut bodies = HashMap::new();
+
+    for (def, owner) in krate.owners.iter_enumerated() {
+        let results = match owner {
+            Some(hir::Owner::Item(item)) => match &item.kind {
+                hir::ItemKind::Fn(sig, generics, Some(body)) => {
+                    check::check_fn(&items, def, item.span, sig, generics, body, &mut errors)
+                }
+                hir::ItemKind::Const(ty, body) | hir::ItemKind::Static(ty, body) => {
+                    check::check_const(&items, def, ty, body, &mut errors)
+                }
+                hir::ItemKind::Struct(generics, _)
+                | hir::ItemKind::Enum(generics, _)
+                | hir::ItemKind::Fn(_, generics, None)
+                | hir::ItemKind::TypeAlias(generics, _)
+                | hir::ItemKind::Trait { generics, .. }
+                | hir::ItemKind::Impl { generics, .. }
+                    if has_where_exprs(generics) =>
+                {
+                    check::check_where_clauses(&items, def, generics, item.span, &mut errors)
+                }
+                _ => continue,
+            },
+            Some(hir::Owner::AssocItem(item)) => match &item.kind {
+                hir::AssocItemKind::Fn(sig, generics, Some(body)) => {
+                    check::check_fn(&items, def, item.span, sig, generics, body, &mut errors)
+                }
+                hir::AssocItemKind::Fn(_, generics, None) | hir::AssocItemKind::Type { generics, .. }
+                    if has_where_exprs(generics) =>
+                {
+                    check::check_where_clauses(&items, def, generics, item.span, &mut errors)
+                }
+                _ => continue,
+            },
+            None => continue,
+        };
+
+        bodies.insert(def, results);
+    }
+
+    // after the bodies, which the functions the clauses call are evaluated with
+    eval::check_trait_where_clauses(&items, &bodies, &mut errors);
+
+    (CrateTypes { items, bodies }, errors)
+}
+
+fn has_where_exprs(generics: &hir::Generics) -> bool {
+    generics
+        .predicates
+        .iter()
+        .any(|predicate| matches!(predicate, hir::WherePredicate::Expr(_)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+    use tangic_middle::DataLayout;
+
+    use super::*;
+
+    /// Resolves, lowers and checks `items`, which have to get past the passes before type
+    /// checking. What is checked is leaked, to live as long as the test.
+    pub(crate) fn check(
+        b: &Builder,
+        items: Vec<ast::Item>,
+    ) -> (CrateTypes<'static>, &'static hir::Crate, Vec<TypeckError>) {
+        let krate = b.file(items);
+
+        let (resolutions, errors) = tangic_resolve::resolve(&krate);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let (hir, errors) = tangic_ast_lowering::lower(&krate, &resolutions);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let cx = Box::leak(Box::new(Cx::new(DataLayout::host())));
+        let hir = Box::leak(Box::new(hir));
+        let (types, errors) = check_crate(cx, hir);
+
+        (types, hir, errors)
+    }
+
+    /// The item or associated item named `name`.
+    pub(crate) fn def_named(hir: &hir::Crate, name: &str) -> DefIndex {
+        hir.owners
+            .iter_enumerated()
+            .find_map(|(def, owner)| {
+                let owner_name = match owner.as_ref()? {
+                    hir::Owner::Item(item) => item.name?,
+                    hir::Owner::AssocItem(item) => item.name,
+                };
+
+                (owner_name.as_str() == name).then_some(def)
+            })
+            .unwrap_or_else(|| panic!("no item named `{name}`"))
+    }
+
+    /// The statements of the body of the function `def`.
+    pub(crate) fn statements(hir: &hir::Crate, def: DefIndex) -> &[hir::Expr] {
+        let body = match &hir.owners[def] {
+            Some(hir::Owner::Item(hir::Item {
+                kind: hir::ItemKind::Fn(_, _, Some(body)),
+                ..
+            }))
+            | Some(hir::Owner::AssocItem(hir::AssocItem {
+                kind: hir::AssocItemKind::Fn(_, _, Some(body)),
+                ..
+            })) => body,
+            owner => panic!("expected a function with a body, found {owner:?}"),
+        };
+
+        match &body.value.kind {
+            hir::ExprKind::Block(block) => &block.statements,
+            kind => panic!("expected a block, found {kind:?}"),
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+
+use tangic_middle::{
+    hir::{self, DefKind, Res},
+    span::Span,
+    symbol::{kw, Symbol},
+    ty::{self, Const, ParamConst, Predicate, ProjectionPredicate, Region, ScalarInt, Ty, TyKind, ValTree},
+    DefId, DefIndex,
+};
+
+use crate::{collect::ItemTypes, TypeckError};
+
+/// Lowers the types written in the HIR to [`Ty`]s, in signatures and in bodies alike. What is
+/// left out, like elided lifetimes and generic arguments, is up to where the type is written.
+pub(crate) trait TyLowerer<'a> {
+    fn items(&self) -> &ItemTypes<'a>;
+
+    /// What the type alias or associated type of an impl `def` stands for, or the self type of
+    /// the impl `def`, used at `span`.
+    fn type_of(&mut self, def: DefIndex, span: Span) -> Ty;
+
+    /// The region of a reference without a lifetime.
+    fn re_infer(&mut self) -> Region;
+
+    /// A type to infer, for generic arguments that are left out. `None` where they can't be.
+    fn ty_infer(&mut self) -> Option<Ty>;
+
+    fn report(&mut self, error: TypeckError);
+
+    fn lower_ty(&mut self, ty: &hir::Ty) -> Ty {
+        let cx = self.items().cx;
+
+        match &ty.kind {
+            hir::TyKind::Prim(prim) => cx.mk_prim(*prim),
+            hir::TyKind::Never => cx.types.never,
+            hir::TyKind::Tuple(types) => {
+                let types: Vec<_> = types.iter().map(|ty| self.lower_ty(ty)).collect();
+                cx.mk_tuple(&types)
+            }
+            hir::TyKind::Ref(lifetime, mutability, inner) => {
+                let region = self.lower_region(lifetime.as_ref());
+                let inner = self.lower_ty(inner);
+
+                cx.mk_ref(region, inner, *mutability)
+            }
+            hir::TyKind::Path(hir::QPath::Resolved(path)) => self.lower_path(path),
+            hir::TyKind::Path(hir::QPath::TypeRelative(base, segment)) => {
+                self.lower_type_relative(base, segment)
+            }
+            hir::TyKind::Projection { ty, trait_, item } => {
+                let self_ty = self.lower_ty(ty);
+
+                let Res::Def(DefKind::Trait, trait_def) = trait_.path.res else {
+                    return cx.types.error;
+                };
+
+                let trait_args = self.lower_trait_args(trait_);
+
+                self.project(self_ty, trait_def.index, &trait_args, item.name.name, &mut HashSet::new())
+                    .unwrap_or_else(|| self.report_no_assoc_type(self_ty, item.name.name, item.span))
+            }
+            hir::TyKind::Array(inner, len) => {
+                let inner = self.lower_ty(inner);
+
+                match self.lower_array_len(len) {
+                    Some(len) => cx.mk_array(inner, len),
+                    None => cx.types.error,
+                }
+            }
+            hir::TyKind::Slice(inner) => {
+                let inner = self.lower_ty(inner);
+                cx.mk_slice(inner)
+            }
+            hir::TyKind::Impl(_) => self.report_unsupported("`impl Trait` types", ty.span),
+            hir::TyKind::Dyn(_) => self.report_unsupported("`dyn Trait` types", ty.span),
+            hir::TyKind::Err => cx.types.error,
+        }
+    }
+
+    fn lower_region(&mut self, lifetime: Option<&hir::Lifetime>) -> Region {
+        let param = lifetime.and_then(|lifetime| match lifetime.res {
+            Res::Def(DefKind::LifetimeParam, def) => self.items().params.get(&def.index).copied(),
+            _ => None,
+        });
+
+        match param {
+            Some(param) => Region::EarlyParam(ty::EarlyParamRegion {
+                index: param.index,
+                name: param.name,
+            }),
+            None => self.re_infer(),
+        }
+    }
+
+    /// The type a resolved path refers to, with the generic arguments of its last segment.
+    fn lower_path(&mut self, path: &hir::Path) -> Ty {
+        let cx = self.items().cx;
+        let last = path.segments.last().expect("paths have a segment");
+
+        match path.res {
+            Res::Def(DefKind::Struct | DefKind::Enum, def) => {
+                let args = self.lower_generic_args(def.index, last, path.span);
+                cx.mk_adt(def, &args)
+            }
+            Res::Def(DefKind::TypeAlias, def) => {
+                let args = self.lower_generic_args(def.index, last, path.span);
+                self.type_of(def.index, path.span).subst(cx, &args)
+            }
+            Res::Def(DefKind::TyParam, def) => match self.items().params.get(&def.index) {
+                Some(param) => cx.mk_param(param.index, param.name),
+                None => cx.types.error,
+            },
+            Res::SelfTy(def) => match self.items().item(def.index).map(|item| &item.kind) {
+                Some(hir::ItemKind::Impl { .. }) => self.type_of(def.index, path.span),
+                _ => cx.mk_param(0, kw::SelfUpper),
+            },
+            Res::Def(DefKind::AssocType, _) => {
+                self.report_unsupported("associated types without a self type", path.span)
+            }
+            Res::Def(kind, _) => {
+                self.report(TypeckError::ExpectedType {
+                    found: format!("{} `{}`", describe(kind), last.name),
+                    at: path.span.range().into(),
+                    file: path.span.file,
+                });
+
+                cx.types.error
+            }
+            Res::Local(_) | Res::Err => cx.types.error,
+        }
+    }
+
+    /// The type arguments given to `def` in `segment`, or types to infer for them if there are
+    /// none and they can be inferred. Lifetime arguments are left out, like regions are of
+    /// [`TyKind::Adt`](ty::TyKind::Adt).
+    fn lower_generic_args(&mut self, def: DefIndex, segment: &hir::PathSegment, span: Span) -> Vec<Ty> {
+        let cx = self.items().cx;
+        let expected = self.items().generics_of(def).own_types().count();
+
+        let mut args = vec![];
+
+        for arg in &segment.args {
+            match arg {
+                hir::GenericArg::Type(ty) => args.push(self.lower_ty(ty)),
+                hir::GenericArg::Lifetime(_) => {}
+                hir::GenericArg::Binding { ty, .. } => {
+                    self.report_unsupported("associated type bindings outside of bounds", ty.span);
+                }
+            }
+        }
+
+        if args.len() == expected {
+            return args;
+        }
+
+        if args.is_empty() {
+            if let Some(args) = (0..expected).map(|_| self.ty_infer()).collect::<Option<Vec<_>>>() {
+                return args;
+            }
+        }
+
+        self.report(TypeckError::GenericArgCount {
+            name: segment.name.name,
+            expected,
+            found: args.len(),
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        args.resize(expected, cx.types.error);
+        args
+    }
+
+    /// The type arguments of a trait in a bound, after its self type.
+    fn lower_trait_args(&mut self, trait_ref: &hir::TraitRef) -> Vec<Ty> {
+        let Some(segment) = trait_ref.path.segments.last() else {
+            return vec![];
+        };
+
+        segment
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                hir::GenericArg::Type(ty) => Some(self.lower_ty(ty)),
+                hir::GenericArg::Lifetime(_) | hir::GenericArg::Binding { .. } => None,
+            })
+            .collect()
+    }
+
+    /// `self_ty: Trait[args]` for the trait `trait_ref` refers to, or `None` if it is not one.
+    fn lower_trait_ref(&mut self, self_ty: Ty, trait_ref: &hir::TraitRef) -> Option<ty::TraitRef> {
+        let cx = self.items().cx;
+        let span = trait_ref.path.span;
+        let last = trait_ref.path.segments.last().expect("paths have a segment");
+
+        let found = match trait_ref.path.res {
+            Res::Def(DefKind::Trait, def) => {
+                let args: Vec<_> = [self_ty]
+                    .into_iter()
+                    .chain(self.lower_trait_args(trait_ref))
+                    .collect();
+
+                return Some(ty::TraitRef {
+                    def,
+                    args: cx.mk_args(&args),
+                });
+            }
+            Res::Def(kind, _) => format!("{} `{}`", describe(kind), last.name),
+            Res::SelfTy(_) => "self type `Self`".to_owned(),
+            Res::Local(_) => format!("local variable `{}`", last.name),
+            Res::Err => return None,
+        };
+
+        self.report(TypeckError::ExpectedTrait {
+            found,
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        None
+    }
+
+    /// `self_ty: Bound` for each of `bounds`, and the associated types they bind, like the
+    /// `Item = i32` of `T: Iterator[Item = i32]`.
+    fn lower_bounds(&mut self, self_ty: Ty, bounds: &[hir::TraitRef], predicates: &mut Vec<Predicate>) {
+        for bound in bounds {
+            let Some(trait_ref) = self.lower_trait_ref(self_ty, bound) else {
+                continue;
+            };
+
+            predicates.push(Predicate::Trait(trait_ref));
+
+            let bindings = bound.path.segments.last().into_iter().flat_map(|segment| &segment.args);
+
+            for arg in bindings {
+                let hir::GenericArg::Binding { name, ty } = arg else {
+                    continue;
+                };
+
+                let term = self.lower_ty(ty);
+                let projection = self.project(
+                    self_ty,
+                    trait_ref.def.index,
+                    &trait_ref.args[1..],
+                    name.name,
+                    &mut HashSet::new(),
+                );
+
+                match projection.map(|projection| projection.kind()) {
+                    Some(&TyKind::Projection(alias)) => {
+                        predicates.push(Predicate::Projection(ProjectionPredicate { alias, term }));
+                    }
+                    _ => {
+                        self.report_no_assoc_type(self_ty, name.name, name.span);
+                    }
+                }
+            }
+        }
+    }
+
+    /// `T.Item`, an associated type of a trait that `T` is bounded by, or of the trait or impl
+    /// that `Self` is of.
+    fn lower_type_relative(&mut self, base: &hir::Ty, segment: &hir::PathSegment) -> Ty {
+        let cx = self.items().cx;
+        let self_ty = self.lower_ty(base);
+        let name = segment.name.name;
+
+        let res = match &base.kind {
+            hir::TyKind::Path(hir::QPath::Resolved(path)) => path.res,
+            _ => Res::Err,
+        };
+
+        let found = match res {
+            Res::SelfTy(def) => match &self.items().item(def.index).map(|item| &item.kind) {
+                Some(hir::ItemKind::Trait { .. }) => {
+                    let trait_args = self.items().identity_args(def.index);
+                    self.project(self_ty, def.index, &trait_args[1..], name, &mut HashSet::new())
+                }
+                Some(hir::ItemKind::Impl { trait_, .. }) => {
+                    let assoc = self
+                        .items()
+                        .find_assoc_item(def.index, name)
+                        .filter(|assoc| matches!(assoc.kind, hir::AssocItemKind::Type { .. }));
+
+                    match (assoc, trait_) {
+                        (Some(assoc), _) => Some(self.type_of(assoc.hir_id.owner, segment.span)),
+                        (None, Some(trait_ref)) => self.project_bound(self_ty, trait_ref, name),
+                        (None, None) => None,
+                    }
+                }
+                _ => None,
+            },
+            Res::Def(DefKind::TyParam, def) => {
+                let bounds = self.items().bounds.get(&def.index).cloned().unwrap_or_default();
+                bounds
+                    .iter()
+                    .find_map(|bound| self.project_bound(self_ty, bound, name))
+            }
+            Res::Err => return cx.types.error,
+            _ => None,
+        };
+
+        found.unwrap_or_else(|| {
+            if self_ty.is_error() {
+                return self_ty;
+            }
+
+            self.report_no_assoc_type(self_ty, name, segment.span)
+        })
+    }
+
+    /// The associated type named `name` of the trait in `bound`, for `self_ty`.
+    fn project_bound(&mut self, self_ty: Ty, bound: &hir::TraitRef, name: Symbol) -> Option<Ty> {
+        let Res::Def(DefKind::Trait, trait_def) = bound.path.res else {
+            return None;
+        };
+
+        let trait_args = self.lower_trait_args(bound);
+        self.project(self_ty, trait_def.index, &trait_args, name, &mut HashSet::new())
+    }
+
+    /// The associated type named `name` of `trait_def` or one of its supertraits, as a
+    /// projection of `self_ty`.
+    fn project(
+        &mut self,
+        self_ty: Ty,
+        trait_def: DefIndex,
+        trait_args: &[Ty],
+        name: Symbol,
+        visited: &mut HashSet<DefIndex>,
+    ) -> Option<Ty> {
+        let cx = self.items().cx;
+
+        if !visited.insert(trait_def) {
+            return None;
+        }
+
+        let args: Vec<_> = [self_ty].into_iter().chain(trait_args.iter().copied()).collect();
+
+        if let Some(assoc) = self.items().find_assoc_item(trait_def, name) {
+            if let hir::AssocItemKind::Type { .. } = assoc.kind {
+                return Some(cx.mk_projection(DefId::local(assoc.hir_id.owner), &args));
+            }
+        }
+
+        let Some(hir::ItemKind::Trait { supertraits, .. }) =
+            self.items().item(trait_def).map(|item| &item.kind)
+        else {
+            return None;
+        };
+
+        supertraits.iter().find_map(|supertrait| {
+            let Res::Def(DefKind::Trait, def) = supertrait.path.res else {
+                return None;
+            };
+
+            // the arguments of a supertrait are written in terms of the `Self` of the subtrait
+            let super_args: Vec<_> = self
+                .lower_trait_args(supertrait)
+                .into_iter()
+                .map(|arg| arg.subst(cx, &args))
+                .collect();
+
+            self.project(self_ty, def.index, &super_args, name, visited)
+        })
+    }
+
+    /// The length of an array type, which is an integer literal or a const parameter until
+    /// constants can be evaluated.
+    fn lower_array_len(&mut self, len: &hir::Expr) -> Option<Const> {
+        let cx = self.items().cx;
+
+        match &len.kind {
+            hir::ExprKind::Lit(hir::Lit::Int(value, None | Some(hir::PrimTy::Uint(hir::UintTy::Usize)))) => {
+                if let Some(scalar) = ScalarInt::try_from_target_usize(*value, cx) {
+                    return Some(Const::Value(ValTree::Leaf(scalar)));
+                }
+            }
+            hir::ExprKind::Path(hir::QPath::Resolved(hir::Path {
+                res: Res::Def(DefKind::ConstParam, def),
+                ..
+            })) => {
+                if let Some(param) = self.items().params.get(&def.index) {
+                    return Some(Const::Param(ParamConst {
+                        index: param.index,
+                        name: param.name,
+                    }));
+                }
+            }
+            hir::ExprKind::Err => return None,
+            _ => {}
+        }
+
+        self.report_unsupported("array lengths other than `usize` literals and const parameters", len.span);
+        None
+    }
+
+    fn report_no_assoc_type(&mut self, self_ty: Ty, name: Symbol, span: Span) -> Ty {
+        let ty = self.items().ty_to_string(self_ty);
+
+        self.report(TypeckError::NoAssocType {
+            name,
+            ty,
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        self.items().cx.types.error
+    }
+
+    fn report_unsupported(&mut self, what: &'static str, span: Span) -> Ty {
+        self.report(TypeckError::Unsupported {
+            what,
+            at: span.range().into(),
+            file: span.file,
+        });
+
+        self.items().cx.types.error
+    }
+}
+
+/// What a definition of `kind` is called in diagnostics.
+pub(crate) fn describe(kind: DefKind) -> &'static str {
+    match kind {
+        DefKind::Mod => "module",
+        DefKind::Struct => "struct",
+        DefKind::Enum => "enum",
+        DefKind::Variant => "variant",
+        DefKind::Fn => "function",
+        DefKind::TypeAlias => "type alias",
+        DefKind::Const => "constant",
+        DefKind::Static => "static",
+        DefKind::Trait => "trait",
+        DefKind::Impl => "impl",
+        DefKind::AssocType => "associated type",
+        DefKind::AssocFn => "associated function",
+        DefKind::TyParam => "type parameter",
+        DefKind::LifetimeParam => "lifetime parameter",
+        DefKind::ConstParam => "const parameter",
+    }
+}
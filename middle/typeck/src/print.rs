@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use tangic_middle::{
+    hir::{self, FloatTy, IntTy, LangItem, Mutability, UintTy},
+    ty::{Const, FnSig, InferTy, Instance, TraitRef, Ty, TyKind, ValTree},
+    DefId,
+};
+
+use crate::collect::ItemTypes;
+
+impl ItemTypes<'_> {
+    /// `ty` as it would be written in the source, for diagnostics. Types that are left to infer
+    /// are `_`, or `{integer}` and `{float}` for those of literals.
+    pub fn ty_to_string(&self, ty: Ty) -> String {
+        let mut out = String::new();
+        self.print_ty(&mut out, ty);
+        out
+    }
+
+    /// `Trait[args]`, without the self type.
+    pub fn trait_ref_to_string(&self, trait_ref: TraitRef) -> String {
+        let mut out = self
+            .name(trait_ref.def.index)
+            .map_or("<unnamed>", |name| name.as_str())
+            .to_owned();
+
+        self.print_args(&mut out, &trait_ref.args[1..]);
+        out
+    }
+
+    /// `function[args]`, with the arguments of its trait and then its own. Functions of impls
+    /// are `Type.function[args]`, with the self type of the impl and only their own arguments.
+    pub fn instance_to_string(&self, instance: Instance) -> String {
+        let mut out = String::new();
+        let mut args = &instance.args[..];
+
+        match self.parents.get(&instance.def.index) {
+            Some(&parent)
+                if matches!(
+                    self.item(parent).map(|item| &item.kind),
+                    Some(hir::ItemKind::Impl { .. })
+                ) =>
+            {
+                let (impl_args, own) = args.split_at(self.type_params(parent).len());
+                self.print_ty(&mut out, self.types[&parent].subst(self.cx, impl_args));
+                out.push('.');
+                args = own;
+            }
+            Some(&parent) => {
+                out.push_str(self.name(parent).map_or("<unnamed>", |name| name.as_str()));
+                out.push('.');
+            }
+            None => {}
+        }
+
+        out.push_str(self.name(instance.def.index).map_or("<unnamed>", |name| name.as_str()));
+        self.print_args(&mut out, args);
+        out
+    }
+
+    fn print_ty(&self, out: &mut String, ty: Ty) {
+        let name = |def: DefId| {
+            self.name(def.index).map_or("<unnamed>", |name| name.as_str())
+        };
+
+        match *ty.kind() {
+            TyKind::Bool => out.push_str("bool"),
+            TyKind::Char => out.push_str("char"),
+            TyKind::Int(int) => out.push_str(int_name(int)),
+            TyKind::Uint(uint) => out.push_str(uint_name(uint)),
+            TyKind::Float(FloatTy::F32) => out.push_str("f32"),
+            TyKind::Float(FloatTy::F64) => out.push_str("f64"),
+            TyKind::Str => out.push_str("str"),
+            TyKind::Never => out.push('!'),
+            TyKind::Ref(_, inner, mutability) => {
+                out.push_str(match mutability {
+                    Mutability::Not => "&",
+                    Mutability::Mut => "&mut ",
+                });
+                self.print_ty(out, inner);
+            }
+            TyKind::Adt(def, args) if self.krate.lang_items.get(&LangItem::Option) == Some(&def) => {
+                out.push('?');
+
+                if let [inner] = &args[..] {
+                    self.print_ty(out, *inner);
+                }
+            }
+            TyKind::Adt(def, args) => {
+                out.push_str(name(def));
+                self.print_args(out, &args);
+            }
+            TyKind::FnDef(def, _) => {
+                if let Some(&sig) = self.fn_sigs.get(&def.index) {
+                    self.print_sig(out, sig);
+                    out.push(' ');
+                }
+
+                out.push_str(name(def));
+            }
+            TyKind::FnPtr(sig) => self.print_sig(out, sig),
+            TyKind::Tuple(tys) => {
+                out.push('(');
+                self.print_list(out, &tys);
+                out.push(')');
+            }
+            TyKind::Array(inner, ref len) => {
+                out.push('[');
+
+                match len {
+                    Const::Param(param) => out.push_str(param.name.as_str()),
+                    Const::Value(ValTree::Leaf(scalar)) => {
+                        let _ = write!(out, "{scalar}");
+                    }
+                    Const::Infer(_) | Const::Value(ValTree::Branch(_)) => out.push('_'),
+                }
+
+                out.push(']');
+                self.print_ty(out, inner);
+            }
+            TyKind::Slice(inner) => {
+                out.push_str("[]");
+                self.print_ty(out, inner);
+            }
+            TyKind::Param(param) => out.push_str(param.name.as_str()),
+            TyKind::Infer(InferTy::TyVar(_)) => out.push('_'),
+            TyKind::Infer(InferTy::IntVar(_)) => out.push_str("{integer}"),
+            TyKind::Infer(InferTy::FloatVar(_)) => out.push_str("{float}"),
+            TyKind::Projection(alias) => {
+                out.push('(');
+                self.print_ty(out, alias.self_ty());
+                out.push_str(" as ");
+
+                if let Some(&trait_def) = self.parents.get(&alias.def.index) {
+                    out.push_str(self.name(trait_def).map_or("<unnamed>", |name| name.as_str()));
+                }
+
+                self.print_args(out, &alias.args[1..]);
+                out.push_str(").");
+                out.push_str(name(alias.def));
+            }
+            TyKind::Error => out.push_str("{error}"),
+        }
+    }
+
+    fn print_args(&self, out: &mut String, args: &[Ty]) {
+        if !args.is_empty() {
+            out.push('[');
+            self.print_list(out, args);
+            out.push(']');
+        }
+    }
+
+    /// `(A, B)R`, like functions are declared.
+    fn print_sig(&self, out: &mut String, sig: FnSig) {
+        out.push('(');
+        self.print_list(out, sig.inputs());
+        out.push(')');
+        self.print_ty(out, sig.output());
+    }
+
+    fn print_list(&self, out: &mut String, tys: &[Ty]) {
+        for (i, &ty) in tys.iter().enumerate() {
+            if i != 0 {
+                out.push_str(", ");
+            }
+
+            self.print_ty(out, ty);
+        }
+    }
+}
+
+fn int_name(int: IntTy) -> &'static str {
+    match int {
+        IntTy::Isize => "isize",
+        IntTy::I8 => "i8",
+        IntTy::I16 => "i16",
+        IntTy::I32 => "i32",
+        IntTy::I64 => "i64",
+        IntTy::I128 => "i128",
+    }
+}
+
+fn uint_name(uint: UintTy) -> &'static str {
+    match uint {
+        UintTy::Usize => "usize",
+        UintTy::U8 => "u8",
+        UintTy::U16 => "u16",
+        UintTy::U32 => "u32",
+        UintTy::U64 => "u64",
+        UintTy::U128 => "u128",
+    }
+}
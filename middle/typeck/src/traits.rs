@@ -0,0 +1,1080 @@
+//! The trait solver. An obligation is a predicate that has to hold somewhere, like `T: Trait`,
+//! which holds by a `where` clause in scope or by the one impl of the trait that applies to the
+//! types. Projections like `(T as Trait).Item` are normalized to the type that impl gives the
+//! associated type, or stay as they are if `T` is only known by its bounds.
+
+use std::mem;
+
+use tangic_middle::{
+    hir,
+    span::Span,
+    ty::{AliasTy, GenericArgs, Instance, Predicate, TraitRef, Ty, TyKind, TypeFolder},
+    Cx, DefId, DefIndex,
+};
+
+use crate::{collect::ItemTypes, infer::InferCtxt, TypeckError};
+
+/// How deep normalizing a type can go, for associated types that are defined in terms of
+/// themselves.
+const NORMALIZE_DEPTH_LIMIT: usize = 64;
+
+/// A predicate that has to hold, and where it comes from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Obligation {
+    pub predicate: Predicate,
+    pub span: Span,
+}
+
+/// How a trait bound holds.
+enum Selection {
+    /// By a `where` clause, or by a bound of an associated type, so there is no impl to look in.
+    Bound,
+    /// By an impl, instantiated with `args`, whose own predicates have to hold too.
+    Impl {
+        def: DefIndex,
+        args: Vec<Ty>,
+        nested: Vec<Predicate>,
+    },
+    /// The types have errors, which are reported already.
+    Error,
+}
+
+/// The bound doesn't hold, whatever the variables in it turn out to be.
+struct NoSolution;
+
+/// What a projection normalizes to.
+enum Projected {
+    /// The type an impl gives it, with the predicates of the impl.
+    Normalized(Ty, Vec<Predicate>),
+    /// Nothing, it holds by a bound, so the projection is a type of its own.
+    Rigid,
+    /// It's not known yet which impl applies.
+    Ambiguous,
+    NoSolution,
+}
+
+pub(crate) struct SelectionCtxt<'a, 'b> {
+    pub items: &'b ItemTypes<'a>,
+    pub infcx: &'b mut InferCtxt<'a>,
+    /// The predicates that hold where the obligations are, with the supertraits they imply.
+    pub param_env: &'b [Predicate],
+}
+
+impl SelectionCtxt<'_, '_> {
+    /// Finds how `trait_ref` holds, binding the variables in it to what they have to be for it
+    /// to hold, if only one way can. `Ok(None)` if it might hold in more than one way.
+    fn select(&mut self, trait_ref: TraitRef) -> Result<Option<Selection>, NoSolution> {
+        let cx = self.items.cx;
+        let args: Vec<_> = trait_ref
+            .args
+            .iter()
+            .map(|&arg| self.infcx.resolve_vars(arg))
+            .collect();
+
+        if args.iter().any(|arg| arg.any(&mut |ty| ty.is_error())) {
+            return Ok(Some(Selection::Error));
+        }
+
+        let self_ty = args[0];
+
+        if self_ty.is_ty_var() {
+            return Ok(None);
+        }
+
+        for predicate in self.param_env {
+            if let Predicate::Trait(bound) = predicate {
+                if bound.def == trait_ref.def && self.infcx.unify_args(&bound.args, &args).is_ok() {
+                    return Ok(Some(Selection::Bound));
+                }
+            }
+        }
+
+        // `(T as Trait).Item: Bound` holds by a bound of `Item`
+        if let TyKind::Projection(alias) = self_ty.kind() {
+            for predicate in self.items.item_bounds.get(&alias.def.index).into_iter().flatten() {
+                if let Predicate::Trait(bound) = predicate.subst(cx, &alias.args) {
+                    if bound.def == trait_ref.def && self.infcx.unify_args(&bound.args, &args).is_ok() {
+                        return Ok(Some(Selection::Bound));
+                    }
+                }
+            }
+        }
+
+        let items = self.items;
+        let impls = items.trait_impls.get(&trait_ref.def.index).map_or(&[][..], Vec::as_slice);
+        let mut candidates = impls.iter().copied().filter(|&impl_def| {
+            self.infcx
+                .probe(|infcx| match_impl(items, infcx, impl_def, &args).is_some())
+        });
+
+        let Some(impl_def) = candidates.next() else {
+            return Err(NoSolution);
+        };
+
+        if candidates.next().is_some() {
+            return Ok(None);
+        }
+
+        let impl_args = match_impl(items, self.infcx, impl_def, &args).expect("the impl matched in the probe");
+        let nested = items
+            .predicates_of(impl_def)
+            .into_iter()
+            .map(|predicate| predicate.subst(cx, &impl_args))
+            .collect();
+
+        Ok(Some(Selection::Impl {
+            def: impl_def,
+            args: impl_args,
+            nested,
+        }))
+    }
+
+    /// Whether `trait_ref` holds or might hold, without binding anything.
+    pub fn may_hold(&mut self, trait_ref: TraitRef) -> bool {
+        let items = self.items;
+        let param_env = self.param_env;
+
+        self.infcx.probe(|infcx| {
+            let mut selcx = SelectionCtxt {
+                items,
+                infcx,
+                param_env,
+            };
+
+            selcx.select(trait_ref).is_ok()
+        })
+    }
+
+    fn project(&mut self, alias: AliasTy) -> Projected {
+        let cx = self.items.cx;
+
+        if self.infcx.shallow_resolve(alias.self_ty()).is_ty_var() {
+            return Projected::Ambiguous;
+        }
+
+        // `where T: Trait[Item = U]`
+        for predicate in self.param_env {
+            if let Predicate::Projection(projection) = predicate {
+                if projection.alias.def == alias.def
+                    && self.infcx.unify_args(&projection.alias.args, &alias.args).is_ok()
+                {
+                    return Projected::Normalized(projection.term, vec![]);
+                }
+            }
+        }
+
+        let Some(trait_ref) = self.trait_ref_of(alias) else {
+            return Projected::NoSolution;
+        };
+
+        match self.select(trait_ref) {
+            Err(NoSolution) => Projected::NoSolution,
+            Ok(None) => Projected::Ambiguous,
+            Ok(Some(Selection::Bound)) => Projected::Rigid,
+            Ok(Some(Selection::Error)) => Projected::Normalized(cx.types.error, vec![]),
+            Ok(Some(Selection::Impl { def, mut args, nested })) => {
+                let ty = self
+                    .items
+                    .name(alias.def.index)
+                    .and_then(|name| self.items.find_assoc_item(def, name))
+                    .and_then(|assoc| self.items.types.get(&assoc.hir_id.owner));
+
+                // the generic arguments of the associated type itself come after those of the
+                // trait, and after those of the impl in the impl
+                args.extend_from_slice(&alias.args[trait_ref.args.len()..]);
+
+                match ty {
+                    Some(ty) => Projected::Normalized(ty.subst(cx, &args), nested),
+                    // missing from the impl, which is reported
+                    None => Projected::Normalized(cx.types.error, nested),
+                }
+            }
+        }
+    }
+
+    /// The trait the associated type of `alias` is of, for the arguments of `alias`.
+    fn trait_ref_of(&self, alias: AliasTy) -> Option<TraitRef> {
+        let trait_def = *self.items.parents.get(&alias.def.index)?;
+        let count = self.items.generics_of(trait_def).type_count as usize;
+
+        Some(TraitRef {
+            def: DefId::local(trait_def),
+            args: self.items.cx.mk_args(&alias.args[..count]),
+        })
+    }
+}
+
+/// Instantiates the impl `impl_def` with fresh variables, if the trait it implements can be
+/// the one with `args`, and binds them so.
+fn match_impl(items: &ItemTypes, infcx: &mut InferCtxt, impl_def: DefIndex, args: &[Ty]) -> Option<Vec<Ty>> {
+    let cx = items.cx;
+    let impl_args: Vec<_> = items
+        .type_params(impl_def)
+        .iter()
+        .map(|_| infcx.next_ty_var())
+        .collect();
+
+    let impl_trait_args: Vec<_> = items.impl_trait_refs[&impl_def]
+        .args
+        .iter()
+        .map(|ty| ty.subst(cx, &impl_args))
+        .collect();
+
+    infcx.unify_args(&impl_trait_args, args).ok()?;
+    Some(impl_args)
+}
+
+/// `ty` with the projections in it normalized, as far as they can be yet. Those that can't are
+/// replaced by variables, with obligations to find what they are later.
+pub(crate) fn normalize(selcx: &mut SelectionCtxt, ty: Ty, span: Span, obligations: &mut Vec<Obligation>) -> Ty {
+    if !ty.any(&mut |ty| matches!(ty.kind(), TyKind::Projection(_))) {
+        return ty;
+    }
+
+    ty.fold_with(&mut Normalizer {
+        selcx,
+        span,
+        obligations,
+        depth: 0,
+    })
+}
+
+struct Normalizer<'s, 'a, 'b> {
+    selcx: &'s mut SelectionCtxt<'a, 'b>,
+    span: Span,
+    obligations: &'s mut Vec<Obligation>,
+    depth: usize,
+}
+
+impl TypeFolder for Normalizer<'_, '_, '_> {
+    fn cx(&self) -> &Cx {
+        self.selcx.items.cx
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        let cx = self.selcx.items.cx;
+        let ty = ty.super_fold_with(self);
+
+        let TyKind::Projection(alias) = *ty.kind() else {
+            return ty;
+        };
+
+        match self.selcx.project(alias) {
+            Projected::Normalized(normalized, nested) => {
+                let span = self.span;
+                self.obligations
+                    .extend(nested.into_iter().map(|predicate| Obligation { predicate, span }));
+
+                if self.depth == NORMALIZE_DEPTH_LIMIT {
+                    return cx.types.error;
+                }
+
+                // what the impl gives may be a projection again
+                self.depth += 1;
+                let normalized = normalized.fold_with(self);
+                self.depth -= 1;
+
+                normalized
+            }
+            Projected::Rigid => ty,
+            Projected::Ambiguous => {
+                let var = self.selcx.infcx.next_ty_var();
+
+                self.obligations.push(Obligation {
+                    predicate: Predicate::Projection(tangic_middle::ty::ProjectionPredicate {
+                        alias,
+                        term: var,
+                    }),
+                    span: self.span,
+                });
+
+                var
+            }
+            Projected::NoSolution => {
+                // reported as the bound that doesn't hold
+                if let Some(trait_ref) = self.selcx.trait_ref_of(alias) {
+                    self.obligations.push(Obligation {
+                        predicate: Predicate::Trait(trait_ref),
+                        span: self.span,
+                    });
+                }
+
+                cx.types.error
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum FulfillmentErrorKind {
+    Unsatisfied,
+    /// A projection normalized to `found`, but `expected` was bound to it.
+    Mismatch { expected: Ty, found: Ty },
+    /// There was nothing to tell which impl applies.
+    Ambiguous,
+}
+
+#[derive(Debug)]
+pub(crate) struct FulfillmentError {
+    pub obligation: Obligation,
+    pub kind: FulfillmentErrorKind,
+}
+
+/// The obligations that have yet to be proven.
+#[derive(Default)]
+pub(crate) struct FulfillmentCtxt {
+    pending: Vec<Obligation>,
+}
+
+impl FulfillmentCtxt {
+    pub fn register(&mut self, obligation: Obligation) {
+        self.pending.push(obligation);
+    }
+
+    /// Proves the obligations that can be proven with what is known so far, which may bind
+    /// variables and so make others provable, until none can be.
+    pub fn select_where_possible(&mut self, selcx: &mut SelectionCtxt) -> Vec<FulfillmentError> {
+        let mut errors = vec![];
+
+        loop {
+            let mut progress = false;
+
+            for obligation in mem::take(&mut self.pending) {
+                match process(selcx, obligation) {
+                    Ok(Some(nested)) => {
+                        progress = true;
+                        self.pending.extend(nested);
+                    }
+                    Ok(None) => self.pending.push(obligation),
+                    Err(kind) => {
+                        progress = true;
+                        errors.push(FulfillmentError { obligation, kind });
+                    }
+                }
+            }
+
+            if !progress {
+                return errors;
+            }
+        }
+    }
+
+    /// Proves every obligation, the ones that can't be are errors.
+    pub fn select_all_or_error(&mut self, selcx: &mut SelectionCtxt) -> Vec<FulfillmentError> {
+        let mut errors = self.select_where_possible(selcx);
+
+        errors.extend(mem::take(&mut self.pending).into_iter().map(|obligation| FulfillmentError {
+            obligation,
+            kind: FulfillmentErrorKind::Ambiguous,
+        }));
+
+        errors
+    }
+}
+
+/// Proves `obligation`, giving the obligations it needs in turn, or `None` if it can't be
+/// proven yet.
+fn process(selcx: &mut SelectionCtxt, obligation: Obligation) -> Result<Option<Vec<Obligation>>, FulfillmentErrorKind> {
+    let cx = selcx.items.cx;
+    let span = obligation.span;
+    let mut nested = vec![];
+
+    let predicates = match obligation.predicate {
+        Predicate::Trait(trait_ref) => {
+            // the predicates of impls can have projections in them, which are proven (and
+            // reported) as what they normalize to
+            let args: Vec<_> = trait_ref
+                .args
+                .iter()
+                .map(|&arg| normalize(selcx, arg, span, &mut nested))
+                .collect();
+
+            if args[..] != trait_ref.args[..] {
+                let predicate = Predicate::Trait(TraitRef {
+                    def: trait_ref.def,
+                    args: cx.mk_args(&args),
+                });
+
+                nested.push(Obligation { predicate, span });
+                return Ok(Some(nested));
+            }
+
+            match selcx.select(trait_ref) {
+                Err(NoSolution) => return Err(FulfillmentErrorKind::Unsatisfied),
+                Ok(None) => return Ok(None),
+                Ok(Some(Selection::Impl { nested, .. })) => nested,
+                Ok(Some(Selection::Bound | Selection::Error)) => vec![],
+            }
+        }
+        Predicate::Projection(projection) => {
+            let (ty, predicates) = match selcx.project(projection.alias) {
+                Projected::Normalized(ty, predicates) => (normalize(selcx, ty, span, &mut nested), predicates),
+                Projected::Rigid => (cx.mk_ty(TyKind::Projection(projection.alias)), vec![]),
+                Projected::Ambiguous => return Ok(None),
+                Projected::NoSolution => return Err(FulfillmentErrorKind::Unsatisfied),
+            };
+
+            if selcx.infcx.unify(ty, projection.term).is_err() {
+                return Err(FulfillmentErrorKind::Mismatch {
+                    expected: projection.term,
+                    found: ty,
+                });
+            }
+
+            predicates
+        }
+    };
+
+    nested.extend(predicates.into_iter().map(|predicate| Obligation { predicate, span }));
+    Ok(Some(nested))
+}
+
+/// `predicates`, and the predicates of the traits in them, like the supertraits of a bound.
+pub(crate) fn elaborate(items: &ItemTypes, predicates: Vec<Predicate>) -> Vec<Predicate> {
+    let cx = items.cx;
+    let mut elaborated = vec![];
+    let mut stack = predicates;
+    stack.reverse();
+
+    while let Some(predicate) = stack.pop() {
+        if elaborated.contains(&predicate) {
+            continue;
+        }
+
+        elaborated.push(predicate);
+
+        if let Predicate::Trait(trait_ref) = predicate {
+            let implied = items.predicates.get(&trait_ref.def.index).into_iter().flatten();
+            stack.extend(implied.rev().map(|implied| implied.subst(cx, &trait_ref.args)));
+        }
+    }
+
+    elaborated
+}
+
+/// Reports the obligations that don't hold. Ambiguities are only reported if `report_ambiguity`,
+/// since they are usually caused by other errors.
+pub(crate) fn report_fulfillment_errors(
+    items: &ItemTypes,
+    infcx: &InferCtxt,
+    errors: Vec<FulfillmentError>,
+    report_ambiguity: bool,
+    out: &mut Vec<TypeckError>,
+) {
+    let has_error = |ty: Ty| ty.any(&mut |ty| ty.is_error());
+    let mut reported_ambiguity = false;
+
+    for error in errors {
+        let span = error.obligation.span;
+        let predicate = error.obligation.predicate.fold_with(&mut ResolveVars { infcx });
+
+        let error = match error.kind {
+            FulfillmentErrorKind::Unsatisfied => {
+                let trait_ref = match predicate {
+                    Predicate::Trait(trait_ref) => Some(trait_ref),
+                    Predicate::Projection(projection) => {
+                        let trait_def = items.parents.get(&projection.alias.def.index);
+
+                        trait_def.map(|&trait_def| TraitRef {
+                            def: DefId::local(trait_def),
+                            args: items.cx.mk_args(
+                                &projection.alias.args[..items.generics_of(trait_def).type_count as usize],
+                            ),
+                        })
+                    }
+                };
+
+                let Some(trait_ref) = trait_ref.filter(|trait_ref| !trait_ref.args.iter().any(|&ty| has_error(ty)))
+                else {
+                    continue;
+                };
+
+                TypeckError::Unsatisfied {
+                    ty: items.ty_to_string(trait_ref.self_ty()),
+                    trait_: items.trait_ref_to_string(trait_ref),
+                    at: span.range().into(),
+                    file: span.file,
+                }
+            }
+            FulfillmentErrorKind::Mismatch { expected, found } => {
+                let (expected, found) = (infcx.resolve_vars(expected), infcx.resolve_vars(found));
+
+                if has_error(expected) || has_error(found) {
+                    continue;
+                }
+
+                TypeckError::Mismatch {
+                    expected: items.ty_to_string(expected),
+                    found: items.ty_to_string(found),
+                    at: span.range().into(),
+                    origin: None,
+                    reason: "",
+                    help: None,
+                    file: span.file,
+                }
+            }
+            FulfillmentErrorKind::Ambiguous => {
+                if !report_ambiguity || reported_ambiguity {
+                    continue;
+                }
+
+                reported_ambiguity = true;
+
+                TypeckError::AnnotationsNeeded {
+                    at: span.range().into(),
+                    file: span.file,
+                }
+            }
+        };
+
+        out.push(error);
+    }
+}
+
+struct ResolveVars<'b, 'a> {
+    infcx: &'b InferCtxt<'a>,
+}
+
+impl TypeFolder for ResolveVars<'_, '_> {
+    fn cx(&self) -> &Cx {
+        self.infcx.cx
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        self.infcx.resolve_vars(ty)
+    }
+}
+
+/// `ty` with every projection in it normalized, for a type without parameters, like one in an
+/// instance of a function at `span`. The bounds that don't hold are reported by type checking,
+/// and normalize to errors.
+pub fn fully_normalize(items: &ItemTypes, ty: Ty, span: Span) -> Ty {
+    let mut infcx = InferCtxt::new(items.cx);
+    let mut selcx = SelectionCtxt {
+        items,
+        infcx: &mut infcx,
+        param_env: &[],
+    };
+
+    let mut obligations = vec![];
+    let ty = normalize(&mut selcx, ty, span, &mut obligations);
+    let mut fulfillment = FulfillmentCtxt { pending: obligations };
+    fulfillment.select_all_or_error(&mut selcx);
+
+    infcx.resolve_vars_fully(ty)
+}
+
+/// The function called by calling `def` with `args`, which have no parameters. Methods of traits
+/// resolve to the method of the impl that applies, or to the default of the trait if the impl
+/// has none. `None` if no impl applies, which type checking reports.
+pub fn resolve_instance(items: &ItemTypes, def: DefId, args: GenericArgs) -> Option<Instance> {
+    let cx = items.cx;
+
+    let Some(&trait_def) = items
+        .parents
+        .get(&def.index)
+        .filter(|&&parent| matches!(items.item(parent).map(|item| &item.kind), Some(hir::ItemKind::Trait { .. })))
+    else {
+        return Some(Instance { def, args });
+    };
+
+    let count = items.generics_of(trait_def).type_count as usize;
+    let trait_ref = TraitRef {
+        def: DefId::local(trait_def),
+        args: cx.mk_args(&args[..count]),
+    };
+
+    let mut infcx = InferCtxt::new(cx);
+    let mut selcx = SelectionCtxt {
+        items,
+        infcx: &mut infcx,
+        param_env: &[],
+    };
+
+    let Ok(Some(Selection::Impl { def: impl_def, args: impl_args, .. })) = selcx.select(trait_ref) else {
+        return None;
+    };
+
+    let method = items
+        .name(def.index)
+        .and_then(|name| items.find_assoc_item(impl_def, name));
+
+    let Some(method) = method else {
+        return Some(Instance { def, args });
+    };
+
+    // the arguments of the method itself come after those of the impl
+    let args: Vec<_> = impl_args
+        .iter()
+        .map(|&arg| infcx.resolve_vars_fully(arg))
+        .chain(args[count..].iter().copied())
+        .collect();
+
+    Some(Instance {
+        def: DefId::local(method.hir_id.owner),
+        args: cx.mk_args(&args),
+    })
+}
+
+/// Checks that the impls of traits have the items of their traits, that the supertraits and
+/// the bounds of the associated types of their traits hold for them, and that no two of them
+/// apply to the same types.
+pub(crate) fn check_impls(items: &ItemTypes, errors: &mut Vec<TypeckError>) {
+    for (def, owner) in items.krate.owners.iter_enumerated() {
+        let Some(hir::Owner::Item(item)) = owner else {
+            continue;
+        };
+
+        match &item.kind {
+            hir::ItemKind::Impl {
+                trait_: Some(trait_),
+                items: impl_items,
+                ..
+            } => {
+                if let Some(&trait_ref) = items.impl_trait_refs.get(&def) {
+                    check_impl_items(items, trait_ref.def.index, impl_items, trait_.span, errors);
+                    check_impl_predicates(items, def, trait_ref, trait_.span, errors);
+                }
+            }
+            hir::ItemKind::Trait { .. } => check_overlap(items, def, errors),
+            _ => {}
+        }
+    }
+}
+
+fn check_impl_items(
+    items: &ItemTypes,
+    trait_def: DefIndex,
+    impl_items: &[DefIndex],
+    span: Span,
+    errors: &mut Vec<TypeckError>,
+) {
+    let Some(hir::ItemKind::Trait {
+        items: trait_items, ..
+    }) = items.item(trait_def).map(|item| &item.kind)
+    else {
+        return;
+    };
+
+    let trait_name = items.name(trait_def).expect("traits have names");
+
+    for assoc in impl_items.iter().filter_map(|&def| items.assoc_item(def)) {
+        if items.find_assoc_item(trait_def, assoc.name.name).is_none() {
+            errors.push(TypeckError::NotMemberOfTrait {
+                name: assoc.name.name,
+                trait_: trait_name,
+                at: assoc.name.span.range().into(),
+                file: assoc.name.span.file,
+            });
+        }
+    }
+
+    let missing: Vec<_> = trait_items
+        .iter()
+        .filter_map(|&def| items.assoc_item(def))
+        .filter(|assoc| {
+            // without a default for impls to fall back to
+            matches!(
+                assoc.kind,
+                hir::AssocItemKind::Fn(_, _, None) | hir::AssocItemKind::Type { ty: None, .. }
+            )
+        })
+        .filter(|assoc| {
+            impl_items
+                .iter()
+                .filter_map(|&def| items.assoc_item(def))
+                .all(|item| item.name.name != assoc.name.name)
+        })
+        .map(|assoc| format!("`{}`", assoc.name))
+        .collect();
+
+    if !missing.is_empty() {
+        errors.push(TypeckError::MissingItems {
+            missing: missing.join(", "),
+            at: span.range().into(),
+            file: span.file,
+        });
+    }
+}
+
+fn check_impl_predicates(
+    items: &ItemTypes,
+    impl_def: DefIndex,
+    trait_ref: TraitRef,
+    span: Span,
+    errors: &mut Vec<TypeckError>,
+) {
+    let cx = items.cx;
+    let trait_def = trait_ref.def.index;
+    let mut infcx = InferCtxt::new(cx);
+    let param_env = elaborate(items, items.predicates_of(impl_def));
+    let mut fulfillment = FulfillmentCtxt::default();
+
+    // the supertraits, and the bounds and `where` clauses of the trait, but not the
+    // `Self: Trait` the impl is for
+    for predicate in items.predicates.get(&trait_def).into_iter().flatten() {
+        if !matches!(predicate, Predicate::Trait(own) if own.def == trait_ref.def) {
+            fulfillment.register(Obligation {
+                predicate: predicate.subst(cx, &trait_ref.args),
+                span,
+            });
+        }
+    }
+
+    let impl_type_count = items.type_params(impl_def).len();
+
+    for (&assoc_def, bounds) in &items.item_bounds {
+        if items.parents.get(&assoc_def) != Some(&trait_def) {
+            continue;
+        }
+
+        let Some(impl_assoc) = items
+            .name(assoc_def)
+            .and_then(|name| items.find_assoc_item(impl_def, name))
+        else {
+            continue;
+        };
+
+        // the parameters of the associated type itself, as the impl has them
+        let own_args = &items.identity_args(impl_assoc.hir_id.owner)[impl_type_count..];
+        let args: Vec<_> = trait_ref.args.iter().chain(own_args).copied().collect();
+
+        for bound in bounds {
+            fulfillment.register(Obligation {
+                predicate: bound.subst(cx, &args),
+                span: impl_assoc.span,
+            });
+        }
+    }
+
+    let mut selcx = SelectionCtxt {
+        items,
+        infcx: &mut infcx,
+        param_env: &param_env,
+    };
+
+    let fulfillment_errors = fulfillment.select_all_or_error(&mut selcx);
+    report_fulfillment_errors(items, &infcx, fulfillment_errors, true, errors);
+}
+
+/// Reports the impls of `trait_def` that apply to the same types as an impl before them.
+fn check_overlap(items: &ItemTypes, trait_def: DefIndex, errors: &mut Vec<TypeckError>) {
+    let cx = items.cx;
+    let impls = items.trait_impls.get(&trait_def).map_or(&[][..], Vec::as_slice);
+
+    let span_of = |impl_def: DefIndex| match items.item(impl_def).map(|item| &item.kind) {
+        Some(hir::ItemKind::Impl {
+            trait_: Some(trait_), ..
+        }) => trait_.span,
+        _ => items.item(impl_def).expect("impls are items").span,
+    };
+
+    for (i, &first) in impls.iter().enumerate() {
+        for &second in &impls[i + 1..] {
+            let mut infcx = InferCtxt::new(cx);
+
+            let Some(first_args) = instantiate_impl(items, &mut infcx, first) else {
+                continue;
+            };
+            let Some(second_args) = instantiate_impl(items, &mut infcx, second) else {
+                continue;
+            };
+
+            if infcx.unify_args(&first_args, &second_args).is_err() {
+                continue;
+            }
+
+            let trait_ref = TraitRef {
+                def: DefId::local(trait_def),
+                args: cx.mk_args(&first_args),
+            }
+            .fold_with(&mut ResolveVars { infcx: &infcx });
+
+            if trait_ref.args.iter().any(|ty| ty.any(&mut |ty| ty.is_error())) {
+                continue;
+            }
+
+            let (first_span, span) = (span_of(first), span_of(second));
+
+            errors.push(TypeckError::ConflictingImpls {
+                trait_: items.trait_ref_to_string(trait_ref),
+                ty: items.ty_to_string(trait_ref.self_ty()),
+                first: (first_span.file == span.file).then(|| first_span.range().into()),
+                at: span.range().into(),
+                file: span.file,
+            });
+        }
+    }
+}
+
+/// The trait arguments of the impl `impl_def`, with fresh variables for its parameters.
+fn instantiate_impl(items: &ItemTypes, infcx: &mut InferCtxt, impl_def: DefIndex) -> Option<Vec<Ty>> {
+    let cx = items.cx;
+    let impl_args: Vec<_> = items
+        .type_params(impl_def)
+        .iter()
+        .map(|_| infcx.next_ty_var())
+        .collect();
+
+    let trait_ref = items.impl_trait_refs.get(&impl_def)?;
+    Some(trait_ref.args.iter().map(|ty| ty.subst(cx, &impl_args)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use tangic_ast::{self as ast, build::Builder};
+    use tangic_middle::symbol::Symbol;
+
+    use super::*;
+    use crate::tests::{check, def_named};
+
+    fn trait_(b: &Builder, name: &str, supertraits: &[&str], items: Vec<ast::AssocItem>) -> ast::Item {
+        let supertraits = supertraits.iter().map(|name| b.trait_ref(name, vec![])).collect();
+        ast::Item::Trait(b.trait_(name, supertraits, items))
+    }
+
+    fn impl_(b: &Builder, params: &[&str], trait_: &str, self_ty: ast::Type, items: Vec<ast::AssocItem>) -> ast::Item {
+        ast::Item::Impl(b.impl_(params, Some(b.trait_ref(trait_, vec![])), self_ty, items))
+    }
+
+    /// `name[T: bound](arg: T) { statements }`
+    fn generic_fn(b: &Builder, name: &str, bound: &str, statements: Vec<ast::Expr>) -> ast::Item {
+        ast::Item::Fn(ast::Function {
+            params: vec![b.param("T", vec![b.trait_ref(bound, vec![])])],
+            ..b.function(name, vec![("arg", b.ty("T"))], b.unit_ty(), statements)
+        })
+    }
+
+    /// `trait Iterator { type Item; next(self) -> Self.Item }`
+    fn iterator(b: &Builder, item_bounds: &[&str]) -> ast::Item {
+        let item_bounds = item_bounds.iter().map(|name| b.trait_ref(name, vec![])).collect();
+        let items = vec![
+            b.assoc_type("Item", item_bounds, None),
+            ast::AssocItem::Fn(b.fn_decl("next", vec![b.ty("self")], b.ty("Self.Item"))),
+        ];
+
+        trait_(b, "Iterator", &[], items)
+    }
+
+    /// `impl Iterator for Counter { type Item = item; next(self) -> item = value }`
+    fn counter(b: &Builder, item: &str, value: ast::Expr) -> ast::Item {
+        let items = vec![
+            b.assoc_type("Item", vec![], Some(b.ty(item))),
+            ast::AssocItem::Fn(b.function("next", vec![("self", b.ty("self"))], b.ty(item), vec![value])),
+        ];
+
+        impl_(b, &[], "Iterator", b.ty("Counter"), items)
+    }
+
+    fn impl_named(items: &ItemTypes, trait_name: &str) -> DefIndex {
+        items
+            .impl_trait_refs
+            .iter()
+            .find(|(_, trait_ref)| items.name(trait_ref.def.index).unwrap().as_str() == trait_name)
+            .map(|(&def, _)| def)
+            .unwrap_or_else(|| panic!("no impl of `{trait_name}`"))
+    }
+
+    #[test]
+    fn bounds_hold_by_impls() {
+        let b = Builder::new();
+        let statements = vec![
+            b.call(b.name("show"), vec![b.bool(true)]),
+            b.call(b.name("show"), vec![b.unit()]),
+        ];
+        let items = vec![
+            trait_(&b, "Show", &[], vec![]),
+            impl_(&b, &[], "Show", b.ty("bool"), vec![]),
+            generic_fn(&b, "show", "Show", vec![]),
+            ast::Item::Fn(b.function("f", vec![], b.unit_ty(), statements)),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Unsatisfied { ty, trait_, .. }] if ty == "()" && trait_ == "Show"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn bounds_hold_by_bounds_and_their_supertraits() {
+        let b = Builder::new();
+        let items = vec![
+            trait_(&b, "Eq", &[], vec![]),
+            trait_(&b, "Ord", &["Eq"], vec![]),
+            generic_fn(&b, "needs_eq", "Eq", vec![]),
+            generic_fn(&b, "f", "Ord", vec![b.call(b.name("needs_eq"), vec![b.name("arg")])]),
+        ];
+        let (_, _, errors) = check(&b, items);
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn impls_need_the_supertraits_of_their_trait() {
+        let b = Builder::new();
+        let items = vec![
+            trait_(&b, "Eq", &[], vec![]),
+            trait_(&b, "Ord", &["Eq"], vec![]),
+            b.structure("S", &[], vec![]),
+            impl_(&b, &[], "Ord", b.ty("S"), vec![]),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(&errors[..], [TypeckError::Unsatisfied { ty, trait_, .. }] if ty == "S" && trait_ == "Eq"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn impls_need_the_items_of_their_trait() {
+        let b = Builder::new();
+        let other = b.function("other", vec![("self", b.ty("self"))], b.unit_ty(), vec![]);
+        let items = vec![
+            iterator(&b, &[]),
+            b.structure("Counter", &[], vec![]),
+            impl_(&b, &[], "Iterator", b.ty("Counter"), vec![ast::AssocItem::Fn(other)]),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::NotMemberOfTrait { name, .. }, TypeckError::MissingItems { missing, .. }]
+                    if name.as_str() == "other" && missing == "`Item`, `next`"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn overlapping_impls() {
+        let b = Builder::new();
+        let items = vec![
+            trait_(&b, "Show", &[], vec![]),
+            b.structure("S", &[], vec![]),
+            impl_(&b, &["T"], "Show", b.ty("T"), vec![]),
+            impl_(&b, &[], "Show", b.ty("bool"), vec![]),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::ConflictingImpls { trait_, ty, first: Some(_), .. }]
+                    if trait_ == "Show" && ty == "bool"
+            ),
+            "{errors:?}"
+        );
+
+        let b = Builder::new();
+        let items = vec![
+            trait_(&b, "Show", &[], vec![]),
+            b.structure("S", &[], vec![]),
+            impl_(&b, &[], "Show", b.ty("S"), vec![]),
+            impl_(&b, &[], "Show", b.ty("bool"), vec![]),
+        ];
+        let (_, _, errors) = check(&b, items);
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn projections_normalize_to_what_the_impl_gives() {
+        let b = Builder::new();
+        let statements = vec![
+            b.let_(
+                b.pattern("x"),
+                false,
+                Some(b.ty("i32")),
+                Some(b.method_call(b.name("c"), "next", vec![])),
+            ),
+            b.let_(
+                b.pattern("y"),
+                false,
+                Some(b.ty("bool")),
+                Some(b.method_call(b.name("c"), "next", vec![])),
+            ),
+        ];
+        let items = vec![
+            iterator(&b, &[]),
+            b.structure("Counter", &[], vec![]),
+            counter(&b, "i32", b.int(1)),
+            ast::Item::Fn(b.function("f", vec![("c", b.ty("Counter"))], b.unit_ty(), statements)),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, .. }] if expected == "bool" && found == "i32"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn projections_of_parameters_stay_as_they_are() {
+        let b = Builder::new();
+        let next = b.method_call(b.name("arg"), "next", vec![]);
+        let statements = vec![b.let_(b.pattern("x"), false, Some(b.ty("i32")), Some(next))];
+        let items = vec![iterator(&b, &[]), generic_fn(&b, "f", "Iterator", statements)];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(
+                &errors[..],
+                [TypeckError::Mismatch { expected, found, .. }] if expected == "i32" && found != "i32"
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn impls_need_the_bounds_of_associated_types() {
+        let b = Builder::new();
+        let items = vec![
+            trait_(&b, "Show", &[], vec![]),
+            impl_(&b, &[], "Show", b.ty("bool"), vec![]),
+            iterator(&b, &["Show"]),
+            b.structure("Counter", &[], vec![]),
+            counter(&b, "i32", b.int(1)),
+        ];
+        let (_, _, errors) = check(&b, items);
+
+        assert!(
+            matches!(&errors[..], [TypeckError::Unsatisfied { ty, trait_, .. }] if ty == "i32" && trait_ == "Show"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn trait_methods_resolve_to_the_method_of_the_impl() {
+        let b = Builder::new();
+        let items = vec![
+            iterator(&b, &[]),
+            b.structure("Counter", &[], vec![]),
+            counter(&b, "i32", b.int(1)),
+        ];
+        let (types, hir, errors) = check(&b, items);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let items = &types.items;
+        let counter = items.cx.mk_adt(DefId::local(def_named(hir, "Counter")), &[]);
+        let trait_next = DefId::local(def_named(hir, "next"));
+        let impl_next = items
+            .find_assoc_item(impl_named(items, "Iterator"), Symbol::intern("next"))
+            .unwrap()
+            .hir_id
+            .owner;
+
+        let instance = resolve_instance(items, trait_next, items.cx.mk_args(&[counter])).unwrap();
+        assert_eq!(instance.def, DefId::local(impl_next));
+        assert!(instance.args.is_empty());
+
+        // nothing implements it for `bool`
+        assert!(resolve_instance(items, trait_next, items.cx.mk_args(&[items.cx.types.bool])).is_none());
+    }
+}